@@ -0,0 +1,60 @@
+//! Compares the startup time of a navmesh with about 500k nodes read with `parse_graph_binary`
+//! and `parse_graph_mmap`. Run with `cargo bench --bench graph_mmap`.
+
+use std::time::{Duration, Instant};
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::utils::{parse_graph_binary, parse_graph_mmap, save_graph_binary, save_graph_mmap};
+use repath::Coord;
+
+const GRID_SIZE: usize = 708;
+const RUNS: usize = 5;
+
+/// The fastest of `RUNS` parses of the file.
+fn measure(parse: fn(&str) -> Result<Graph, repath::RePathError>, filename: &str) -> (Graph, Duration) {
+    let mut best = Duration::MAX;
+    let mut graph = None;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        graph = Some(parse(filename).expect("Failed to parse navmesh"));
+        best = best.min(start.elapsed());
+    }
+    (graph.unwrap(), best)
+}
+
+fn main() {
+    let mut graph = Graph::new();
+    for z in 0..GRID_SIZE {
+        for x in 0..GRID_SIZE {
+            graph.add_node(Node::new(graph.nodes.len(), x as Coord, ((x * z) % 7) as Coord * 0.1, z as Coord));
+        }
+    }
+    for z in 0..GRID_SIZE - 1 {
+        for x in 0..GRID_SIZE - 1 {
+            let a = z * GRID_SIZE + x;
+            let (b, c, d) = (a + 1, a + GRID_SIZE + 1, a + GRID_SIZE);
+            for (from, to) in [(a, b), (b, c), (c, a), (a, c), (c, d), (d, a)] {
+                graph.add_edge(from, to, graph.heuristic(from, to));
+            }
+        }
+    }
+
+    let dir = std::env::temp_dir();
+    let binary = dir.join("repath_bench_graph_mmap.rpnav");
+    let mapped = dir.join("repath_bench_graph_mmap.rpmap");
+    let (binary, mapped) = (binary.to_str().unwrap(), mapped.to_str().unwrap());
+    save_graph_binary(&graph, binary).expect("Failed to write binary navmesh");
+    save_graph_mmap(&graph, mapped).expect("Failed to write mapped navmesh");
+    let size = |filename: &str| std::fs::metadata(filename).unwrap().len() as f64 / (1024.0 * 1024.0);
+    println!("{} nodes", graph.nodes.len());
+
+    let (from_binary, binary_duration) = measure(parse_graph_binary, binary);
+    let (from_mapped, mapped_duration) = measure(parse_graph_mmap, mapped);
+    assert!(from_binary == graph && from_mapped == graph);
+    println!("parse_graph_binary: {:?} ({:.1} MiB)", binary_duration, size(binary));
+    println!("parse_graph_mmap:   {:?} ({:.1} MiB)", mapped_duration, size(mapped));
+    println!(
+        "Speedup: {:.2}x",
+        binary_duration.as_secs_f64() / mapped_duration.as_secs_f64()
+    );
+}
@@ -0,0 +1,75 @@
+//! Compares the number of nodes expanded by A* and Jump Point Search.
+//! Run with `cargo bench --bench jps`.
+
+use repath::graph::Graph;
+use repath::jps::GridLayout;
+use repath::node::Node;
+use repath::utils::parse_obj;
+use repath::Coord;
+
+/// Builds an 8-connected grid with a few walls that force detours.
+fn build_grid(size: usize) -> Graph {
+    let blocked = |x: usize, y: usize| (x % 20 == 10 && !y.is_multiple_of(40)) || (y % 30 == 15 && !x.is_multiple_of(50));
+    let mut graph = Graph::new();
+    let mut ids = vec![None; size * size];
+
+    for y in 0..size {
+        for x in 0..size {
+            if !blocked(x, y) {
+                ids[y * size + x] = Some(graph.nodes.len());
+                graph.add_node(Node::new(graph.nodes.len(), x as Coord, y as Coord, 0.0));
+            }
+        }
+    }
+
+    for y in 0..size as i64 {
+        for x in 0..size as i64 {
+            let Some(from) = ids[(y * size as i64 + x) as usize] else {
+                continue;
+            };
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= size as i64 || ny >= size as i64 {
+                    continue;
+                }
+                if let Some(to) = ids[(ny * size as i64 + nx) as usize] {
+                    let cost = if dx != 0 && dy != 0 { std::f64::consts::SQRT_2 as Coord } else { 1.0 };
+                    graph.add_edge(from, to, cost);
+                }
+            }
+        }
+    }
+
+    graph
+}
+
+fn compare(name: &str, graph: &Graph, pairs: &[(usize, usize)]) {
+    println!("{} ({} nodes, uniform grid: {})", name, graph.nodes.len(), GridLayout::detect(graph).is_some());
+
+    let (mut a_star_total, mut jps_total) = (0, 0);
+    for &(start, goal) in pairs {
+        let (a_star_path, a_star_stats) = graph.a_star_with_stats(start, goal);
+        let (jps_path, jps_stats) = graph.jps_with_stats(start, goal);
+        assert_eq!(a_star_path.is_some(), jps_path.is_some());
+
+        a_star_total += a_star_stats.nodes_expanded;
+        jps_total += jps_stats.nodes_expanded;
+    }
+
+    println!("  A* expanded {} nodes over {} queries", a_star_total, pairs.len());
+    println!("  JPS expanded {} nodes over {} queries", jps_total, pairs.len());
+}
+
+fn main() {
+    let grid = build_grid(200);
+    let last = grid.nodes.len() - 1;
+    let pairs: Vec<_> = (0..20).map(|i| (i * 97 % last, last - i * 89 % last)).collect();
+    compare("Synthetic 200x200 grid", &grid, &pairs);
+
+    let navmesh = parse_obj("NavMesh.obj").expect("Failed to parse navmesh");
+    let count = navmesh.nodes.len();
+    let pairs: Vec<_> = (0..20)
+        .map(|i| (i * count / 20, (i * count / 20 + 1) % count))
+        .collect();
+    compare("NavMesh.obj", &navmesh, &pairs);
+}
@@ -0,0 +1,57 @@
+//! Compares the sequential and the parallel linear nearest node scan.
+//! Run with `cargo bench --bench parallel_nearest`.
+
+use std::time::Instant;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::Coord;
+
+const NODE_COUNT: usize = 500_000;
+const LOOKUPS: usize = 200;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut graph = Graph::new();
+    for id in 0..NODE_COUNT {
+        graph.add_node(Node::new(
+            id,
+            rng.gen_range(-5000.0..5000.0),
+            rng.gen_range(-100.0..100.0),
+            rng.gen_range(-5000.0..5000.0),
+        ));
+    }
+    let queries: Vec<(Coord, Coord, Coord)> = (0..LOOKUPS)
+        .map(|_| {
+            (
+                rng.gen_range(-5000.0..5000.0),
+                rng.gen_range(-100.0..100.0),
+                rng.gen_range(-5000.0..5000.0),
+            )
+        })
+        .collect();
+
+    let sequential_start = Instant::now();
+    let sequential: Vec<_> = queries
+        .iter()
+        .map(|&(x, y, z)| graph.nearest_node_with_threshold(x, y, z, usize::MAX))
+        .collect();
+    let sequential_duration = sequential_start.elapsed();
+
+    let parallel_start = Instant::now();
+    let parallel: Vec<_> = queries.iter().map(|&(x, y, z)| graph.nearest_node_parallel(x, y, z)).collect();
+    let parallel_duration = parallel_start.elapsed();
+
+    assert_eq!(sequential, parallel);
+    println!(
+        "{} lookups over {} nodes on {} threads: sequential {:?} ({:?} each), parallel {:?} ({:?} each)",
+        LOOKUPS,
+        NODE_COUNT,
+        rayon::current_num_threads(),
+        sequential_duration,
+        sequential_duration / LOOKUPS as u32,
+        parallel_duration,
+        parallel_duration / LOOKUPS as u32
+    );
+}
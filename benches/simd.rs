@@ -0,0 +1,53 @@
+//! Compares the scalar nearest node lookup with the SSE one of the `simd` feature.
+//! Run with `cargo bench --features simd --bench simd`.
+
+use std::time::Instant;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::Coord;
+
+const NODE_COUNT: usize = 100_000;
+const LOOKUPS: usize = 1_000;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut graph = Graph::new();
+    for id in 0..NODE_COUNT {
+        graph.add_node(Node::new(
+            id,
+            rng.gen_range(-5000.0..5000.0),
+            rng.gen_range(-100.0..100.0),
+            rng.gen_range(-5000.0..5000.0),
+        ));
+    }
+    let queries: Vec<(Coord, Coord, Coord)> = (0..LOOKUPS)
+        .map(|_| {
+            (
+                rng.gen_range(-5000.0..5000.0),
+                rng.gen_range(-100.0..100.0),
+                rng.gen_range(-5000.0..5000.0),
+            )
+        })
+        .collect();
+
+    let scalar_start = Instant::now();
+    let scalar: Vec<_> = queries.iter().map(|&(x, y, z)| graph.nearest_node(x, y, z)).collect();
+    let scalar_duration = scalar_start.elapsed();
+
+    let simd_start = Instant::now();
+    let simd: Vec<_> = queries.iter().map(|&(x, y, z)| graph.nearest_node_simd(x, y, z)).collect();
+    let simd_duration = simd_start.elapsed();
+
+    assert_eq!(scalar, simd);
+    println!(
+        "{} lookups over {} nodes: scalar {:?} ({:?} each), simd {:?} ({:?} each)",
+        LOOKUPS,
+        NODE_COUNT,
+        scalar_duration,
+        scalar_duration / LOOKUPS as u32,
+        simd_duration,
+        simd_duration / LOOKUPS as u32
+    );
+}
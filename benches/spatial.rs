@@ -0,0 +1,57 @@
+//! Compares the linear nearest node lookup with the k-d tree of `SpatialIndex`.
+//! Run with `cargo bench --bench spatial`.
+
+use std::time::Instant;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::spatial::SpatialIndex;
+use repath::Coord;
+
+const NODE_COUNT: usize = 100_000;
+const LOOKUPS: usize = 1_000;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let mut graph = Graph::new();
+    for id in 0..NODE_COUNT {
+        graph.add_node(Node::new(
+            id,
+            rng.gen_range(-5000.0..5000.0),
+            rng.gen_range(-100.0..100.0),
+            rng.gen_range(-5000.0..5000.0),
+        ));
+    }
+    let queries: Vec<(Coord, Coord, Coord)> = (0..LOOKUPS)
+        .map(|_| {
+            (
+                rng.gen_range(-5000.0..5000.0),
+                rng.gen_range(-100.0..100.0),
+                rng.gen_range(-5000.0..5000.0),
+            )
+        })
+        .collect();
+
+    let build_start = Instant::now();
+    let index = SpatialIndex::new(&graph);
+    println!("Index build time for {} nodes: {:?}", NODE_COUNT, build_start.elapsed());
+
+    let linear_start = Instant::now();
+    let linear: Vec<_> = queries.iter().map(|&(x, y, z)| graph.nearest_node(x, y, z)).collect();
+    let linear_duration = linear_start.elapsed();
+
+    let indexed_start = Instant::now();
+    let indexed: Vec<_> = queries.iter().map(|&(x, y, z)| index.nearest_node_fast(x, y, z)).collect();
+    let indexed_duration = indexed_start.elapsed();
+
+    assert_eq!(linear, indexed);
+    println!(
+        "{} lookups: linear {:?} ({:?} each), indexed {:?} ({:?} each)",
+        LOOKUPS,
+        linear_duration,
+        linear_duration / LOOKUPS as u32,
+        indexed_duration,
+        indexed_duration / LOOKUPS as u32
+    );
+}
@@ -0,0 +1,64 @@
+//! Compares the throughput of cached queries from 16 threads with and without the thread-local
+//! layer of the cache. Run with `cargo bench --bench thread_local_cache`.
+
+use std::time::Instant;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use repath::settings::RePathSettings;
+use repath::{Coord, RePathfinder};
+
+const THREADS: usize = 16;
+const QUERIES_PER_THREAD: usize = 200_000;
+const DISTINCT_QUERIES: usize = 256;
+
+type Position = (Coord, Coord, Coord);
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(42);
+    let graph = repath::utils::parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let position = |id: usize| (graph.nodes[id].x, graph.nodes[id].y, graph.nodes[id].z);
+    let queries: Vec<(Position, Position)> = (0..DISTINCT_QUERIES)
+        .map(|_| {
+            let start = rng.gen_range(0..graph.nodes.len() - 2000);
+            (position(start), position(start + rng.gen_range(0..2000)))
+        })
+        .collect();
+
+    for thread_local_cache_capacity in [0, 1024] {
+        let settings = RePathSettings {
+            navmesh_filename: "navmesh_varied.obj".to_string(),
+            total_precompute_pairs: 0,
+            use_precomputed_cache: false,
+            thread_local_cache_capacity,
+            ..RePathSettings::default()
+        };
+        let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
+        // Fill the shared cache, so only lookups are measured
+        for &(start, end) in &queries {
+            pathfinder.find_path(start, end).expect("No path found");
+        }
+
+        let start_time = Instant::now();
+        std::thread::scope(|scope| {
+            for thread in 0..THREADS {
+                let (pathfinder, queries) = (&pathfinder, &queries);
+                scope.spawn(move || {
+                    for index in 0..QUERIES_PER_THREAD {
+                        let (start, end) = queries[(index * 7 + thread) % queries.len()];
+                        pathfinder.find_path(start, end).expect("No path found");
+                    }
+                });
+            }
+        });
+        let duration = start_time.elapsed();
+        let queries_per_second = (THREADS * QUERIES_PER_THREAD) as f64 / duration.as_secs_f64();
+        println!(
+            "Thread-local capacity {}: {} queries on {} threads in {:?}, {:.0} queries/s",
+            thread_local_cache_capacity,
+            THREADS * QUERIES_PER_THREAD,
+            THREADS,
+            duration,
+            queries_per_second
+        );
+    }
+}
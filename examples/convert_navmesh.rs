@@ -0,0 +1,57 @@
+//! Converts an OBJ navmesh to the binary navmesh format, or to the mapped navmesh format if the
+//! output file ends with `.rpmap`.
+//!
+//! Usage: `cargo run --release --example convert_navmesh -- <input.obj> [output.rpnav|output.rpmap]`
+
+use std::time::Instant;
+use repath::utils::{parse_graph_binary, parse_graph_mmap, parse_obj, save_graph_binary, save_graph_mmap};
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    let Some(input) = args.get(1) else {
+        eprintln!("Usage: convert_navmesh <input.obj> [output.rpnav|output.rpmap]");
+        std::process::exit(1);
+    };
+    let output = args
+        .get(2)
+        .cloned()
+        .unwrap_or_else(|| format!("{}.rpnav", input.trim_end_matches(".obj")));
+
+    let parse_start = Instant::now();
+    let graph = parse_obj(input).unwrap_or_else(|err| {
+        eprintln!("Failed to parse {}: {}", input, err);
+        std::process::exit(1);
+    });
+    let obj_duration = parse_start.elapsed();
+
+    let mapped = output.ends_with(".rpmap");
+    let saved = if mapped { save_graph_mmap(&graph, &output) } else { save_graph_binary(&graph, &output) };
+    if let Err(err) = saved {
+        eprintln!("Failed to write {}: {}", output, err);
+        std::process::exit(1);
+    }
+
+    let parse_start = Instant::now();
+    let parsed = if mapped { parse_graph_mmap(&output) } else { parse_graph_binary(&output) };
+    if let Err(err) = parsed {
+        eprintln!("Failed to read back {}: {}", output, err);
+        std::process::exit(1);
+    }
+    let binary_duration = parse_start.elapsed();
+
+    let obj_size = std::fs::metadata(input).map(|metadata| metadata.len()).unwrap_or(0);
+    let binary_size = std::fs::metadata(&output).map(|metadata| metadata.len()).unwrap_or(0);
+    println!(
+        "Converted {} nodes from {} to {}",
+        graph.nodes.len(),
+        input,
+        output
+    );
+    println!("OBJ:    {} bytes, parsed in {:?}", obj_size, obj_duration);
+    println!(
+        "Binary: {} bytes ({:.1}% of OBJ), parsed in {:?}",
+        binary_size,
+        binary_size as f64 / obj_size.max(1) as f64 * 100.0,
+        binary_duration
+    );
+}
@@ -0,0 +1,66 @@
+//! Finds random paths in the background and serves the pathfinding metrics to Prometheus.
+//!
+//! Usage: `cargo run --release --features metrics --example metrics_server -- [navmesh.obj]`,
+//! then scrape `http://127.0.0.1:9898/metrics`.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use rand::Rng;
+use repath::settings::RePathSettings;
+use repath::RePathfinder;
+
+const ADDRESS: &str = "127.0.0.1:9898";
+
+fn main() {
+    let navmesh_filename = std::env::args().nth(1).unwrap_or_else(|| "NavMesh.obj".to_string());
+    let settings = RePathSettings {
+        navmesh_filename,
+        precompute_radius: 100.0,
+        total_precompute_pairs: 1000,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new(settings).unwrap_or_else(|err| {
+        eprintln!("Failed to create pathfinder: {}", err);
+        std::process::exit(1);
+    });
+
+    // Simulate the agents of a game server
+    let worker = pathfinder.clone();
+    std::thread::spawn(move || {
+        let mut rng = rand::thread_rng();
+        let graph = worker.graph();
+        let nodes = &graph.nodes;
+        loop {
+            let start = &nodes[rng.gen_range(0..nodes.len())];
+            let end = &nodes[rng.gen_range(0..nodes.len())];
+            let _ = worker.find_path((start.x, start.y, start.z), (end.x, end.y, end.z));
+        }
+    });
+
+    let listener = TcpListener::bind(ADDRESS).expect("Failed to bind the metrics address");
+    println!("Serving metrics on http://{}/metrics", ADDRESS);
+    for stream in listener.incoming().flatten() {
+        if let Err(err) = handle(stream, &pathfinder) {
+            eprintln!("Failed to answer a request: {}", err);
+        }
+    }
+}
+
+/// Answers a single HTTP request, with the metrics for `GET /metrics` and 404 otherwise.
+fn handle(mut stream: TcpStream, pathfinder: &RePathfinder) -> std::io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        ("200 OK", pathfinder.metrics_to_prometheus())
+    } else {
+        ("404 Not Found", String::new())
+    };
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    )
+}
@@ -0,0 +1,44 @@
+//! Shows a progress bar while the pathfinder precomputes paths.
+//!
+//! Usage: `cargo run --release --example progress -- [navmesh.obj]`
+
+use std::io::Write;
+use repath::settings::RePathSettings;
+use repath::RePathfinder;
+
+/// The number of characters of the progress bar.
+const BAR_WIDTH: usize = 40;
+
+fn main() {
+    let navmesh_filename = std::env::args().nth(1).unwrap_or_else(|| "NavMesh.obj".to_string());
+    let settings = RePathSettings {
+        navmesh_filename,
+        precompute_radius: 100.0,
+        total_precompute_pairs: 10_000,
+        use_precomputed_cache: true,
+        ..RePathSettings::default()
+    };
+
+    let pathfinder = RePathfinder::new_with_progress(settings, |completed, total| {
+        // Only redraw when the percentage changes, the callback runs for every pair
+        let percent = completed * 100 / total;
+        if completed > 1 && percent == (completed - 1) * 100 / total {
+            return;
+        }
+        let filled = percent * BAR_WIDTH / 100;
+        print!(
+            "\r[{}{}] {:3}%",
+            "#".repeat(filled),
+            " ".repeat(BAR_WIDTH - filled),
+            percent
+        );
+        let _ = std::io::stdout().flush();
+    })
+    .unwrap_or_else(|err| {
+        eprintln!("Failed to create pathfinder: {}", err);
+        std::process::exit(1);
+    });
+
+    println!();
+    println!("Cached {} paths", pathfinder.cache_len());
+}
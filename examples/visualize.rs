@@ -0,0 +1,40 @@
+//! Writes a navmesh and a path across it as a Graphviz DOT file, which can be rendered with
+//! e.g. `dot -Tsvg navmesh.dot -o navmesh.svg`.
+//!
+//! Usage: `cargo run --release --example visualize -- [navmesh.obj] [output.dot]`
+
+use repath::settings::RePathSettings;
+use repath::RePathfinderBuilder;
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let navmesh_filename = args.next().unwrap_or_else(|| "NavMesh.obj".to_string());
+    let output = args.next().unwrap_or_else(|| "navmesh.dot".to_string());
+    let settings = RePathSettings {
+        navmesh_filename,
+        ..RePathSettings::default()
+    };
+
+    let pathfinder = RePathfinderBuilder::new(settings)
+        .skip_precomputation()
+        .build()
+        .unwrap_or_else(|err| {
+            eprintln!("Failed to create pathfinder: {}", err);
+            std::process::exit(1);
+        });
+
+    // Highlight a path between the first and the last node, if there is one
+    let graph = pathfinder.graph();
+    let nodes = &graph.nodes;
+    let (first, last) = (&nodes[0], &nodes[nodes.len() - 1]);
+    let path = pathfinder
+        .find_path((first.x, first.y, first.z), (last.x, last.y, last.z))
+        .ok()
+        .map(|result| result.nodes);
+
+    if let Err(err) = pathfinder.export_dot(&output, path.as_ref()) {
+        eprintln!("Failed to write {}: {}", output, err);
+        std::process::exit(1);
+    }
+    println!("Wrote {} nodes to {}", nodes.len(), output);
+}
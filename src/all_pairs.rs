@@ -0,0 +1,113 @@
+use std::sync::Arc;
+use rayon::prelude::*;
+use crate::error::RePathError;
+use crate::graph::Graph;
+use crate::node::Node;
+use crate::path::Path;
+use crate::Coord;
+
+/// The largest graph `Graph::floyd_warshall` accepts, since the matrices grow quadratically with
+/// the number of nodes, to about 300 MB at this size.
+pub const FLOYD_WARSHALL_NODE_LIMIT: usize = 5000;
+
+/// The costs of the cheapest paths between all pairs of nodes, see `Graph::floyd_warshall`.
+#[derive(Debug, Clone)]
+pub struct AllPairsDistances {
+    nodes: Vec<Node>,
+    /// The cost from node `i` to node `j` at index `i * n + j`.
+    distances: Vec<Coord>,
+    /// The node before node `j` on the cheapest path from node `i` at index `i * n + j`, or
+    /// `usize::MAX` if there is none.
+    predecessors: Vec<usize>,
+}
+
+impl AllPairsDistances {
+    /// Returns the cost of the cheapest path from start to goal, or infinity if there is none.
+    /// Panics if a node id is not in the graph.
+    pub fn distance(&self, start: usize, goal: usize) -> Coord {
+        self.distances[self.index(start, goal)]
+    }
+
+    /// Returns the cheapest path from start to goal, or `None` if there is none. Panics if a node
+    /// id is not in the graph.
+    pub fn path(&self, start: usize, goal: usize) -> Option<Path> {
+        if !self.distance(start, goal).is_finite() {
+            return None;
+        }
+        let mut path = vec![self.nodes[goal].clone()];
+        let mut current = goal;
+        while current != start {
+            current = self.predecessors[self.index(start, current)];
+            path.push(self.nodes[current].clone());
+        }
+        path.reverse();
+        Some(Arc::new(path))
+    }
+
+    fn index(&self, start: usize, goal: usize) -> usize {
+        let node_count = self.nodes.len();
+        assert!(start < node_count && goal < node_count, "Node id out of range");
+        start * node_count + goal
+    }
+}
+
+impl Graph {
+    /// Computes the cheapest paths between all pairs of nodes with the Floyd-Warshall algorithm,
+    /// after which `AllPairsDistances` answers every query in constant time. Takes time cubic and
+    /// memory quadratic in the number of nodes, so graphs with more than
+    /// `FLOYD_WARSHALL_NODE_LIMIT` nodes return `Err(RePathError::GraphTooLarge)`, see
+    /// `floyd_warshall_with_limit`.
+    pub fn floyd_warshall(&self) -> Result<AllPairsDistances, RePathError> {
+        self.floyd_warshall_with_limit(FLOYD_WARSHALL_NODE_LIMIT)
+    }
+
+    /// Like `floyd_warshall`, but with graphs of up to `max_nodes` nodes.
+    pub fn floyd_warshall_with_limit(&self, max_nodes: usize) -> Result<AllPairsDistances, RePathError> {
+        let node_count = self.nodes.len();
+        if node_count > max_nodes {
+            return Err(RePathError::GraphTooLarge(node_count));
+        }
+
+        let mut distances = vec![Coord::INFINITY; node_count * node_count];
+        let mut predecessors = vec![usize::MAX; node_count * node_count];
+        for (from, edges) in self.edges.iter().enumerate() {
+            distances[from * node_count + from] = 0.0;
+            for edge in edges {
+                let index = from * node_count + edge.to;
+                let cost = self.traversal_cost(edge);
+                if cost < distances[index] {
+                    distances[index] = cost;
+                    predecessors[index] = from;
+                }
+            }
+        }
+
+        for via in 0..node_count {
+            // The paths from the intermediate node do not change while passing through it
+            let via_distances = distances[via * node_count..(via + 1) * node_count].to_vec();
+            let via_predecessors = predecessors[via * node_count..(via + 1) * node_count].to_vec();
+            distances
+                .par_chunks_mut(node_count)
+                .zip(predecessors.par_chunks_mut(node_count))
+                .for_each(|(row_distances, row_predecessors)| {
+                    let to_via = row_distances[via];
+                    if !to_via.is_finite() {
+                        return;
+                    }
+                    for goal in 0..node_count {
+                        let tentative_dist = to_via + via_distances[goal];
+                        if tentative_dist < row_distances[goal] {
+                            row_distances[goal] = tentative_dist;
+                            row_predecessors[goal] = via_predecessors[goal];
+                        }
+                    }
+                });
+        }
+
+        Ok(AllPairsDistances {
+            nodes: self.nodes.clone(),
+            distances,
+            predecessors,
+        })
+    }
+}
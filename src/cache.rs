@@ -0,0 +1,597 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::io::{BufReader, Seek, SeekFrom, Write};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, Weak};
+use dashmap::DashMap;
+use crate::error::RePathError;
+use crate::graph::Graph;
+use crate::node::Node;
+use crate::path::Path;
+
+/// Storage for search results keyed by `(start, goal)` node ids.
+/// A stored `None` records that no path exists between the two nodes.
+///
+/// Searches that depend on more than the two nodes use a wider key, e.g. `a_star_with_cost`
+/// stores its results under `(cost function id, start, goal)`.
+///
+/// Implemented by `DashMap` for an unbounded cache and by `LruPathCache` for a bounded one.
+pub trait PathCache<K = (usize, usize)> {
+    /// Returns the stored result for the key, or `None` if nothing is stored.
+    fn get_path(&self, key: &K) -> Option<Option<Path>>;
+
+    /// Stores the result for the key, replacing any previous one.
+    fn insert_path(&self, key: K, path: Option<Path>);
+}
+
+impl<K: Eq + Hash> PathCache<K> for DashMap<K, Option<Path>> {
+    fn get_path(&self, key: &K) -> Option<Option<Path>> {
+        self.get(key).map(|entry| entry.clone())
+    }
+
+    fn insert_path(&self, key: K, path: Option<Path>) {
+        self.insert(key, path);
+    }
+}
+
+/// Stores the results of a search keyed by `(start, goal)` in a cache keyed by
+/// `(prefix, start, goal)`, so searches whose results depend on further parameters can share the
+/// cache if the prefix identifies those parameters.
+pub(crate) struct PrefixedCache<'a, C> {
+    prefix: u64,
+    cache: &'a C,
+}
+
+impl<'a, C> PrefixedCache<'a, C> {
+    pub(crate) fn new(prefix: u64, cache: &'a C) -> Self {
+        PrefixedCache { prefix, cache }
+    }
+}
+
+impl<C: PathCache<(u64, usize, usize)>> PathCache for PrefixedCache<'_, C> {
+    fn get_path(&self, &(start, goal): &(usize, usize)) -> Option<Option<Path>> {
+        self.cache.get_path(&(self.prefix, start, goal))
+    }
+
+    fn insert_path(&self, (start, goal): (usize, usize), path: Option<Path>) {
+        self.cache.insert_path((self.prefix, start, goal), path);
+    }
+}
+
+/// The reverse of `PrefixedCache`: stores the results of a search keyed by
+/// `(prefix, start, goal)` in a cache keyed by `(start, goal)`, ignoring the prefix, for a cache
+/// that only ever holds the results of one set of parameters, see `Graph::a_star`.
+pub(crate) struct UnprefixedCache<'a, C> {
+    cache: &'a C,
+}
+
+impl<'a, C> UnprefixedCache<'a, C> {
+    pub(crate) fn new(cache: &'a C) -> Self {
+        UnprefixedCache { cache }
+    }
+}
+
+impl<C: PathCache> PathCache<(u64, usize, usize)> for UnprefixedCache<'_, C> {
+    fn get_path(&self, &(_, start, goal): &(u64, usize, usize)) -> Option<Option<Path>> {
+        self.cache.get_path(&(start, goal))
+    }
+
+    fn insert_path(&self, (_, start, goal): (u64, usize, usize), path: Option<Path>) {
+        self.cache.insert_path((start, goal), path);
+    }
+}
+
+/// The maximum number of independently locked shards of an `LruPathCache`.
+const MAX_SHARDS: usize = 16;
+
+/// The minimum capacity of a shard, smaller caches use fewer shards and are exact LRU caches.
+const MIN_SHARD_CAPACITY: usize = 1024;
+
+/// The source of the ids that tell the thread-local layers of different caches apart.
+static NEXT_CACHE_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    /// The thread-local layers of the `LruPathCache`s used on this thread, by cache id.
+    static LOCAL_LAYERS: RefCell<HashMap<u64, LocalLayer>> = RefCell::new(HashMap::new());
+}
+
+/// The results of one cache most recently used on one thread, see
+/// `LruPathCache::with_thread_local_capacity`.
+struct LocalLayer {
+    /// Gone once the cache was dropped, so the layer can be dropped as well.
+    owner: Weak<()>,
+    /// The `LruPathCache::epoch` the entries were copied in, older entries may be outdated.
+    epoch: u64,
+    /// A `HashMap<K, Option<Path>>` with the key type of the cache.
+    entries: Box<dyn Any>,
+}
+
+/// A concurrent cache holding at most `capacity` results.
+///
+/// Large caches spread their entries over several shards with their own lock, and each shard
+/// evicts its least recently used entry when it is full. Eviction is therefore least-recently-used
+/// per shard, which approximates a global LRU without a single lock shared by all threads.
+///
+/// With `with_thread_local_capacity`, every thread keeps a small copy of the results it used last
+/// in front of the shards, which answers repeated lookups without taking any lock.
+pub struct LruPathCache<K = (usize, usize)> {
+    shards: Vec<Mutex<LruShard<K>>>,
+    hasher: RandomState,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    id: u64,
+    /// Keeps the thread-local layers of the cache alive, see `LocalLayer::owner`.
+    alive: Arc<()>,
+    /// Incremented whenever results are removed or replaced, which outdates all thread-local
+    /// layers at once.
+    epoch: AtomicU64,
+    thread_local_capacity: usize,
+}
+
+struct LruShard<K> {
+    capacity: usize,
+    /// The stored results together with the tick of their last use.
+    entries: HashMap<K, (Option<Path>, u64)>,
+    /// The keys of all entries ordered by the tick of their last use.
+    recency: BTreeMap<u64, K>,
+    tick: u64,
+}
+
+impl<K> LruShard<K> {
+    /// Marks the entry as used now and returns its new tick.
+    fn touch(&mut self, old_tick: u64, key: K) -> u64 {
+        self.recency.remove(&old_tick);
+        self.tick += 1;
+        self.recency.insert(self.tick, key);
+        self.tick
+    }
+}
+
+impl<K: Eq + Hash + Clone> LruPathCache<K> {
+    /// Creates a cache holding at most `capacity` results. A capacity of 0 disables caching.
+    pub fn new(capacity: usize) -> Self {
+        let shard_count = (capacity / MIN_SHARD_CAPACITY).clamp(1, MAX_SHARDS);
+        let shards = (0..shard_count)
+            .map(|index| {
+                // Spread the remainder over the first shards so the capacities sum up exactly
+                let shard_capacity = capacity / shard_count + usize::from(index < capacity % shard_count);
+                Mutex::new(LruShard {
+                    capacity: shard_capacity,
+                    entries: HashMap::new(),
+                    recency: BTreeMap::new(),
+                    tick: 0,
+                })
+            })
+            .collect();
+
+        LruPathCache {
+            shards,
+            hasher: RandomState::new(),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            id: NEXT_CACHE_ID.fetch_add(1, Ordering::Relaxed),
+            alive: Arc::new(()),
+            epoch: AtomicU64::new(0),
+            thread_local_capacity: 0,
+        }
+    }
+
+    /// Adds a thread-local layer holding up to `capacity` results per thread in front of the
+    /// shared shards. Lookups check the layer of the current thread first, which needs no lock,
+    /// and results found in the shards or inserted are copied into it. A full layer is emptied
+    /// before the next result is copied in. 0, the default, disables the layer.
+    ///
+    /// Results served by a layer do not count as used for the eviction from the shards, so a
+    /// result may still be served by a layer after it was evicted. Removing or replacing results
+    /// outdates all layers.
+    pub fn with_thread_local_capacity(mut self, capacity: usize) -> Self {
+        self.thread_local_capacity = capacity.min(self.capacity());
+        self
+    }
+
+    /// The maximum number of results the cache holds.
+    pub fn capacity(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).capacity).sum()
+    }
+
+    /// The number of results currently stored.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).entries.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The fraction of lookups that found a stored result, or 0 if there were no lookups yet.
+    pub fn hit_rate(&self) -> f64 {
+        let hits = self.hits.load(Ordering::Relaxed);
+        let lookups = hits + self.misses.load(Ordering::Relaxed);
+        if lookups == 0 {
+            return 0.0;
+        }
+        hits as f64 / lookups as f64
+    }
+
+    /// Returns all stored results, within each shard ordered from least to most recently used,
+    /// so inserting them in order restores the eviction order.
+    pub fn entries(&self) -> Vec<(K, Option<Path>)> {
+        let mut entries = Vec::with_capacity(self.len());
+        for shard in &self.shards {
+            let shard = lock(shard);
+            entries.extend(shard.recency.values().map(|key| (key.clone(), shard.entries[key].0.clone())));
+        }
+        entries
+    }
+
+    /// Removes all results for which `keep` returns false.
+    pub fn retain<F>(&self, keep: F)
+    where
+        F: Fn(&K, &Option<Path>) -> bool,
+    {
+        for shard in &self.shards {
+            let mut shard = lock(shard);
+            let mut removed_ticks = Vec::new();
+            shard.entries.retain(|key, (path, tick)| {
+                let kept = keep(key, path);
+                if !kept {
+                    removed_ticks.push(*tick);
+                }
+                kept
+            });
+            for tick in removed_ticks {
+                shard.recency.remove(&tick);
+            }
+        }
+        self.epoch.fetch_add(1, Ordering::Release);
+    }
+
+    /// Removes all results.
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            // Swap the maps out so they are dropped after the lock is released
+            let (entries, recency) = {
+                let mut shard = lock(shard);
+                (std::mem::take(&mut shard.entries), std::mem::take(&mut shard.recency))
+            };
+            drop((entries, recency));
+        }
+        self.epoch.fetch_add(1, Ordering::Release);
+    }
+
+    /// Returns whether a result is stored for the key, without counting as a lookup or
+    /// changing the eviction order.
+    pub fn contains(&self, key: &K) -> bool {
+        lock(self.shard(key)).entries.contains_key(key)
+    }
+
+    /// The number of lookups that found a stored result.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// The number of lookups that found no stored result.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Resets the lookup statistics used by `hit_rate`.
+    pub fn reset_stats(&self) {
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+    }
+
+    fn shard(&self, key: &K) -> &Mutex<LruShard<K>> {
+        let index = self.hasher.hash_one(key) as usize % self.shards.len();
+        &self.shards[index]
+    }
+}
+
+impl<K: Eq + Hash + Clone + 'static> LruPathCache<K> {
+    /// Returns the result for the key from the thread-local layer of the current thread if it
+    /// was copied in the given epoch.
+    fn get_local(&self, key: &K, epoch: u64) -> Option<Option<Path>> {
+        if self.thread_local_capacity == 0 {
+            return None;
+        }
+        LOCAL_LAYERS.with_borrow(|layers| {
+            let layer = layers.get(&self.id).filter(|layer| layer.epoch == epoch)?;
+            layer.entries.downcast_ref::<HashMap<K, Option<Path>>>()?.get(key).cloned()
+        })
+    }
+
+    /// Copies the result read from the shards in the given epoch into the thread-local layer of
+    /// the current thread.
+    fn insert_local(&self, key: K, path: Option<Path>, epoch: u64) {
+        if self.thread_local_capacity == 0 {
+            return;
+        }
+        LOCAL_LAYERS.with_borrow_mut(|layers| {
+            if !layers.contains_key(&self.id) {
+                // Drop the layers of dropped caches before adding another one
+                layers.retain(|_, layer| layer.owner.strong_count() > 0);
+            }
+            let layer = layers.entry(self.id).or_insert_with(|| LocalLayer {
+                owner: Arc::downgrade(&self.alive),
+                epoch,
+                entries: Box::new(HashMap::<K, Option<Path>>::new()),
+            });
+            let Some(entries) = layer.entries.downcast_mut::<HashMap<K, Option<Path>>>() else {
+                return;
+            };
+            if layer.epoch != epoch || entries.len() >= self.thread_local_capacity {
+                entries.clear();
+                layer.epoch = epoch;
+            }
+            entries.insert(key, path);
+        });
+    }
+}
+
+fn lock<K>(shard: &Mutex<LruShard<K>>) -> MutexGuard<'_, LruShard<K>> {
+    // A panic while holding the lock cannot leave a shard inconsistent enough to matter
+    // for a cache, so keep using it
+    shard.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl<K: Eq + Hash + Clone + 'static> PathCache<K> for LruPathCache<K> {
+    fn get_path(&self, key: &K) -> Option<Option<Path>> {
+        // Read before the shards, so a result removed in between is never copied in as current
+        let epoch = self.epoch.load(Ordering::Acquire);
+        if let Some(path) = self.get_local(key, epoch) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Some(path);
+        }
+
+        let mut shard = lock(self.shard(key));
+        let Some(&(_, old_tick)) = shard.entries.get(key) else {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+            return None;
+        };
+        self.hits.fetch_add(1, Ordering::Relaxed);
+
+        let tick = shard.touch(old_tick, key.clone());
+        let entry = shard.entries.get_mut(key)?;
+        entry.1 = tick;
+        let path = entry.0.clone();
+        drop(shard);
+        self.insert_local(key.clone(), path.clone(), epoch);
+        Some(path)
+    }
+
+    fn insert_path(&self, key: K, path: Option<Path>) {
+        let epoch = self.epoch.load(Ordering::Acquire);
+        let mut shard = lock(self.shard(&key));
+        if shard.capacity == 0 {
+            return;
+        }
+
+        if let Some(&(_, old_tick)) = shard.entries.get(&key) {
+            let tick = shard.touch(old_tick, key.clone());
+            shard.entries.insert(key, (path, tick));
+            // Other threads may still hold the replaced result
+            self.epoch.fetch_add(1, Ordering::Release);
+            return;
+        }
+
+        if shard.entries.len() >= shard.capacity {
+            if let Some((_, evicted)) = shard.recency.pop_first() {
+                shard.entries.remove(&evicted);
+            }
+        }
+        shard.tick += 1;
+        let tick = shard.tick;
+        shard.recency.insert(tick, key.clone());
+        shard.entries.insert(key.clone(), (path.clone(), tick));
+        drop(shard);
+        self.insert_local(key, path, epoch);
+    }
+}
+
+/// Identifies the files of a `DiskPathCache`.
+const DISK_CACHE_MAGIC: &[u8; 4] = b"RPDC";
+
+/// The version of the `DiskPathCache` file format, incremented on incompatible changes.
+const DISK_CACHE_VERSION: u32 = 2;
+
+/// The header of a `DiskPathCache` file: the magic bytes, the format version and the
+/// `Graph::fingerprint` of the navmesh the paths belong to.
+type DiskCacheHeader = ([u8; 4], u32, u64);
+
+/// A record of a `DiskPathCache` file. `None` removes the result stored for the key before.
+type DiskCacheRecord = ((usize, usize), Option<Option<Vec<Node>>>);
+
+/// A cache that keeps its results in a file, so they survive restarts, see
+/// `RePathfinder::with_disk_cache`.
+///
+/// The file is a log of bincode records appended by every insertion and removal. Opening it
+/// reads the log once to find the latest record of every key, after which a lookup reads a
+/// single record, which takes well below a millisecond. The file only shrinks when the cache is
+/// cleared.
+///
+/// Failing to read or write the file after it was opened only loses results, lookups return
+/// `None` and insertions are dropped, since the searches can always be run again.
+pub struct DiskPathCache {
+    file: Mutex<DiskCacheFile>,
+}
+
+struct DiskCacheFile {
+    file: File,
+    /// The offset of the latest record of every stored key.
+    offsets: HashMap<(usize, usize), u64>,
+    /// The offset after the header, where the first record starts.
+    start: u64,
+    /// The offset after the last record, where the next one is appended.
+    end: u64,
+}
+
+impl DiskPathCache {
+    /// Opens the cache stored in `file` for the graph, or creates it if it does not exist. A
+    /// record cut off by a crash while it was written is dropped.
+    /// Returns an error if the file cannot be read or written, is no cache file or was written
+    /// for another graph, e.g. after the navmesh was edited or loaded with another `slope_factor`
+    /// or `mode`, since its paths may no longer be the shortest or even exist.
+    pub fn open(file: impl AsRef<std::path::Path>, graph: &Graph) -> Result<Self, RePathError> {
+        let node_count = graph.nodes.len();
+        let fingerprint = graph.fingerprint();
+        let mut file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(file)?;
+        let header: DiskCacheHeader = (*DISK_CACHE_MAGIC, DISK_CACHE_VERSION, fingerprint);
+        if file.metadata()?.len() == 0 {
+            bincode::serialize_into(&mut file, &header)?;
+            file.rewind()?;
+        }
+
+        let mut reader = BufReader::new(&mut file);
+        let (magic, version, written_for): DiskCacheHeader = bincode::deserialize_from(&mut reader)?;
+        if magic != *DISK_CACHE_MAGIC || version != DISK_CACHE_VERSION {
+            return Err(RePathError::SerializationError("Not a disk cache file of this version".to_string()));
+        }
+        if written_for != fingerprint {
+            return Err(RePathError::SerializationError(
+                "The disk cache was written for another navmesh or other settings".to_string(),
+            ));
+        }
+
+        let mut offsets = HashMap::new();
+        let start = reader.stream_position()?;
+        let mut end = start;
+        while let Ok(((start, goal), path)) = bincode::deserialize_from::<_, DiskCacheRecord>(&mut reader) {
+            let path_in_graph = path.iter().flatten().flatten().all(|node| node.id < node_count);
+            if start >= node_count || goal >= node_count || !path_in_graph {
+                return Err(RePathError::SerializationError(
+                    "The disk cache refers to nodes that are not in the navmesh".to_string(),
+                ));
+            }
+            match path {
+                Some(_) => offsets.insert((start, goal), end),
+                None => offsets.remove(&(start, goal)),
+            };
+            end = reader.stream_position()?;
+        }
+        // Drop the incomplete record, if any, so the next one is appended after complete ones
+        drop(reader);
+        file.set_len(end)?;
+
+        Ok(DiskPathCache {
+            file: Mutex::new(DiskCacheFile { file, offsets, start, end }),
+        })
+    }
+
+    /// The number of results currently stored.
+    pub fn len(&self) -> usize {
+        lock_file(&self.file).offsets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether a result is stored for the key, without reading it.
+    pub fn contains(&self, key: &(usize, usize)) -> bool {
+        lock_file(&self.file).offsets.contains_key(key)
+    }
+
+    /// Removes all results for which `keep` returns false.
+    pub fn retain<F>(&self, keep: F)
+    where
+        F: Fn(&(usize, usize), &Option<Path>) -> bool,
+    {
+        let mut file = lock_file(&self.file);
+        let keys: Vec<_> = file.offsets.keys().copied().collect();
+        for key in keys {
+            let kept = match file.read(key) {
+                Ok(path) => keep(&key, &path.map(Arc::new)),
+                Err(_) => false,
+            };
+            if !kept {
+                let _ = file.append(&(key, None));
+            }
+        }
+    }
+
+    /// Removes all results and empties the file.
+    pub fn clear(&self) {
+        let mut file = lock_file(&self.file);
+        file.offsets.clear();
+        if file.file.set_len(file.start).is_ok() {
+            file.end = file.start;
+        }
+    }
+}
+
+impl DiskCacheFile {
+    /// Reads the latest record of a stored key.
+    fn read(&mut self, key: (usize, usize)) -> Result<Option<Vec<Node>>, RePathError> {
+        let offset = self.offsets[&key];
+        self.file.seek(SeekFrom::Start(offset))?;
+        let (_, path): DiskCacheRecord = bincode::deserialize_from(BufReader::new(&mut self.file))?;
+        path.ok_or_else(|| RePathError::SerializationError("The disk cache record was removed".to_string()))
+    }
+
+    /// Appends a record and updates the offset of its key.
+    fn append(&mut self, record: &DiskCacheRecord) -> Result<(), RePathError> {
+        let bytes = bincode::serialize(record)?;
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&bytes)?;
+        match record.1 {
+            Some(_) => self.offsets.insert(record.0, self.end),
+            None => self.offsets.remove(&record.0),
+        };
+        self.end += bytes.len() as u64;
+        Ok(())
+    }
+}
+
+fn lock_file(file: &Mutex<DiskCacheFile>) -> MutexGuard<'_, DiskCacheFile> {
+    // Every record is complete before its offset is stored, so the file stays usable
+    file.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl PathCache for DiskPathCache {
+    fn get_path(&self, key: &(usize, usize)) -> Option<Option<Path>> {
+        let mut file = lock_file(&self.file);
+        if !file.offsets.contains_key(key) {
+            return None;
+        }
+        file.read(*key).ok().map(|path| path.map(Arc::new))
+    }
+
+    fn insert_path(&self, key: (usize, usize), path: Option<Path>) {
+        let path = path.map(|path| path.to_vec());
+        let _ = lock_file(&self.file).append(&(key, Some(path)));
+    }
+}
+
+/// Looks up results in `memory` first and then in `disk`, copying those found on disk into
+/// `memory`, and inserts results into both, see `RePathfinder::with_disk_cache`.
+pub(crate) struct WriteThroughCache<'a> {
+    memory: &'a LruPathCache,
+    disk: Option<&'a DiskPathCache>,
+}
+
+impl<'a> WriteThroughCache<'a> {
+    pub(crate) fn new(memory: &'a LruPathCache, disk: Option<&'a DiskPathCache>) -> Self {
+        WriteThroughCache { memory, disk }
+    }
+}
+
+impl PathCache for WriteThroughCache<'_> {
+    fn get_path(&self, key: &(usize, usize)) -> Option<Option<Path>> {
+        if let Some(path) = self.memory.get_path(key) {
+            return Some(path);
+        }
+        let path = self.disk?.get_path(key)?;
+        self.memory.insert_path(*key, path.clone());
+        Some(path)
+    }
+
+    fn insert_path(&self, key: (usize, usize), path: Option<Path>) {
+        if let Some(disk) = self.disk {
+            disk.insert_path(key, path.clone());
+        }
+        self.memory.insert_path(key, path);
+    }
+}
@@ -0,0 +1,337 @@
+use crate::edge::Edge;
+use crate::graph::Graph;
+use crate::Coord;
+
+/// A disjoint-set forest over node ids with path halving and union by size.
+struct UnionFind {
+    parents: Vec<usize>,
+    sizes: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(len: usize) -> Self {
+        UnionFind {
+            parents: (0..len).collect(),
+            sizes: vec![1; len],
+        }
+    }
+
+    fn find(&mut self, mut id: usize) -> usize {
+        while self.parents[id] != id {
+            self.parents[id] = self.parents[self.parents[id]];
+            id = self.parents[id];
+        }
+        id
+    }
+
+    /// Merges the sets of both ids and returns whether they were different sets.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (a, b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        let (small, large) = if self.sizes[a] < self.sizes[b] { (a, b) } else { (b, a) };
+        self.parents[small] = large;
+        self.sizes[large] += self.sizes[small];
+        true
+    }
+}
+
+/// Labels every node with the index of its connected component, numbering the components in the
+/// order of their smallest node id.
+fn label_components(graph: &Graph) -> Vec<usize> {
+    let mut union_find = UnionFind::new(graph.nodes.len());
+    for (from, edges) in graph.edges.iter().enumerate() {
+        for edge in edges {
+            union_find.union(from, edge.to);
+        }
+    }
+
+    let mut labels = vec![usize::MAX; graph.nodes.len()];
+    let mut root_labels = vec![usize::MAX; graph.nodes.len()];
+    let mut count = 0;
+    for (id, label) in labels.iter_mut().enumerate() {
+        let root = union_find.find(id);
+        if root_labels[root] == usize::MAX {
+            root_labels[root] = count;
+            count += 1;
+        }
+        *label = root_labels[root];
+    }
+    labels
+}
+
+/// Labels every node with the index of its strongly connected component using Tarjan's
+/// algorithm, numbering the components in the order of their smallest node id.
+pub(crate) fn label_strongly_connected_components(graph: &Graph) -> Vec<usize> {
+    const UNVISITED: usize = usize::MAX;
+    let node_count = graph.nodes.len();
+    let mut indices = vec![UNVISITED; node_count];
+    let mut low_links = vec![0; node_count];
+    let mut on_stack = vec![false; node_count];
+    let mut stack = Vec::new();
+    let mut labels = vec![0; node_count];
+    let mut label_count = 0;
+    let mut next_index = 0;
+
+    for root in 0..node_count {
+        if indices[root] != UNVISITED {
+            continue;
+        }
+
+        // An explicit call stack of nodes and the position of the next edge to follow, since
+        // recursion would overflow the stack on large navmeshes
+        let mut calls = vec![(root, 0)];
+        indices[root] = next_index;
+        low_links[root] = next_index;
+        next_index += 1;
+        stack.push(root);
+        on_stack[root] = true;
+
+        while let Some((node, edge_index)) = calls.last_mut() {
+            let node = *node;
+            if let Some(edge) = graph.edges[node].get(*edge_index) {
+                *edge_index += 1;
+                let next = edge.to;
+                if indices[next] == UNVISITED {
+                    indices[next] = next_index;
+                    low_links[next] = next_index;
+                    next_index += 1;
+                    stack.push(next);
+                    on_stack[next] = true;
+                    calls.push((next, 0));
+                } else if on_stack[next] {
+                    low_links[node] = low_links[node].min(indices[next]);
+                }
+                continue;
+            }
+
+            calls.pop();
+            if let Some(&(parent, _)) = calls.last() {
+                low_links[parent] = low_links[parent].min(low_links[node]);
+            }
+            if low_links[node] == indices[node] {
+                // The node is the root of a component made up of the nodes above it on the stack
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    labels[member] = label_count;
+                    if member == node {
+                        break;
+                    }
+                }
+                label_count += 1;
+            }
+        }
+    }
+
+    // Tarjan's algorithm finds the components in reverse topological order, renumber them
+    let mut renumbered = vec![usize::MAX; label_count];
+    let mut count = 0;
+    for label in &mut labels {
+        if renumbered[*label] == usize::MAX {
+            renumbered[*label] = count;
+            count += 1;
+        }
+        *label = renumbered[*label];
+    }
+    labels
+}
+
+/// The result of a depth-first search over the undirected view of a graph, see
+/// `Graph::articulation_points` and `Graph::bridges`.
+struct CutAnalysis {
+    articulation_points: Vec<usize>,
+    bridges: Vec<(usize, usize)>,
+}
+
+/// Finds the articulation points and bridges with Tarjan's algorithm, treating every edge as
+/// two-way and merging parallel edges.
+fn find_cuts(graph: &Graph) -> CutAnalysis {
+    const UNVISITED: usize = usize::MAX;
+    let node_count = graph.nodes.len();
+
+    let mut neighbors = vec![Vec::new(); node_count];
+    for (from, edges) in graph.edges.iter().enumerate() {
+        for edge in edges.iter().filter(|edge| edge.to != from) {
+            neighbors[from].push(edge.to);
+            neighbors[edge.to].push(from);
+        }
+    }
+    for list in &mut neighbors {
+        list.sort_unstable();
+        list.dedup();
+    }
+
+    let mut discovery = vec![UNVISITED; node_count];
+    let mut low = vec![0; node_count];
+    let mut is_articulation_point = vec![false; node_count];
+    let mut bridges = Vec::new();
+    let mut next_discovery = 0;
+
+    for root in 0..node_count {
+        if discovery[root] != UNVISITED {
+            continue;
+        }
+        discovery[root] = next_discovery;
+        low[root] = next_discovery;
+        next_discovery += 1;
+        let mut root_children = 0;
+
+        // An explicit call stack of nodes and the position of the next neighbor to visit
+        let mut calls = vec![(root, 0)];
+        while let Some(&(node, neighbor_index)) = calls.last() {
+            let depth = calls.len() - 1;
+            let parent = depth.checked_sub(1).map(|index| calls[index].0);
+            let Some(&next) = neighbors[node].get(neighbor_index) else {
+                calls.pop();
+                if let Some(parent) = parent {
+                    low[parent] = low[parent].min(low[node]);
+                    if low[node] > discovery[parent] {
+                        bridges.push((parent.min(node), parent.max(node)));
+                    }
+                    if parent != root && low[node] >= discovery[parent] {
+                        is_articulation_point[parent] = true;
+                    }
+                }
+                continue;
+            };
+            calls[depth].1 += 1;
+
+            if discovery[next] == UNVISITED {
+                discovery[next] = next_discovery;
+                low[next] = next_discovery;
+                next_discovery += 1;
+                if node == root {
+                    root_children += 1;
+                }
+                calls.push((next, 0));
+            } else if Some(next) != parent {
+                low[node] = low[node].min(discovery[next]);
+            }
+        }
+
+        // The root separates its subtrees only if it has more than one
+        is_articulation_point[root] = root_children > 1;
+    }
+
+    bridges.sort_unstable();
+    CutAnalysis {
+        articulation_points: (0..node_count).filter(|&id| is_articulation_point[id]).collect(),
+        bridges,
+    }
+}
+
+/// Groups node ids by their component label, which must number the components in the order of
+/// their smallest node id.
+fn group_by_label(labels: &[usize]) -> Vec<Vec<usize>> {
+    let mut components: Vec<Vec<usize>> = Vec::new();
+    for (id, &label) in labels.iter().enumerate() {
+        if label == components.len() {
+            components.push(Vec::new());
+        }
+        components[label].push(id);
+    }
+    components
+}
+
+impl Graph {
+    /// The component label of every node, built on first use and cached on the graph.
+    fn component_labels(&self) -> &[usize] {
+        self.components.get_or_init(|| label_components(self))
+    }
+
+    /// Returns the connected components of the graph, each as a sorted list of node ids, ordered
+    /// by their smallest node id. Edges are treated as two-way, so a one-way edge connects its
+    /// nodes as well. A fully connected navmesh has a single component.
+    pub fn connected_components(&self) -> Vec<Vec<usize>> {
+        group_by_label(self.component_labels())
+    }
+
+    /// Returns the index of the component containing the node in `connected_components`.
+    pub fn component_of(&self, node_id: usize) -> usize {
+        self.component_labels()[node_id]
+    }
+
+    /// Returns whether the nodes are in the same connected component, which is necessary for a
+    /// path between them to exist. Only the first call after a change of the graph is O(n).
+    pub fn are_connected(&self, a: usize, b: usize) -> bool {
+        let labels = self.component_labels();
+        labels[a] == labels[b]
+    }
+
+    /// Returns the strongly connected components of the graph, each as a sorted list of node ids,
+    /// ordered by their smallest node id. Unlike `connected_components`, edges are followed only
+    /// in their direction, so every node of a component can reach every other one.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<usize>> {
+        group_by_label(&label_strongly_connected_components(self))
+    }
+
+    /// Returns whether every node can reach every other node. An empty graph counts as strongly
+    /// connected.
+    pub fn is_strongly_connected(&self) -> bool {
+        label_strongly_connected_components(self).iter().all(|&label| label == 0)
+    }
+
+    /// Returns the ids of the nodes whose removal splits their connected component, in ascending
+    /// order, e.g. for a map editor to highlight critical nodes. Edges are treated as two-way.
+    pub fn articulation_points(&self) -> Vec<usize> {
+        find_cuts(self).articulation_points
+    }
+
+    /// Returns the node pairs connected by an edge whose removal splits their connected
+    /// component, each as `(smaller id, larger id)` and in ascending order. Edges are treated as
+    /// two-way, so removing a bridge means removing the edges in both directions.
+    pub fn bridges(&self) -> Vec<(usize, usize)> {
+        find_cuts(self).bridges
+    }
+
+    /// Returns a copy of the graph with only the edges of a minimum spanning tree, the cheapest
+    /// set of edges that keeps every connected component connected, e.g. to plan a road network
+    /// or to inspect how a navmesh hangs together. Edges are treated as two-way and compared by
+    /// their `cost`, so the tree has every chosen edge in both directions with the data of the
+    /// cheapest edge between its nodes. A graph with several components gets a spanning forest
+    /// with one tree per component.
+    ///
+    /// Uses Kruskal's algorithm with a union-find, in O(|E| log |E|).
+    pub fn minimum_spanning_tree(&self) -> Graph {
+        let mut tree = Graph::new();
+        for node in &self.nodes {
+            tree.add_node(node.clone());
+        }
+        for (from, edge) in self.minimum_spanning_tree_edges() {
+            let to = edge.to;
+            tree.edges[to].push(Edge { to: from, ..edge.clone() });
+            tree.edges[from].push(edge);
+        }
+        tree
+    }
+
+    /// Returns the total cost of the edges of `minimum_spanning_tree`, counting every edge once.
+    pub fn minimum_spanning_tree_cost(&self) -> Coord {
+        self.minimum_spanning_tree_edges().iter().map(|(_, edge)| edge.cost).sum()
+    }
+
+    /// The edges chosen by Kruskal's algorithm for `minimum_spanning_tree`, each once with the
+    /// node it starts at, in ascending order of the cost.
+    fn minimum_spanning_tree_edges(&self) -> Vec<(usize, Edge)> {
+        let mut candidates: Vec<(usize, &Edge)> = self
+            .edges
+            .iter()
+            .enumerate()
+            .flat_map(|(from, edges)| edges.iter().map(move |edge| (from, edge)))
+            .filter(|&(from, edge)| from != edge.to)
+            .collect();
+        // Ties are broken by the nodes, so the tree does not depend on the order of the edges
+        candidates.sort_by(|&(a_from, a), &(b_from, b)| {
+            let nodes = |from: usize, to: usize| (from.min(to), from.max(to));
+            a.cost.total_cmp(&b.cost).then(nodes(a_from, a.to).cmp(&nodes(b_from, b.to)))
+        });
+
+        let mut union_find = UnionFind::new(self.nodes.len());
+        candidates
+            .into_iter()
+            .filter(|&(from, edge)| union_find.union(from, edge.to))
+            .map(|(from, edge)| (from, edge.clone()))
+            .collect()
+    }
+}
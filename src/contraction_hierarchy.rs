@@ -0,0 +1,278 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use crate::graph::{Graph, State};
+use crate::node::Node;
+use crate::path::Path;
+use crate::Coord;
+
+/// The number of nodes a witness search settles before giving up and keeping the shortcut, which
+/// bounds the preprocessing time at the price of a few unnecessary shortcuts.
+const WITNESS_SEARCH_LIMIT: usize = 500;
+
+/// An edge of the contraction hierarchy. Shortcuts replace the two edges through the node that
+/// was contracted when they were added, `middle`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct ChEdge {
+    to: usize,
+    cost: Coord,
+    middle: Option<usize>,
+}
+
+/// A graph preprocessed with contraction hierarchies for fast shortest path queries on navmeshes
+/// that do not change, such as large static road or terrain networks.
+///
+/// `build` contracts the nodes one by one in increasing order of importance, adding a shortcut
+/// edge between two neighbors of a contracted node wherever the path through it is the only
+/// cheapest one. `query` then runs a bidirectional Dijkstra search that only moves up the
+/// hierarchy, which settles a tiny fraction of the nodes of a full search.
+///
+/// The hierarchy keeps its own copy of the nodes and edge costs, so it does not notice later
+/// changes to the graph. It can be serialized, e.g. with bincode, to skip the preprocessing on
+/// the next start.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContractionHierarchy {
+    nodes: Vec<Node>,
+    /// The edges from every node to the nodes contracted after it.
+    upward: Vec<Vec<ChEdge>>,
+    /// The edges into every node from the nodes contracted after it, with `to` pointing to the
+    /// node they come from.
+    downward: Vec<Vec<ChEdge>>,
+}
+
+/// The remaining graph during the contraction, with the cheapest edge to and from every
+/// neighbor.
+struct Contraction {
+    successors: Vec<HashMap<usize, ChEdge>>,
+    predecessors: Vec<HashMap<usize, ChEdge>>,
+    contracted: Vec<bool>,
+    /// The number of contracted neighbors of every node, which spreads the contraction evenly.
+    contracted_neighbors: Vec<usize>,
+}
+
+impl Contraction {
+    fn new(graph: &Graph) -> Self {
+        let node_count = graph.nodes.len();
+        let mut contraction = Contraction {
+            successors: vec![HashMap::new(); node_count],
+            predecessors: vec![HashMap::new(); node_count],
+            contracted: vec![false; node_count],
+            contracted_neighbors: vec![0; node_count],
+        };
+        for (from, edges) in graph.edges.iter().enumerate() {
+            for edge in edges.iter().filter(|edge| edge.to != from) {
+                let cost = graph.traversal_cost(edge);
+                contraction.insert_edge(from, ChEdge { to: edge.to, cost, middle: None });
+            }
+        }
+        contraction
+    }
+
+    /// Adds an edge unless there is a cheaper one between the same nodes already.
+    fn insert_edge(&mut self, from: usize, edge: ChEdge) {
+        if self.successors[from].get(&edge.to).is_some_and(|existing| existing.cost <= edge.cost) {
+            return;
+        }
+        self.successors[from].insert(edge.to, edge);
+        self.predecessors[edge.to].insert(from, ChEdge { to: from, ..edge });
+    }
+
+    /// Returns the shortcuts needed to contract `node`: every path through it between two of its
+    /// remaining neighbors that has no witness, a path at most as cheap that avoids it.
+    fn shortcuts(&self, node: usize) -> Vec<(usize, ChEdge)> {
+        let mut shortcuts = Vec::new();
+        for incoming in self.predecessors[node].values() {
+            let targets: Vec<&ChEdge> = self.successors[node].values().filter(|edge| edge.to != incoming.to).collect();
+            let Some(max_cost) = targets.iter().map(|edge| incoming.cost + edge.cost).reduce(Coord::max) else {
+                continue;
+            };
+            let dist = self.witness_search(incoming.to, node, max_cost);
+            for outgoing in targets {
+                let cost = incoming.cost + outgoing.cost;
+                if dist.get(&outgoing.to).is_none_or(|&witness| witness > cost) {
+                    shortcuts.push((incoming.to, ChEdge { to: outgoing.to, cost, middle: Some(node) }));
+                }
+            }
+        }
+        shortcuts
+    }
+
+    /// Runs Dijkstra's algorithm from `source` on the remaining graph without `excluded` until
+    /// the costs exceed `max_cost` or `WITNESS_SEARCH_LIMIT` nodes are settled.
+    fn witness_search(&self, source: usize, excluded: usize, max_cost: Coord) -> HashMap<usize, Coord> {
+        let mut dist = HashMap::from([(source, 0.0)]);
+        let mut open_set = BinaryHeap::from([State { cost: 0.0, position: source }]);
+        let mut settled = 0;
+        while let Some(State { cost, position }) = open_set.pop() {
+            if cost > dist[&position] {
+                continue;
+            }
+            settled += 1;
+            if cost > max_cost || settled > WITNESS_SEARCH_LIMIT {
+                break;
+            }
+            for edge in self.successors[position].values().filter(|edge| edge.to != excluded) {
+                let tentative_dist = cost + edge.cost;
+                if dist.get(&edge.to).is_none_or(|&known| tentative_dist < known) {
+                    dist.insert(edge.to, tentative_dist);
+                    open_set.push(State { cost: tentative_dist, position: edge.to });
+                }
+            }
+        }
+        dist
+    }
+
+    /// The edge difference of contracting `node`, lower values are contracted first.
+    fn priority(&self, node: usize) -> isize {
+        let removed = self.successors[node].len() + self.predecessors[node].len();
+        self.shortcuts(node).len() as isize - removed as isize + self.contracted_neighbors[node] as isize
+    }
+
+    /// Removes `node` from the remaining graph, adds its shortcuts and returns its edges to and
+    /// from the remaining nodes.
+    fn contract(&mut self, node: usize) -> (Vec<ChEdge>, Vec<ChEdge>) {
+        for (from, shortcut) in self.shortcuts(node) {
+            self.insert_edge(from, shortcut);
+        }
+        self.contracted[node] = true;
+        let upward: Vec<ChEdge> = self.successors[node].drain().map(|(_, edge)| edge).collect();
+        let downward: Vec<ChEdge> = self.predecessors[node].drain().map(|(_, edge)| edge).collect();
+        for edge in &upward {
+            self.predecessors[edge.to].remove(&node);
+            self.contracted_neighbors[edge.to] += 1;
+        }
+        for edge in &downward {
+            self.successors[edge.to].remove(&node);
+            self.contracted_neighbors[edge.to] += 1;
+        }
+        (upward, downward)
+    }
+}
+
+impl ContractionHierarchy {
+    /// Preprocesses the graph, which takes a while on large graphs but only has to be done once,
+    /// see `ContractionHierarchy`. Edge costs include the node weights like `Graph::traversal_cost`.
+    pub fn build(graph: &Graph) -> ContractionHierarchy {
+        let node_count = graph.nodes.len();
+        let mut contraction = Contraction::new(graph);
+        // The node with the lowest priority comes first
+        let mut queue: BinaryHeap<Reverse<(isize, usize)>> =
+            (0..node_count).map(|node| Reverse((contraction.priority(node), node))).collect();
+        let mut upward = vec![Vec::new(); node_count];
+        let mut downward = vec![Vec::new(); node_count];
+
+        while let Some(Reverse((priority, node))) = queue.pop() {
+            if contraction.contracted[node] {
+                continue;
+            }
+            // Priorities change as neighbors are contracted, so they are updated lazily
+            let current = contraction.priority(node);
+            if current > priority {
+                queue.push(Reverse((current, node)));
+                continue;
+            }
+            (upward[node], downward[node]) = contraction.contract(node);
+        }
+
+        ContractionHierarchy {
+            nodes: graph.nodes.clone(),
+            upward,
+            downward,
+        }
+    }
+
+    /// Returns the number of shortcut edges added by `build`.
+    pub fn shortcut_count(&self) -> usize {
+        self.upward
+            .iter()
+            .chain(&self.downward)
+            .flatten()
+            .filter(|edge| edge.middle.is_some())
+            .count()
+    }
+
+    /// Finds the cheapest path from start to goal with a bidirectional Dijkstra search over the
+    /// hierarchy and expands the shortcuts on it, or returns `None` if there is none. Panics if a
+    /// node id is not in the graph the hierarchy was built from.
+    pub fn query(&self, start: usize, goal: usize) -> Option<Path> {
+        let node_count = self.nodes.len();
+        assert!(start < node_count && goal < node_count, "Node id out of range");
+        // The distances and the edge used to reach every node from both sides
+        let mut dist = [vec![Coord::INFINITY; node_count], vec![Coord::INFINITY; node_count]];
+        let mut parents: [Vec<Option<(usize, ChEdge)>>; 2] = [vec![None; node_count], vec![None; node_count]];
+        let mut open_sets = [BinaryHeap::new(), BinaryHeap::new()];
+        let edges = [&self.upward, &self.downward];
+        for (side, source) in [start, goal].into_iter().enumerate() {
+            dist[side][source] = 0.0;
+            open_sets[side].push(State { cost: 0.0, position: source });
+        }
+
+        let mut best: Option<(Coord, usize)> = (start == goal).then_some((0.0, start));
+        loop {
+            // Continue on the side with the cheaper next node, until no side can improve the best
+            let side = match (open_sets[0].peek(), open_sets[1].peek()) {
+                (Some(forward), Some(backward)) => usize::from(backward.cost < forward.cost),
+                (Some(_), None) => 0,
+                (None, Some(_)) => 1,
+                (None, None) => break,
+            };
+            let State { cost, position } = open_sets[side].pop().unwrap();
+            if best.is_some_and(|(best_cost, _)| cost >= best_cost) {
+                open_sets[side].clear();
+                continue;
+            }
+            if cost > dist[side][position] {
+                continue;
+            }
+            for edge in &edges[side][position] {
+                let tentative_dist = cost + edge.cost;
+                if tentative_dist < dist[side][edge.to] {
+                    dist[side][edge.to] = tentative_dist;
+                    parents[side][edge.to] = Some((position, *edge));
+                    open_sets[side].push(State { cost: tentative_dist, position: edge.to });
+                    let total = tentative_dist + dist[1 - side][edge.to];
+                    if best.is_none_or(|(best_cost, _)| total < best_cost) {
+                        best = Some((total, edge.to));
+                    }
+                }
+            }
+        }
+
+        let (_, meeting) = best?;
+        let mut path = vec![start];
+        let mut forward = Vec::new();
+        let mut current = meeting;
+        while let Some((previous, edge)) = parents[0][current] {
+            forward.push((previous, edge));
+            current = previous;
+        }
+        for (previous, edge) in forward.into_iter().rev() {
+            self.unpack(previous, edge, &mut path);
+        }
+        let mut current = meeting;
+        while let Some((next, edge)) = parents[1][current] {
+            // Backward edges point to the node they come from
+            self.unpack(current, ChEdge { to: next, ..edge }, &mut path);
+            current = next;
+        }
+        Some(Arc::new(path.into_iter().map(|id| self.nodes[id].clone()).collect()))
+    }
+
+    /// Appends the nodes after `from` of the path the edge stands for to `path`.
+    fn unpack(&self, from: usize, edge: ChEdge, path: &mut Vec<usize>) {
+        let mut stack = vec![(from, edge)];
+        while let Some((from, edge)) = stack.pop() {
+            let Some(middle) = edge.middle else {
+                path.push(edge.to);
+                continue;
+            };
+            // Both halves are edges of the middle node, which was contracted before their ends
+            let find = |edges: &[ChEdge], to: usize| *edges.iter().find(|edge| edge.to == to).unwrap();
+            let first = find(&self.downward[middle], from);
+            let second = find(&self.upward[middle], edge.to);
+            stack.push((middle, second));
+            stack.push((from, ChEdge { to: middle, ..first }));
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::sync::Arc;
+use crate::graph::Graph;
+use crate::path::Path;
+use crate::Coord;
+
+/// Incremental path planner based on D* Lite.
+///
+/// D* Lite searches backward from the goal and keeps its search state between queries. When edge
+/// costs change through `update_edge`, only the nodes whose distance to the goal is affected are
+/// re-expanded on the next `find_path`, which is much cheaper than running A* from scratch.
+///
+/// The planner keeps its own copy of the edge costs, the graph itself is never modified.
+pub struct DStarLite<'a> {
+    graph: &'a Graph,
+    start: usize,
+    goal: usize,
+    /// Outgoing edges of every node with the cheapest cost to each neighbor.
+    successors: Vec<Vec<(usize, Coord)>>,
+    /// Incoming edges of every node with the cheapest cost from each neighbor.
+    predecessors: Vec<Vec<(usize, Coord)>>,
+    g: Vec<Coord>,
+    rhs: Vec<Coord>,
+    /// The current key of every node in the queue, stale heap entries are skipped.
+    queued_keys: Vec<Option<Key>>,
+    queue: BinaryHeap<QueueEntry>,
+}
+
+impl<'a> DStarLite<'a> {
+    /// Creates a planner for paths from start to goal on the given graph.
+    pub fn new(graph: &'a Graph, start: usize, goal: usize) -> Self {
+        let num_nodes = graph.nodes.len();
+        let mut successors: Vec<Vec<(usize, Coord)>> = vec![Vec::new(); num_nodes];
+        let mut predecessors: Vec<Vec<(usize, Coord)>> = vec![Vec::new(); num_nodes];
+
+        for (from, edges) in graph.edges.iter().enumerate() {
+            for edge in edges {
+                let edge_cost = graph.traversal_cost(edge);
+                match successors[from].iter_mut().find(|(to, _)| *to == edge.to) {
+                    Some((_, cost)) => *cost = cost.min(edge_cost),
+                    None => successors[from].push((edge.to, edge_cost)),
+                }
+            }
+        }
+        for (from, edges) in successors.iter().enumerate() {
+            for &(to, cost) in edges {
+                predecessors[to].push((from, cost));
+            }
+        }
+
+        let mut planner = DStarLite {
+            graph,
+            start,
+            goal,
+            successors,
+            predecessors,
+            g: vec![Coord::INFINITY; num_nodes],
+            rhs: vec![Coord::INFINITY; num_nodes],
+            queued_keys: vec![None; num_nodes],
+            queue: BinaryHeap::new(),
+        };
+
+        planner.rhs[goal] = 0.0;
+        let key = planner.calculate_key(goal);
+        planner.push(goal, key);
+
+        planner
+    }
+
+    /// Returns the cheapest path from start to goal under the current edge costs.
+    /// Only the part of the search affected by edge updates since the last call is recomputed.
+    pub fn find_path(&mut self) -> Option<Path> {
+        self.compute_shortest_path();
+
+        if self.rhs[self.start].is_infinite() {
+            return None;
+        }
+
+        // Follow the cheapest successor from start, guarding against cycles of equal cost
+        let mut total_path = vec![self.graph.nodes[self.start].clone()];
+        let mut current = self.start;
+        while current != self.goal {
+            if total_path.len() > self.graph.nodes.len() {
+                return None;
+            }
+            let (next, _) = self.successors[current]
+                .iter()
+                .map(|&(to, cost)| (to, cost + self.g[to]))
+                .min_by(|a, b| a.1.total_cmp(&b.1))?;
+            total_path.push(self.graph.nodes[next].clone());
+            current = next;
+        }
+
+        Some(Arc::new(total_path))
+    }
+
+    /// Changes the cost of the edge from `from` to `to`. Unknown edges are ignored.
+    /// The affected nodes are queued for replanning on the next `find_path`.
+    pub fn update_edge(&mut self, from: usize, to: usize, new_cost: Coord) {
+        let Some(old_cost) = self.successors[from]
+            .iter_mut()
+            .find(|(id, _)| *id == to)
+            .map(|(_, cost)| std::mem::replace(cost, new_cost))
+        else {
+            return;
+        };
+        if let Some((_, cost)) = self.predecessors[to].iter_mut().find(|(id, _)| *id == from) {
+            *cost = new_cost;
+        }
+
+        if from != self.goal {
+            if old_cost > new_cost {
+                self.rhs[from] = self.rhs[from].min(new_cost + self.g[to]);
+            } else if self.rhs[from] == old_cost + self.g[to] {
+                self.rhs[from] = self.best_successor_cost(from);
+            }
+        }
+        self.update_vertex(from);
+    }
+
+    fn compute_shortest_path(&mut self) {
+        while let Some(&QueueEntry { key: old_key, node }) = self.queue.peek() {
+            if self.queued_keys[node] != Some(old_key) {
+                // Stale entry of a node that was removed or re-queued with another key
+                self.queue.pop();
+                continue;
+            }
+
+            let start_key = self.calculate_key(self.start);
+            if old_key >= start_key && self.rhs[self.start] <= self.g[self.start] {
+                break;
+            }
+
+            let new_key = self.calculate_key(node);
+            if old_key < new_key {
+                self.push(node, new_key);
+            } else if self.g[node] > self.rhs[node] {
+                self.g[node] = self.rhs[node];
+                self.remove(node);
+                for index in 0..self.predecessors[node].len() {
+                    let (predecessor, cost) = self.predecessors[node][index];
+                    if predecessor != self.goal {
+                        self.rhs[predecessor] = self.rhs[predecessor].min(cost + self.g[node]);
+                    }
+                    self.update_vertex(predecessor);
+                }
+            } else {
+                let old_g = self.g[node];
+                self.g[node] = Coord::INFINITY;
+                for index in 0..self.predecessors[node].len() {
+                    let (predecessor, cost) = self.predecessors[node][index];
+                    if predecessor != self.goal && self.rhs[predecessor] == cost + old_g {
+                        self.rhs[predecessor] = self.best_successor_cost(predecessor);
+                    }
+                    self.update_vertex(predecessor);
+                }
+                if node != self.goal {
+                    self.rhs[node] = self.best_successor_cost(node);
+                }
+                self.update_vertex(node);
+            }
+        }
+    }
+
+    /// The cheapest cost of reaching the goal through any successor of `node`.
+    fn best_successor_cost(&self, node: usize) -> Coord {
+        self.successors[node]
+            .iter()
+            .map(|&(to, cost)| cost + self.g[to])
+            .fold(Coord::INFINITY, Coord::min)
+    }
+
+    fn update_vertex(&mut self, node: usize) {
+        if self.g[node] != self.rhs[node] {
+            let key = self.calculate_key(node);
+            self.push(node, key);
+        } else {
+            self.remove(node);
+        }
+    }
+
+    fn calculate_key(&self, node: usize) -> Key {
+        let cost = self.g[node].min(self.rhs[node]);
+        Key(cost + self.graph.heuristic(self.start, node), cost)
+    }
+
+    fn push(&mut self, node: usize, key: Key) {
+        self.queued_keys[node] = Some(key);
+        self.queue.push(QueueEntry { key, node });
+    }
+
+    fn remove(&mut self, node: usize) {
+        self.queued_keys[node] = None;
+    }
+}
+
+impl Graph {
+    /// Creates a D* Lite planner for incremental replanning between start and goal,
+    /// see `DStarLite`.
+    pub fn d_star_lite(&self, start: usize, goal: usize) -> DStarLite<'_> {
+        DStarLite::new(self, start, goal)
+    }
+}
+
+/// The D* Lite priority, compared lexicographically.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Key(Coord, Coord);
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.0.total_cmp(&other.0).then(self.1.total_cmp(&other.1)))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct QueueEntry {
+    key: Key,
+    node: usize,
+}
+
+impl Ord for QueueEntry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // Reversed so that the binary heap pops the smallest key first
+        other.key.partial_cmp(&self.key).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for QueueEntry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for QueueEntry {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl Eq for QueueEntry {}
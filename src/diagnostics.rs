@@ -0,0 +1,105 @@
+use std::fmt;
+use serde::Serialize;
+use crate::graph::Graph;
+use crate::Coord;
+
+/// The number of edges listed in `DiagnosticReport::most_expensive_edges`.
+pub const MOST_EXPENSIVE_EDGES: usize = 10;
+
+/// An edge listed in a `DiagnosticReport`.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EdgeSummary {
+    pub from: usize,
+    pub to: usize,
+    pub cost: Coord,
+}
+
+/// An overview of the state of a navmesh and the cache, as returned by
+/// `RePathfinder::diagnostic_report`. Printing it gives a human-readable summary, serializing it
+/// gives the same fields as JSON.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiagnosticReport {
+    pub node_count: usize,
+    /// The number of directed edges, an edge in both directions counts twice.
+    pub edge_count: usize,
+    /// The nodes without an edge to or from any other node.
+    pub isolated_node_count: usize,
+    /// The number of `Graph::connected_components`, including the isolated nodes.
+    pub component_count: usize,
+    /// See `Graph::density`.
+    pub density: Coord,
+    /// The lowest edge cost, or 0 without edges.
+    pub min_edge_cost: Coord,
+    /// The highest edge cost, or 0 without edges.
+    pub max_edge_cost: Coord,
+    /// The average edge cost, or 0 without edges.
+    pub average_edge_cost: Coord,
+    /// How full the path cache is, between 0 and 100.
+    pub cache_warm_percentage: f64,
+    /// The `MOST_EXPENSIVE_EDGES` edges with the highest cost, the most expensive first. Edges of
+    /// the same cost are ordered by their nodes.
+    pub most_expensive_edges: Vec<EdgeSummary>,
+}
+
+impl DiagnosticReport {
+    /// Collects the statistics of the graph, with the given cache fill level in percent.
+    pub(crate) fn new(graph: &Graph, cache_warm_percentage: f64) -> Self {
+        let mut edges: Vec<EdgeSummary> = graph
+            .edges
+            .iter()
+            .enumerate()
+            .flat_map(|(from, edges)| edges.iter().map(move |edge| EdgeSummary { from, to: edge.to, cost: edge.cost }))
+            .collect();
+        let edge_count = edges.len();
+        let (min_edge_cost, max_edge_cost, average_edge_cost) = if edges.is_empty() {
+            (0.0, 0.0, 0.0)
+        } else {
+            let costs = edges.iter().map(|edge| edge.cost);
+            (
+                costs.clone().fold(Coord::INFINITY, Coord::min),
+                costs.clone().fold(Coord::NEG_INFINITY, Coord::max),
+                costs.sum::<Coord>() / edge_count as Coord,
+            )
+        };
+        edges.sort_by(|a, b| b.cost.total_cmp(&a.cost).then((a.from, a.to).cmp(&(b.from, b.to))));
+        edges.truncate(MOST_EXPENSIVE_EDGES);
+
+        let components = graph.connected_components();
+        DiagnosticReport {
+            node_count: graph.nodes.len(),
+            edge_count,
+            isolated_node_count: components
+                .iter()
+                .filter(|component| component.len() == 1)
+                .count(),
+            component_count: components.len(),
+            density: graph.density(),
+            min_edge_cost,
+            max_edge_cost,
+            average_edge_cost,
+            cache_warm_percentage,
+            most_expensive_edges: edges,
+        }
+    }
+}
+
+impl fmt::Display for DiagnosticReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Nodes: {}", self.node_count)?;
+        writeln!(f, "Edges: {}", self.edge_count)?;
+        writeln!(f, "Isolated nodes: {}", self.isolated_node_count)?;
+        writeln!(f, "Connected components: {}", self.component_count)?;
+        writeln!(f, "Density: {:.4}", self.density)?;
+        writeln!(
+            f,
+            "Edge cost: min {}, max {}, average {:.2}",
+            self.min_edge_cost, self.max_edge_cost, self.average_edge_cost
+        )?;
+        writeln!(f, "Cache warm: {:.1}%", self.cache_warm_percentage)?;
+        write!(f, "Most expensive edges:")?;
+        for edge in &self.most_expensive_edges {
+            write!(f, "\n  {} -> {}: {}", edge.from, edge.to, edge.cost)?;
+        }
+        Ok(())
+    }
+}
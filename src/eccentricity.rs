@@ -0,0 +1,138 @@
+use std::collections::BinaryHeap;
+use rand::seq::SliceRandom;
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+use crate::edge::Edge;
+use crate::graph::{Graph, State};
+use crate::Coord;
+
+/// Graphs with more nodes than this estimate the eccentricities in `radius`, `center_node` and
+/// `peripheral_nodes` from `SAMPLED_SOURCES` random nodes instead of computing them exactly.
+const EXACT_ECCENTRICITY_LIMIT: usize = 2000;
+
+/// The number of random nodes the eccentricities of large graphs are estimated from.
+const SAMPLED_SOURCES: usize = 64;
+
+/// Returns the cost of the cheapest path from every node to `target`, or infinity if there is
+/// none, by running Dijkstra's algorithm from `target` over the reversed edges.
+pub(crate) fn distances_to(graph: &Graph, reverse_edges: &[Vec<Edge>], target: usize) -> Vec<Coord> {
+    let mut dist = vec![Coord::INFINITY; graph.nodes.len()];
+    let mut open_set = BinaryHeap::new();
+    dist[target] = 0.0;
+    open_set.push(State { cost: 0.0, position: target });
+
+    while let Some(State { cost, position: current }) = open_set.pop() {
+        if cost > dist[current] {
+            continue;
+        }
+        for edge in &reverse_edges[current] {
+            // Reversed edges enter the current node in the original graph
+            let tentative_dist = cost + edge.cost * graph.nodes[current].weight;
+            if tentative_dist < dist[edge.to] {
+                dist[edge.to] = tentative_dist;
+                open_set.push(State { cost: tentative_dist, position: edge.to });
+            }
+        }
+    }
+    dist
+}
+
+impl Graph {
+    /// Returns the eccentricity of every node with respect to `sources`: the cost of the most
+    /// expensive of the cheapest paths from the node to a source it can reach, not counting
+    /// itself. Nodes that reach no source get `None`.
+    fn eccentricities(&self, sources: &[usize]) -> Vec<Option<Coord>> {
+        let reverse_edges = self.reverse_edges();
+        let unknown = || vec![None; self.nodes.len()];
+        sources
+            .par_iter()
+            .map(|&source| {
+                let mut eccentricities = unknown();
+                for (id, &dist) in distances_to(self, &reverse_edges, source).iter().enumerate() {
+                    if id != source && dist.is_finite() {
+                        eccentricities[id] = Some(dist);
+                    }
+                }
+                eccentricities
+            })
+            .reduce(unknown, |a, b| {
+                a.into_iter()
+                    .zip(b)
+                    .map(|pair| match pair {
+                        (Some(a), Some(b)) => Some(a.max(b)),
+                        (a, b) => a.or(b),
+                    })
+                    .collect()
+            })
+    }
+
+    /// Returns `count` distinct random node ids, or all of them if the graph has fewer nodes.
+    fn sample_nodes(&self, count: usize) -> Vec<usize> {
+        let node_ids: Vec<usize> = (0..self.nodes.len()).collect();
+        node_ids.choose_multiple(&mut rand::thread_rng(), count).copied().collect()
+    }
+
+    /// All nodes of small graphs, `SAMPLED_SOURCES` random nodes of large ones.
+    fn eccentricity_sources(&self) -> Vec<usize> {
+        if self.nodes.len() <= EXACT_ECCENTRICITY_LIMIT {
+            return (0..self.nodes.len()).collect();
+        }
+        self.sample_nodes(SAMPLED_SOURCES)
+    }
+
+    /// Returns the cost of the most expensive of the cheapest paths between any two nodes, the
+    /// diameter of the graph, or 0 if no node can reach another. Nodes that cannot reach each
+    /// other are ignored rather than making the diameter infinite.
+    ///
+    /// This runs Dijkstra's algorithm from every node, which is slow on large graphs. With
+    /// `sample_size`, only the paths to that many random nodes are considered, which gives a lower
+    /// bound of the diameter.
+    pub fn diameter(&self, sample_size: Option<usize>) -> Coord {
+        let sources = match sample_size {
+            Some(sample_size) => self.sample_nodes(sample_size),
+            None => (0..self.nodes.len()).collect(),
+        };
+        self.eccentricities(&sources)
+            .into_iter()
+            .flatten()
+            .fold(0.0, Coord::max)
+    }
+
+    /// Returns the smallest eccentricity of a node, the radius of the graph, or 0 if no node can
+    /// reach another. The eccentricity of a node is the cost of the most expensive of the
+    /// cheapest paths from it to the nodes it can reach. Nodes that reach no other node are
+    /// ignored.
+    ///
+    /// Graphs with more than 2000 nodes estimate the eccentricities from the paths to 64 random
+    /// nodes, the same applies to `center_node` and `peripheral_nodes`.
+    pub fn radius(&self) -> Coord {
+        self.eccentricities(&self.eccentricity_sources())
+            .into_iter()
+            .flatten()
+            .reduce(Coord::min)
+            .unwrap_or(0.0)
+    }
+
+    /// Returns the node with the smallest eccentricity, see `radius`, e.g. a good spot for a
+    /// central base or spawn point. Ties go to the smallest id. Returns `None` if no node can
+    /// reach another.
+    pub fn center_node(&self) -> Option<usize> {
+        self.eccentricities(&self.eccentricity_sources())
+            .into_iter()
+            .enumerate()
+            .filter_map(|(id, eccentricity)| Some((id, eccentricity?)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+
+    /// Returns the nodes with the largest eccentricity, see `radius`, in ascending order. These are
+    /// the ends of the paths that make up the `diameter`.
+    pub fn peripheral_nodes(&self) -> Vec<usize> {
+        let eccentricities = self.eccentricities(&self.eccentricity_sources());
+        let Some(max) = eccentricities.iter().flatten().copied().reduce(Coord::max) else {
+            return Vec::new();
+        };
+        (0..eccentricities.len())
+            .filter(|&id| eccentricities[id] == Some(max))
+            .collect()
+    }
+}
@@ -1,7 +1,68 @@
 use serde::{Serialize, Deserialize};
+use crate::Coord;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Edge {
     pub to: usize,
-    pub cost: f32,
+    pub cost: Coord,
+    /// Marks an edge that is one-way on purpose, e.g. a ledge that can be dropped off but not
+    /// climbed. Searches follow every edge only in its direction either way, the flag makes
+    /// exporters such as `write_obj` keep the edge one-way instead of adding the reverse.
+    #[serde(default)]
+    pub directed: bool,
+    /// How an agent traverses the edge, e.g. to keep wheelchairs off stairs with
+    /// `RePathfinder::find_path_filtered`.
+    #[serde(default)]
+    pub kind: EdgeKind,
+    /// The radius of the largest agent that fits through the edge, e.g. 1 for a narrow corridor,
+    /// see `Graph::a_star_with_radius`. Infinite for edges without such a restriction.
+    #[serde(default = "unlimited_clearance")]
+    pub clearance: Coord,
+    /// Free-form tags of designers, e.g. `requires_swimming` or `destructible`, see
+    /// `Graph::a_star_with_labels`. Faces after a `usemtl` line of an OBJ navmesh are labeled
+    /// with the material name.
+    #[serde(default)]
+    pub labels: Vec<String>,
+}
+
+fn unlimited_clearance() -> Coord {
+    Coord::INFINITY
+}
+
+/// How an agent traverses an edge. Edges between floors of a building are usually `Stairs` or
+/// `Elevator` edges, all others `Walk` edges.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum EdgeKind {
+    #[default]
+    Walk,
+    Stairs,
+    Elevator,
+    Jump,
+}
+
+impl EdgeKind {
+    /// All kinds, in the order `write_obj` writes their groups.
+    pub const ALL: [EdgeKind; 4] = [EdgeKind::Walk, EdgeKind::Stairs, EdgeKind::Elevator, EdgeKind::Jump];
+
+    /// Returns the kind an OBJ group name stands for, see `parse_obj`. Names starting with
+    /// `stairs`, `elevator` or `jump`, ignoring case, e.g. `elevator_up`, give that kind, all
+    /// other names `Walk`.
+    pub fn from_group_name(name: &str) -> EdgeKind {
+        let name = name.to_ascii_lowercase();
+        EdgeKind::ALL
+            .into_iter()
+            .skip(1)
+            .find(|kind| name.starts_with(kind.group_name()))
+            .unwrap_or(EdgeKind::Walk)
+    }
+
+    /// The OBJ group name `write_obj` writes before edges of this kind.
+    pub fn group_name(self) -> &'static str {
+        match self {
+            EdgeKind::Walk => "walk",
+            EdgeKind::Stairs => "stairs",
+            EdgeKind::Elevator => "elevator",
+            EdgeKind::Jump => "jump",
+        }
+    }
 }
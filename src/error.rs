@@ -0,0 +1,103 @@
+use std::fmt;
+use crate::validation::ValidationError;
+
+/// Errors that can occur while loading a navmesh or finding a path.
+#[derive(Debug)]
+pub enum RePathError {
+    /// The navmesh file could not be opened or read.
+    IoError(std::io::Error),
+
+    /// A line of the navmesh file could not be parsed.
+    ParseError { line: usize, detail: String },
+
+    /// The navmesh file is not a valid file of its format.
+    InvalidNavmesh(String),
+
+    /// The graph does not contain any nodes.
+    EmptyGraph,
+
+    /// No path exists between the requested points.
+    NoPath,
+
+    /// A cache file could not be serialized or deserialized.
+    SerializationError(String),
+
+    /// The graph has anomalies that break pathfinding, see `Graph::validate`.
+    InvalidGraph(Vec<ValidationError>),
+
+    /// A cycle of edges with a negative total cost can be reached, see `Graph::spfa`.
+    NegativeCycle,
+
+    /// The graph has more nodes than the algorithm allows, see `Graph::floyd_warshall`.
+    GraphTooLarge(usize),
+}
+
+impl fmt::Display for RePathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RePathError::IoError(err) => write!(f, "I/O error: {}", err),
+            RePathError::ParseError { line, detail } => {
+                write!(f, "Parse error on line {}: {}", line, detail)
+            }
+            RePathError::InvalidNavmesh(detail) => write!(f, "Invalid navmesh: {}", detail),
+            RePathError::EmptyGraph => write!(f, "The graph does not contain any nodes"),
+            RePathError::NoPath => write!(f, "No path found"),
+            RePathError::SerializationError(detail) => write!(f, "Serialization error: {}", detail),
+            RePathError::InvalidGraph(errors) => {
+                write!(f, "Invalid graph with {} issues", errors.len())?;
+                if let Some(first) = errors.first() {
+                    write!(f, ", the first: {}", first)?;
+                }
+                Ok(())
+            }
+            RePathError::NegativeCycle => write!(f, "The graph contains a cycle with a negative cost"),
+            RePathError::GraphTooLarge(nodes) => write!(f, "The graph is too large with {} nodes", nodes),
+        }
+    }
+}
+
+impl std::error::Error for RePathError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RePathError::IoError(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for RePathError {
+    fn from(err: std::io::Error) -> Self {
+        RePathError::IoError(err)
+    }
+}
+
+impl From<bincode::Error> for RePathError {
+    fn from(err: bincode::Error) -> Self {
+        RePathError::SerializationError(err.to_string())
+    }
+}
+
+impl From<csv::Error> for RePathError {
+    fn from(err: csv::Error) -> Self {
+        RePathError::IoError(err.into())
+    }
+}
+
+impl From<gltf::Error> for RePathError {
+    fn from(err: gltf::Error) -> Self {
+        match err {
+            gltf::Error::Io(err) => RePathError::IoError(err),
+            err => RePathError::InvalidNavmesh(err.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for RePathError {
+    fn from(err: serde_json::Error) -> Self {
+        if err.is_io() {
+            RePathError::IoError(err.into())
+        } else {
+            RePathError::SerializationError(err.to_string())
+        }
+    }
+}
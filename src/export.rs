@@ -0,0 +1,140 @@
+use std::collections::HashSet;
+use std::io::Write;
+use serde::Serialize;
+use crate::edge::EdgeKind;
+use crate::error::RePathError;
+use crate::graph::Graph;
+use crate::node::Node;
+use crate::Coord;
+
+/// A GeoJSON `FeatureCollection` as written by `Graph::to_geojson`. The `type` members are the
+/// names of the structs and variants.
+#[derive(Serialize)]
+#[serde(tag = "type")]
+struct FeatureCollection {
+    features: Vec<Feature>,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+struct Feature {
+    geometry: Geometry,
+    properties: Properties,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type")]
+enum Geometry {
+    Point { coordinates: Vec<Coord> },
+    LineString { coordinates: Vec<Vec<Coord>> },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Properties {
+    Node { id: usize, weight: Coord },
+    Edge { from: usize, to: usize, cost: Coord, kind: EdgeKind },
+    Path { path: bool },
+}
+
+impl Graph {
+    /// Writes the graph in the Graphviz DOT format, e.g. to render it with `dot -Tsvg`. Every node
+    /// is declared with the label `id(x,y,z)` and every edge, including both directions of a
+    /// two-way edge, is written as a `->` statement labeled with its cost.
+    pub fn to_dot(&self, writer: &mut impl Write) -> Result<(), std::io::Error> {
+        self.write_dot(writer, &[])
+    }
+
+    /// Writes the graph like `to_dot`, drawing the nodes of `highlight` and the edges between
+    /// consecutive nodes of it in red.
+    pub(crate) fn write_dot(&self, writer: &mut impl Write, highlight: &[Node]) -> Result<(), std::io::Error> {
+        let highlighted_nodes: HashSet<usize> = highlight.iter().map(|node| node.id).collect();
+        let highlighted_edges: HashSet<(usize, usize)> =
+            highlight.windows(2).map(|pair| (pair[0].id, pair[1].id)).collect();
+        let color = |highlighted: bool| if highlighted { ", color=red" } else { "" };
+
+        writeln!(writer, "digraph navmesh {{")?;
+        for node in &self.nodes {
+            writeln!(
+                writer,
+                "    {} [label=\"{}({},{},{})\"{}];",
+                node.id,
+                node.id,
+                node.x,
+                node.y,
+                node.z,
+                color(highlighted_nodes.contains(&node.id))
+            )?;
+        }
+        for (from, edges) in self.edges.iter().enumerate() {
+            for edge in edges {
+                writeln!(
+                    writer,
+                    "    {} -> {} [label=\"{}\"{}];",
+                    from,
+                    edge.to,
+                    edge.cost,
+                    color(highlighted_edges.contains(&(from, edge.to)))
+                )?;
+            }
+        }
+        writeln!(writer, "}}")
+    }
+
+    /// Writes the graph as a GeoJSON `FeatureCollection`, e.g. to inspect it in QGIS or Mapbox.
+    /// Every node becomes a `Point` feature with its id and weight as properties, and every edge,
+    /// including both directions of a two-way edge, a `LineString` feature with its end nodes,
+    /// cost and kind.
+    ///
+    /// Positions are written as `[x, z, y]`, so that the ground plane maps to the plane of the map
+    /// and the height to the altitude. With `lat_lon_mode`, the height is left out and x and z are
+    /// the longitude and latitude in degrees.
+    pub fn to_geojson(&self, writer: &mut impl Write, lat_lon_mode: bool) -> Result<(), RePathError> {
+        self.write_geojson(writer, lat_lon_mode, None)
+    }
+
+    /// Writes the graph like `to_geojson`, followed by `path` as a `LineString` feature with the
+    /// property `"path": true`.
+    pub(crate) fn write_geojson(
+        &self,
+        writer: &mut impl Write,
+        lat_lon_mode: bool,
+        path: Option<&[Node]>,
+    ) -> Result<(), RePathError> {
+        let position = |node: &Node| {
+            if lat_lon_mode {
+                vec![node.x, node.z]
+            } else {
+                vec![node.x, node.z, node.y]
+            }
+        };
+
+        let edge_count: usize = self.edges.iter().map(Vec::len).sum();
+        let mut features = Vec::with_capacity(self.nodes.len() + edge_count + 1);
+        for node in &self.nodes {
+            features.push(Feature {
+                geometry: Geometry::Point { coordinates: position(node) },
+                properties: Properties::Node { id: node.id, weight: node.weight },
+            });
+        }
+        for (from, edges) in self.edges.iter().enumerate() {
+            for edge in edges {
+                features.push(Feature {
+                    geometry: Geometry::LineString {
+                        coordinates: vec![position(&self.nodes[from]), position(&self.nodes[edge.to])],
+                    },
+                    properties: Properties::Edge { from, to: edge.to, cost: edge.cost, kind: edge.kind },
+                });
+            }
+        }
+        if let Some(path) = path {
+            features.push(Feature {
+                geometry: Geometry::LineString { coordinates: path.iter().map(position).collect() },
+                properties: Properties::Path { path: true },
+            });
+        }
+
+        serde_json::to_writer(writer, &FeatureCollection { features })?;
+        Ok(())
+    }
+}
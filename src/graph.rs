@@ -1,16 +1,49 @@
 use std::cmp::Ordering;
-use std::collections::BinaryHeap;
-use std::sync::Arc;
-use dashmap::DashMap;
+use std::collections::{BTreeSet, BinaryHeap, HashSet};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock};
+use std::time::Instant;
+use crate::cache::{PathCache, PrefixedCache, UnprefixedCache};
 use rand::prelude::*;
-use crate::edge::Edge;
+use rayon::prelude::*;
+use crate::eccentricity::distances_to;
+use crate::edge::{Edge, EdgeKind};
+use crate::heap::IndexedBinaryHeap;
+use crate::lazy::LazyRegions;
 use crate::node::Node;
-use crate::path::Path;
+use crate::path::{PartialPath, Path, PathIter};
 use crate::utils::distance;
+use crate::Coord;
 
+/// The number of nodes from which `Graph::nearest_node` scans them in parallel.
+pub const PARALLEL_NEAREST_NODE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone)]
 pub struct Graph {
     pub nodes: Vec<Node>,
     pub edges: Vec<Vec<Edge>>,
+    /// Counts the changes made after construction that invalidate cached paths, see `generation`.
+    pub(crate) generation: u64,
+    /// The connected component of every node, see `connected_components`. Reset by the methods
+    /// that add or remove nodes or edges, after changing `nodes` or `edges` directly it is stale.
+    pub(crate) components: OnceLock<Vec<usize>>,
+    /// The number of edges leading to every node, see `in_degree`. Reset like `components`.
+    in_degrees: OnceLock<Vec<usize>>,
+    /// The regions whose edges are still to be loaded, see `load_region`.
+    pub(crate) lazy: Option<LazyRegions>,
+}
+
+impl PartialEq for Graph {
+    fn eq(&self, other: &Self) -> bool {
+        self.nodes == other.nodes && self.edges == other.edges
+    }
+}
+
+impl Default for Graph {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Graph {
@@ -18,19 +51,334 @@ impl Graph {
         Graph {
             nodes: Vec::new(),
             edges: Vec::new(),
+            generation: 0,
+            components: OnceLock::new(),
+            in_degrees: OnceLock::new(),
+            lazy: None,
+        }
+    }
+
+    /// A counter incremented by every change that invalidates cached paths or node ids:
+    /// `remove_node`, `add_node_with_edges`, `connect_nodes`, `set_edge_cost`,
+    /// `scale_all_edge_costs` and `move_node`. `RePathfinder::update_graph` clears its cache when
+    /// it changes. Building a graph with `add_node` and `add_edge` does not change it.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// A hash of everything searches depend on: the positions and weights of the nodes and the
+    /// edges with their costs, kinds, clearances and labels. Unlike `generation`, equal graphs
+    /// have the same fingerprint, also in other runs and on other platforms, so it tells e.g.
+    /// whether a `DiskPathCache` was written for this graph.
+    pub fn fingerprint(&self) -> u64 {
+        // FNV-1a, which unlike `DefaultHasher` gives the same hash in every Rust version
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let mut write = |bytes: &[u8]| {
+            for &byte in bytes {
+                hash = (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3);
+            }
+        };
+        let coord = |value: Coord| value.to_bits().to_le_bytes();
+
+        write(&(self.nodes.len() as u64).to_le_bytes());
+        for node in &self.nodes {
+            for value in [node.x, node.y, node.z, node.weight] {
+                write(&coord(value));
+            }
         }
+        for edges in &self.edges {
+            write(&(edges.len() as u64).to_le_bytes());
+            for edge in edges {
+                write(&(edge.to as u64).to_le_bytes());
+                write(&coord(edge.cost));
+                write(&coord(edge.clearance));
+                write(&[u8::from(edge.directed), edge.kind as u8]);
+                write(&(edge.labels.len() as u64).to_le_bytes());
+                for label in &edge.labels {
+                    write(&(label.len() as u64).to_le_bytes());
+                    write(label.as_bytes());
+                }
+            }
+        }
+        hash
     }
 
     pub fn add_node(&mut self, node: Node) {
         self.nodes.push(node);
         self.edges.push(Vec::new());
+        self.reset_indices();
+    }
+
+    pub fn add_edge(&mut self, from: usize, to: usize, cost: Coord) {
+        self.push_edge(from, Edge {
+            to,
+            cost,
+            directed: false,
+            kind: EdgeKind::Walk,
+            clearance: Coord::INFINITY,
+            labels: Vec::new(),
+        });
+    }
+
+    /// Drops the indices built from the edges on first use, after nodes or edges changed.
+    fn reset_indices(&mut self) {
+        self.components.take();
+        self.in_degrees.take();
+    }
+
+    /// Adds a one-way edge from `from` to `to` that is marked as `directed`, see `Edge::directed`.
+    pub fn add_edge_directed(&mut self, from: usize, to: usize, cost: Coord) {
+        self.push_edge(from, Edge {
+            to,
+            cost,
+            directed: true,
+            kind: EdgeKind::Walk,
+            clearance: Coord::INFINITY,
+            labels: Vec::new(),
+        });
+    }
+
+    /// Adds an edge from `from` to `to` that is traversed as `kind`, e.g. a flight of stairs
+    /// between two floors. Like `add_edge`, this adds no reverse edge.
+    pub fn add_edge_with_kind(&mut self, from: usize, to: usize, cost: Coord, kind: EdgeKind) {
+        self.push_edge(from, Edge {
+            to,
+            cost,
+            directed: false,
+            kind,
+            clearance: Coord::INFINITY,
+            labels: Vec::new(),
+        });
+    }
+
+    /// Adds an edge from `from` to `to` tagged with `labels`, e.g. `requires_swimming`, see
+    /// `Edge::labels`. Like `add_edge`, this adds no reverse edge.
+    pub fn add_edge_with_labels(&mut self, from: usize, to: usize, cost: Coord, labels: Vec<String>) {
+        self.push_edge(from, Edge {
+            to,
+            cost,
+            directed: false,
+            kind: EdgeKind::Walk,
+            clearance: Coord::INFINITY,
+            labels,
+        });
+    }
+
+    /// Adds an edge starting at `from` with all of its attributes, as read by the parsers.
+    pub(crate) fn push_edge(&mut self, from: usize, edge: Edge) {
+        self.edges[from].push(edge);
+        self.reset_indices();
+    }
+
+    /// Appends a node connected both ways to existing nodes, e.g. an area behind a door that was
+    /// opened at runtime, and returns its id. The id of `node` is replaced by its index.
+    ///
+    /// Panics without changing the graph if a connection refers to a node that does not exist.
+    /// The new node may shorten cached paths or connect nodes cached as unreachable, so this
+    /// increments the `generation`.
+    pub fn add_node_with_edges(&mut self, node: Node, connections: &[(usize, Coord)]) -> usize {
+        let id = self.nodes.len();
+        if let Some(&(to, _)) = connections.iter().find(|&&(to, _)| to >= id) {
+            panic!("Cannot connect to node {} of a graph with {} nodes", to, id);
+        }
+
+        self.add_node(Node { id, ..node });
+        for &(to, cost) in connections {
+            self.add_edge(id, to, cost);
+            self.add_edge(to, id, cost);
+        }
+        self.generation += 1;
+        id
+    }
+
+    /// Adds edges between `a` and `b` in both directions and increments the `generation`.
+    pub fn connect_nodes(&mut self, a: usize, b: usize, cost: Coord) {
+        assert!(
+            a < self.nodes.len() && b < self.nodes.len(),
+            "Cannot connect nodes {} and {} of a graph with {} nodes",
+            a,
+            b,
+            self.nodes.len()
+        );
+        self.add_edge(a, b, cost);
+        self.add_edge(b, a, cost);
+        self.generation += 1;
+    }
+
+    /// Removes the node and all edges from and to it, e.g. when part of the navmesh collapses,
+    /// and returns whether it existed. The following nodes move down by one so that every node id
+    /// stays equal to its index.
+    ///
+    /// Since this changes node ids, it increments the `generation`. Ids held by the caller and
+    /// cached paths refer to the old numbering and must be discarded.
+    pub fn remove_node(&mut self, id: usize) -> bool {
+        if id >= self.nodes.len() {
+            return false;
+        }
+
+        self.nodes.remove(id);
+        self.edges.remove(id);
+        for node in &mut self.nodes[id..] {
+            node.id -= 1;
+        }
+        for edges in &mut self.edges {
+            edges.retain(|edge| edge.to != id);
+            for edge in edges.iter_mut().filter(|edge| edge.to > id) {
+                edge.to -= 1;
+            }
+        }
+
+        self.generation += 1;
+        self.reset_indices();
+        true
+    }
+
+    /// Returns the cost of the cheapest edge from `from` to `to`, or `None` if there is none.
+    pub fn edge_cost(&self, from: usize, to: usize) -> Option<Coord> {
+        self.edges[from]
+            .iter()
+            .filter(|edge| edge.to == to)
+            .map(|edge| edge.cost)
+            .min_by(Coord::total_cmp)
+    }
+
+    /// The cost of traversing the edge during a search: its cost multiplied by the weight of the
+    /// node it enters, see `Node::weight`.
+    pub fn traversal_cost(&self, edge: &Edge) -> Coord {
+        edge.cost * self.nodes[edge.to].weight
+    }
+
+    /// Changes the cost of the edges from `from` to `to`, including parallel ones, and returns
+    /// whether there was such an edge. Increments the `generation` if there was one.
+    ///
+    /// Paths stored in caches are not updated, which `RePathfinder::update_graph` takes care of by
+    /// clearing its cache. Other caches must be cleared before searching again.
+    pub fn set_edge_cost(&mut self, from: usize, to: usize, new_cost: Coord) -> bool {
+        let Some(edges) = self.edges.get_mut(from) else {
+            return false;
+        };
+
+        let mut found = false;
+        for edge in edges.iter_mut().filter(|edge| edge.to == to) {
+            edge.cost = new_cost;
+            found = true;
+        }
+        if found {
+            self.generation += 1;
+        }
+        found
+    }
+
+    /// Changes the clearance of the edges from `from` to `to`, including parallel ones, see
+    /// `Edge::clearance`, and returns whether there was such an edge. Like `set_edge_cost`, this
+    /// increments the `generation` if there was one.
+    pub fn set_edge_clearance(&mut self, from: usize, to: usize, clearance: Coord) -> bool {
+        let Some(edges) = self.edges.get_mut(from) else {
+            return false;
+        };
+
+        let mut found = false;
+        for edge in edges.iter_mut().filter(|edge| edge.to == to) {
+            edge.clearance = clearance;
+            found = true;
+        }
+        if found {
+            self.generation += 1;
+        }
+        found
+    }
+
+    /// Moves a node, e.g. after terrain deformed, and scales the cost of every edge from or to it
+    /// by the ratio of the new and the old distance between its nodes. Returns whether the node
+    /// exists, and increments the `generation` if it does, so `RePathfinder::update_graph` clears
+    /// the cached paths.
+    ///
+    /// Costs that differed from the distance before, e.g. after `apply_slope_factor` or
+    /// `set_edge_cost`, keep their ratio to it, the slope factor is not recomputed for the new
+    /// slope. Edges between nodes at the same position get the new distance as cost. Finding the
+    /// edges into the node checks every edge, see `move_nodes_batch` for moving many nodes at
+    /// once.
+    pub fn move_node(&mut self, id: usize, new_x: Coord, new_y: Coord, new_z: Coord) -> bool {
+        self.move_nodes_batch(&[(id, new_x, new_y, new_z)]) == 1
+    }
+
+    /// Moves several nodes like `move_node`, but checks every edge only once. Updates of nodes
+    /// that do not exist are skipped, the number of moved nodes is returned. A node moved more
+    /// than once ends up at its last position, costs are scaled from where it was before.
+    pub fn move_nodes_batch(&mut self, updates: &[(usize, Coord, Coord, Coord)]) -> usize {
+        // The positions of the moved nodes before the batch
+        let mut old_positions: Vec<Option<(Coord, Coord, Coord)>> = vec![None; self.nodes.len()];
+        let mut count = 0;
+        for &(id, x, y, z) in updates {
+            let Some(node) = self.nodes.get_mut(id) else {
+                continue;
+            };
+            if old_positions[id].is_none() {
+                old_positions[id] = Some((node.x, node.y, node.z));
+                count += 1;
+            }
+            (node.x, node.y, node.z) = (x, y, z);
+        }
+        if count == 0 {
+            return 0;
+        }
+
+        let nodes = &self.nodes;
+        let position = |id: usize| (nodes[id].x, nodes[id].y, nodes[id].z);
+        for (from, edges) in self.edges.iter_mut().enumerate() {
+            for edge in edges.iter_mut() {
+                if old_positions[from].is_none() && old_positions[edge.to].is_none() {
+                    continue;
+                }
+                let old_length = distance(
+                    &old_positions[from].unwrap_or_else(|| position(from)),
+                    &old_positions[edge.to].unwrap_or_else(|| position(edge.to)),
+                );
+                let new_length = distance(&position(from), &position(edge.to));
+                edge.cost = if old_length > 0.0 { edge.cost * new_length / old_length } else { new_length };
+            }
+        }
+        self.generation += 1;
+        count
+    }
+
+    /// Multiplies the cost of every edge by `factor`, e.g. to slow down all movement at night.
+    ///
+    /// A factor below 1 makes edges cheaper than the distance between their nodes, so the default
+    /// Euclidean heuristic may overestimate and A* may no longer return the cheapest path. As with
+    /// `set_edge_cost`, this increments the `generation`.
+    pub fn scale_all_edge_costs(&mut self, factor: Coord) {
+        for edge in self.edges.iter_mut().flatten() {
+            edge.cost *= factor;
+        }
+        self.generation += 1;
+    }
+
+    /// Makes steep edges more expensive for ground-based agents: the cost of every edge is
+    /// multiplied by `1 + slope_factor * sin(angle)`, where `angle` is the angle between the edge
+    /// and the horizontal plane. A vertical edge thus costs `1 + slope_factor` times as much as
+    /// before, and horizontal edges keep their cost. Increments the `generation`.
+    pub fn apply_slope_factor(&mut self, slope_factor: Coord) {
+        self.apply_slope_factor_after(&vec![0; self.nodes.len()], slope_factor);
     }
 
-    pub fn add_edge(&mut self, from: usize, to: usize, cost: f32) {
-        self.edges[from].push(Edge { to, cost });
+    /// Like `apply_slope_factor`, but only changes the edges of every node from its index in
+    /// `first_edges` on, e.g. the edges added by `load_region`.
+    pub(crate) fn apply_slope_factor_after(&mut self, first_edges: &[usize], slope_factor: Coord) {
+        for (from, edges) in self.edges.iter_mut().enumerate() {
+            let start = &self.nodes[from];
+            for edge in &mut edges[first_edges[from]..] {
+                let end = &self.nodes[edge.to];
+                let length = distance(&(start.x, start.y, start.z), &(end.x, end.y, end.z));
+                if length > 0.0 {
+                    edge.cost *= 1.0 + slope_factor * (end.y - start.y).abs() / length;
+                }
+            }
+        }
+        self.generation += 1;
     }
 
-    pub fn heuristic(&self, start: usize, goal: usize) -> f32 {
+    pub fn heuristic(&self, start: usize, goal: usize) -> Coord {
         let start_node = &self.nodes[start];
         let goal_node = &self.nodes[goal];
         let dx = start_node.x - goal_node.x;
@@ -39,55 +387,891 @@ impl Graph {
         (dx * dx + dy * dy + dz * dz).sqrt()
     }
 
-
+    /// Finds the cheapest path from start to goal with A*, i.e. `a_star_with_heuristic` with the
+    /// Euclidean distance of `heuristic`. The result is cached under `(start, goal)`.
     pub fn a_star(
         &self,
         start: usize,
         goal: usize,
-        cache: &DashMap<(usize, usize), Option<Path>>,
+        cache: &impl PathCache,
+    ) -> Option<Path> {
+        self.a_star_with_heuristic(start, goal, 0, &UnprefixedCache::new(cache), |node, goal| {
+            self.heuristic(node, goal)
+        })
+    }
+
+    /// Runs A* using the given heuristic, which receives the ids of the current node and the goal.
+    /// An inadmissible heuristic (one that overestimates the remaining cost) may find a
+    /// suboptimal path.
+    ///
+    /// The result is cached under `(heuristic_id, start, goal)`, so every heuristic must use its
+    /// own id.
+    pub fn a_star_with_heuristic<H: Fn(usize, usize) -> Coord>(
+        &self,
+        start: usize,
+        goal: usize,
+        heuristic_id: u64,
+        cache: &impl PathCache<(u64, usize, usize)>,
+        heuristic: H,
+    ) -> Option<Path> {
+        let cache_key = (heuristic_id, start, goal);
+
+        // Check if the path is already in cache
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        let result = self.a_star_search(
+            &[start],
+            &[goal],
+            |node| heuristic(node, goal),
+            |_, edge| Some(self.traversal_cost(edge)),
+            &mut SearchStats::default(),
+        )
+        .ok();
+
+        // Cache the result
+        cache.insert_path(cache_key, result.clone());
+
+        result
+    }
+
+    /// Finds the cheapest path to goal from any of the given start nodes.
+    /// All start nodes are placed in the open set at once, so this costs about as much as a single
+    /// A* search. The first node of the returned path is the start node it was found from.
+    /// The result is cached under the key of that start node.
+    pub fn a_star_multi_source(
+        &self,
+        starts: &[usize],
+        goal: usize,
+        cache: &impl PathCache,
     ) -> Option<Path> {
+        let result = self.a_star_search(
+            starts,
+            &[goal],
+            |node| self.heuristic(node, goal),
+            |_, edge| Some(self.traversal_cost(edge)),
+            &mut SearchStats::default(),
+        )
+        .ok()?;
+
+        // Cache the result
+        cache.insert_path((result[0].id, goal), Some(result.clone()));
+
+        Some(result)
+    }
+
+    /// Finds the path from start to whichever of the goals is cheapest to reach.
+    /// The heuristic is the Euclidean distance to the nearest goal. Returns the index of the
+    /// reached goal in `goals` together with the path, which is cached under the key of that goal.
+    pub fn a_star_nearest_goal(
+        &self,
+        start: usize,
+        goals: &[usize],
+        cache: &impl PathCache,
+    ) -> Option<(usize, Path)> {
+        let result = self.a_star_search(
+            &[start],
+            goals,
+            |node| {
+                goals
+                    .iter()
+                    .map(|&goal| self.heuristic(node, goal))
+                    .fold(Coord::INFINITY, Coord::min)
+            },
+            |_, edge| Some(self.traversal_cost(edge)),
+            &mut SearchStats::default(),
+        )
+        .ok()?;
+
+        let goal = result[result.len() - 1].id;
+        let goal_index = goals.iter().position(|&id| id == goal)?;
+
+        // Cache the result
+        cache.insert_path((start, goal), Some(result.clone()));
+
+        Some((goal_index, result))
+    }
+
+    /// Like `a_star`, but if the goal is unreachable returns the path to the reachable node
+    /// closest to the goal instead of nothing, with `complete` set to false.
+    /// The node is chosen by the lowest remaining heuristic among the expanded nodes, since the
+    /// lowest `f_score` is usually found right at the start. Only complete paths are cached.
+    pub fn a_star_partial(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+    ) -> PartialPath {
         let cache_key = (start, goal);
 
         // Check if the path is already in cache
-        if let Some(result) = cache.get(&cache_key) {
-            return result.clone();
+        if let Some(Some(path)) = cache.get_path(&cache_key) {
+            return PartialPath {
+                path,
+                complete: true,
+            };
+        }
+
+        let result = self.a_star_search(
+            &[start],
+            &[goal],
+            |node| self.heuristic(node, goal),
+            |_, edge| Some(self.traversal_cost(edge)),
+            &mut SearchStats::default(),
+        );
+
+        // Cache the result
+        cache.insert_path(cache_key, result.as_ref().ok().cloned());
+
+        match result {
+            Ok(path) => PartialPath { path, complete: true },
+            Err(path) => PartialPath { path, complete: false },
+        }
+    }
+
+    /// Runs A* like `a_star_with_stats` and returns an iterator over the nodes of the path, e.g.
+    /// for streaming them to a client one by one. The whole path is still searched first, but
+    /// the iterator reads it in place without copying the nodes.
+    pub fn a_star_iter(&self, start: usize, goal: usize) -> Option<PathIter> {
+        self.a_star_with_stats(start, goal).0.map(PathIter::new)
+    }
+
+    /// Runs A* with the Euclidean heuristic without touching any cache and reports how many
+    /// nodes were expanded. Useful for comparing search strategies.
+    pub fn a_star_with_stats(&self, start: usize, goal: usize) -> (Option<Path>, SearchStats) {
+        let mut stats = SearchStats::default();
+        let result = self.a_star_search(
+            &[start],
+            &[goal],
+            |node| self.heuristic(node, goal),
+            |_, edge| Some(self.traversal_cost(edge)),
+            &mut stats,
+        )
+        .ok();
+        (result, stats)
+    }
+
+    /// Runs A* with the edge costs given by `cost_fn` instead of `Edge::cost`, e.g. for agents
+    /// that traverse terrain differently. `cost_fn` must not return negative costs, its result is
+    /// still multiplied by the weight of the entered node.
+    ///
+    /// The result is cached under `(cache_key_prefix, start, goal)`, so every cost function needs
+    /// its own prefix. With the Euclidean heuristic, paths are only guaranteed to be the cheapest
+    /// if `cost_fn` never makes an edge cheaper than the distance between its nodes.
+    pub fn a_star_with_cost<C: Fn(&Edge) -> Coord>(
+        &self,
+        start: usize,
+        goal: usize,
+        cache_key_prefix: u64,
+        cache: &impl PathCache<(u64, usize, usize)>,
+        cost_fn: C,
+    ) -> Option<Path> {
+        let cache_key = (cache_key_prefix, start, goal);
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        let result = self
+            .a_star_search(
+                &[start],
+                &[goal],
+                |node| self.heuristic(node, goal),
+                |_, edge| Some(cost_fn(edge) * self.nodes[edge.to].weight),
+                &mut SearchStats::default(),
+            )
+            .ok();
+        cache.insert_path(cache_key, result.clone());
+        result
+    }
+
+    /// Like `a_star`, but only returns a path whose cost is at most `max_cost`, e.g. for agents with
+    /// limited fuel or stamina. Nodes that can only be reached at a higher cost are not expanded,
+    /// so this is faster than `a_star` for goals that are far away or unreachable. Returns `None`
+    /// if the goal cannot be reached within the budget or `max_cost` is negative or NaN.
+    ///
+    /// The search runs with `max_cost` rounded up to the next power of two and the result is
+    /// cached under `(bucket, start, goal)`, where `bucket` identifies the rounded budget. Budgets
+    /// in the same bucket share the cached path, which is returned if it is cheap enough, so the
+    /// cache holds at most one entry per bucket and node pair.
+    pub fn a_star_budgeted(
+        &self,
+        start: usize,
+        goal: usize,
+        max_cost: Coord,
+        cache: &impl PathCache<(u64, usize, usize)>,
+    ) -> Option<Path> {
+        if max_cost.is_nan() || max_cost < 0.0 {
+            return None;
+        }
+        let (bucket, bucket_budget) = budget_bucket(max_cost);
+        let cache_key = (bucket, start, goal);
+        let path = match cache.get_path(&cache_key) {
+            Some(result) => result,
+            None => {
+                let limit = SearchLimit {
+                    max_cost: Some(bucket_budget),
+                    ..SearchLimit::default()
+                };
+                let heuristic = |node| self.heuristic(node, goal);
+                let edge_cost = |_, edge: &Edge| Some(self.traversal_cost(edge));
+                let result = self
+                    .a_star_search_limited(&[start], &[goal], heuristic, edge_cost, limit, &mut SearchStats::default())
+                    .ok();
+                cache.insert_path(cache_key, result.clone());
+                result
+            }
+        }?;
+
+        // The cheapest path within the rounded budget is the cheapest one within `max_cost` too,
+        // if it is cheap enough
+        let cost: Coord = path
+            .windows(2)
+            .map(|pair| {
+                let edge_cost = self.edge_cost(pair[0].id, pair[1].id).unwrap_or(Coord::INFINITY);
+                edge_cost * self.nodes[pair[1].id].weight
+            })
+            .sum();
+        (cost <= max_cost).then_some(path)
+    }
+
+    /// Like `a_star`, but gives up and returns `None` once more than `max_nodes` states have been
+    /// taken from the open set, so a search for an unreachable goal on a huge navmesh cannot take
+    /// arbitrarily long. Results of searches that gave up are not cached, since a path may exist.
+    pub fn a_star_bounded(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+        max_nodes: usize,
+    ) -> Option<Path> {
+        let limit = SearchLimit {
+            max_nodes: Some(max_nodes),
+            ..SearchLimit::default()
+        };
+        let heuristic = |node| self.heuristic(node, goal);
+        self.a_star_limited(start, goal, cache, heuristic, |edge| Some(self.traversal_cost(edge)), limit)
+    }
+
+    /// Like `a_star`, but gives up and returns `None` once `deadline` has passed. The clock is
+    /// only read every few hundred expanded nodes, so the search may end slightly after the
+    /// deadline. Results of searches that gave up are not cached.
+    pub fn a_star_with_timeout(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+        deadline: Instant,
+    ) -> Option<Path> {
+        let limit = SearchLimit {
+            deadline: Some(deadline),
+            ..SearchLimit::default()
+        };
+        let heuristic = |node| self.heuristic(node, goal);
+        self.a_star_limited(start, goal, cache, heuristic, |edge| Some(self.traversal_cost(edge)), limit)
+    }
+
+    /// Like `a_star`, but never enters the `blocked` nodes, e.g. behind a locked door or on fire.
+    /// The start node may be blocked, a blocked goal cannot be reached.
+    ///
+    /// The result is cached under the same key as `a_star`, so a cache must only be shared by
+    /// searches with the same blocked nodes.
+    pub fn a_star_with_blocked(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+        blocked: &HashSet<usize>,
+    ) -> Option<Path> {
+        let heuristic = |node| self.heuristic(node, goal);
+        let edge_cost = |edge: &Edge| (!blocked.contains(&edge.to)).then(|| self.traversal_cost(edge));
+        self.a_star_limited(start, goal, cache, heuristic, edge_cost, SearchLimit::default())
+    }
+
+    /// Like `a_star_with_blocked`, but for avoiding nodes in a single query without a shared set
+    /// of blocked nodes, e.g. the positions of other agents.
+    ///
+    /// The result is cached under `(cache_key_prefix, start, goal)`, so every set of avoided nodes
+    /// needs its own prefix, such as a hash of the sorted node ids.
+    pub fn a_star_avoiding(
+        &self,
+        start: usize,
+        goal: usize,
+        avoid: &[usize],
+        cache_key_prefix: u64,
+        cache: &impl PathCache<(u64, usize, usize)>,
+    ) -> Option<Path> {
+        let cache_key = (cache_key_prefix, start, goal);
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        let avoid: HashSet<usize> = avoid.iter().copied().collect();
+        let result = self
+            .a_star_search(
+                &[start],
+                &[goal],
+                |node| self.heuristic(node, goal),
+                |_, edge| (!avoid.contains(&edge.to)).then(|| self.traversal_cost(edge)),
+                &mut SearchStats::default(),
+            )
+            .ok();
+        cache.insert_path(cache_key, result.clone());
+        result
+    }
+
+    /// Like `a_star`, but only traverses edges whose kind is one of `allowed_kinds`, e.g. to keep
+    /// a wheelchair on one floor unless there is an elevator.
+    ///
+    /// The result is cached under `(filter, start, goal)`, where `filter` identifies the set of
+    /// allowed kinds, so searches with different kinds can share a cache.
+    pub fn a_star_filtered(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache<(u64, usize, usize)>,
+        allowed_kinds: &[EdgeKind],
+    ) -> Option<Path> {
+        let mut kinds = allowed_kinds.to_vec();
+        kinds.sort_by_key(|&kind| kind as u8);
+        kinds.dedup();
+        let cache = PrefixedCache::new(cache_key_prefix(("filtered", kinds)), cache);
+
+        let heuristic = |node| self.heuristic(node, goal);
+        let edge_cost = |edge: &Edge| allowed_kinds.contains(&edge.kind).then(|| self.traversal_cost(edge));
+        self.a_star_limited(start, goal, &cache, heuristic, edge_cost, SearchLimit::default())
+    }
+
+    /// Like `a_star`, but only traverses edges that carry all `required` labels and none of the
+    /// `forbidden` ones, see `Edge::labels`, e.g. to keep agents that cannot swim out of water.
+    ///
+    /// The result is cached under `(filter, start, goal)`, where `filter` identifies the sets of
+    /// required and forbidden labels, so searches with different labels can share a cache.
+    pub fn a_star_with_labels(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache<(u64, usize, usize)>,
+        required: &[&str],
+        forbidden: &[&str],
+    ) -> Option<Path> {
+        let label_sets = (required.iter().collect::<BTreeSet<_>>(), forbidden.iter().collect::<BTreeSet<_>>());
+        let cache = PrefixedCache::new(cache_key_prefix(("labels", label_sets)), cache);
+
+        let heuristic = |node| self.heuristic(node, goal);
+        let has_label = |edge: &Edge, label: &str| edge.labels.iter().any(|edge_label| edge_label == label);
+        let edge_cost = |edge: &Edge| {
+            let allowed = required.iter().all(|label| has_label(edge, label))
+                && !forbidden.iter().any(|label| has_label(edge, label));
+            allowed.then(|| self.traversal_cost(edge))
+        };
+        self.a_star_limited(start, goal, &cache, heuristic, edge_cost, SearchLimit::default())
+    }
+
+    /// Like `a_star`, but prefers edges that point in the `heading` direction, e.g. for a vehicle
+    /// that cannot easily turn around. Every edge whose direction deviates from `heading` by more
+    /// than `cone_half_angle` costs `heading_weight` times the excess angle more. Angles are in
+    /// radians. The penalty is added to the edge costs rather than the heuristic, so the
+    /// heuristic stays admissible and the path is the cheapest one with the penalties. A zero
+    /// `heading` or `heading_weight` finds the same path as `a_star`.
+    ///
+    /// The result is cached under `(direction, start, goal)`, where `direction` identifies the
+    /// heading, cone and weight, so searches in different directions can share a cache.
+    pub fn a_star_directed(
+        &self,
+        start: usize,
+        goal: usize,
+        heading: (Coord, Coord, Coord),
+        cone_half_angle: Coord,
+        heading_weight: Coord,
+        cache: &impl PathCache<(u64, usize, usize)>,
+    ) -> Option<Path> {
+        let parameters = [heading.0, heading.1, heading.2, cone_half_angle, heading_weight].map(Coord::to_bits);
+        let cache_key = (cache_key_prefix(("directed", parameters)), start, goal);
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        let heading_length = distance(&(0.0, 0.0, 0.0), &heading);
+        let edge_cost = |from: usize, edge: &Edge| {
+            let (a, b) = (&self.nodes[from], &self.nodes[edge.to]);
+            let direction = (b.x - a.x, b.y - a.y, b.z - a.z);
+            let length = distance(&(0.0, 0.0, 0.0), &direction);
+            let deviation = if heading_length == 0.0 || length == 0.0 {
+                0.0
+            } else {
+                let dot = direction.0 * heading.0 + direction.1 * heading.1 + direction.2 * heading.2;
+                let angle = (dot / (length * heading_length)).clamp(-1.0, 1.0).acos();
+                (angle - cone_half_angle).max(0.0)
+            };
+            Some(self.traversal_cost(edge) + heading_weight * deviation)
+        };
+        let result = self
+            .a_star_search(&[start], &[goal], |node| self.heuristic(node, goal), edge_cost, &mut SearchStats::default())
+            .ok();
+        cache.insert_path(cache_key, result.clone());
+        result
+    }
+
+    /// Like `a_star`, but only traverses edges an agent of radius `agent_radius` fits through, see
+    /// `Edge::clearance`, e.g. to keep a vehicle out of narrow corridors.
+    ///
+    /// The result is cached under `(radius, start, goal)`, where `radius` identifies the agent
+    /// radius. Unlike the budget of `a_star_budgeted`, the radius is not rounded, since a larger
+    /// radius could exclude edges the agent fits through.
+    pub fn a_star_with_radius(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache<(u64, usize, usize)>,
+        agent_radius: Coord,
+    ) -> Option<Path> {
+        let cache = PrefixedCache::new(cache_key_prefix(("radius", agent_radius.to_bits())), cache);
+        let heuristic = |node| self.heuristic(node, goal);
+        let edge_cost = |edge: &Edge| (edge.clearance >= agent_radius).then(|| self.traversal_cost(edge));
+        self.a_star_limited(start, goal, &cache, heuristic, edge_cost, SearchLimit::default())
+    }
+
+    /// The cached A* search behind `a_star_bounded`, `a_star_with_timeout`,
+    /// `a_star_with_blocked`, `a_star_filtered`, `a_star_with_labels` and `a_star_with_radius`. `heuristic` estimates
+    /// the remaining cost from a node to the goal, a zero heuristic turns the search into
+    /// Dijkstra's algorithm. `edge_cost` returns the cost of traversing an edge, or `None` if the
+    /// edge must not be used.
+    /// Only complete searches are cached, a search that hit the `limit` may have missed a path.
+    /// Searches with other edge costs than `a_star` need a `PrefixedCache`.
+    pub(crate) fn a_star_limited(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+        heuristic: impl Fn(usize) -> Coord,
+        edge_cost: impl Fn(&Edge) -> Option<Coord>,
+        limit: SearchLimit,
+    ) -> Option<Path> {
+        let cache_key = (start, goal);
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        let mut stats = SearchStats::default();
+        let result = self
+            .a_star_search_limited(&[start], &[goal], heuristic, |_, edge| edge_cost(edge), limit, &mut stats)
+            .ok();
+        if !stats.limit_reached {
+            cache.insert_path(cache_key, result.clone());
         }
+        result
+    }
+
+    /// The uncached A* search shared by all A* entry points. The search starts from all `starts` at
+    /// once and ends at whichever of the `goals` is reached first.
+    /// `heuristic` estimates the remaining cost from a node to the goals.
+    /// `edge_cost` receives the node an edge starts at and the edge itself and returns the cost of
+    /// traversing it, or `None` if the edge must not be used.
+    ///
+    /// If no goal can be reached, the error holds the path to the expanded node with the lowest
+    /// heuristic, i.e. the closest the search got to the goals.
+    pub(crate) fn a_star_search<H, C>(
+        &self,
+        starts: &[usize],
+        goals: &[usize],
+        heuristic: H,
+        edge_cost: C,
+        stats: &mut SearchStats,
+    ) -> Result<Path, Path>
+    where
+        H: Fn(usize) -> Coord,
+        C: Fn(usize, &Edge) -> Option<Coord>,
+    {
+        self.a_star_search_limited(starts, goals, heuristic, edge_cost, SearchLimit::default(), stats)
+    }
 
+    /// Like `a_star_search`, but gives up once the `limit` is exceeded. A search that gave up
+    /// fails like one that found no path, with `stats.limit_reached` set.
+    pub(crate) fn a_star_search_limited<H, C>(
+        &self,
+        starts: &[usize],
+        goals: &[usize],
+        heuristic: H,
+        edge_cost: C,
+        limit: SearchLimit,
+        stats: &mut SearchStats,
+    ) -> Result<Path, Path>
+    where
+        H: Fn(usize) -> Coord,
+        C: Fn(usize, &Edge) -> Option<Coord>,
+    {
         let num_nodes = self.nodes.len();
-        let mut open_set = BinaryHeap::with_capacity(num_nodes);
+        // Every node is in the open set at most once, its priority is lowered when a cheaper
+        // path to it is found
+        let mut open_set = IndexedBinaryHeap::new(num_nodes);
         let mut came_from = vec![None; num_nodes];
-        let mut g_score = vec![f32::INFINITY; num_nodes];
-        let mut f_score = vec![f32::INFINITY; num_nodes];
+        let mut g_score = vec![Coord::INFINITY; num_nodes];
+        let mut f_score = vec![Coord::INFINITY; num_nodes];
         let mut closed_set = vec![false; num_nodes];
+        let mut closest: Option<(Coord, usize)> = None;
+        let mut popped = 0;
 
-        g_score[start] = 0.0;
-        f_score[start] = self.heuristic(start, goal);
+        for &start in starts {
+            if open_set.contains(start) {
+                continue;
+            }
+            g_score[start] = 0.0;
+            f_score[start] = heuristic(start);
+            open_set.push(start, f_score[start]);
+        }
 
-        open_set.push(State {
-            cost: f_score[start],
-            position: start,
-        });
+        while let Some((current, _)) = open_set.pop() {
+            popped += 1;
+            stats.states_popped += 1;
+            if limit.is_exceeded(popped) {
+                stats.limit_reached = true;
+                break;
+            }
 
-        while let Some(State { cost: _, position: current }) = open_set.pop() {
-            if current == goal {
+            if goals.contains(&current) {
                 // Path found
-                let mut total_path = Vec::new();
-                let mut current = current;
+                return Ok(self.reconstruct_path(&came_from, current));
+            }
 
-                total_path.push(self.nodes[current]);
+            closed_set[current] = true;
+            stats.nodes_expanded += 1;
 
-                while let Some(next) = came_from[current] {
-                    total_path.push(self.nodes[next]);
-                    current = next;
+            let remaining = f_score[current] - g_score[current];
+            if closest.is_none_or(|(closest_remaining, _)| remaining < closest_remaining) {
+                closest = Some((remaining, current));
+            }
+
+            for edge in &self.edges[current] {
+                let neighbor = edge.to;
+
+                if closed_set[neighbor] {
+                    continue;
+                }
+
+                let Some(cost) = edge_cost(current, edge) else {
+                    continue;
+                };
+                let tentative_g_score = g_score[current] + cost;
+
+                if tentative_g_score < g_score[neighbor]
+                    && limit.max_cost.is_none_or(|max_cost| tentative_g_score <= max_cost)
+                {
+                    came_from[neighbor] = Some(current);
+                    g_score[neighbor] = tentative_g_score;
+                    f_score[neighbor] = tentative_g_score + heuristic(neighbor);
+                    if open_set.contains(neighbor) {
+                        open_set.decrease_key(neighbor, f_score[neighbor]);
+                    } else {
+                        open_set.push(neighbor, f_score[neighbor]);
+                    }
+                }
+            }
+        }
+
+        Err(match closest {
+            Some((_, node)) => self.reconstruct_path(&came_from, node),
+            None => Arc::new(Vec::new()),
+        })
+    }
+
+    /// Starts an A* search from start to goal that runs in slices, e.g. to stay within the time
+    /// budget of a frame on huge graphs. Each call of `a_star_step` expands one node, and the
+    /// completed search finds the same path as `a_star`. The cache is not used.
+    pub fn a_star_start(&self, start: usize, goal: usize) -> AStarState {
+        let num_nodes = self.nodes.len();
+        let mut g_score = vec![Coord::INFINITY; num_nodes];
+        g_score[start] = 0.0;
+        let mut open_set = IndexedBinaryHeap::new(num_nodes);
+        open_set.push(start, self.heuristic(start, goal));
+        AStarState {
+            goal,
+            open_set,
+            came_from: vec![None; num_nodes],
+            g_score,
+            closed_set: vec![false; num_nodes],
+            result: None,
+        }
+    }
+
+    /// Advances a search started with `a_star_start` by one node. Returns
+    /// `StepResult::InProgress` until the goal is reached or every reachable node is expanded,
+    /// and the same `StepResult::Complete` on every call after that. The state must not be used
+    /// with a different graph.
+    pub fn a_star_step(&self, state: &mut AStarState) -> StepResult {
+        if let Some(result) = &state.result {
+            return StepResult::Complete(result.clone());
+        }
+
+        let Some((current, _)) = state.open_set.pop() else {
+            state.result = Some(None);
+            return StepResult::Complete(None);
+        };
+        if current == state.goal {
+            let path = self.reconstruct_path(&state.came_from, current);
+            state.result = Some(Some(path.clone()));
+            return StepResult::Complete(Some(path));
+        }
+        state.closed_set[current] = true;
+
+        for edge in &self.edges[current] {
+            let neighbor = edge.to;
+            if state.closed_set[neighbor] {
+                continue;
+            }
+            let tentative_g_score = state.g_score[current] + self.traversal_cost(edge);
+            if tentative_g_score < state.g_score[neighbor] {
+                state.came_from[neighbor] = Some(current);
+                state.g_score[neighbor] = tentative_g_score;
+                let f_score = tentative_g_score + self.heuristic(neighbor, state.goal);
+                if state.open_set.contains(neighbor) {
+                    state.open_set.decrease_key(neighbor, f_score);
+                } else {
+                    state.open_set.push(neighbor, f_score);
+                }
+            }
+        }
+        StepResult::InProgress
+    }
+
+    /// Finds a path from start to goal using iterative-deepening A* (IDA*).
+    ///
+    /// IDA* runs repeated depth-first searches bounded by an increasing f-score threshold. It only
+    /// keeps the current branch in memory, so memory use is proportional to the path length
+    /// instead of the graph size, which makes it suitable for memory-constrained targets. The
+    /// price is speed: nodes are re-expanded on every iteration and on navmeshes, where many
+    /// routes lead to the same node, the number of expansions can grow exponentially with the
+    /// path length. Prefer `a_star` unless memory is the limiting factor.
+    ///
+    /// The cache is accepted for uniformity with the other search methods but is neither read
+    /// nor written, since storing paths would defeat the purpose of IDA*.
+    pub fn ida_star(
+        &self,
+        start: usize,
+        goal: usize,
+        _cache: &impl PathCache,
+    ) -> Option<Path> {
+        if start == goal {
+            return Some(Arc::new(vec![self.nodes[start].clone()]));
+        }
+
+        let mut threshold = self.heuristic(start, goal);
+
+        loop {
+            let mut next_threshold = Coord::INFINITY;
+            // The current branch as (node, index of the next edge to try) with its g-scores
+            let mut branch = vec![(start, 0)];
+            let mut g_scores = vec![0.0];
+
+            while let Some((node, edge_index)) = branch.last_mut() {
+                let Some(edge) = self.edges[*node].get(*edge_index) else {
+                    branch.pop();
+                    g_scores.pop();
+                    continue;
+                };
+                *edge_index += 1;
+
+                let neighbor = edge.to;
+                if branch.iter().any(|&(id, _)| id == neighbor) {
+                    continue;
+                }
+
+                let g_score = g_scores[g_scores.len() - 1] + self.traversal_cost(edge);
+                let f_score = g_score + self.heuristic(neighbor, goal);
+                if f_score > threshold {
+                    next_threshold = next_threshold.min(f_score);
+                    continue;
+                }
+
+                if neighbor == goal {
+                    let total_path = branch
+                        .iter()
+                        .map(|&(id, _)| self.nodes[id].clone())
+                        .chain(std::iter::once(self.nodes[goal].clone()))
+                        .collect();
+                    return Some(Arc::new(total_path));
+                }
+
+                branch.push((neighbor, 0));
+                g_scores.push(g_score);
+            }
+
+            if next_threshold.is_infinite() {
+                return None;
+            }
+            threshold = next_threshold;
+        }
+    }
+
+    /// Runs A* simultaneously forward from start and backward from goal.
+    /// The search keeps track of the cheapest path found through any node reached by both
+    /// frontiers and stops once neither frontier can produce a cheaper one, so the result costs
+    /// no more than the one returned by `a_star`. Results share the cache with `a_star`.
+    pub fn a_star_bidirectional(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+    ) -> Option<Path> {
+        let cache_key = (start, goal);
+
+        // Check if the path is already in cache
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        if start == goal {
+            let result = Some(Arc::new(vec![self.nodes[start].clone()]));
+            cache.insert_path(cache_key, result.clone());
+            return result;
+        }
+
+        let num_nodes = self.nodes.len();
+        let reverse_edges = self.reverse_edges();
+
+        let mut open_sets = [BinaryHeap::new(), BinaryHeap::new()];
+        let mut came_from = [vec![None; num_nodes], vec![None; num_nodes]];
+        let mut g_scores = [vec![Coord::INFINITY; num_nodes], vec![Coord::INFINITY; num_nodes]];
+        let mut closed_sets = [vec![false; num_nodes], vec![false; num_nodes]];
+        let targets = [goal, start];
+
+        for (direction, origin) in [start, goal].into_iter().enumerate() {
+            g_scores[direction][origin] = 0.0;
+            open_sets[direction].push(State {
+                cost: self.heuristic(origin, targets[direction]),
+                position: origin,
+            });
+        }
+
+        // Cost of the best complete path found so far and the node where both searches meet
+        let mut best_cost = Coord::INFINITY;
+        let mut meeting_node = None;
+
+        loop {
+            // Drop entries of nodes that were already expanded
+            for direction in 0..2 {
+                while let Some(state) = open_sets[direction].peek() {
+                    if closed_sets[direction][state.position] {
+                        open_sets[direction].pop();
+                    } else {
+                        break;
+                    }
+                }
+            }
+
+            let (Some(forward), Some(backward)) = (open_sets[0].peek(), open_sets[1].peek()) else {
+                break;
+            };
+
+            // Every path not found yet costs at least as much as the lowest f-score of either
+            // frontier, so once both reach the best cost it is optimal
+            if forward.cost >= best_cost || backward.cost >= best_cost {
+                break;
+            }
+
+            // Expand the frontier with the smaller open set
+            let direction = if open_sets[0].len() <= open_sets[1].len() { 0 } else { 1 };
+            let other = 1 - direction;
+            let State { cost: _, position: current } = open_sets[direction].pop().unwrap();
+            closed_sets[direction][current] = true;
+
+            let edges = if direction == 0 {
+                &self.edges[current]
+            } else {
+                &reverse_edges[current]
+            };
+
+            for edge in edges {
+                let neighbor = edge.to;
+
+                if closed_sets[direction][neighbor] {
+                    continue;
+                }
+
+                // Reversed edges enter the current node in the original graph
+                let entered = if direction == 0 { neighbor } else { current };
+                let tentative_g_score = g_scores[direction][current] + edge.cost * self.nodes[entered].weight;
+
+                if tentative_g_score < g_scores[direction][neighbor] {
+                    came_from[direction][neighbor] = Some(current);
+                    g_scores[direction][neighbor] = tentative_g_score;
+                    open_sets[direction].push(State {
+                        cost: tentative_g_score + self.heuristic(neighbor, targets[direction]),
+                        position: neighbor,
+                    });
                 }
 
-                total_path.reverse();
+                let total_cost = g_scores[direction][neighbor] + g_scores[other][neighbor];
+                if total_cost < best_cost {
+                    best_cost = total_cost;
+                    meeting_node = Some(neighbor);
+                }
+            }
+        }
+
+        let result = meeting_node.map(|meeting_node| {
+            // Forward half ends at the meeting node, backward half continues from it to goal
+            let mut total_path = (*self.reconstruct_path(&came_from[0], meeting_node)).clone();
+            let mut current = meeting_node;
+            while let Some(next) = came_from[1][current] {
+                total_path.push(self.nodes[next].clone());
+                current = next;
+            }
+            Arc::new(total_path)
+        });
+
+        cache.insert_path(cache_key, result.clone());
+
+        result
+    }
+
+    /// Builds the transposed adjacency list, where `edges[to]` contains an edge back to `from`
+    /// for every edge `from -> to` of the graph.
+    pub(crate) fn reverse_edges(&self) -> Vec<Vec<Edge>> {
+        let mut reverse_edges = vec![Vec::new(); self.nodes.len()];
+        for (from, edges) in self.edges.iter().enumerate() {
+            for edge in edges {
+                reverse_edges[edge.to].push(Edge {
+                    to: from,
+                    ..edge.clone()
+                });
+            }
+        }
+        reverse_edges
+    }
+
+    /// Finds the cheapest path from start to goal using Dijkstra's algorithm.
+    /// Unlike `a_star` this does not rely on a heuristic, so it returns optimal paths even when
+    /// edge costs do not correspond to Euclidean distances. Results share the cache with `a_star`.
+    pub fn dijkstra(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+    ) -> Option<Path> {
+        let cache_key = (start, goal);
+
+        // Check if the path is already in cache
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
 
-                let result = Some(Arc::new(total_path));
+        let num_nodes = self.nodes.len();
+        let mut open_set = BinaryHeap::with_capacity(num_nodes);
+        let mut came_from = vec![None; num_nodes];
+        let mut dist = vec![Coord::INFINITY; num_nodes];
+        let mut closed_set = vec![false; num_nodes];
 
-                // Cache the result
-                cache.insert(cache_key, result.clone());
+        dist[start] = 0.0;
+        open_set.push(State {
+            cost: 0.0,
+            position: start,
+        });
 
+        while let Some(State { cost: _, position: current }) = open_set.pop() {
+            if current == goal {
+                let result = Some(self.reconstruct_path(&came_from, current));
+                cache.insert_path(cache_key, result.clone());
                 return result;
             }
 
@@ -103,27 +1287,86 @@ impl Graph {
                     continue;
                 }
 
-                let tentative_g_score = g_score[current] + edge.cost;
+                let tentative_dist = dist[current] + self.traversal_cost(edge);
 
-                if tentative_g_score < g_score[neighbor] {
+                if tentative_dist < dist[neighbor] {
                     came_from[neighbor] = Some(current);
-                    g_score[neighbor] = tentative_g_score;
-                    f_score[neighbor] = tentative_g_score + self.heuristic(neighbor, goal);
+                    dist[neighbor] = tentative_dist;
                     open_set.push(State {
-                        cost: f_score[neighbor],
+                        cost: tentative_dist,
                         position: neighbor,
                     });
                 }
             }
         }
 
-        // Cache the non-result
-        cache.insert(cache_key, None);
+        cache.insert_path(cache_key, None);
 
         None
     }
 
-    pub fn nearest_node(&self, x: f32, y: f32, z: f32) -> Option<usize> {
+    /// Returns every node that can be reached from `start` at a cost of at most `max_cost`, e.g.
+    /// to pick spawn points or loot locations within reach, paired with the cost of the cheapest
+    /// path to it. Costs vary between edges, so this runs Dijkstra's algorithm rather than a
+    /// breadth-first search, and stops once the cheapest remaining node is too expensive. The
+    /// result is sorted by the cost and then the node id, and starts with `(start, 0)`.
+    pub fn reachable_nodes_bfs(&self, start: usize, max_cost: Coord) -> Vec<(usize, Coord)> {
+        let mut dist = vec![Coord::INFINITY; self.nodes.len()];
+        let mut open_set = BinaryHeap::new();
+        let mut reachable = Vec::new();
+        if max_cost < 0.0 {
+            return reachable;
+        }
+        dist[start] = 0.0;
+        open_set.push(State { cost: 0.0, position: start });
+
+        while let Some(State { cost, position: current }) = open_set.pop() {
+            if cost > dist[current] {
+                continue;
+            }
+            reachable.push((current, cost));
+            for edge in &self.edges[current] {
+                let tentative_dist = cost + self.traversal_cost(edge);
+                if tentative_dist <= max_cost && tentative_dist < dist[edge.to] {
+                    dist[edge.to] = tentative_dist;
+                    open_set.push(State { cost: tentative_dist, position: edge.to });
+                }
+            }
+        }
+        reachable.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+        reachable
+    }
+
+    /// Walks the `came_from` links back from `goal` and returns the path in start-to-goal order.
+    pub(crate) fn reconstruct_path(&self, came_from: &[Option<usize>], goal: usize) -> Path {
+        let mut total_path = Vec::new();
+        let mut current = goal;
+
+        total_path.push(self.nodes[current].clone());
+
+        while let Some(next) = came_from[current] {
+            total_path.push(self.nodes[next].clone());
+            current = next;
+        }
+
+        total_path.reverse();
+
+        Arc::new(total_path)
+    }
+
+    /// Returns the id of the node closest to the given coordinates, the lowest id on ties, or
+    /// `None` if the graph has no nodes. Graphs with at least `PARALLEL_NEAREST_NODE_THRESHOLD`
+    /// nodes are scanned in parallel, see `nearest_node_with_threshold`.
+    pub fn nearest_node(&self, x: Coord, y: Coord, z: Coord) -> Option<usize> {
+        self.nearest_node_with_threshold(x, y, z, PARALLEL_NEAREST_NODE_THRESHOLD)
+    }
+
+    /// Like `nearest_node`, but scans the nodes in parallel only if there are at least
+    /// `parallel_threshold` of them, e.g. `usize::MAX` to always stay on the current thread.
+    pub fn nearest_node_with_threshold(&self, x: Coord, y: Coord, z: Coord, parallel_threshold: usize) -> Option<usize> {
+        if self.nodes.len() >= parallel_threshold {
+            return self.nearest_node_parallel(x, y, z);
+        }
         self.nodes
             .iter()
             .enumerate()
@@ -131,10 +1374,47 @@ impl Graph {
                 let d = distance(&(node.x, node.y, node.z), &(x, y, z));
                 (d, id)
             })
-            .min_by(|a, b| a.0.partial_cmp(&b.0).unwrap())
+            .min_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, id)| id)
+    }
+
+    /// Like `nearest_node`, but always splits the scan across the rayon thread pool, which only
+    /// pays off on large graphs. Returns the same node as the sequential scan.
+    pub fn nearest_node_parallel(&self, x: Coord, y: Coord, z: Coord) -> Option<usize> {
+        self.nodes
+            .par_iter()
+            .enumerate()
+            .map(|(id, node)| (distance(&(node.x, node.y, node.z), &(x, y, z)), id))
+            // Comparing the ids as well keeps the lowest id on ties however the nodes are split
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
             .map(|(_, id)| id)
     }
 
+    /// Like `nearest_node`, but compares the squared distances to four nodes at once with SSE on
+    /// x86_64. Other targets and the `precision-f64` feature use `nearest_node`. Among nodes at
+    /// the same distance, the one with the lowest id is returned as well.
+    #[cfg(feature = "simd")]
+    pub fn nearest_node_simd(&self, x: Coord, y: Coord, z: Coord) -> Option<usize> {
+        #[cfg(all(target_arch = "x86_64", not(feature = "precision-f64")))]
+        {
+            nearest_node_sse(&self.nodes, x, y, z)
+        }
+        #[cfg(not(all(target_arch = "x86_64", not(feature = "precision-f64"))))]
+        {
+            self.nearest_node(x, y, z)
+        }
+    }
+
+    /// Returns the ids of the nodes whose `Node::group` is `group`, in ascending order, e.g. the
+    /// nodes of a room named by an `o` or `g` line of an OBJ navmesh.
+    pub fn nodes_in_group(&self, group: &str) -> Vec<usize> {
+        self.nodes
+            .iter()
+            .filter(|node| node.group.as_deref() == Some(group))
+            .map(|node| node.id)
+            .collect()
+    }
+
     pub fn random_node(&self) -> Option<usize> {
         let node_ids: Vec<_> = (0..self.nodes.len()).collect();
         if node_ids.is_empty() {
@@ -144,11 +1424,572 @@ impl Graph {
             Some(*node_ids.choose(&mut rng).unwrap())
         }
     }
+
+    /// Walks `steps` random edges from `start`, e.g. for exploration behavior, and returns the
+    /// ids of the visited nodes starting with `start`. Each step picks an edge of the current node
+    /// with a probability proportional to the inverse of its cost, so cheaper edges are more
+    /// likely, and edges without cost are taken before all others. The walk ends early at a node
+    /// without edges. Panics if `start` is not in the graph.
+    pub fn random_walk(&self, start: usize, steps: usize, rng: &mut impl Rng) -> Vec<usize> {
+        let mut walk = vec![start];
+        let mut current = start;
+        for _ in 0..steps {
+            let Some(next) = self.random_neighbor(current, rng) else {
+                break;
+            };
+            walk.push(next);
+            current = next;
+        }
+        walk
+    }
+
+    /// Like `random_walk`, but also ends before a node would be visited a second time, so all
+    /// returned ids are distinct.
+    pub fn random_walk_unique(&self, start: usize, steps: usize, rng: &mut impl Rng) -> Vec<usize> {
+        let mut visited = HashSet::from([start]);
+        let mut walk = vec![start];
+        let mut current = start;
+        for _ in 0..steps {
+            match self.random_neighbor(current, rng) {
+                Some(next) if visited.insert(next) => {
+                    walk.push(next);
+                    current = next;
+                }
+                _ => break,
+            }
+        }
+        walk
+    }
+
+    /// Finds a random path from start to goal, e.g. so that agents do not all take the same
+    /// route. Each step picks an edge of the current node with a probability proportional to
+    /// `exp(-excess / temperature)`, where `excess` is how much more expensive the cheapest path
+    /// to the goal through the edge is than through the best edge. A temperature of 0 always
+    /// takes the best edge and finds a cheapest path, high temperatures approach a random walk
+    /// among the nodes that can reach the goal. Loops are cut out of the path, so every node
+    /// appears once.
+    ///
+    /// The cheapest costs to the goal are computed up front with Dijkstra's algorithm in
+    /// O(|E| log |V|). Returns `None` if the goal cannot be reached, or if it was not reached after
+    /// `RANDOM_PATH_STEPS_PER_NODE` steps per node of the graph.
+    pub fn random_weighted_path(
+        &self,
+        start: usize,
+        goal: usize,
+        rng: &mut impl Rng,
+        temperature: Coord,
+    ) -> Option<Path> {
+        let remaining = distances_to(self, &self.reverse_edges(), goal);
+        if !remaining[start].is_finite() {
+            return None;
+        }
+
+        let mut path = vec![start];
+        // The index of every node in `path`, or `usize::MAX` if it is not on the path
+        let mut positions = vec![usize::MAX; self.nodes.len()];
+        positions[start] = 0;
+        let mut current = start;
+        for _ in 0..self.nodes.len().saturating_mul(RANDOM_PATH_STEPS_PER_NODE) {
+            if current == goal {
+                return Some(Arc::new(path.iter().map(|&id| self.nodes[id].clone()).collect()));
+            }
+
+            // Every node on the way can reach the goal, so at least one edge leads closer to it
+            let candidates: Vec<(usize, Coord)> = self.edges[current]
+                .iter()
+                .map(|edge| (edge.to, self.traversal_cost(edge) + remaining[edge.to]))
+                .filter(|(_, cost)| cost.is_finite())
+                .collect();
+            let best = candidates.iter().map(|&(_, cost)| cost).fold(Coord::INFINITY, Coord::min);
+            let next = if temperature <= 0.0 {
+                candidates.iter().find(|&&(_, cost)| cost == best).map(|&(id, _)| id)?
+            } else {
+                let weights: Vec<Coord> =
+                    candidates.iter().map(|&(_, cost)| (-(cost - best) / temperature).exp()).collect();
+                let mut sample = rng.gen::<Coord>() * weights.iter().sum::<Coord>();
+                let mut next = candidates.last()?.0;
+                for (&(id, _), weight) in candidates.iter().zip(&weights) {
+                    if sample < *weight {
+                        next = id;
+                        break;
+                    }
+                    sample -= weight;
+                }
+                next
+            };
+
+            if positions[next] == usize::MAX {
+                positions[next] = path.len();
+                path.push(next);
+            } else {
+                // Back at a node of the path, cut out the loop
+                for id in path.drain(positions[next] + 1..) {
+                    positions[id] = usize::MAX;
+                }
+            }
+            current = next;
+        }
+        None
+    }
+
+    /// Picks an edge of the node as described in `random_walk` and returns the node it leads to.
+    fn random_neighbor(&self, node_id: usize, rng: &mut impl Rng) -> Option<usize> {
+        let edges = &self.edges[node_id];
+        let free: Vec<&Edge> = edges.iter().filter(|edge| edge.cost <= 0.0).collect();
+        if let Some(edge) = free.choose(rng) {
+            return Some(edge.to);
+        }
+
+        let weights: Vec<Coord> = edges.iter().map(|edge| 1.0 / edge.cost).collect();
+        let mut remaining = rng.gen::<Coord>() * weights.iter().sum::<Coord>();
+        for (edge, weight) in edges.iter().zip(&weights) {
+            if remaining < *weight {
+                return Some(edge.to);
+            }
+            remaining -= weight;
+        }
+        // Rounding can leave a tiny remainder after the last edge
+        edges.last().map(|edge| edge.to)
+    }
+
+    /// Returns the number of edges leading away from the node in O(1).
+    pub fn out_degree(&self, node_id: usize) -> usize {
+        self.edges[node_id].len()
+    }
+
+    /// Returns the number of edges leading to the node. The first call after a change of the
+    /// graph builds an index of all nodes in O(|V| + |E|), later calls take O(1).
+    pub fn in_degree(&self, node_id: usize) -> usize {
+        let in_degrees = self.in_degrees.get_or_init(|| {
+            let mut in_degrees = vec![0; self.nodes.len()];
+            for edge in self.edges.iter().flatten() {
+                in_degrees[edge.to] += 1;
+            }
+            in_degrees
+        });
+        in_degrees[node_id]
+    }
+
+    /// Returns the average out-degree of the nodes, i.e. the number of edges per node, in O(|V|),
+    /// or 0 for an empty graph. Edges in both directions count twice.
+    pub fn average_degree(&self) -> Coord {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+        self.edges.iter().map(Vec::len).sum::<usize>() as Coord / self.nodes.len() as Coord
+    }
+
+    /// Returns how many nodes have each out-degree as `(degree, count)` pairs in ascending order
+    /// of the degree, leaving out degrees no node has, in O(|V| log |V|). Many nodes of degree 0
+    /// or 1 hint at a poorly connected navmesh.
+    pub fn degree_histogram(&self) -> Vec<(usize, usize)> {
+        let mut degrees: Vec<usize> = self.edges.iter().map(Vec::len).collect();
+        degrees.sort_unstable();
+        degrees
+            .chunk_by(|a, b| a == b)
+            .map(|group| (group[0], group.len()))
+            .collect()
+    }
+
+    /// Returns the fraction of possible edges the graph has in O(|V|): 1 if every node is
+    /// connected to every other one, 0 without edges or with fewer than two nodes. Edges in both
+    /// directions count as one, so this is `2 * |E| / (|V| * (|V| - 1))` for an undirected graph
+    /// with `|E|` edges. Self-loops and parallel edges can make it exceed 1.
+    pub fn density(&self) -> Coord {
+        let node_count = self.nodes.len() as f64;
+        if node_count < 2.0 {
+            return 0.0;
+        }
+        let directed_edges = self.edges.iter().map(Vec::len).sum::<usize>() as f64;
+        (directed_edges / (node_count * (node_count - 1.0))) as Coord
+    }
+
+    /// Returns how close the neighborhood of the node is to a clique, between 0 and 1. The
+    /// neighbors are the `k` other nodes the node has an edge to or from, and the coefficient is
+    /// `links / (k * (k - 1))`, where `links` counts the ordered pairs of neighbors `(a, b)` with an
+    /// edge from `a` to `b`. For a graph with edges in both directions this is the usual
+    /// undirected coefficient, the number of connected pairs of neighbors divided by
+    /// `k * (k - 1) / 2`. Nodes with fewer than two neighbors have the coefficient 0.
+    ///
+    /// Finding the nodes with an edge to this one takes O(|E|), use
+    /// `average_clustering_coefficient` for all nodes.
+    pub fn local_clustering_coefficient(&self, node_id: usize) -> Coord {
+        let incoming: Vec<usize> = self
+            .edges
+            .iter()
+            .enumerate()
+            .filter(|(_, edges)| edges.iter().any(|edge| edge.to == node_id))
+            .map(|(from, _)| from)
+            .collect();
+        self.clustering_coefficient(node_id, &incoming)
+    }
+
+    /// Returns the mean of `local_clustering_coefficient` over all nodes, or 0 for an empty
+    /// graph. Triangulated navmeshes have a high average, graphs made up of corridors a low one.
+    pub fn average_clustering_coefficient(&self) -> Coord {
+        if self.nodes.is_empty() {
+            return 0.0;
+        }
+        let reverse_edges = self.reverse_edges();
+        let sum: Coord = (0..self.nodes.len())
+            .map(|id| {
+                let incoming: Vec<usize> = reverse_edges[id].iter().map(|edge| edge.to).collect();
+                self.clustering_coefficient(id, &incoming)
+            })
+            .sum();
+        sum / self.nodes.len() as Coord
+    }
+
+    /// Computes `local_clustering_coefficient` with the nodes that have an edge to the node given.
+    fn clustering_coefficient(&self, node_id: usize, incoming: &[usize]) -> Coord {
+        let neighbors: HashSet<usize> = self.edges[node_id]
+            .iter()
+            .map(|edge| edge.to)
+            .chain(incoming.iter().copied())
+            .filter(|&id| id != node_id)
+            .collect();
+        let k = neighbors.len();
+        if k < 2 {
+            return 0.0;
+        }
+
+        let links: usize = neighbors
+            .iter()
+            .map(|&a| {
+                let targets: HashSet<usize> = self.edges[a].iter().map(|edge| edge.to).collect();
+                targets.iter().filter(|&&b| b != a && neighbors.contains(&b)).count()
+            })
+            .sum();
+        links as Coord / (k * (k - 1)) as Coord
+    }
+
+    /// Returns the corners of the axis-aligned bounding box of all nodes as synthetic nodes with
+    /// the id `usize::MAX`: the minimum and the maximum of every coordinate. Returns `None` for
+    /// an empty graph.
+    pub fn bounds(&self) -> Option<(Node, Node)> {
+        let first = self.nodes.first()?;
+        let (mut min, mut max) = ((first.x, first.y, first.z), (first.x, first.y, first.z));
+        for node in &self.nodes[1..] {
+            min = (min.0.min(node.x), min.1.min(node.y), min.2.min(node.z));
+            max = (max.0.max(node.x), max.1.max(node.y), max.2.max(node.z));
+        }
+        Some((
+            Node::new(usize::MAX, min.0, min.1, min.2),
+            Node::new(usize::MAX, max.0, max.1, max.2),
+        ))
+    }
+
+    /// Returns the centroid of the graph, i.e. the average position of its nodes, or the origin
+    /// for an empty graph.
+    // The casts are no-ops with the `precision-f64` feature
+    #[allow(clippy::unnecessary_cast)]
+    pub fn center(&self) -> (Coord, Coord, Coord) {
+        if self.nodes.is_empty() {
+            return (0.0, 0.0, 0.0);
+        }
+        // Summed up in f64, since f32 sums lose precision on large navmeshes
+        let sum = self.nodes.iter().fold((0.0, 0.0, 0.0), |sum: (f64, f64, f64), node| {
+            (sum.0 + node.x as f64, sum.1 + node.y as f64, sum.2 + node.z as f64)
+        });
+        let count = self.nodes.len() as f64;
+        ((sum.0 / count) as Coord, (sum.1 / count) as Coord, (sum.2 / count) as Coord)
+    }
+
+    /// Returns a copy of the graph moved and scaled into the unit cube `[0, 1]³`, e.g. to compare
+    /// navmeshes of different sizes. The longest side of the bounding box is mapped to `[0, 1]`
+    /// with `x' = (x - min_x) / (max_x - min_x)`, and the other sides are scaled by the same
+    /// factor and centered in the cube, so the shape and the proportions of the edge costs are
+    /// kept. The edge costs are scaled by the same factor and the edges stay unchanged.
+    pub fn normalize_coordinates(&self) -> Graph {
+        let mut normalized = self.clone();
+        let Some((min, max)) = self.bounds() else {
+            return normalized;
+        };
+        let extent = (max.x - min.x, max.y - min.y, max.z - min.z);
+        let longest = extent.0.max(extent.1).max(extent.2);
+        // A graph on a single point only moves to the center of the cube
+        let longest = if longest > 0.0 { longest } else { 1.0 };
+        let offset = |extent: Coord| (1.0 - extent / longest) / 2.0;
+        let offset = (offset(extent.0), offset(extent.1), offset(extent.2));
+        // Clamped, since rounding could move the maximum of a shorter side just past 1
+        let normalize = |value: Coord, min: Coord, offset: Coord| ((value - min) / longest + offset).clamp(0.0, 1.0);
+
+        for node in &mut normalized.nodes {
+            node.x = normalize(node.x, min.x, offset.0);
+            node.y = normalize(node.y, min.y, offset.1);
+            node.z = normalize(node.z, min.z, offset.2);
+        }
+        for edge in normalized.edges.iter_mut().flatten() {
+            edge.cost /= longest;
+        }
+        normalized
+    }
+
+    /// Returns a copy of the graph projected onto the `y = 0` plane, so distances and the
+    /// heuristic only measure `sqrt(dx² + dz²)`. Every edge cost is scaled by how much shorter
+    /// the edge becomes, so edges costing their length cost their flat length afterwards. Edges
+    /// that become, or were, zero length keep their cost.
+    pub fn flatten(&self) -> Graph {
+        let mut flattened = self.clone();
+        for (from, edges) in flattened.edges.iter_mut().enumerate() {
+            let start = &self.nodes[from];
+            for edge in edges {
+                let end = &self.nodes[edge.to];
+                let length = distance(&(start.x, start.y, start.z), &(end.x, end.y, end.z));
+                let flat_length = distance(&(start.x, 0.0, start.z), &(end.x, 0.0, end.z));
+                if flat_length > 0.0 {
+                    edge.cost *= flat_length / length;
+                }
+            }
+        }
+        for node in &mut flattened.nodes {
+            node.y = 0.0;
+        }
+        flattened
+    }
+
+    /// Returns a new graph with only the given nodes and the edges between them, e.g. a single
+    /// room of a level. The nodes get the ids `0..` in the order of `node_ids`, repeated ids are
+    /// taken once. Edges keep their attributes. Panics if a node id is not in the graph.
+    pub fn subgraph(&self, node_ids: &[usize]) -> Graph {
+        let mut new_ids = vec![usize::MAX; self.nodes.len()];
+        let mut subgraph = Graph::new();
+        let mut kept = Vec::with_capacity(node_ids.len());
+        for &id in node_ids {
+            if new_ids[id] == usize::MAX {
+                new_ids[id] = kept.len();
+                kept.push(id);
+                subgraph.add_node(Node {
+                    id: new_ids[id],
+                    ..self.nodes[id].clone()
+                });
+            }
+        }
+
+        for (from, &old_id) in kept.iter().enumerate() {
+            for edge in self.edges[old_id].iter().filter(|edge| new_ids[edge.to] != usize::MAX) {
+                subgraph.push_edge(from, Edge {
+                    to: new_ids[edge.to],
+                    ..edge.clone()
+                });
+            }
+        }
+        subgraph
+    }
+
+    /// Returns `subgraph` of the nodes inside the axis-aligned box from the minimum to the maximum
+    /// coordinates, including its faces, in the order of their ids.
+    pub fn subgraph_in_aabb(
+        &self,
+        min_x: Coord,
+        min_y: Coord,
+        min_z: Coord,
+        max_x: Coord,
+        max_y: Coord,
+        max_z: Coord,
+    ) -> Graph {
+        let node_ids: Vec<usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                (min_x..=max_x).contains(&node.x)
+                    && (min_y..=max_y).contains(&node.y)
+                    && (min_z..=max_z).contains(&node.z)
+            })
+            .map(|(id, _)| id)
+            .collect();
+        self.subgraph(&node_ids)
+    }
+
+    /// Appends the nodes and edges of `other`, e.g. the navmesh of a world chunk that was
+    /// streamed in, and returns the combined graph. The nodes of this graph keep their ids, the
+    /// ids of the nodes of `other` and its edges are offset by the number of nodes of this graph.
+    ///
+    /// The graphs are not connected, see `merge_with_bridges`. Like `add_node_with_edges`, this
+    /// increments the `generation`.
+    pub fn merge(mut self, other: Graph) -> Graph {
+        let offset = self.nodes.len();
+        for (id, (node, edges)) in other.nodes.into_iter().zip(other.edges).enumerate() {
+            self.add_node(Node {
+                id: id + offset,
+                ..node
+            });
+            for edge in edges {
+                self.push_edge(id + offset, Edge {
+                    to: edge.to + offset,
+                    ..edge
+                });
+            }
+        }
+        self.generation += 1;
+        self
+    }
+
+    /// Like `merge`, but also connects the two graphs: `bridge_fn` is called with every node of
+    /// this graph and every node of `other`, and where it returns a cost the nodes are connected
+    /// in both directions with edges of that cost. This calls `bridge_fn` `|V| * |V_other|`
+    /// times, so it should reject distant pairs quickly.
+    pub fn merge_with_bridges(self, other: Graph, bridge_fn: impl Fn(&Node, &Node) -> Option<Coord>) -> Graph {
+        let mut bridges = Vec::new();
+        for (a, node) in self.nodes.iter().enumerate() {
+            for (b, other_node) in other.nodes.iter().enumerate() {
+                if let Some(cost) = bridge_fn(node, other_node) {
+                    bridges.push((a, b, cost));
+                }
+            }
+        }
+
+        let offset = self.nodes.len();
+        let mut merged = self.merge(other);
+        for (a, b, cost) in bridges {
+            merged.add_edge(a, b + offset, cost);
+            merged.add_edge(b + offset, a, cost);
+        }
+        merged
+    }
+}
+
+/// The nearest node with SSE, see `Graph::nearest_node_simd`. Every lane keeps the closest of
+/// every fourth node, the lanes and the remaining nodes are compared at the end.
+#[cfg(all(feature = "simd", target_arch = "x86_64", not(feature = "precision-f64")))]
+fn nearest_node_sse(nodes: &[Node], x: f32, y: f32, z: f32) -> Option<usize> {
+    use std::arch::x86_64::*;
+    // SAFETY: SSE2 is part of every x86_64 target, and the unaligned stores write the four lanes
+    // into arrays of four
+    unsafe {
+        let chunks = nodes.chunks_exact(4);
+        let remainder = chunks.remainder();
+        let (query_x, query_y, query_z) = (_mm_set1_ps(x), _mm_set1_ps(y), _mm_set1_ps(z));
+        let mut best_distances = _mm_set1_ps(f32::INFINITY);
+        let mut best_ids = _mm_set1_epi32(-1);
+        let mut ids = _mm_setr_epi32(0, 1, 2, 3);
+        for chunk in chunks {
+            let component = |get: fn(&Node) -> f32, query| {
+                let difference = _mm_sub_ps(_mm_setr_ps(get(&chunk[0]), get(&chunk[1]), get(&chunk[2]), get(&chunk[3])), query);
+                _mm_mul_ps(difference, difference)
+            };
+            let distances = _mm_add_ps(
+                _mm_add_ps(component(|node| node.x, query_x), component(|node| node.y, query_y)),
+                component(|node| node.z, query_z),
+            );
+            // Only strictly closer nodes replace the best of a lane, which keeps the lowest id
+            let closer = _mm_castps_si128(_mm_cmplt_ps(distances, best_distances));
+            best_distances = _mm_min_ps(distances, best_distances);
+            best_ids = _mm_or_si128(_mm_and_si128(closer, ids), _mm_andnot_si128(closer, best_ids));
+            ids = _mm_add_epi32(ids, _mm_set1_epi32(4));
+        }
+
+        let mut lane_distances = [0.0; 4];
+        let mut lane_ids = [0; 4];
+        _mm_storeu_ps(lane_distances.as_mut_ptr(), best_distances);
+        _mm_storeu_si128(lane_ids.as_mut_ptr().cast(), best_ids);
+        let offset = nodes.len() - remainder.len();
+        lane_distances
+            .into_iter()
+            .zip(lane_ids)
+            .filter(|&(_, id)| id >= 0)
+            .map(|(distance, id)| (distance, id as usize))
+            .chain(remainder.iter().enumerate().map(|(index, node)| {
+                let (dx, dy, dz) = (node.x - x, node.y - y, node.z - z);
+                (dx * dx + dy * dy + dz * dz, offset + index)
+            }))
+            .min_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)))
+            .map(|(_, id)| id)
+    }
+}
+
+/// Statistics collected while running a search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchStats {
+    /// The number of nodes taken from the open set and expanded.
+    pub nodes_expanded: usize,
+    /// The number of states taken from the open set, including outdated duplicates of nodes that
+    /// searches with a plain `BinaryHeap` skip. `a_star` pops every node at most once.
+    pub states_popped: usize,
+    /// Whether the search gave up because it exceeded its node or time limit.
+    pub limit_reached: bool,
+}
+
+/// The open set, closed set, scores and parents of an A* search that can be paused between
+/// steps, see `Graph::a_star_start`.
+#[derive(Debug, Clone)]
+pub struct AStarState {
+    goal: usize,
+    open_set: IndexedBinaryHeap<Coord>,
+    came_from: Vec<Option<usize>>,
+    g_score: Vec<Coord>,
+    closed_set: Vec<bool>,
+    /// The result once the search is complete.
+    result: Option<Option<Path>>,
+}
+
+/// The outcome of a step of `Graph::a_star_step`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum StepResult {
+    /// The search is complete, with the path or `None` if the goal cannot be reached.
+    Complete(Option<Path>),
+    /// The search needs more steps.
+    InProgress,
+}
+
+/// Bounds on the work of a search, see `Graph::a_star_bounded` and `Graph::a_star_with_timeout`.
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct SearchLimit {
+    /// The maximum number of states taken from the open set.
+    pub max_nodes: Option<usize>,
+    /// The time after which the search gives up.
+    pub deadline: Option<Instant>,
+    /// The highest cost at which a node may be reached, nodes only reachable at a higher cost are
+    /// never entered. Unlike the other limits, running out of this budget is not giving up.
+    pub max_cost: Option<Coord>,
+}
+
+/// Hashes the parameters of a search into the first element of its cache key, see
+/// `a_star_filtered`. The parameters should start with the name of the search, so different
+/// searches sharing a cache do not collide.
+fn cache_key_prefix(parameters: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    parameters.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Returns the cache key and the rounded budget of the bucket a budget of `Graph::a_star_budgeted`
+/// falls into: the next power of two, or the budget itself for 0 and infinity.
+fn budget_bucket(max_cost: Coord) -> (u64, Coord) {
+    let bucket_budget = if max_cost == 0.0 || max_cost.is_infinite() {
+        max_cost
+    } else {
+        let rounded = max_cost.log2().ceil().exp2();
+        // Rounding of the logarithm can land one power of two too low
+        if rounded < max_cost { rounded * 2.0 } else { rounded }
+    };
+    let mut hasher = DefaultHasher::new();
+    bucket_budget.to_bits().hash(&mut hasher);
+    (hasher.finish(), bucket_budget)
+}
+
+/// The number of steps per node after which `Graph::random_weighted_path` gives up.
+pub const RANDOM_PATH_STEPS_PER_NODE: usize = 100;
+
+/// The number of states taken from the open set between two checks of the deadline, since
+/// reading the clock is slow compared to expanding a node.
+const DEADLINE_CHECK_INTERVAL: usize = 256;
+
+impl SearchLimit {
+    /// Returns whether a search that took `popped` states from the open set has to give up.
+    fn is_exceeded(&self, popped: usize) -> bool {
+        if self.max_nodes.is_some_and(|max_nodes| popped > max_nodes) {
+            return true;
+        }
+        popped.is_multiple_of(DEADLINE_CHECK_INTERVAL)
+            && self.deadline.is_some_and(|deadline| Instant::now() > deadline)
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct State {
-    pub cost: f32,
+    pub cost: Coord,
     pub position: usize,
 }
 
@@ -0,0 +1,98 @@
+/// A binary min-heap of the items `0..capacity`, such as node ids, that knows where every item
+/// is stored. Unlike `std::collections::BinaryHeap`, the priority of an item in the heap can be
+/// lowered in place with `decrease_key`, so a search never holds outdated duplicates of a node.
+#[derive(Debug, Clone)]
+pub struct IndexedBinaryHeap<T> {
+    /// The items with their priorities, in heap order.
+    heap: Vec<(usize, T)>,
+    /// The index in `heap` of every item, or `usize::MAX` if it is not in the heap.
+    positions: Vec<usize>,
+}
+
+impl<T: PartialOrd + Copy> IndexedBinaryHeap<T> {
+    /// Creates an empty heap for the items `0..capacity`.
+    pub fn new(capacity: usize) -> Self {
+        IndexedBinaryHeap {
+            heap: Vec::new(),
+            positions: vec![usize::MAX; capacity],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Returns whether the item is in the heap.
+    pub fn contains(&self, item: usize) -> bool {
+        self.positions[item] != usize::MAX
+    }
+
+    /// Adds an item that is not in the heap yet. Panics if it is already there or out of range.
+    pub fn push(&mut self, item: usize, priority: T) {
+        assert!(!self.contains(item), "Item is already in the heap");
+        self.heap.push((item, priority));
+        self.positions[item] = self.heap.len() - 1;
+        self.sift_up(self.heap.len() - 1);
+    }
+
+    /// Lowers the priority of an item in the heap. Priorities that are not lower are ignored.
+    /// Panics if the item is not in the heap.
+    pub fn decrease_key(&mut self, item: usize, priority: T) {
+        assert!(self.contains(item), "Item is not in the heap");
+        let position = self.positions[item];
+        if priority < self.heap[position].1 {
+            self.heap[position].1 = priority;
+            self.sift_up(position);
+        }
+    }
+
+    /// Removes and returns the item with the lowest priority.
+    pub fn pop(&mut self) -> Option<(usize, T)> {
+        if self.heap.is_empty() {
+            return None;
+        }
+        let last = self.heap.len() - 1;
+        self.swap(0, last);
+        let (item, priority) = self.heap.pop()?;
+        self.positions[item] = usize::MAX;
+        self.sift_down(0);
+        Some((item, priority))
+    }
+
+    fn sift_up(&mut self, mut position: usize) {
+        while position > 0 {
+            let parent = (position - 1) / 2;
+            if self.heap[position].1 >= self.heap[parent].1 {
+                break;
+            }
+            self.swap(position, parent);
+            position = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut position: usize) {
+        loop {
+            let mut smallest = position;
+            for child in [2 * position + 1, 2 * position + 2] {
+                if child < self.heap.len() && self.heap[child].1 < self.heap[smallest].1 {
+                    smallest = child;
+                }
+            }
+            if smallest == position {
+                break;
+            }
+            self.swap(position, smallest);
+            position = smallest;
+        }
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.positions[self.heap[a].0] = a;
+        self.positions[self.heap[b].0] = b;
+    }
+}
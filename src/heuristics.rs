@@ -0,0 +1,27 @@
+use crate::node::Node;
+use crate::Coord;
+
+/// Straight-line distance between two nodes. Admissible when edge costs are Euclidean distances.
+pub fn euclidean(a: &Node, b: &Node) -> Coord {
+    let dx = a.x - b.x;
+    let dy = a.y - b.y;
+    let dz = a.z - b.z;
+    (dx * dx + dy * dy + dz * dz).sqrt()
+}
+
+/// Sum of the absolute differences along each axis.
+/// Suited for grid-like navmeshes where movement is restricted to the axes.
+pub fn manhattan(a: &Node, b: &Node) -> Coord {
+    (a.x - b.x).abs() + (a.y - b.y).abs() + (a.z - b.z).abs()
+}
+
+/// Largest absolute difference along any axis.
+/// Suited for grid-like navmeshes that allow diagonal movement at the same cost as straight movement.
+pub fn chebyshev(a: &Node, b: &Node) -> Coord {
+    (a.x - b.x).abs().max((a.y - b.y).abs()).max((a.z - b.z).abs())
+}
+
+/// Always returns zero, which turns A* into Dijkstra's algorithm.
+pub fn zero(_a: &Node, _b: &Node) -> Coord {
+    0.0
+}
@@ -0,0 +1,81 @@
+use std::collections::{HashMap, HashSet};
+use dashmap::DashMap;
+use crate::graph::{Graph, SearchStats};
+use crate::node::Node;
+use crate::path::Path;
+use crate::Coord;
+
+impl Graph {
+    /// Partitions the nodes into cubes with an edge length of `cell_size` and returns the abstract
+    /// graph of the non-empty cubes together with the id of the abstract node of every node.
+    ///
+    /// Each abstract node lies at the mean position of the nodes in its cube, and gets ids in the
+    /// order the cubes first appear among the node ids. An abstract edge connects two cubes if any
+    /// edge leads from a node in one to a node in the other, with the distance between the
+    /// abstract nodes as its cost.
+    pub fn cluster(&self, cell_size: Coord) -> (Graph, Vec<usize>) {
+        let cell_key = |value: Coord| (value / cell_size).floor() as i64;
+        let mut cells = HashMap::new();
+        let mut sums: Vec<(Coord, Coord, Coord, usize)> = Vec::new();
+        let mut clusters = Vec::with_capacity(self.nodes.len());
+        for node in &self.nodes {
+            let key = (cell_key(node.x), cell_key(node.y), cell_key(node.z));
+            let cluster = *cells.entry(key).or_insert_with(|| {
+                sums.push((0.0, 0.0, 0.0, 0));
+                sums.len() - 1
+            });
+            let sum = &mut sums[cluster];
+            *sum = (sum.0 + node.x, sum.1 + node.y, sum.2 + node.z, sum.3 + 1);
+            clusters.push(cluster);
+        }
+
+        let mut abstract_graph = Graph::new();
+        for (id, &(x, y, z, count)) in sums.iter().enumerate() {
+            let count = count as Coord;
+            abstract_graph.add_node(Node::new(id, x / count, y / count, z / count));
+        }
+        let mut connected = HashSet::new();
+        for (from, edges) in self.edges.iter().enumerate() {
+            for edge in edges {
+                let (a, b) = (clusters[from], clusters[edge.to]);
+                if a != b && connected.insert((a, b)) {
+                    let cost = abstract_graph.heuristic(a, b);
+                    abstract_graph.add_edge_directed(a, b, cost);
+                }
+            }
+        }
+        (abstract_graph, clusters)
+    }
+
+    /// Finds a path with hierarchical A*: a coarse path through the abstract graph of `cluster`
+    /// first, then the cheapest path through the cubes along it only, which keeps the search
+    /// local on large navmeshes. Returns `None` only if there is no path at all.
+    ///
+    /// The path is not always the cheapest one, since the cheapest one may leave the cubes of the
+    /// coarse path. If the nodes of a cube are not connected within it, the refinement can fail,
+    /// in which case the whole graph is searched instead. The result is not cached.
+    pub fn a_star_hierarchical(&self, start: usize, goal: usize, cell_size: Coord) -> Option<Path> {
+        let (abstract_graph, clusters) = self.cluster(cell_size);
+        // Every path in the graph passes through a chain of connected cubes, so without an
+        // abstract path there is none
+        let abstract_path = abstract_graph.a_star(clusters[start], clusters[goal], &DashMap::new())?;
+        let mut corridor = vec![false; abstract_graph.nodes.len()];
+        for node in abstract_path.iter() {
+            corridor[node.id] = true;
+        }
+
+        let heuristic = |node| self.heuristic(node, goal);
+        let mut stats = SearchStats::default();
+        self.a_star_search(
+            &[start],
+            &[goal],
+            heuristic,
+            |_, edge| corridor[clusters[edge.to]].then(|| self.traversal_cost(edge)),
+            &mut stats,
+        )
+        .or_else(|_| {
+            self.a_star_search(&[start], &[goal], heuristic, |_, edge| Some(self.traversal_cost(edge)), &mut stats)
+        })
+        .ok()
+    }
+}
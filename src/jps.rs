@@ -0,0 +1,384 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::Arc;
+use crate::cache::PathCache;
+use crate::graph::{Graph, SearchStats, State};
+use crate::node::Node;
+use crate::path::Path;
+use crate::Coord;
+
+/// Relative tolerance used when matching node positions and edge costs to the grid.
+const GRID_TOLERANCE: Coord = 1e-3;
+
+/// The layout of a graph whose nodes lie on a flat, uniformly spaced 8-connected grid.
+/// Jump Point Search is only valid on such graphs.
+#[derive(Debug, Clone)]
+pub struct GridLayout {
+    spacing: Coord,
+    cells: HashMap<(i32, i32), usize>,
+    positions: Vec<(i32, i32)>,
+}
+
+impl GridLayout {
+    /// Detects whether the graph is a uniform grid.
+    ///
+    /// All nodes must share the same coordinate along one axis and lie on a square lattice in the
+    /// plane of the other two. Every node must have exactly one edge to each of its (up to eight)
+    /// neighboring cells, edge costs must equal the distance between the cells and all node
+    /// weights must be 1. Returns `None` if any of these conditions does not hold.
+    pub fn detect(graph: &Graph) -> Option<GridLayout> {
+        let first = graph.nodes.first()?;
+        if graph.nodes.iter().any(|node| node.weight != 1.0) {
+            return None;
+        }
+        let flat_axis = (0..3).find(|&axis| {
+            graph
+                .nodes
+                .iter()
+                .all(|node| approx_eq(coordinate(node, axis), coordinate(first, axis)))
+        })?;
+        let axes = match flat_axis {
+            0 => (1, 2),
+            1 => (0, 2),
+            _ => (0, 1),
+        };
+
+        // The grid spacing is the shortest step along either axis of the plane
+        let spacing = graph
+            .edges
+            .iter()
+            .enumerate()
+            .flat_map(|(from, edges)| edges.iter().map(move |edge| (from, edge.to)))
+            .flat_map(|(from, to)| {
+                let (a, b) = (&graph.nodes[from], &graph.nodes[to]);
+                [
+                    (coordinate(b, axes.0) - coordinate(a, axes.0)).abs(),
+                    (coordinate(b, axes.1) - coordinate(a, axes.1)).abs(),
+                ]
+            })
+            .filter(|&delta| delta > 0.0)
+            .min_by(|a, b| a.total_cmp(b))?;
+
+        let origin = graph.nodes.iter().fold((Coord::MAX, Coord::MAX), |origin, node| {
+            (
+                origin.0.min(coordinate(node, axes.0)),
+                origin.1.min(coordinate(node, axes.1)),
+            )
+        });
+
+        let mut cells = HashMap::with_capacity(graph.nodes.len());
+        let mut positions = Vec::with_capacity(graph.nodes.len());
+        for (id, node) in graph.nodes.iter().enumerate() {
+            let u = (coordinate(node, axes.0) - origin.0) / spacing;
+            let v = (coordinate(node, axes.1) - origin.1) / spacing;
+            if (u - u.round()).abs() > GRID_TOLERANCE || (v - v.round()).abs() > GRID_TOLERANCE {
+                return None;
+            }
+
+            let cell = (u.round() as i32, v.round() as i32);
+            if cells.insert(cell, id).is_some() {
+                return None;
+            }
+            positions.push(cell);
+        }
+
+        let layout = GridLayout { spacing, cells, positions };
+
+        for (from, edges) in graph.edges.iter().enumerate() {
+            let cell = layout.positions[from];
+            let mut seen = Vec::with_capacity(edges.len());
+
+            for edge in edges {
+                let to = *layout.positions.get(edge.to)?;
+                let step = (to.0 - cell.0, to.1 - cell.1);
+                if step == (0, 0) || step.0.abs() > 1 || step.1.abs() > 1 || seen.contains(&step) {
+                    return None;
+                }
+                if !approx_eq(edge.cost, layout.step_cost(cell, to)) {
+                    return None;
+                }
+                seen.push(step);
+            }
+
+            let neighbor_count = DIRECTIONS
+                .iter()
+                .filter(|&&(dx, dy)| layout.is_walkable((cell.0 + dx, cell.1 + dy)))
+                .count();
+            if neighbor_count != edges.len() {
+                return None;
+            }
+        }
+
+        Some(layout)
+    }
+
+    fn is_walkable(&self, cell: (i32, i32)) -> bool {
+        self.cells.contains_key(&cell)
+    }
+
+    /// Cost of moving in a straight or diagonal line between two cells.
+    fn step_cost(&self, from: (i32, i32), to: (i32, i32)) -> Coord {
+        let du = (to.0 - from.0).abs();
+        let dv = (to.1 - from.1).abs();
+        let diagonal = du.min(dv) as Coord;
+        let straight = (du - dv).abs() as Coord;
+        (diagonal * std::f64::consts::SQRT_2 as Coord + straight) * self.spacing
+    }
+
+    /// Returns the directions worth exploring from `cell` when it was reached from `parent`.
+    fn pruned_directions(&self, cell: (i32, i32), parent: Option<(i32, i32)>) -> Vec<(i32, i32)> {
+        let Some(parent) = parent else {
+            return DIRECTIONS
+                .iter()
+                .copied()
+                .filter(|&(dx, dy)| self.is_walkable((cell.0 + dx, cell.1 + dy)))
+                .collect();
+        };
+
+        let (x, y) = cell;
+        let dx = (x - parent.0).signum();
+        let dy = (y - parent.1).signum();
+        let mut directions = Vec::with_capacity(5);
+
+        if dx != 0 && dy != 0 {
+            if self.is_walkable((x, y + dy)) {
+                directions.push((0, dy));
+            }
+            if self.is_walkable((x + dx, y)) {
+                directions.push((dx, 0));
+            }
+            if self.is_walkable((x + dx, y + dy)) {
+                directions.push((dx, dy));
+            }
+            if !self.is_walkable((x - dx, y)) {
+                directions.push((-dx, dy));
+            }
+            if !self.is_walkable((x, y - dy)) {
+                directions.push((dx, -dy));
+            }
+        } else if dx == 0 {
+            if self.is_walkable((x, y + dy)) {
+                directions.push((0, dy));
+            }
+            if !self.is_walkable((x + 1, y)) {
+                directions.push((1, dy));
+            }
+            if !self.is_walkable((x - 1, y)) {
+                directions.push((-1, dy));
+            }
+        } else {
+            if self.is_walkable((x + dx, y)) {
+                directions.push((dx, 0));
+            }
+            if !self.is_walkable((x, y + 1)) {
+                directions.push((dx, 1));
+            }
+            if !self.is_walkable((x, y - 1)) {
+                directions.push((dx, -1));
+            }
+        }
+
+        directions
+    }
+
+    /// Moves from `cell` in `direction` until reaching the goal, a cell with a forced neighbor
+    /// or a dead end. Returns the jump point, if any.
+    fn jump(&self, mut cell: (i32, i32), direction: (i32, i32), goal: (i32, i32)) -> Option<(i32, i32)> {
+        let (dx, dy) = direction;
+
+        loop {
+            cell = (cell.0 + dx, cell.1 + dy);
+            if !self.is_walkable(cell) {
+                return None;
+            }
+            if cell == goal {
+                return Some(cell);
+            }
+
+            let (x, y) = cell;
+            if dx != 0 && dy != 0 {
+                if (self.is_walkable((x - dx, y + dy)) && !self.is_walkable((x - dx, y)))
+                    || (self.is_walkable((x + dx, y - dy)) && !self.is_walkable((x, y - dy)))
+                {
+                    return Some(cell);
+                }
+                // Diagonal moves stop where a straight jump finds something
+                if self.jump(cell, (dx, 0), goal).is_some() || self.jump(cell, (0, dy), goal).is_some() {
+                    return Some(cell);
+                }
+            } else if dx != 0 {
+                if (self.is_walkable((x + dx, y + 1)) && !self.is_walkable((x, y + 1)))
+                    || (self.is_walkable((x + dx, y - 1)) && !self.is_walkable((x, y - 1)))
+                {
+                    return Some(cell);
+                }
+            } else if (self.is_walkable((x + 1, y + dy)) && !self.is_walkable((x + 1, y)))
+                || (self.is_walkable((x - 1, y + dy)) && !self.is_walkable((x - 1, y)))
+            {
+                return Some(cell);
+            }
+        }
+    }
+}
+
+/// The eight directions of an 8-connected grid.
+const DIRECTIONS: [(i32, i32); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+fn coordinate(node: &Node, axis: usize) -> Coord {
+    match axis {
+        0 => node.x,
+        1 => node.y,
+        _ => node.z,
+    }
+}
+
+fn approx_eq(a: Coord, b: Coord) -> bool {
+    (a - b).abs() <= GRID_TOLERANCE * a.abs().max(b.abs()).max(1.0)
+}
+
+impl Graph {
+    /// Finds a path from start to goal using Jump Point Search.
+    /// JPS only expands jump points instead of every node along straight lines, which greatly
+    /// reduces the number of expanded nodes on uniform grids. If the graph is not a uniform grid
+    /// (see `GridLayout::detect`) this falls back to `a_star`. Results share the cache with `a_star`.
+    ///
+    /// Grid detection is O(V + E) and runs on every call, use `jps_with_layout` to reuse it.
+    pub fn jps(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+    ) -> Option<Path> {
+        match GridLayout::detect(self) {
+            Some(layout) => self.jps_with_layout(start, goal, cache, &layout),
+            None => self.a_star(start, goal, cache),
+        }
+    }
+
+    /// Finds a path from start to goal using Jump Point Search on a previously detected layout.
+    /// The layout must have been detected on this graph and the graph must not have changed since.
+    pub fn jps_with_layout(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+        layout: &GridLayout,
+    ) -> Option<Path> {
+        let cache_key = (start, goal);
+
+        // Check if the path is already in cache
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        let result = self.jps_search(start, goal, layout, &mut SearchStats::default());
+
+        // Cache the result
+        cache.insert_path(cache_key, result.clone());
+
+        result
+    }
+
+    /// Runs Jump Point Search without touching any cache and reports how many nodes were expanded.
+    /// Falls back to `a_star_with_stats` if the graph is not a uniform grid.
+    pub fn jps_with_stats(&self, start: usize, goal: usize) -> (Option<Path>, SearchStats) {
+        let Some(layout) = GridLayout::detect(self) else {
+            return self.a_star_with_stats(start, goal);
+        };
+
+        let mut stats = SearchStats::default();
+        let result = self.jps_search(start, goal, &layout, &mut stats);
+        (result, stats)
+    }
+
+    fn jps_search(
+        &self,
+        start: usize,
+        goal: usize,
+        layout: &GridLayout,
+        stats: &mut SearchStats,
+    ) -> Option<Path> {
+        let num_nodes = self.nodes.len();
+        let mut open_set = BinaryHeap::new();
+        let mut came_from: Vec<Option<usize>> = vec![None; num_nodes];
+        let mut g_score = vec![Coord::INFINITY; num_nodes];
+        let mut closed_set = vec![false; num_nodes];
+        let goal_cell = layout.positions[goal];
+
+        g_score[start] = 0.0;
+        open_set.push(State {
+            cost: self.heuristic(start, goal),
+            position: start,
+        });
+
+        while let Some(State { cost: _, position: current }) = open_set.pop() {
+            stats.states_popped += 1;
+            if current == goal {
+                return Some(self.expand_jump_points(&came_from, goal, layout));
+            }
+
+            if closed_set[current] {
+                continue;
+            }
+            closed_set[current] = true;
+            stats.nodes_expanded += 1;
+
+            let cell = layout.positions[current];
+            let parent = came_from[current].map(|parent| layout.positions[parent]);
+
+            for direction in layout.pruned_directions(cell, parent) {
+                let Some(jump_point) = layout.jump(cell, direction, goal_cell) else {
+                    continue;
+                };
+                let neighbor = layout.cells[&jump_point];
+
+                if closed_set[neighbor] {
+                    continue;
+                }
+
+                let tentative_g_score = g_score[current] + layout.step_cost(cell, jump_point);
+
+                if tentative_g_score < g_score[neighbor] {
+                    came_from[neighbor] = Some(current);
+                    g_score[neighbor] = tentative_g_score;
+                    open_set.push(State {
+                        cost: tentative_g_score + self.heuristic(neighbor, goal),
+                        position: neighbor,
+                    });
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Reconstructs the full node path by filling in the cells skipped between jump points.
+    fn expand_jump_points(&self, came_from: &[Option<usize>], goal: usize, layout: &GridLayout) -> Path {
+        let mut total_path = vec![self.nodes[goal].clone()];
+        let mut current = goal;
+
+        while let Some(parent) = came_from[current] {
+            let mut cell = layout.positions[current];
+            let target = layout.positions[parent];
+            let direction = ((target.0 - cell.0).signum(), (target.1 - cell.1).signum());
+
+            while cell != target {
+                cell = (cell.0 + direction.0, cell.1 + direction.1);
+                total_path.push(self.nodes[layout.cells[&cell]].clone());
+            }
+            current = parent;
+        }
+
+        total_path.reverse();
+
+        Arc::new(total_path)
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use crate::cache::PathCache;
+use crate::graph::{Graph, SearchStats};
+use crate::node::Node;
+use crate::path::Path;
+use crate::Coord;
+
+impl Graph {
+    /// Finds up to `k` loopless paths from start to goal using Yen's algorithm.
+    ///
+    /// The first path is the one returned by `a_star` (and is taken from or stored in the cache),
+    /// every further path is found by re-running A* from each node of the previous path with the
+    /// edges used by already found paths removed. The paths are sorted ascending by the sum of
+    /// their edge costs and never repeat the same node sequence.
+    pub fn k_shortest_paths(
+        &self,
+        start: usize,
+        goal: usize,
+        k: usize,
+        cache: &impl PathCache,
+    ) -> Vec<Path> {
+        if k == 0 {
+            return Vec::new();
+        }
+        let Some(first) = self.a_star(start, goal, cache) else {
+            return Vec::new();
+        };
+
+        let mut found: Vec<Vec<usize>> = vec![first.iter().map(|node| node.id).collect()];
+        let mut candidates: Vec<(Coord, Vec<usize>)> = Vec::new();
+        let mut seen: HashSet<Vec<usize>> = found.iter().cloned().collect();
+
+        while found.len() < k {
+            let previous = found.last().unwrap().clone();
+
+            for spur_index in 0..previous.len() - 1 {
+                let spur_node = previous[spur_index];
+                let root = &previous[..=spur_index];
+
+                // Edges leaving the spur node along any found path sharing the same root
+                let removed_edges: HashSet<(usize, usize)> = found
+                    .iter()
+                    .filter(|path| path.len() > spur_index + 1 && &path[..=spur_index] == root)
+                    .map(|path| (path[spur_index], path[spur_index + 1]))
+                    .collect();
+                // Nodes of the root must not be revisited to keep the path loopless
+                let removed_nodes: HashSet<usize> = root[..spur_index].iter().copied().collect();
+
+                let spur_path = self.a_star_search(
+                    &[spur_node],
+                    &[goal],
+                    |node| self.heuristic(node, goal),
+                    |from, edge| {
+                        if removed_nodes.contains(&edge.to) || removed_edges.contains(&(from, edge.to)) {
+                            None
+                        } else {
+                            Some(self.traversal_cost(edge))
+                        }
+                    },
+                    &mut SearchStats::default(),
+                );
+
+                if let Ok(spur_path) = spur_path {
+                    let mut candidate = root[..spur_index].to_vec();
+                    candidate.extend(spur_path.iter().map(|node| node.id));
+                    if seen.insert(candidate.clone()) {
+                        candidates.push((self.node_path_cost(&candidate), candidate));
+                    }
+                }
+            }
+
+            let Some(best) = candidates
+                .iter()
+                .enumerate()
+                .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0))
+                .map(|(index, _)| index)
+            else {
+                break;
+            };
+            found.push(candidates.swap_remove(best).1);
+        }
+
+        found
+            .into_iter()
+            .map(|ids| Arc::new(ids.into_iter().map(|id| self.nodes[id].clone()).collect::<Vec<Node>>()))
+            .collect()
+    }
+
+    /// Sums the cheapest traversal cost between each pair of consecutive nodes.
+    fn node_path_cost(&self, ids: &[usize]) -> Coord {
+        ids.windows(2)
+            .map(|pair| {
+                self.edges[pair[0]]
+                    .iter()
+                    .filter(|edge| edge.to == pair[1])
+                    .map(|edge| self.traversal_cost(edge))
+                    .fold(Coord::INFINITY, Coord::min)
+            })
+            .sum()
+    }
+}
@@ -0,0 +1,191 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::sync::Arc;
+use crate::error::RePathError;
+use crate::graph::Graph;
+use crate::utils::{add_obj_element, parse_obj_group, parse_obj_position, parse_obj_vertex, parse_vertex_index, ObjElementAttributes};
+use crate::Coord;
+
+/// An axis-aligned box given by its minimum and maximum corner.
+pub type Aabb = ((Coord, Coord, Coord), (Coord, Coord, Coord));
+
+/// The position of a cubic chunk of a lazily loaded navmesh in units of the chunk size.
+type ChunkKey = (i64, i64, i64);
+
+/// A face or line element of an OBJ file whose edges have not been added yet.
+#[derive(Debug)]
+struct DeferredElement {
+    /// The byte offset of the line in the file.
+    offset: u64,
+    line_number: usize,
+    attributes: ObjElementAttributes,
+    /// The number of vertices and normals before the line, which it may refer to.
+    vertex_count: usize,
+    normal_count: usize,
+}
+
+/// The faces and line elements of an OBJ file grouped by the chunk of their first vertex, as
+/// found by `parse_obj_lazy`.
+#[derive(Debug)]
+struct LazyObj {
+    filename: String,
+    chunk_size: Coord,
+    normals: Vec<(Coord, Coord, Coord)>,
+    chunks: HashMap<ChunkKey, Vec<DeferredElement>>,
+}
+
+impl LazyObj {
+    fn chunk_key(&self, (x, y, z): (Coord, Coord, Coord)) -> ChunkKey {
+        let key = |value: Coord| (value / self.chunk_size).floor() as i64;
+        (key(x), key(y), key(z))
+    }
+
+    /// Returns the chunks with elements that overlap the box.
+    fn chunks_in(&self, (min, max): Aabb) -> impl Iterator<Item = ChunkKey> + '_ {
+        let (min, max) = (self.chunk_key(min), self.chunk_key(max));
+        self.chunks.keys().copied().filter(move |key| {
+            (min.0..=max.0).contains(&key.0) && (min.1..=max.1).contains(&key.1) && (min.2..=max.2).contains(&key.2)
+        })
+    }
+}
+
+/// The state of a graph whose edges are loaded region by region, see `Graph::load_region`.
+#[derive(Debug, Clone)]
+pub(crate) struct LazyRegions {
+    source: Arc<LazyObj>,
+    loaded: HashSet<ChunkKey>,
+}
+
+impl Graph {
+    /// Returns whether the edges of all faces and line elements in the box were added, see
+    /// `load_region`. Always true for graphs that were not loaded lazily.
+    pub fn is_region_loaded(&self, aabb: Aabb) -> bool {
+        let Some(lazy) = &self.lazy else {
+            return true;
+        };
+        lazy.source.chunks_in(aabb).all(|key| lazy.loaded.contains(&key))
+    }
+
+    /// Adds the edges of the faces and line elements in the box to a graph loaded with
+    /// `parse_obj_lazy`, reading them from the navmesh file. The navmesh is split into
+    /// cubes with the edge length of the chunk size, and every cube overlapping the box is loaded
+    /// with all elements whose first vertex lies in it, so edges may reach a bit past the box.
+    ///
+    /// Increments the `generation` if edges were added, since they may shorten cached paths.
+    /// Does nothing for graphs that were not loaded lazily. Returns an error if the navmesh file
+    /// cannot be read anymore or an element refers to vertices that do not exist, in which case no
+    /// edges are added.
+    pub fn load_region(&mut self, aabb: Aabb) -> Result<(), RePathError> {
+        let Some(lazy) = &self.lazy else {
+            return Ok(());
+        };
+        let source = Arc::clone(&lazy.source);
+        let keys: Vec<ChunkKey> = source.chunks_in(aabb).filter(|key| !lazy.loaded.contains(key)).collect();
+        if keys.is_empty() {
+            return Ok(());
+        }
+
+        let mut elements: Vec<&DeferredElement> = keys.iter().flat_map(|key| &source.chunks[key]).collect();
+        // Read the file front to back
+        elements.sort_unstable_by_key(|element| element.offset);
+        let edge_counts: Vec<usize> = self.edges.iter().map(Vec::len).collect();
+        if let Err(err) = self.add_deferred_elements(&source, &elements) {
+            for (edges, &count) in self.edges.iter_mut().zip(&edge_counts) {
+                edges.truncate(count);
+            }
+            return Err(err);
+        }
+
+        if let Some(lazy) = &mut self.lazy {
+            lazy.loaded.extend(keys);
+        }
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// Reads the elements from the file of `source` and adds their edges.
+    fn add_deferred_elements(&mut self, source: &LazyObj, elements: &[&DeferredElement]) -> Result<(), RePathError> {
+        let mut reader = BufReader::new(File::open(&source.filename)?);
+        let mut line = String::new();
+        for element in elements {
+            reader.seek(SeekFrom::Start(element.offset))?;
+            line.clear();
+            reader.read_line(&mut line)?;
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            add_obj_element(
+                self,
+                &parts,
+                &element.attributes,
+                element.vertex_count,
+                &source.normals[..element.normal_count],
+                element.line_number,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads the vertices of an OBJ navmesh like `utils::parse_obj`, but only notes where its faces
+/// and line elements are instead of adding their edges, which `Graph::load_region` adds later
+/// region by region in cubes with an edge length of `chunk_size`. The returned graph starts
+/// without edges, which makes loading huge navmeshes much faster if only parts of them are used.
+/// Returns an error if the file cannot be read or a vertex cannot be parsed.
+pub fn parse_obj_lazy(filename: &str, chunk_size: Coord) -> Result<Graph, RePathError> {
+    let mut reader = BufReader::new(File::open(filename)?);
+    let mut graph = Graph::new();
+    let mut source = LazyObj {
+        filename: filename.to_string(),
+        chunk_size,
+        normals: Vec::new(),
+        chunks: HashMap::new(),
+    };
+    let mut attributes = ObjElementAttributes::default();
+    let mut group = None;
+
+    let mut line = String::new();
+    let mut offset = 0;
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        let length = reader.read_line(&mut line)?;
+        if length == 0 {
+            break;
+        }
+        let line_offset = offset;
+        offset += length as u64;
+        line_number += 1;
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.is_empty() {
+            continue;
+        }
+
+        match parts[0] {
+            "v" => graph.add_node(parse_obj_vertex(&parts, graph.nodes.len(), group.clone(), line_number)?),
+            "vn" => source.normals.push(parse_obj_position(&parts, line_number)?),
+            "f" | "l" => {
+                let first = &graph.nodes[parse_vertex_index(&parts, 1, graph.nodes.len(), line_number)?];
+                let key = source.chunk_key((first.x, first.y, first.z));
+                source.chunks.entry(key).or_default().push(DeferredElement {
+                    offset: line_offset,
+                    line_number,
+                    attributes: attributes.clone(),
+                    vertex_count: graph.nodes.len(),
+                    normal_count: source.normals.len(),
+                });
+                attributes.directed = false;
+            }
+            "o" | "g" => {
+                group = parse_obj_group(&parts);
+                attributes.update(&parts, line_number)?;
+            }
+            _ => attributes.update(&parts, line_number)?,
+        }
+    }
+
+    graph.lazy = Some(LazyRegions {
+        source: Arc::new(source),
+        loaded: HashSet::new(),
+    });
+    Ok(graph)
+}
@@ -1,13 +1,46 @@
 pub mod node;
 pub mod edge;
+pub mod all_pairs;
+pub mod cache;
+pub mod components;
+pub mod contraction_hierarchy;
+pub mod d_star;
+pub mod diagnostics;
+pub mod eccentricity;
+pub mod error;
+pub mod export;
 pub mod graph;
+pub mod heap;
+pub mod heuristics;
+pub mod hierarchical;
+pub mod jps;
+pub mod k_shortest_paths;
+pub mod lazy;
 pub mod metrics;
-mod path;
+pub mod path;
 pub mod pathfinder;
+pub mod planarity;
+pub mod serialization;
 pub mod settings;
+pub mod simplify;
+pub mod spfa;
+pub mod spatial;
+pub mod theta_star;
+pub mod triangles;
 pub mod utils;
+pub mod validation;
 
-pub use pathfinder::RePathfinder;
+pub use error::RePathError;
+pub use pathfinder::{RePathfinder, RePathfinderBuilder};
+
+/// The floating point type of coordinates, distances and costs. `f32` by default, which loses
+/// precision in worlds larger than about 100 km, and `f64` with the `precision-f64` feature.
+#[cfg(not(feature = "precision-f64"))]
+pub type Coord = f32;
+/// The floating point type of coordinates, distances and costs. `f32` by default, which loses
+/// precision in worlds larger than about 100 km, and `f64` with the `precision-f64` feature.
+#[cfg(feature = "precision-f64")]
+pub type Coord = f64;
 
 #[cfg(test)]
 mod tests {
@@ -25,42 +58,24 @@ mod tests {
             precompute_radius: 10000.0,
             total_precompute_pairs: 5000,
             use_precomputed_cache: true,
+            ..RePathSettings::default()
         };
 
         // Create a new RePathfinder instance
-        let pathfinder = RePathfinder::new(settings);
-
-        // Optionally, print the graph bounds
-        fn print_graph_bounds(graph: &Graph) {
-            let mut min_x = f32::MAX;
-            let mut min_y = f32::MAX;
-            let mut min_z = f32::MAX;
-            let mut max_x = f32::MIN;
-            let mut max_y = f32::MIN;
-            let mut max_z = f32::MIN;
-
-            for node in &graph.nodes {
-                if node.x < min_x { min_x = node.x; }
-                if node.y < min_y { min_y = node.y; }
-                if node.z < min_z { min_z = node.z; }
-                if node.x > max_x { max_x = node.x; }
-                if node.y > max_y { max_y = node.y; }
-                if node.z > max_z { max_z = node.z; }
-            }
-
-            println!("Graph bounds:");
-            println!("X: {} to {}", min_x, max_x);
-            println!("Y: {} to {}", min_y, max_y);
-            println!("Z: {} to {}", min_z, max_z);
-        }
+        let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
 
         // Print the graph bounds
-        print_graph_bounds(&pathfinder.graph);
+        let graph = pathfinder.graph();
+        let (min, max) = graph.bounds().expect("The graph has no nodes");
+        println!("Graph bounds:");
+        println!("X: {} to {}", min.x, max.x);
+        println!("Y: {} to {}", min.y, max.y);
+        println!("Z: {} to {}", min.z, max.z);
 
         // Find a non-isolated start node
-        let start_node_id = find_non_isolated_start_node(&pathfinder.graph)
+        let start_node_id = find_non_isolated_start_node(&graph)
             .expect("Could not find a non-isolated start node");
-        let start_node = &pathfinder.graph.nodes[start_node_id];
+        let start_node = &graph.nodes[start_node_id];
         let start_coords = (start_node.x, start_node.y, start_node.z);
 
         println!(
@@ -72,17 +87,17 @@ mod tests {
         println!(
             "Edges from start node (ID: {}): {:?}",
             start_node_id,
-            pathfinder.graph.edges[start_node_id]
+            graph.edges[start_node_id]
         );
         println!(
             "Number of edges from start node: {}",
-            pathfinder.graph.edges[start_node_id].len()
+            graph.edges[start_node_id].len()
         );
 
         // Find a node connected to the start node
-        let end_node_id = find_connected_node(&pathfinder.graph, start_node_id)
+        let end_node_id = find_connected_node(&graph, start_node_id)
             .expect("Could not find a node connected to the start node");
-        let end_node = &pathfinder.graph.nodes[end_node_id];
+        let end_node = &graph.nodes[end_node_id];
 
         println!(
             "Selected end node ID: {}, Position: {:?}",
@@ -93,34 +108,34 @@ mod tests {
         let end_coords = (end_node.x, end_node.y, end_node.z);
 
         // Confirm connectivity
-        let connected = are_nodes_connected(&pathfinder.graph, start_node_id, end_node_id);
+        let connected = are_nodes_connected(&graph, start_node_id, end_node_id);
         assert!(connected, "Start and end nodes are not connected");
 
         // Find path using a single thread
         let start_time = std::time::Instant::now();
         let path1 = pathfinder.find_path(start_coords, end_coords);
         println!("Time to find path single-threaded: {:?}", start_time.elapsed());
-        if let Some(path) = &path1 {
-            println!("Path found with {} nodes.", path.len());
+        if let Ok(path) = &path1 {
+            println!("Path found with {} nodes.", path.nodes.len());
         } else {
             println!("No path found between start_coords and end_coords");
         }
 
-        assert!(path1.is_some(), "No path found between start_coords and end_coords");
+        assert!(path1.is_ok(), "No path found between start_coords and end_coords");
 
         // Find path using multiple threads
         let start_time = std::time::Instant::now();
         let path2 = pathfinder.find_path_multithreaded(start_coords, end_coords, 4);
         println!("Time to find path multi-threaded: {:?}", start_time.elapsed());
 
-        if let Some(path) = &path2 {
-            println!("Multithreaded path found with {} nodes.", path.len());
+        if let Ok(path) = &path2 {
+            println!("Multithreaded path found with {} nodes.", path.nodes.len());
         } else {
             println!("No path found between start_coords and end_coords using multithreaded pathfinding");
         }
 
         assert!(
-            path2.is_some(),
+            path2.is_ok(),
             "No path found between start_coords and end_coords with multithreaded pathfinding"
         );
     }
@@ -1,6 +1,11 @@
 use serde::{Serialize, Deserialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
 use crate::settings::RePathSettings;
+use crate::Coord;
 
+/// The results of a benchmark run, see `utils::save_metrics_to_csv`. `PathfindingMetrics` holds
+/// the statistics a `RePathfinder` collects on its own.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Metrics {
     #[serde(flatten)]
@@ -20,3 +25,79 @@ impl Metrics {
         }
     }
 }
+
+/// The timing of a single pathfinding call, see `RePathfinder::record_path_metric`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PathMetric {
+    /// When the call started, in milliseconds since the Unix epoch.
+    pub timestamp: u64,
+    pub start: (Coord, Coord, Coord),
+    pub end: (Coord, Coord, Coord),
+    /// The number of nodes of the found path, 0 if none was found.
+    pub path_len: usize,
+    pub duration: Duration,
+    /// Whether the path was already cached.
+    pub cache_hit: bool,
+}
+
+/// A snapshot of the statistics of `RePathfinder::find_path` calls, see `RePathfinder::metrics`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct PathfindingMetrics {
+    /// The number of `find_path` calls, including failed ones.
+    pub calls: u64,
+    /// The sum of the node counts of all found paths.
+    pub total_nodes_in_paths: u64,
+    /// The number of cache lookups that found a stored result.
+    pub cache_hits: u64,
+    /// The number of cache lookups that had to search.
+    pub cache_misses: u64,
+    /// The average time a `find_path` call took, including cache hits.
+    pub average_duration: Duration,
+    /// The number of paths searched by the precomputation, `precompute_pairs` and
+    /// `precompute_region`, which `reset_metrics` does not reset.
+    pub precomputed_paths: u64,
+}
+
+/// The counters behind `PathfindingMetrics`, shared by all clones of a `RePathfinder`.
+#[derive(Debug, Default)]
+pub(crate) struct PathfindingCounters {
+    calls: AtomicU64,
+    total_nodes_in_paths: AtomicU64,
+    total_nanos: AtomicU64,
+    precomputed_paths: AtomicU64,
+}
+
+impl PathfindingCounters {
+    /// Records a `find_path` call that found a path with `nodes` nodes, or none for 0.
+    pub(crate) fn record(&self, nodes: usize, duration: Duration) {
+        self.calls.fetch_add(1, Ordering::Relaxed);
+        self.total_nodes_in_paths.fetch_add(nodes as u64, Ordering::Relaxed);
+        self.total_nanos.fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+
+    /// Records paths searched to fill the cache.
+    pub(crate) fn record_precomputed(&self, paths: usize) {
+        self.precomputed_paths.fetch_add(paths as u64, Ordering::Relaxed);
+    }
+
+    /// Takes a snapshot of the counters, together with the given cache statistics.
+    pub(crate) fn snapshot(&self, cache_hits: u64, cache_misses: u64) -> PathfindingMetrics {
+        let calls = self.calls.load(Ordering::Relaxed);
+        let total_nanos = self.total_nanos.load(Ordering::Relaxed);
+        PathfindingMetrics {
+            calls,
+            total_nodes_in_paths: self.total_nodes_in_paths.load(Ordering::Relaxed),
+            cache_hits,
+            cache_misses,
+            average_duration: Duration::from_nanos(total_nanos.checked_div(calls).unwrap_or(0)),
+            precomputed_paths: self.precomputed_paths.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Resets the statistics of `find_path` calls.
+    pub(crate) fn reset(&self) {
+        self.calls.store(0, Ordering::Relaxed);
+        self.total_nodes_in_paths.store(0, Ordering::Relaxed);
+        self.total_nanos.store(0, Ordering::Relaxed);
+    }
+}
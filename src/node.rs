@@ -1,17 +1,46 @@
 use serde::{Serialize, Deserialize};
 use std::cmp::Ordering;
+use crate::Coord;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Node {
     pub id: usize,
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+    pub x: Coord,
+    pub y: Coord,
+    pub z: Coord,
+    /// Multiplies the cost of every edge entering the node, e.g. above 1 for a node standing in
+    /// fire. Weights below 1 can make A* with the Euclidean heuristic miss the cheapest path.
+    #[serde(default = "default_weight")]
+    pub weight: Coord,
+    /// The surface normal at the node, read from the `vn` lines of an OBJ navmesh, if any.
+    #[serde(default)]
+    pub normal: Option<(Coord, Coord, Coord)>,
+    /// The name of the object or group of an OBJ navmesh the node belongs to, e.g. a room or
+    /// platform, read from the last `o` or `g` line before its vertex.
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+fn default_weight() -> Coord {
+    1.0
 }
 
 impl Node {
-    pub fn new(id: usize, x: f32, y: f32, z: f32) -> Self {
-        Node { id, x, y, z }
+    pub fn new(id: usize, x: Coord, y: Coord, z: Coord) -> Self {
+        Node {
+            id,
+            x,
+            y,
+            z,
+            weight: default_weight(),
+            normal: None,
+            group: None,
+        }
+    }
+
+    /// Creates a node with the given traversal weight, see `Node::weight`.
+    pub fn with_weight(id: usize, x: Coord, y: Coord, z: Coord, weight: Coord) -> Self {
+        Node { weight, ..Node::new(id, x, y, z) }
     }
 }
 
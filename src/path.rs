@@ -1,4 +1,378 @@
 use std::sync::Arc;
+use crate::graph::Graph;
 use crate::node::Node;
+use crate::triangles::TriangleMesh;
+use crate::utils::distance;
+use crate::Coord;
 
-pub type Path = Arc<Vec<Node>>;
\ No newline at end of file
+pub type Path = Arc<Vec<Node>>;
+
+/// A path together with its cost and length, as returned by `RePathfinder`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathResult {
+    pub nodes: Path,
+    /// The sum of the edge costs along the path, each multiplied by the weight of the node it
+    /// enters. Consecutive nodes that are not connected by an edge, e.g. after smoothing, count
+    /// with their Euclidean distance instead of an edge cost.
+    pub total_cost: Coord,
+    /// The sum of the Euclidean distances between consecutive nodes, see `path_cost`.
+    pub euclidean_length: Coord,
+}
+
+impl PathResult {
+    /// Computes the cost and length of a path found on the graph.
+    pub fn new(nodes: Path, graph: &Graph) -> Self {
+        let total_cost = nodes
+            .windows(2)
+            .map(|pair| {
+                let cost = graph
+                    .edge_cost(pair[0].id, pair[1].id)
+                    .unwrap_or_else(|| graph.heuristic(pair[0].id, pair[1].id));
+                cost * graph.nodes[pair[1].id].weight
+            })
+            .sum();
+        let euclidean_length = path_cost(&nodes);
+
+        PathResult {
+            nodes,
+            total_cost,
+            euclidean_length,
+        }
+    }
+}
+
+/// A path that may end short of the requested goal, see `Graph::a_star_partial`.
+#[derive(Debug, Clone)]
+pub struct PartialPath {
+    pub path: Path,
+    /// True if the path reaches the goal, false if it ends at the reachable node closest to it.
+    pub complete: bool,
+}
+
+/// Yields the nodes of a path one by one, see `Graph::a_star_iter`. It shares the path instead of
+/// copying its nodes, so creating it is O(1) however long the path is.
+#[derive(Debug, Clone)]
+pub struct PathIter {
+    path: Path,
+    /// The index of the next node from the front.
+    front: usize,
+    /// One past the index of the next node from the back.
+    back: usize,
+}
+
+impl PathIter {
+    pub fn new(path: Path) -> Self {
+        let back = path.len();
+        PathIter { path, front: 0, back }
+    }
+}
+
+impl Iterator for PathIter {
+    type Item = Node;
+
+    fn next(&mut self) -> Option<Node> {
+        if self.front == self.back {
+            return None;
+        }
+        self.front += 1;
+        Some(self.path[self.front - 1].clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.front;
+        (remaining, Some(remaining))
+    }
+}
+
+impl DoubleEndedIterator for PathIter {
+    fn next_back(&mut self) -> Option<Node> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.path[self.back].clone())
+    }
+}
+
+impl ExactSizeIterator for PathIter {}
+
+impl std::iter::FusedIterator for PathIter {}
+
+/// Returns the total length of the path, i.e. the sum of the distances between consecutive nodes.
+/// For navmeshes loaded with `parse_obj` this equals the sum of the edge costs along the path.
+pub fn path_cost(path: &Path) -> Coord {
+    path.windows(2)
+        .map(|pair| distance(&(pair[0].x, pair[0].y, pair[0].z), &(pair[1].x, pair[1].y, pair[1].z)))
+        .sum()
+}
+
+/// The angle in degrees below which `smooth` treats a turn as a straight line.
+pub const DEFAULT_SMOOTHING_ANGLE: Coord = 1.0;
+
+/// Smooths a path found on the graph by string pulling: every waypoint that can be skipped
+/// because the next ones are in straight line of sight on the navmesh is removed, as well as
+/// waypoints where the path turns by less than `DEFAULT_SMOOTHING_ANGLE` and the waypoints
+/// around them are in line of sight of each other.
+///
+/// Builds the triangle mesh of the graph on every call, use `smooth_with_mesh` to reuse it.
+pub fn smooth(path: &[Node], graph: &Graph) -> Vec<Node> {
+    smooth_with_mesh(path, graph, &TriangleMesh::new(graph), DEFAULT_SMOOTHING_ANGLE)
+}
+
+/// Smooths a path like `smooth` on a previously built mesh, treating turns by less than
+/// `angle_threshold` degrees as straight lines.
+///
+/// The first and last node are always kept, and the result is never longer than the input.
+/// Consecutive waypoints of the result are always in line of sight, so a slight turn around the
+/// corner of an obstacle is kept whatever the threshold.
+pub fn smooth_with_mesh(
+    path: &[Node],
+    graph: &Graph,
+    mesh: &TriangleMesh,
+    angle_threshold: Coord,
+) -> Vec<Node> {
+    if path.len() <= 2 {
+        return path.to_vec();
+    }
+
+    // Pull the string: from each waypoint, go straight to the furthest node still in sight
+    let mut pulled = vec![path[0].clone()];
+    let mut anchor = 0;
+    while anchor < path.len() - 1 {
+        let mut next = anchor + 1;
+        while next + 1 < path.len() && mesh.line_of_sight(graph, path[anchor].id, path[next + 1].id) {
+            next += 1;
+        }
+        pulled.push(path[next].clone());
+        anchor = next;
+    }
+
+    // Drop waypoints where the path hardly turns, unless skipping them would cut a corner
+    let mut smoothed: Vec<Node> = Vec::with_capacity(pulled.len());
+    for node in pulled {
+        while smoothed.len() >= 2
+            && turn_angle(&smoothed[smoothed.len() - 2], &smoothed[smoothed.len() - 1], &node)
+                < angle_threshold
+            && mesh.line_of_sight(graph, smoothed[smoothed.len() - 2].id, node.id)
+        {
+            smoothed.pop();
+        }
+        smoothed.push(node);
+    }
+    smoothed
+}
+
+/// Reduces the number of waypoints with the Ramer-Douglas-Peucker algorithm: the path is split at
+/// the node furthest from the straight line between the first and last node, recursively, and
+/// nodes closer than `epsilon` to that line are dropped.
+///
+/// The first and last node are always kept. With `epsilon = 0.0` the path is returned unchanged.
+/// Unlike `smooth` this does not check the navmesh, so the result may cut corners by up to
+/// `epsilon`.
+pub fn decimate(path: &[Node], epsilon: Coord) -> Vec<Node> {
+    if path.len() <= 2 {
+        return path.to_vec();
+    }
+
+    let mut keep = vec![false; path.len()];
+    keep[0] = true;
+    keep[path.len() - 1] = true;
+
+    // Ranges of the path still to split, processed with a stack instead of recursion so that long
+    // paths cannot overflow the call stack
+    let mut ranges = vec![(0, path.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let furthest = (first + 1..last)
+            .map(|index| (index, segment_distance(&path[index], &path[first], &path[last])))
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+
+        if let Some((index, max_distance)) = furthest {
+            if max_distance >= epsilon {
+                keep[index] = true;
+                ranges.push((first, index));
+                ranges.push((index, last));
+            }
+        }
+    }
+
+    path.iter()
+        .zip(keep)
+        .filter(|&(_, kept)| kept)
+        .map(|(node, _)| node.clone())
+        .collect()
+}
+
+/// Finds where an agent at `position` that wandered off the path should rejoin it. Returns the
+/// index `i` of the segment from `path[i]` to `path[i + 1]` closest to the position, and the
+/// closest point on that segment, which may lie between the nodes. On ties the earlier segment
+/// wins. A path with a single node is treated as a segment of length 0 at index 0, an empty path
+/// returns `None`.
+pub fn closest_point_on_path(path: &[Node], position: (Coord, Coord, Coord)) -> Option<(usize, (Coord, Coord, Coord))> {
+    closest_projection(path, position).map(|(index, _, point)| (index, point))
+}
+
+/// Returns how far along the path the point closest to `position` is, see
+/// `closest_point_on_path`, as the fraction of the path length in `[0, 1]`. Returns 0 for an
+/// empty path or a path of length 0.
+pub fn progress_fraction(path: &[Node], position: (Coord, Coord, Coord)) -> Coord {
+    let Some((index, t, _)) = closest_projection(path, position) else {
+        return 0.0;
+    };
+    let segment_lengths: Vec<Coord> = path
+        .windows(2)
+        .map(|pair| distance(&(pair[0].x, pair[0].y, pair[0].z), &(pair[1].x, pair[1].y, pair[1].z)))
+        .collect();
+    let total: Coord = segment_lengths.iter().sum();
+    if total == 0.0 {
+        return 0.0;
+    }
+    let travelled: Coord = segment_lengths[..index].iter().sum::<Coord>() + t * segment_lengths.get(index).unwrap_or(&0.0);
+    (travelled / total).clamp(0.0, 1.0)
+}
+
+/// The segment index, the fraction along that segment and the point of the closest point on the
+/// path, see `closest_point_on_path`.
+fn closest_projection(path: &[Node], position: (Coord, Coord, Coord)) -> Option<(usize, Coord, (Coord, Coord, Coord))> {
+    let first = path.first()?;
+    if path.len() == 1 {
+        return Some((0, 0.0, (first.x, first.y, first.z)));
+    }
+
+    let mut best_distance = Coord::INFINITY;
+    let mut best = None;
+    for (index, pair) in path.windows(2).enumerate() {
+        let (t, point) = project_onto_segment(position, &pair[0], &pair[1]);
+        let d = distance(&position, &point);
+        if best.is_none() || d < best_distance {
+            best_distance = d;
+            best = Some((index, t, point));
+        }
+    }
+    best
+}
+
+/// Walks along the path and returns a point every `step_size` world units, e.g. for animations
+/// that need equally spaced positions. The distance is measured along the path, so a point may
+/// be closer to the previous one in straight line if the path turns in between.
+///
+/// The first and last point are exactly the first and last node, the last point may be closer
+/// than `step_size` to the one before it. Panics if `step_size` is not positive.
+pub fn interpolate(path: &[Node], step_size: Coord) -> Vec<(Coord, Coord, Coord)> {
+    assert!(step_size > 0.0, "step_size must be positive, got {}", step_size);
+    let (Some(first), Some(last)) = (path.first(), path.last()) else {
+        return Vec::new();
+    };
+
+    let mut points = vec![(first.x, first.y, first.z)];
+    // The distance from the start of the current segment to the next point
+    let mut offset = step_size;
+    for pair in path.windows(2) {
+        let (a, b) = ((pair[0].x, pair[0].y, pair[0].z), (pair[1].x, pair[1].y, pair[1].z));
+        let length = distance(&a, &b);
+        // Multiplied instead of summed up, so rounding errors do not accumulate on long segments
+        let mut steps = 0;
+        loop {
+            let along = offset + steps as Coord * step_size;
+            if along >= length {
+                offset = along - length;
+                break;
+            }
+            let t = along / length;
+            points.push((a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t, a.2 + (b.2 - a.2) * t));
+            steps += 1;
+        }
+    }
+    if path.len() > 1 {
+        points.push((last.x, last.y, last.z));
+    }
+    points
+}
+
+/// Fits a smooth curve through the waypoints and returns `samples` points along it, e.g. for
+/// camera or agent movement without sharp turns. Every segment between two waypoints is a cubic
+/// Bézier curve with the control points of a Catmull-Rom spline, so the curve passes through
+/// all waypoints and its direction does not jump at them.
+///
+/// The samples are spread evenly over the segments, not by distance, and the first and last
+/// sample are exactly the first and last node. Like `decimate`, this does not check the navmesh,
+/// so the curve may cut corners slightly.
+pub fn to_bezier(path: &[Node], samples: usize) -> Vec<(Coord, Coord, Coord)> {
+    let points: Vec<(Coord, Coord, Coord)> = path.iter().map(|node| (node.x, node.y, node.z)).collect();
+    if points.len() < 2 || samples < 2 {
+        return points.first().map(|&point| vec![point; samples.min(1)]).unwrap_or_default();
+    }
+
+    let segments = points.len() - 1;
+    (0..samples)
+        .map(|sample| {
+            let position = sample as Coord * segments as Coord / (samples - 1) as Coord;
+            let segment = (position as usize).min(segments - 1);
+            let t = position - segment as Coord;
+
+            // The neighbors of the segment, repeating the endpoints at the ends of the path
+            let before = points[segment.saturating_sub(1)];
+            let (start, end) = (points[segment], points[segment + 1]);
+            let after = points[(segment + 2).min(segments)];
+            let control = |from: (Coord, Coord, Coord), a: (Coord, Coord, Coord), b: (Coord, Coord, Coord), sign: Coord| {
+                (
+                    from.0 + sign * (b.0 - a.0) / 6.0,
+                    from.1 + sign * (b.1 - a.1) / 6.0,
+                    from.2 + sign * (b.2 - a.2) / 6.0,
+                )
+            };
+            let first_control = control(start, before, end, 1.0);
+            let second_control = control(end, start, after, -1.0);
+            cubic_bezier(start, first_control, second_control, end, t)
+        })
+        .collect()
+}
+
+/// Evaluates the cubic Bézier curve with the given control points at `t` in `[0, 1]`.
+fn cubic_bezier(
+    p0: (Coord, Coord, Coord),
+    p1: (Coord, Coord, Coord),
+    p2: (Coord, Coord, Coord),
+    p3: (Coord, Coord, Coord),
+    t: Coord,
+) -> (Coord, Coord, Coord) {
+    let u = 1.0 - t;
+    let weights = (u * u * u, 3.0 * u * u * t, 3.0 * u * t * t, t * t * t);
+    let blend = |a: Coord, b: Coord, c: Coord, d: Coord| a * weights.0 + b * weights.1 + c * weights.2 + d * weights.3;
+    (
+        blend(p0.0, p1.0, p2.0, p3.0),
+        blend(p0.1, p1.1, p2.1, p3.1),
+        blend(p0.2, p1.2, p2.2, p3.2),
+    )
+}
+
+/// The distance between `point` and the segment from `a` to `b`.
+fn segment_distance(point: &Node, a: &Node, b: &Node) -> Coord {
+    let (_, closest) = project_onto_segment((point.x, point.y, point.z), a, b);
+    distance(&(point.x, point.y, point.z), &closest)
+}
+
+/// Returns how far along the segment from `a` to `b` the point on it closest to `point` is, as a
+/// fraction in `[0, 1]`, together with that point.
+pub(crate) fn project_onto_segment(point: (Coord, Coord, Coord), a: &Node, b: &Node) -> (Coord, (Coord, Coord, Coord)) {
+    let ab = (b.x - a.x, b.y - a.y, b.z - a.z);
+    let ap = (point.0 - a.x, point.1 - a.y, point.2 - a.z);
+    let length_squared = ab.0 * ab.0 + ab.1 * ab.1 + ab.2 * ab.2;
+    let t = if length_squared == 0.0 {
+        0.0
+    } else {
+        ((ap.0 * ab.0 + ap.1 * ab.1 + ap.2 * ab.2) / length_squared).clamp(0.0, 1.0)
+    };
+    (t, (a.x + t * ab.0, a.y + t * ab.1, a.z + t * ab.2))
+}
+
+/// The angle in degrees between the directions `a -> b` and `b -> c`, 0 if any of them is empty.
+fn turn_angle(a: &Node, b: &Node, c: &Node) -> Coord {
+    let u = (b.x - a.x, b.y - a.y, b.z - a.z);
+    let v = (c.x - b.x, c.y - b.y, c.z - b.z);
+    let lengths = (u.0 * u.0 + u.1 * u.1 + u.2 * u.2).sqrt() * (v.0 * v.0 + v.1 * v.1 + v.2 * v.2).sqrt();
+    if lengths == 0.0 {
+        return 0.0;
+    }
+    let cos = (u.0 * v.0 + u.1 * v.1 + u.2 * v.2) / lengths;
+    cos.clamp(-1.0, 1.0).acos().to_degrees()
+}
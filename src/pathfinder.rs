@@ -1,78 +1,860 @@
-use crate::graph::Graph;
-use crate::settings::RePathSettings;
-use crate::utils::{nodes_within_radius, parse_obj};
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
-use std::sync::Arc;
+use crate::cache::{DiskPathCache, LruPathCache, PathCache, PrefixedCache, WriteThroughCache};
+use crate::components::label_strongly_connected_components;
+use crate::diagnostics::DiagnosticReport;
+use crate::edge::{Edge, EdgeKind};
+use crate::error::RePathError;
+use crate::graph::{AStarState, Graph, SearchLimit, StepResult};
+use crate::jps::GridLayout;
+use crate::lazy::{parse_obj_lazy, Aabb};
+use crate::metrics::{PathMetric, PathfindingCounters, PathfindingMetrics};
+use crate::node::Node;
+use crate::settings::{Algorithm, GraphMode, RePathSettings};
+use crate::spatial::{SpatialIndex, Sweep};
+use crate::triangles::TriangleMesh;
+use crate::utils::{append_path_metric_to_csv, distance, parse_navmesh};
+use crate::validation::ValidationError;
 use dashmap::DashMap;
+use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock, RwLock, RwLockReadGuard, RwLockWriteGuard};
 use rand::prelude::*;
-use crate::path::Path;
+use crate::path::{
+    closest_point_on_path, decimate, interpolate, smooth_with_mesh, to_bezier, PartialPath, Path, PathIter, PathResult, DEFAULT_SMOOTHING_ANGLE,
+};
+use crate::Coord;
+
+/// The start and end coordinates of a query of `RePathfinder::find_paths_batch`.
+pub type PathQuery = ((Coord, Coord, Coord), (Coord, Coord, Coord));
+
+/// A cache entry as written by `RePathfinder::save_cache`.
+type SavedCacheEntry = ((usize, usize), Option<Vec<Node>>);
 
 /// The RePathfinder struct holds the graph and cache used for pathfinding.
+/// Cloning is cheap, clones share the graph and the cache and see each other's `reload` and
+/// `update_graph`.
+#[derive(Clone)]
 pub struct RePathfinder {
-    pub(crate) graph: Graph,
-    cache: Arc<DashMap<(usize, usize), Option<Path>>>,
+    /// Replaced as a whole by `reload` and `update_graph`. Queries work on the state they started with, so a reload
+    /// neither waits for running queries nor lets them mix the previous and the new navmesh.
+    state: Arc<RwLock<Arc<PathfinderState>>>,
+    counters: Arc<PathfindingCounters>,
+}
+
+/// The graph of a `RePathfinder` with its caches and everything else derived from it.
+#[derive(Clone)]
+struct PathfinderState {
+    graph: Arc<Graph>,
+    cache: Arc<LruPathCache>,
+    /// Holds the results of `cache` across restarts, see `RePathfinder::with_disk_cache`.
+    disk_cache: Option<Arc<DiskPathCache>>,
+    /// Results of `find_path_custom_cost`, keyed by the cost function id as well.
+    custom_cost_cache: Arc<LruPathCache<(u64, usize, usize)>>,
+    /// Results of `find_path_avoiding`, keyed by a hash of the avoided nodes as well.
+    avoiding_cache: Arc<LruPathCache<(u64, usize, usize)>>,
+    /// Results of `find_path_within_budget`, keyed by the bucket of the budget as well.
+    budget_cache: Arc<LruPathCache<(u64, usize, usize)>>,
+    /// Results of `find_path` while nodes are blocked or cost zones exist, see `search`. Kept
+    /// apart from `cache`, which the searches that ignore both read and write.
+    restricted_cache: Arc<LruPathCache>,
+    /// Results of `find_path_with_heuristic`, keyed by the heuristic id as well. Like those of
+    /// `restricted_cache` they depend on the blocked nodes and cost zones.
+    heuristic_cache: Arc<LruPathCache<(u64, usize, usize)>>,
+    /// Results of `find_path_filtered`, `find_path_with_label_filter` and `find_path_with_radius`,
+    /// keyed by a hash of the filter as well.
+    filter_cache: Arc<LruPathCache<(u64, usize, usize)>>,
+    /// The nodes `find_path` must not enter, see `block_node`.
+    blocked_nodes: Arc<RwLock<HashSet<usize>>>,
+    /// The regions `find_path` avoids if possible, see `add_cost_zone`.
+    cost_zones: Arc<RwLock<CostZones>>,
+    settings: RePathSettings,
+    grid_layout: Option<Arc<GridLayout>>,
+    spatial_index: Arc<SpatialIndex>,
+    /// Built on first use, since only path smoothing needs it.
+    triangle_mesh: Arc<OnceLock<TriangleMesh>>,
+    /// The `Graph::generation` the cache and the derived indices were built for.
+    graph_generation: u64,
 }
 
 impl RePathfinder {
     /// Creates a new RePathfinder instance with the given settings.
     /// This includes loading the graph from the provided navmesh file and precomputing paths.
-    pub fn new(settings: RePathSettings) -> Self {
-        let graph = parse_obj(&settings.navmesh_filename);
-        let cache = Arc::new(DashMap::new());
+    /// If `cache_file` is set and the file exists, the cache is loaded from it instead of being
+    /// precomputed, otherwise the precomputed cache is saved to it.
+    /// Returns an error if the navmesh cannot be read or parsed, if it contains no nodes, or if
+    /// the cache file cannot be read or written.
+    pub fn new(settings: RePathSettings) -> Result<Self, RePathError> {
+        Self::new_with_progress(settings, |_, _| {})
+    }
 
-        let precompute_start = std::time::Instant::now();
-        let node_ids: Vec<_> = (0..graph.nodes.len()).collect();
+    /// Like `new`, but calls `on_progress(completed, total)` after each precomputed pair, e.g. to
+    /// show a progress bar on large navmeshes. The pairs are precomputed in parallel, so the
+    /// callback is called from several threads at once, and `completed` values may arrive out of
+    /// order. It is not called if the cache is loaded from `cache_file`.
+    pub fn new_with_progress<F>(settings: RePathSettings, on_progress: F) -> Result<Self, RePathError>
+    where
+        F: Fn(usize, usize) + Sync,
+    {
+        Self::build(settings, &on_progress, &AtomicBool::new(false))
+    }
 
-        // Precompute paths between random pairs of nodes within a specified radius
-        (0..settings.total_precompute_pairs)
-            .into_par_iter()
-            .for_each(|_| {
-                let mut rng = rand::thread_rng();
-                let start_node_id = *node_ids.choose(&mut rng).unwrap();
-                let start_node = &graph.nodes[start_node_id];
-                let mut nearby_nodes =
-                    nodes_within_radius(&graph, start_node, settings.precompute_radius);
-
-                // Remove the start node from the list of nearby nodes if present
-                nearby_nodes.retain(|&id| id != start_node_id);
-
-                if let Some(&goal_node_id) = nearby_nodes.choose(&mut rng) {
-                    if start_node_id != goal_node_id {
-                        graph.a_star(start_node_id, goal_node_id, &cache);
-                    }
-                }
-            });
+    /// Like `new`, but stops precomputing as soon as `cancel` is set, e.g. by another thread when
+    /// the navmesh is swapped out while it is still being loaded. The returned pathfinder is fully
+    /// usable, but with a partially precomputed cache its hit rate may be lower.
+    /// A cancelled precomputation is not saved to `cache_file`, so the next start precomputes
+    /// the full cache again.
+    pub fn new_cancellable(settings: RePathSettings, cancel: Arc<AtomicBool>) -> Result<Self, RePathError> {
+        Self::build(settings, &|_, _| {}, &cancel)
+    }
 
-        let precompute_duration = precompute_start.elapsed();
-        println!("Precomputation time: {:?}", precompute_duration);
+    /// Like `new`, but only reads the vertices of the OBJ navmesh at first, see
+    /// `lazy::parse_obj_lazy`, which starts much faster on huge open-world navmeshes. `find_path`
+    /// and the searches built on it, such as `find_path_smooth` or `find_path_through_waypoints`,
+    /// load the region around their points on demand. Other searches, e.g. `find_path_partial`,
+    /// `find_paths_batch` or `find_path_by_id`, only see the regions loaded so far, so load the
+    /// regions they need with `load_region` first. Nothing is precomputed.
+    ///
+    /// The navmesh must be an OBJ file and stay in place while regions are loaded. It is not
+    /// memory-mapped: every region is read again from the file, seeking to its faces.
+    pub fn new_lazy(settings: RePathSettings) -> Result<Self, RePathError> {
+        let graph = parse_obj_lazy(&settings.navmesh_filename, settings.lazy_chunk_size)?;
+        RePathfinderBuilder::new(settings)
+            .with_graph(graph)
+            .skip_precomputation()
+            .build()
+    }
+
+    /// Like `new`, but keeps the paths found by `find_path` and the other searches that depend on
+    /// nothing but the navmesh in a `DiskPathCache` stored at `db_path`, so they survive restarts.
+    /// The in-memory cache stays in front of it: lookups check memory first, then the disk, and
+    /// new results are written to both.
+    ///
+    /// On the first start the precomputed paths are written to the disk cache as well, later
+    /// starts with paths on disk skip the precomputation and `cache_file`. `clear_cache` and the
+    /// `invalidate_cache_for_*` methods remove results from the disk cache too. `reload` and a
+    /// changing `update_graph` leave it behind, since its paths belong to the previous navmesh.
+    /// Returns an error if the disk cache cannot be opened, e.g. because it was written for
+    /// another navmesh or with a `slope_factor` or `mode` that changed the costs, besides the
+    /// errors of `new`.
+    pub fn with_disk_cache(settings: RePathSettings, db_path: &str) -> Result<Self, RePathError> {
+        RePathfinderBuilder::new(settings).with_disk_cache(db_path).build()
+    }
+
+    fn build(
+        settings: RePathSettings,
+        on_progress: &(impl Fn(usize, usize) + Sync),
+        cancel: &AtomicBool,
+    ) -> Result<Self, RePathError> {
+        RePathfinderBuilder::new(settings).build_with(on_progress, cancel)
+    }
+
+    /// Computes the paths between the given `(start, goal)` node ids in parallel and caches them,
+    /// e.g. to warm the cache for routes known to be queried often. Pairs that are already cached
+    /// are not searched again. Panics if a node id is not in the graph.
+    pub fn precompute_pairs(&self, pairs: &[(usize, usize)]) {
+        self.state().precompute_pairs(pairs);
+        self.counters.record_precomputed(pairs.len());
+    }
+
+    /// Computes the paths between all pairs of nodes within `radius` of `center` in parallel and
+    /// caches them, e.g. for a town square many agents walk through. The number of pairs grows
+    /// with the square of the number of nodes in the region, so keep the radius small.
+    pub fn precompute_region(&self, center: (Coord, Coord, Coord), radius: Coord) {
+        let state = self.state();
+        let nodes = state.nodes_within_radius(center, radius);
+        let pairs: Vec<(usize, usize)> = nodes
+            .iter()
+            .flat_map(|&start| nodes.iter().map(move |&goal| (start, goal)))
+            .filter(|(start, goal)| start != goal)
+            .collect();
+        state.precompute_pairs(&pairs);
+        self.counters.record_precomputed(pairs.len());
+    }
+
+    /// Writes all cached paths to a binary file, which can be loaded with `load_cache` after
+    /// a restart instead of precomputing the paths again.
+    pub fn save_cache(&self, file: impl AsRef<std::path::Path>) -> Result<(), RePathError> {
+        let entries: Vec<SavedCacheEntry> = self
+            .state()
+            .cache
+            .entries()
+            .into_iter()
+            .map(|(key, path)| (key, path.map(|path| path.to_vec())))
+            .collect();
+
+        let writer = BufWriter::new(File::create(file)?);
+        bincode::serialize_into(writer, &entries)?;
+        Ok(())
+    }
+
+    /// Adds the paths saved by `save_cache` to the cache.
+    /// Returns an error if the file cannot be read or was not saved for a navmesh of this size.
+    pub fn load_cache(&self, file: impl AsRef<std::path::Path>) -> Result<(), RePathError> {
+        let state = self.state();
+        let reader = BufReader::new(File::open(file)?);
+        let entries: Vec<SavedCacheEntry> = bincode::deserialize_from(reader)?;
+
+        let node_count = state.graph.nodes.len();
+        let in_graph = |id: usize| id < node_count;
+        for ((start, goal), path) in &entries {
+            let path_in_graph = path.iter().flatten().all(|node| in_graph(node.id));
+            if !in_graph(*start) || !in_graph(*goal) || !path_in_graph {
+                return Err(RePathError::SerializationError(
+                    "The cache refers to nodes that are not in the navmesh".to_string(),
+                ));
+            }
+        }
+
+        for (key, path) in entries {
+            state.cache.insert_path(key, path.map(Arc::new));
+        }
+        Ok(())
+    }
+
+    /// Writes the graph to a Graphviz DOT file, see `Graph::to_dot`. The nodes and edges of
+    /// `highlight_path`, e.g. a path returned by `find_path`, are drawn in red.
+    pub fn export_dot(
+        &self,
+        filename: impl AsRef<std::path::Path>,
+        highlight_path: Option<&Path>,
+    ) -> Result<(), RePathError> {
+        let state = self.state();
+        let mut writer = BufWriter::new(File::create(filename)?);
+        let highlight = highlight_path.map_or(&[][..], |path| &path[..]);
+        state.graph.write_dot(&mut writer, highlight)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Writes the graph to a GeoJSON file with the positions as they are, see `Graph::to_geojson`.
+    /// `path`, e.g. a path returned by `find_path`, is added as a separate `LineString` feature
+    /// with the property `"path": true`.
+    pub fn export_geojson(
+        &self,
+        filename: impl AsRef<std::path::Path>,
+        path: Option<&Path>,
+    ) -> Result<(), RePathError> {
+        let state = self.state();
+        let mut writer = BufWriter::new(File::create(filename)?);
+        state.graph.write_geojson(&mut writer, false, path.map(|path| &path[..]))?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Finds a path from start_coords to end_coords using the algorithm configured in the settings.
+    /// The call is recorded in `metrics`. On a pathfinder created with `new_lazy`, the region
+    /// around both points is loaded first if needed, see `load_region_around`.
+    pub fn find_path(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        self.load_region_around(&[start_coords, end_coords])?;
+        self.find_path_on(&self.state(), start_coords, end_coords)
+    }
+
+    /// `find_path` on the given state, for callers that keep using the state afterwards and must
+    /// not see a navmesh swapped in by `reload` in between.
+    fn find_path_on(
+        &self,
+        state: &PathfinderState,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let start_time = Instant::now();
+        let result = state.find_path(start_coords, end_coords);
+        let nodes = result.as_ref().map_or(0, |path| path.nodes.len());
+        self.counters.record(nodes, start_time.elapsed());
+        result
+    }
+
+    /// Finds a path between two nodes like `find_path`, for callers that already know the node
+    /// ids, e.g. from `nearest_node_id`, and want to skip looking up the nearest nodes. Returns
+    /// `RePathError::NoPath` if either id is not a node of the graph.
+    pub fn find_path_by_id(&self, start_node_id: usize, end_node_id: usize) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let node_count = state.graph.nodes.len();
+        if start_node_id >= node_count || end_node_id >= node_count {
+            return Err(RePathError::NoPath);
+        }
+        let start_time = Instant::now();
+        let result = state.find_path_between(start_node_id, end_node_id);
+        let nodes = result.as_ref().map_or(0, |path| path.nodes.len());
+        self.counters.record(nodes, start_time.elapsed());
+        result
+    }
+
+    /// Returns the id of the node nearest to the coordinates, the node `find_path` starts or ends
+    /// at for them, or `None` if the graph is empty. Callers that query from the same positions
+    /// repeatedly can keep the id and use `find_path_by_id`.
+    pub fn nearest_node_id(&self, x: Coord, y: Coord, z: Coord) -> Option<usize> {
+        self.state().nearest_node((x, y, z)).ok()
+    }
+
+    /// Finds a path from start_coords to end_coords using A*, avoiding blocked nodes and
+    /// applying cost zones. Gives up after `max_search_nodes` nodes if the setting is set.
+    pub fn find_path_a_star(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.a_star_between(start_node_id, end_node_id)
+    }
+
+    /// Finds a path from start_coords to end_coords like `find_path_a_star`, avoiding blocked
+    /// nodes, applying cost zones and the search limit, but with a custom heuristic. The
+    /// heuristic receives the ids of the current node and the goal node in `graph`, the
+    /// `heuristics` module has predefined functions of the nodes themselves.
+    /// `heuristic_id` identifies the heuristic in the cache, so every heuristic must use its own
+    /// id. The results are cached separately from other searches, like those of
+    /// `find_path_custom_cost`.
+    pub fn find_path_with_heuristic<H: Fn(usize, usize) -> Coord>(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        heuristic: H,
+        heuristic_id: u64,
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        let blocked = read(&state.blocked_nodes);
+        let zones = read(&state.cost_zones);
+        let cache = PrefixedCache::new(heuristic_id, &*state.heuristic_cache);
+        let heuristic = |node| heuristic(node, end_node_id);
+        state.search_in(start_node_id, end_node_id, heuristic, &cache, &blocked, &zones)
+    }
+
+    /// Finds a path from start_coords to end_coords using A* with the edge costs given by
+    /// `cost_fn`, see `Graph::a_star_with_cost`. `cost_fn_id` identifies the cost function in the
+    /// cache, so every cost function must use its own id. The `total_cost` of the result is
+    /// measured with `cost_fn` as well.
+    ///
+    /// The results are cached separately from other searches, they are not saved by `save_cache`
+    /// and do not count towards `cache_hit_rate`.
+    pub fn find_path_custom_cost<C: Fn(&Edge) -> Coord>(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        cost_fn: C,
+        cost_fn_id: u64,
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        let path = state
+            .graph
+            .a_star_with_cost(start_node_id, end_node_id, cost_fn_id, &*state.custom_cost_cache, &cost_fn)
+            .ok_or(RePathError::NoPath)?;
+        let total_cost = path
+            .windows(2)
+            .map(|pair| {
+                state.graph.edges[pair[0].id]
+                    .iter()
+                    .filter(|edge| edge.to == pair[1].id)
+                    .map(|edge| cost_fn(edge) * pair[1].weight)
+                    .fold(Coord::INFINITY, Coord::min)
+            })
+            .sum();
+        Ok(PathResult {
+            total_cost,
+            ..PathResult::new(path, &state.graph)
+        })
+    }
+
+    /// Finds a path from start_coords to end_coords using A* that only traverses edges whose kind
+    /// is one of `allowed_kinds`, e.g. without `EdgeKind::Stairs` for a wheelchair, see
+    /// `Graph::a_star_filtered`. The results are cached separately from other searches, like
+    /// those of `find_path_custom_cost`.
+    pub fn find_path_filtered(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        allowed_kinds: &[EdgeKind],
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .a_star_filtered(start_node_id, end_node_id, &*state.filter_cache, allowed_kinds)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords using A* that only traverses edges carrying
+    /// all `required` labels and none of the `forbidden` ones, see `Graph::a_star_with_labels`.
+    /// The results are cached like those of `find_path_filtered`.
+    pub fn find_path_with_label_filter(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        required: &[&str],
+        forbidden: &[&str],
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .a_star_with_labels(start_node_id, end_node_id, &*state.filter_cache, required, forbidden)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords using A* that only traverses edges an agent of
+    /// radius `agent_radius` fits through, see `Graph::a_star_with_radius`. The results are
+    /// cached like those of `find_path_filtered`.
+    pub fn find_path_with_radius(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        agent_radius: Coord,
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .a_star_with_radius(start_node_id, end_node_id, &*state.filter_cache, agent_radius)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords using A* that never enters the nodes of
+    /// `group`, see `Graph::nodes_in_group`, e.g. to route around a room. The start node may be in
+    /// the group, see `Graph::a_star_with_blocked`. The result is not cached, since it depends on
+    /// the group.
+    pub fn find_path_avoiding_group(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        group: &str,
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+        let blocked: HashSet<usize> = state.graph.nodes_in_group(group).into_iter().collect();
+
+        state.graph
+            .a_star_with_blocked(start_node_id, end_node_id, &DashMap::new(), &blocked)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords using A* that never enters the nodes nearest
+    /// to `avoid_positions`, e.g. the positions of other agents, without affecting other queries
+    /// like `block_node` does, see `Graph::a_star_avoiding`. The start node may be avoided. The
+    /// result is cached separately for every set of avoided nodes.
+    pub fn find_path_avoiding(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        avoid_positions: &[(Coord, Coord, Coord)],
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+        let mut avoid = avoid_positions
+            .iter()
+            .map(|&position| state.nearest_node(position))
+            .collect::<Result<Vec<_>, _>>()?;
+        avoid.sort_unstable();
+        avoid.dedup();
+        let mut hasher = DefaultHasher::new();
+        avoid.hash(&mut hasher);
+
+        state.graph
+            .a_star_avoiding(start_node_id, end_node_id, &avoid, hasher.finish(), &*state.avoiding_cache)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords using A* whose cost is at most `max_cost`, see
+    /// `Graph::a_star_budgeted`. Returns `RePathError::NoPath` if the end cannot be reached within
+    /// the budget. The results are cached separately from other searches.
+    pub fn find_path_within_budget(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        max_cost: Coord,
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .a_star_budgeted(start_node_id, end_node_id, max_cost, &*state.budget_cache)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a random path from start_coords to end_coords that is the more likely the cheaper it
+    /// is, see `Graph::random_weighted_path`, e.g. to give agents some variety. A `temperature`
+    /// of 0 finds a cheapest path, higher temperatures more varied ones. The result is not
+    /// cached.
+    pub fn find_path_stochastic(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        temperature: Coord,
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .random_weighted_path(start_node_id, end_node_id, &mut thread_rng(), temperature)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Returns every node that can be reached from the node nearest to start_coords at a cost of
+    /// at most `max_cost`, with the cost of the cheapest path to it, sorted by the cost, see
+    /// `Graph::reachable_nodes_bfs`. Blocked nodes and cost zones are ignored. Returns an empty
+    /// list for an empty graph.
+    pub fn reachable_from(&self, start_coords: (Coord, Coord, Coord), max_cost: Coord) -> Vec<(usize, Coord)> {
+        let state = self.state();
+        match state.nearest_node(start_coords) {
+            Ok(start_node_id) => state.graph.reachable_nodes_bfs(start_node_id, max_cost),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Finds a path from start_coords to end_coords using hierarchical A* over cubes with an edge
+    /// length of `cell_size`, see `Graph::a_star_hierarchical`. Faster than `find_path_a_star`
+    /// on large navmeshes, but the path may be a bit more expensive. The result is not cached.
+    pub fn find_path_hierarchical(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        cell_size: Coord,
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .a_star_hierarchical(start_node_id, end_node_id, cell_size)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords using Dijkstra's algorithm.
+    /// Use this instead of A* when edge costs do not correspond to distances.
+    /// Avoids blocked nodes, applies cost zones and gives up after `max_search_nodes` nodes if
+    /// the setting is set.
+    pub fn find_path_dijkstra(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.dijkstra_between(start_node_id, end_node_id)
+    }
+
+    /// Finds a path from start_coords to end_coords using Jump Point Search.
+    /// Falls back to A* if the navmesh is not a uniform grid.
+    pub fn find_path_jps(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        let path = match &state.grid_layout {
+            Some(layout) => state.graph.jps_with_layout(start_node_id, end_node_id, &state.main_cache(), layout),
+            None => state.graph.a_star(start_node_id, end_node_id, &state.main_cache()),
+        };
+        path.map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords using iterative-deepening A*.
+    /// Uses far less memory than A* but can be much slower, see `Graph::ida_star`.
+    /// The cache is not used.
+    pub fn find_path_ida_star(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .ida_star(start_node_id, end_node_id, &state.main_cache())
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds a path from start_coords to end_coords like `find_path` and smooths it with
+    /// `path::smooth`, which removes waypoints the agent can skip by walking in a straight line.
+    pub fn find_path_smooth(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        self.load_region_around(&[start_coords, end_coords])?;
+        let state = self.state();
+        let path = self.find_path_on(&state, start_coords, end_coords)?;
+        let mesh = state.triangle_mesh.get_or_init(|| TriangleMesh::new(&state.graph));
+
+        let smoothed = smooth_with_mesh(&path.nodes, &state.graph, mesh, DEFAULT_SMOOTHING_ANGLE);
+
+        Ok(PathResult::new(Arc::new(smoothed), &state.graph))
+    }
+
+    /// Returns the ids of all nodes a sphere of `radius` overlaps while moving from `start` to
+    /// `end`, in ascending order, see `Graph::sphere_cast`. Only the nodes in the box around the
+    /// swept capsule are checked, found with the spatial index.
+    pub fn sphere_cast(&self, start: (Coord, Coord, Coord), end: (Coord, Coord, Coord), radius: Coord) -> Vec<usize> {
+        let state = self.state();
+        let sweep = Sweep::new(state.to_graph_coords(start), state.to_graph_coords(end), radius);
+        let (min, max) = sweep.bounds();
+        let mut ids = state.spatial_index.nodes_in_aabb_fast(min, max);
+        ids.retain(|&id| sweep.contains(&state.graph.nodes[id]));
+        ids
+    }
+
+    /// Casts a ray against the triangles of the navmesh, see `Graph::raycast`. The triangles are
+    /// reconstructed on first use and shared with path smoothing.
+    pub fn raycast(&self, origin: (Coord, Coord, Coord), direction: (Coord, Coord, Coord)) -> Option<(usize, Coord)> {
+        let state = self.state();
+        let mesh = state.triangle_mesh.get_or_init(|| TriangleMesh::new(&state.graph));
+        mesh.raycast(&state.graph, origin, direction)
+    }
+
+    /// Finds a path from start_coords to end_coords like `find_path` and returns an iterator over
+    /// its nodes, which reads the cached path in place instead of copying it.
+    pub fn find_path_iter(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathIter, RePathError> {
+        Ok(PathIter::new(self.find_path(start_coords, end_coords)?.nodes))
+    }
+
+    /// Finds a path from start_coords to end_coords like `find_path` and reduces its waypoints
+    /// with `path::decimate`, dropping nodes closer than `epsilon` to the simplified path.
+    pub fn find_path_decimated(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        epsilon: Coord,
+    ) -> Result<PathResult, RePathError> {
+        self.load_region_around(&[start_coords, end_coords])?;
+        let state = self.state();
+        let path = self.find_path_on(&state, start_coords, end_coords)?;
+
+        Ok(PathResult::new(Arc::new(decimate(&path.nodes, epsilon)), &state.graph))
+    }
+
+    /// Finds a path from start_coords to end_coords like `find_path` and returns a point every
+    /// `step_size` world units along it, see `path::interpolate`.
+    pub fn find_path_interpolated(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        step_size: Coord,
+    ) -> Result<Vec<(Coord, Coord, Coord)>, RePathError> {
+        let path = self.find_path(start_coords, end_coords)?;
 
-        RePathfinder { graph, cache }
+        Ok(interpolate(&path.nodes, step_size))
     }
 
-    /// Finds a path from start_coords to end_coords.
-    pub fn find_path(&self, start_coords: (f32, f32, f32), end_coords: (f32, f32, f32)) -> Option<Path> {
-        let start_node_id = self.graph.nearest_node(start_coords.0, start_coords.1, start_coords.2)?;
-        let end_node_id = self.graph.nearest_node(end_coords.0, end_coords.1, end_coords.2)?;
+    /// Finds a path from start_coords to end_coords like `find_path` and returns `samples` points
+    /// along a smooth curve through its waypoints, see `path::to_bezier`.
+    pub fn find_path_bezier(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        samples: usize,
+    ) -> Result<Vec<(Coord, Coord, Coord)>, RePathError> {
+        let path = self.find_path(start_coords, end_coords)?;
+
+        Ok(to_bezier(&path.nodes, samples))
+    }
+
+    /// Checks whether an agent at current_pos, e.g. pushed around by physics, strayed more than
+    /// `threshold` from its path, measured to the closest point on the path, see
+    /// `path::closest_point_on_path`. If it did, a new path from current_pos to goal_coords is
+    /// found with `find_path`, otherwise the current path is returned without a search. An empty
+    /// path always counts as deviated.
+    pub fn reroute_if_deviated(
+        &self,
+        current_pos: (Coord, Coord, Coord),
+        current_path: &Path,
+        threshold: Coord,
+        goal_coords: (Coord, Coord, Coord),
+    ) -> Result<Path, RePathError> {
+        if let Some((_, closest)) = closest_point_on_path(current_path, current_pos) {
+            if distance(&self.state().to_graph_coords(current_pos), &closest) <= threshold {
+                return Ok(Arc::clone(current_path));
+            }
+        }
+
+        Ok(self.find_path(current_pos, goal_coords)?.nodes)
+    }
 
-        self.graph.a_star(start_node_id, end_node_id, &self.cache)
+    /// Finds a path from start_coords to end_coords using A*. If the end is unreachable, the
+    /// returned path leads to the reachable node closest to it and `complete` is false.
+    pub fn find_path_partial(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PartialPath, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        Ok(state.graph.a_star_partial(start_node_id, end_node_id, &state.main_cache()))
+    }
+
+    /// Finds the cheapest path to end_coords from whichever of the given start coordinates
+    /// reaches it at the lowest cost, e.g. to find the nearest of several bases.
+    /// The first node of the returned path is the node nearest to the winning start coordinates.
+    pub fn find_path_from_nearest(
+        &self,
+        start_coords_list: &[(Coord, Coord, Coord)],
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_ids = start_coords_list
+            .iter()
+            .map(|&coords| state.nearest_node(coords))
+            .collect::<Result<Vec<_>, _>>()?;
+        let end_node_id = state.nearest_node(end_coords)?;
+
+        state.graph
+            .a_star_multi_source(&start_node_ids, end_node_id, &state.main_cache())
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds the path from start_coords to whichever of the end coordinates is cheapest to reach.
+    /// The last node of the returned path is the node nearest to the winning end coordinates.
+    pub fn find_path_to_nearest(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords_list: &[(Coord, Coord, Coord)],
+    ) -> Result<PathResult, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_ids = end_coords_list
+            .iter()
+            .map(|&coords| state.nearest_node(coords))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        state.graph
+            .a_star_nearest_goal(start_node_id, &end_node_ids, &state.main_cache())
+            .map(|(_, path)| path)
+            .map(|path| PathResult::new(path, &state.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    /// Finds up to `k` alternative paths from start_coords to end_coords using Yen's algorithm.
+    /// The paths are sorted ascending by cost, the first one being the path `find_path_a_star`
+    /// returns. Returns an empty `Vec` if no path exists.
+    pub fn find_k_paths(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        k: usize,
+    ) -> Vec<PathResult> {
+        let state = self.state();
+        let (Ok(start_node_id), Ok(end_node_id)) =
+            (state.nearest_node(start_coords), state.nearest_node(end_coords))
+        else {
+            return Vec::new();
+        };
+
+        state.graph
+            .k_shortest_paths(start_node_id, end_node_id, k, &state.main_cache())
+            .into_iter()
+            .map(|path| PathResult::new(path, &state.graph))
+            .collect()
+    }
+
+    /// Finds the paths of many queries at once, e.g. for a whole squad of agents in one frame, and
+    /// returns the results in the order of the queries. The queries are resolved to their nearest
+    /// nodes and queries between the same nodes are searched only once, with the distinct
+    /// searches running in parallel. The calls are not recorded in `metrics`.
+    pub fn find_paths_batch(
+        &self,
+        queries: &[PathQuery],
+    ) -> Vec<Result<PathResult, RePathError>> {
+        let state = self.state();
+        let node_pairs: Vec<Result<(usize, usize), RePathError>> = queries
+            .par_iter()
+            .map(|&(start_coords, end_coords)| Ok((state.nearest_node(start_coords)?, state.nearest_node(end_coords)?)))
+            .collect();
+
+        let mut distinct_pairs: Vec<(usize, usize)> = node_pairs.iter().flatten().copied().collect();
+        distinct_pairs.sort_unstable();
+        distinct_pairs.dedup();
+        // A failed search can only mean that there is no path, so `None` stands for `NoPath`
+        let paths: HashMap<(usize, usize), Option<PathResult>> = distinct_pairs
+            .into_par_iter()
+            .map(|(start, end)| {
+                ((start, end), state.find_path_between(start, end).ok())
+            })
+            .collect();
+
+        node_pairs
+            .into_iter()
+            .map(|pair| paths[&pair?].clone().ok_or(RePathError::NoPath))
+            .collect()
+    }
+
+    /// Like `find_paths_batch`, but finds every path with `find_path_multithreaded`, splitting it
+    /// into `segment_count` segments, e.g. for long-range queries. Queries run in parallel, but
+    /// identical queries are not merged.
+    pub fn find_paths_batch_multithreaded(
+        &self,
+        queries: &[PathQuery],
+        segment_count: u8,
+    ) -> Vec<Result<PathResult, RePathError>> {
+        queries
+            .par_iter()
+            .map(|&(start_coords, end_coords)| self.find_path_multithreaded(start_coords, end_coords, segment_count))
+            .collect()
     }
 
     /// Finds a path from start_coords to end_coords using multiple threads.
     /// This function splits the pathfinding task into segments, which are processed concurrently.
     pub fn find_path_multithreaded(
         &self,
-        start_coords: (f32, f32, f32),
-        end_coords: (f32, f32, f32),
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
         segment_count: u8,
-    ) -> Option<Path> {
+    ) -> Result<PathResult, RePathError> {
+        self.load_region_around(&[start_coords, end_coords])?;
+        let state = self.state();
         if segment_count <= 1 {
-            return self.find_path(start_coords, end_coords);
+            return self.find_path_on(&state, start_coords, end_coords);
         }
 
         // Calculate intermediate points
         let mut points = vec![start_coords];
         for i in 1..segment_count {
-            let t = i as f32 / segment_count as f32;
+            let t = i as Coord / segment_count as Coord;
             let intermediate_point = (
                 start_coords.0 + t * (end_coords.0 - start_coords.0),
                 start_coords.1 + t * (end_coords.1 - start_coords.1),
@@ -86,30 +868,1002 @@ impl RePathfinder {
         let segments: Vec<_> = points.windows(2).collect();
         let paths: Vec<_> = segments
             .into_par_iter()
-            .map(|segment| {
-                let start_node_id = self
-                    .graph
-                    .nearest_node(segment[0].0, segment[0].1, segment[0].2)?;
-                let end_node_id = self
-                    .graph
-                    .nearest_node(segment[1].0, segment[1].1, segment[1].2)?;
-                self.graph.a_star(start_node_id, end_node_id, &self.cache)
-            })
+            .map(|segment| self.find_path_on(&state, segment[0], segment[1]))
             .collect();
 
         // Combine paths
         let mut full_path = Vec::new();
-        for path_option in paths {
-            if let Some(path) = path_option {
-                if !full_path.is_empty() {
-                    full_path.pop(); // Remove duplicate node
+        for path in paths {
+            // If any segment fails, the whole path fails
+            let path = path?;
+            if !full_path.is_empty() {
+                full_path.pop(); // Remove duplicate node
+            }
+            full_path.extend(path.nodes.iter().cloned());
+        }
+
+        Ok(PathResult::new(Arc::new(full_path), &state.graph))
+    }
+
+    /// Finds a path from start_coords to end_coords that visits the nodes nearest to the
+    /// `waypoints` in order, e.g. checkpoints of a race. Every segment between consecutive points
+    /// is found with `find_path`, so it uses the cache and is recorded in the metrics, and the node
+    /// shared by two segments appears once. Returns `Err(RePathError::NoPath)` if any segment
+    /// cannot be found.
+    pub fn find_path_through_waypoints(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        waypoints: &[(Coord, Coord, Coord)],
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let points: Vec<_> = std::iter::once(start_coords)
+            .chain(waypoints.iter().copied())
+            .chain(std::iter::once(end_coords))
+            .collect();
+        self.load_region_around(&points)?;
+        let state = self.state();
+
+        let mut full_path: Vec<Node> = Vec::new();
+        for segment in points.windows(2) {
+            let path = self.find_path_on(&state, segment[0], segment[1])?;
+            full_path.pop(); // Remove duplicate node
+            full_path.extend(path.nodes.iter().cloned());
+        }
+
+        Ok(PathResult::new(Arc::new(full_path), &state.graph))
+    }
+
+    /// Starts a search from start_coords to end_coords that runs in slices of at most
+    /// `max_steps_per_call` expanded nodes, so a huge search can be spread over several frames,
+    /// see `SlicedPathfinder::advance`. Like `Graph::a_star_start`, it ignores blocked nodes, cost
+    /// zones and the cache. Returns an error if the graph is empty.
+    pub fn find_path_sliced(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        max_steps_per_call: usize,
+    ) -> Result<SlicedPathfinder, RePathError> {
+        let state = self.state();
+        let start_node_id = state.nearest_node(start_coords)?;
+        let end_node_id = state.nearest_node(end_coords)?;
+        Ok(SlicedPathfinder {
+            state: state.graph.a_star_start(start_node_id, end_node_id),
+            graph: Arc::clone(&state.graph),
+            max_steps_per_call,
+        })
+    }
+
+    /// The number of paths currently in the cache, including those of `find_path_custom_cost`,
+    /// `find_path_avoiding`, `find_path_within_budget`, `find_path_with_heuristic` and the
+    /// filtered searches.
+    pub fn cache_len(&self) -> usize {
+        let state = self.state();
+        state.cache.len()
+            + state.custom_cost_cache.len()
+            + state.avoiding_cache.len()
+            + state.budget_cache.len()
+            + state.filter_cache.len()
+            + state.restricted_cache.len()
+            + state.heuristic_cache.len()
+    }
+
+    /// The fraction of cache lookups since the precomputation that found a cached result.
+    pub fn cache_hit_rate(&self) -> f64 {
+        self.state().cache.hit_rate()
+    }
+
+    /// Returns an overview of the graph and the cache, e.g. to check a navmesh after loading or
+    /// modifying it. The cache warm percentage is how full the cache of `find_path` is.
+    pub fn diagnostic_report(&self) -> DiagnosticReport {
+        let state = self.state();
+        let cache_warm_percentage = match state.cache.capacity() {
+            0 => 0.0,
+            capacity => state.cache.len() as f64 / capacity as f64 * 100.0,
+        };
+        DiagnosticReport::new(&state.graph, cache_warm_percentage)
+    }
+
+    /// Finds a path like `find_path` and measures the call. If the `metrics_csv` setting is set,
+    /// a row with the timing is appended to that file, see `utils::append_path_metric_to_csv`.
+    /// Failing to write the row is returned as an error even if a path was found.
+    pub fn record_path_metric(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        self.load_region_around(&[start_coords, end_coords])?;
+        let state = self.state();
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_millis() as u64);
+        let cache_hit = match (state.nearest_node(start_coords), state.nearest_node(end_coords)) {
+            (Ok(start), Ok(end)) => {
+                state.cache.contains(&(start, end))
+                    || state.disk_cache.as_ref().is_some_and(|disk| disk.contains(&(start, end)))
+            }
+            _ => false,
+        };
+        let start_time = Instant::now();
+        let result = self.find_path_on(&state, start_coords, end_coords);
+        let duration = start_time.elapsed();
+
+        if let Some(metrics_csv) = &state.settings.metrics_csv {
+            let metric = PathMetric {
+                timestamp,
+                start: start_coords,
+                end: end_coords,
+                path_len: result.as_ref().map_or(0, |path| path.nodes.len()),
+                duration,
+                cache_hit,
+            };
+            append_path_metric_to_csv(metrics_csv, &metric)?;
+        }
+        result
+    }
+
+    /// Returns a snapshot of the statistics of `find_path` calls since the precomputation or
+    /// `reset_metrics`, including the methods built on it such as `find_path_smooth`. The cache
+    /// statistics count all lookups like `cache_hit_rate`. Clones share the statistics.
+    pub fn metrics(&self) -> PathfindingMetrics {
+        let state = self.state();
+        self.counters.snapshot(state.cache.hits(), state.cache.misses())
+    }
+
+    /// Resets the statistics returned by `metrics` and `cache_hit_rate`.
+    pub fn reset_metrics(&self) {
+        self.counters.reset();
+        self.state().cache.reset_stats();
+    }
+
+    /// Removes all cached results starting or ending at the given node, e.g. after the node was
+    /// moved or its edges changed.
+    pub fn invalidate_cache_for_node(&self, node_id: usize) {
+        let state = read(&self.state);
+        state.cache
+            .retain(|&(start, goal), _| start != node_id && goal != node_id);
+        state.custom_cost_cache
+            .retain(|&(_, start, goal), _| start != node_id && goal != node_id);
+        state.avoiding_cache
+            .retain(|&(_, start, goal), _| start != node_id && goal != node_id);
+        state.budget_cache
+            .retain(|&(_, start, goal), _| start != node_id && goal != node_id);
+        state.filter_cache
+            .retain(|&(_, start, goal), _| start != node_id && goal != node_id);
+        state.restricted_cache
+            .retain(|&(start, goal), _| start != node_id && goal != node_id);
+        state.heuristic_cache
+            .retain(|&(_, start, goal), _| start != node_id && goal != node_id);
+        if let Some(disk) = &state.disk_cache {
+            disk.retain(|&(start, goal), _| start != node_id && goal != node_id);
+        }
+    }
+
+    /// Removes all cached paths that traverse the directed edge from `from` to `to`, e.g. after
+    /// the edge was removed or became more expensive. This checks every cached path, so it is
+    /// proportional to the total length of all cached paths.
+    ///
+    /// Paths that do not use the edge are kept. If edges are added or become cheaper, other
+    /// cached paths and cached failures may no longer be optimal, use `clear_cache` instead.
+    pub fn invalidate_cache_for_edge(&self, from: usize, to: usize) {
+        let state = read(&self.state);
+        let keep = |path: &Option<Path>| {
+            path.as_ref().is_none_or(|path| {
+                !path.windows(2).any(|pair| pair[0].id == from && pair[1].id == to)
+            })
+        };
+        state.cache.retain(|_, path| keep(path));
+        state.custom_cost_cache.retain(|_, path| keep(path));
+        state.avoiding_cache.retain(|_, path| keep(path));
+        state.budget_cache.retain(|_, path| keep(path));
+        state.filter_cache.retain(|_, path| keep(path));
+        state.restricted_cache.retain(|_, path| keep(path));
+        state.heuristic_cache.retain(|_, path| keep(path));
+        if let Some(disk) = &state.disk_cache {
+            disk.retain(|_, path| keep(path));
+        }
+    }
+
+    /// Removes all cached results, including those of the disk cache, which is cheaper than
+    /// invalidating them one by one after large changes of the navmesh.
+    pub fn clear_cache(&self) {
+        let state = read(&self.state);
+        state.cache.clear();
+        state.custom_cost_cache.clear();
+        state.avoiding_cache.clear();
+        state.budget_cache.clear();
+        state.filter_cache.clear();
+        state.restricted_cache.clear();
+        state.heuristic_cache.clear();
+        if let Some(disk) = &state.disk_cache {
+            disk.clear();
+        }
+    }
+
+    /// Marks the node as impassable for `find_path`, e.g. for a locked door, until it is
+    /// unblocked again. Only `find_path`, `find_path_a_star`, `find_path_dijkstra` and
+    /// `find_path_with_heuristic` avoid blocked nodes. While any node is blocked, they cache their results apart from the other
+    /// searches, so neither sees paths of the other.
+    pub fn block_node(&self, node_id: usize) {
+        read(&self.state).block_nodes(&[node_id]);
+    }
+
+    /// Blocks all nodes within `radius` of `center`, see `block_node`.
+    pub fn block_region(&self, center: (Coord, Coord, Coord), radius: Coord) {
+        let state = read(&self.state);
+        state.block_nodes(&state.nodes_within_radius(center, radius));
+    }
+
+    /// Makes a blocked node passable again. Since this can shorten any path and connect nodes
+    /// that were unreachable, all results cached while nodes were blocked are removed.
+    pub fn unblock_node(&self, node_id: usize) {
+        let state = read(&self.state);
+        let mut blocked = write(&state.blocked_nodes);
+        if blocked.remove(&node_id) {
+            // Still holding the lock, so no search with the old blocked nodes can store its result
+            state.restricted_cache.clear();
+            state.heuristic_cache.clear();
+        }
+    }
+
+    /// Makes entering the nodes within `radius` of `center` `multiplier` times as expensive for
+    /// `find_path`, e.g. for a swamp or deep water, and returns the id of the zone for
+    /// `remove_cost_zone`. Overlapping zones stack multiplicatively. Unlike blocked nodes, agents
+    /// still cross a zone if a detour around it costs more.
+    ///
+    /// The `total_cost` of found paths includes the multipliers. A multiplier below 1 can make A*
+    /// miss the cheapest path, since its heuristic may then overestimate the remaining cost.
+    /// While any zone exists, `find_path` caches its results like while nodes are blocked, see
+    /// `block_node`. Those results are removed, as any of them may no longer be the cheapest.
+    pub fn add_cost_zone(&self, center: (Coord, Coord, Coord), radius: Coord, multiplier: Coord) -> CostZoneId {
+        let state = read(&self.state);
+        let mut zones = write(&state.cost_zones);
+        let id = CostZoneId(zones.next_id);
+        zones.next_id += 1;
+        let zone = CostZone {
+            center,
+            radius,
+            multiplier,
+            node_ids: state.nodes_within_radius(center, radius),
+        };
+        for &node_id in &zone.node_ids {
+            *zones.multipliers.entry(node_id).or_insert(1.0) *= multiplier;
+        }
+        zones.zones.insert(id, zone);
+        // Still holding the lock, so no search with the old zones can store its result
+        state.restricted_cache.clear();
+        state.heuristic_cache.clear();
+        id
+    }
+
+    /// Removes a zone added by `add_cost_zone` and returns whether it existed. The results
+    /// cached while zones existed are removed if it did.
+    pub fn remove_cost_zone(&self, id: CostZoneId) -> bool {
+        let state = read(&self.state);
+        let mut zones = write(&state.cost_zones);
+        if zones.zones.remove(&id).is_none() {
+            return false;
+        }
+        // Rebuilt instead of divided out to keep rounding errors from accumulating
+        zones.rebuild_multipliers();
+        state.restricted_cache.clear();
+        state.heuristic_cache.clear();
+        true
+    }
+
+    /// Loads the edges of a region of a pathfinder created with `new_lazy`, see
+    /// `Graph::load_region`, e.g. when a player approaches it. The `slope_factor` setting is
+    /// applied to the new edges. Like `update_graph`, the new graph is swapped in for clones as
+    /// well and the cache is cleared if edges were added. Does nothing for other pathfinders.
+    pub fn load_region(&self, aabb: Aabb) -> Result<(), RePathError> {
+        let slope_factor = self.state().settings.slope_factor;
+        self.update_graph(|graph| {
+            let edge_counts: Vec<usize> = graph.edges.iter().map(Vec::len).collect();
+            graph.load_region(aabb)?;
+            if slope_factor != 0.0 {
+                graph.apply_slope_factor_after(&edge_counts, slope_factor);
+            }
+            Ok(())
+        })
+    }
+
+    /// Loads the region a search between the points needs on a pathfinder created with
+    /// `new_lazy`, unless it is loaded already: the box spanned by the points, grown by
+    /// `lazy_chunk_size` on every side, see `load_region`. A path that has to take a detour
+    /// further out is only found once the region it takes is loaded as well.
+    fn load_region_around(&self, points: &[(Coord, Coord, Coord)]) -> Result<(), RePathError> {
+        let state = self.state();
+        if state.graph.lazy.is_none() {
+            return Ok(());
+        }
+        let margin = state.settings.lazy_chunk_size;
+        let mut min = (Coord::INFINITY, Coord::INFINITY, Coord::INFINITY);
+        let mut max = (Coord::NEG_INFINITY, Coord::NEG_INFINITY, Coord::NEG_INFINITY);
+        for &point in points {
+            let (x, y, z) = state.to_graph_coords(point);
+            min = (min.0.min(x - margin), min.1.min(y - margin), min.2.min(z - margin));
+            max = (max.0.max(x + margin), max.1.max(y + margin), max.2.max(z + margin));
+        }
+        if state.graph.is_region_loaded((min, max)) {
+            return Ok(());
+        }
+        drop(state);
+        self.load_region((min, max))
+    }
+
+    /// The graph searched by this pathfinder. It stays valid after a `reload`, which swaps in
+    /// another one.
+    pub fn graph(&self) -> Arc<Graph> {
+        Arc::clone(&self.state().graph)
+    }
+
+    /// Changes the graph at runtime, e.g. with `Graph::remove_node` or `Graph::set_edge_cost`, and
+    /// returns the result of `update`. Like a `reload`, the changed graph is swapped in for clones
+    /// as well, while queries that started before finish on the previous graph. `update` runs on
+    /// a copy of the graph while other changes of this pathfinder wait, so it must not use the
+    /// pathfinder itself.
+    ///
+    /// If the change increments `Graph::generation`, the cache is cleared and the indices built
+    /// from the graph are rebuilt, since cached paths and node ids may no longer be valid. The
+    /// disk cache is left behind, since its paths belong to the navmesh file. Blocked nodes stay
+    /// blocked under their new ids as long as a node remains at their position, e.g. when
+    /// `Graph::remove_node` moves the following ids down.
+    pub fn update_graph<R>(&self, update: impl FnOnce(&mut Graph) -> R) -> R {
+        let mut current = write(&self.state);
+        let mut state = PathfinderState::clone(&current);
+        let result = update(Arc::make_mut(&mut state.graph));
+        // Queries still running on the previous state must not see blocks of the new one
+        state.blocked_nodes = Arc::new(RwLock::new(read(&current.blocked_nodes).clone()));
+
+        if state.graph.generation() != state.graph_generation {
+            state.graph_generation = state.graph.generation();
+            state.cache = Arc::new(
+                LruPathCache::new(state.settings.cache_capacity)
+                    .with_thread_local_capacity(state.settings.thread_local_cache_capacity),
+            );
+            state.custom_cost_cache = Arc::new(LruPathCache::new(state.settings.cache_capacity));
+            state.avoiding_cache = Arc::new(LruPathCache::new(state.settings.cache_capacity));
+            state.budget_cache = Arc::new(LruPathCache::new(state.settings.cache_capacity));
+            state.filter_cache = Arc::new(LruPathCache::new(state.settings.cache_capacity));
+            state.restricted_cache = Arc::new(LruPathCache::new(state.settings.cache_capacity));
+            state.heuristic_cache = Arc::new(LruPathCache::new(state.settings.cache_capacity));
+            state.disk_cache = None;
+            state.grid_layout = GridLayout::detect(&state.graph).map(Arc::new);
+            state.spatial_index = Arc::new(SpatialIndex::new(&state.graph));
+            state.triangle_mesh = Arc::new(OnceLock::new());
+
+            // Node ids may have changed, so find the blocked nodes and the nodes within the zones
+            // again
+            let blocked = read(&current.blocked_nodes)
+                .iter()
+                .filter_map(|&id| {
+                    let node = current.graph.nodes.get(id)?;
+                    let moved = state.spatial_index.nearest_node_fast(node.x, node.y, node.z)?;
+                    let moved_node = &state.graph.nodes[moved];
+                    ((moved_node.x, moved_node.y, moved_node.z) == (node.x, node.y, node.z)).then_some(moved)
+                })
+                .collect();
+            state.blocked_nodes = Arc::new(RwLock::new(blocked));
+            let zones = read(&state.cost_zones).clone();
+            state.set_cost_zones(zones);
+        }
+        *current = Arc::new(state);
+        result
+    }
+
+    /// Replaces the navmesh and the settings without restarting, e.g. after level designers
+    /// edited the navmesh file. The new navmesh is loaded and its cache precomputed or loaded like
+    /// in `new` before anything changes, so if that fails this pathfinder stays as it was.
+    ///
+    /// Other threads keep finding paths during the reload, e.g. through a shared
+    /// `Arc<RePathfinder>` or on clones, which all use the new navmesh once it is swapped in.
+    /// Queries that started before finish on the previous navmesh with its cache.
+    ///
+    /// The metrics and the cost zones carry over, the zones now contain the nodes of the new
+    /// navmesh within them. Blocked nodes are unblocked, since node ids of the new navmesh refer
+    /// to other nodes, and a disk cache is left behind, see `with_disk_cache`.
+    pub fn reload(&self, new_settings: RePathSettings) -> Result<(), RePathError> {
+        let mut reloaded = PathfinderState::clone(&RePathfinder::new(new_settings)?.state());
+
+        // Zones added or removed until the swap must not get lost
+        let mut state = write(&self.state);
+        reloaded.set_cost_zones(read(&state.cost_zones).clone());
+        *state = Arc::new(reloaded);
+        Ok(())
+    }
+
+    /// The current state, see `reload`. Queries keep it for their whole run, changes of the
+    /// blocked nodes, cost zones and caches hold the lock instead, so `reload` waits for them.
+    fn state(&self) -> Arc<PathfinderState> {
+        Arc::clone(&read(&self.state))
+    }
+}
+
+impl PathfinderState {
+    /// Finds a path with the algorithm configured in the settings, without recording it in the
+    /// metrics.
+    fn find_path(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let start_node_id = self.nearest_node(start_coords)?;
+        let end_node_id = self.nearest_node(end_coords)?;
+        self.find_path_between(start_node_id, end_node_id)
+    }
+
+    /// The cache of the searches that depend on nothing but the graph, backed by the disk cache
+    /// if there is one.
+    fn main_cache(&self) -> WriteThroughCache<'_> {
+        WriteThroughCache::new(&self.cache, self.disk_cache.as_deref())
+    }
+
+    /// Finds a path between two nodes with the algorithm configured in the settings.
+    fn find_path_between(&self, start_node_id: usize, end_node_id: usize) -> Result<PathResult, RePathError> {
+        match self.settings.algorithm {
+            Algorithm::AStar => self.a_star_between(start_node_id, end_node_id),
+            Algorithm::Dijkstra => self.dijkstra_between(start_node_id, end_node_id),
+        }
+    }
+
+    fn precompute_pairs(&self, pairs: &[(usize, usize)]) {
+        pairs.par_iter().for_each(|&(start, goal)| {
+            self.graph.a_star(start, goal, &self.main_cache());
+        });
+    }
+
+    /// Precomputes paths between random pairs of nodes within the precompute radius until all
+    /// pairs are done or `cancel` is set.
+    fn precompute(
+        &self,
+        counters: &PathfindingCounters,
+        on_progress: &(impl Fn(usize, usize) + Sync),
+        cancel: &AtomicBool,
+    ) {
+        let graph = &*self.graph;
+        let precompute_start = Instant::now();
+        let node_ids: Vec<_> = (0..graph.nodes.len()).collect();
+        // There is no path between nodes of different strongly connected components
+        let components = label_strongly_connected_components(graph);
+        let total = self.settings.total_precompute_pairs;
+        let completed = AtomicUsize::new(0);
+
+        // Precompute paths between random pairs of nodes within a specified radius
+        // Returning an error stops rayon from starting further pairs
+        let _ = (0..total)
+            .into_par_iter()
+            .try_for_each(|_| {
+                if cancel.load(Ordering::Relaxed) {
+                    return Err(());
                 }
-                full_path.extend(path.iter());
-            } else {
-                return None; // If any segment fails, the whole path fails
+                if self.precompute_pair(graph, &node_ids, &components) {
+                    counters.record_precomputed(1);
+                }
+                on_progress(completed.fetch_add(1, Ordering::Relaxed) + 1, total);
+                Ok(())
+            });
+
+        let precompute_duration = precompute_start.elapsed();
+        println!("Precomputation time: {:?}", precompute_duration);
+    }
+
+    /// Computes the path between a random node and a random other node within the precompute
+    /// radius, unless they are in different strongly connected components. Returns whether a
+    /// path was searched.
+    fn precompute_pair(&self, graph: &Graph, node_ids: &[usize], components: &[usize]) -> bool {
+        let mut rng = rand::thread_rng();
+        let Some(&start_node_id) = node_ids.choose(&mut rng) else {
+            return false;
+        };
+        let start_node = &graph.nodes[start_node_id];
+        let mut nearby_nodes = self
+            .spatial_index
+            .nodes_within_radius_fast(start_node, self.settings.precompute_radius);
+
+        // Remove the start node from the list of nearby nodes if present
+        nearby_nodes.retain(|&id| id != start_node_id);
+
+        match nearby_nodes.choose(&mut rng) {
+            Some(&goal_node_id) if components[start_node_id] == components[goal_node_id] => {
+                graph.a_star(start_node_id, goal_node_id, &self.main_cache());
+                true
             }
+            _ => false,
         }
+    }
+
+    fn a_star_between(&self, start_node_id: usize, end_node_id: usize) -> Result<PathResult, RePathError> {
+        let heuristic = |node| self.graph.heuristic(node, end_node_id);
+        self.search(start_node_id, end_node_id, heuristic)
+    }
 
-        Some(Arc::new(full_path))
+    fn dijkstra_between(&self, start_node_id: usize, end_node_id: usize) -> Result<PathResult, RePathError> {
+        let unrestricted = read(&self.blocked_nodes).is_empty()
+            && read(&self.cost_zones).zones.is_empty()
+            && self.settings.max_search_nodes.is_none();
+        if !unrestricted {
+            // A* without a heuristic expands the nodes in the same order as Dijkstra's algorithm
+            return self.search(start_node_id, end_node_id, |_| 0.0);
+        }
+
+        self.graph
+            .dijkstra(start_node_id, end_node_id, &self.main_cache())
+            .map(|path| PathResult::new(path, &self.graph))
+            .ok_or(RePathError::NoPath)
+    }
+
+    fn block_nodes(&self, node_ids: &[usize]) {
+        let mut blocked = write(&self.blocked_nodes);
+        let newly_blocked: HashSet<usize> = node_ids.iter().copied().filter(|&id| blocked.insert(id)).collect();
+        if newly_blocked.is_empty() {
+            return;
+        }
+
+        // Cached failures stay valid, blocking cannot make a node reachable
+        let keep = |path: &Option<Path>| {
+            path.as_ref().is_none_or(|path| path.iter().all(|node| !newly_blocked.contains(&node.id)))
+        };
+        self.restricted_cache.retain(|_, path| keep(path));
+        self.heuristic_cache.retain(|_, path| keep(path));
+    }
+
+    /// The cached search behind `find_path_a_star` and `find_path_dijkstra`, which avoids blocked
+    /// nodes, applies cost zones and the search limit of the settings.
+    fn search(&self, start: usize, end: usize, heuristic: impl Fn(usize) -> Coord) -> Result<PathResult, RePathError> {
+        let blocked = read(&self.blocked_nodes);
+        let zones = read(&self.cost_zones);
+        let restricted = !blocked.is_empty() || !zones.zones.is_empty();
+        let cache = if restricted {
+            WriteThroughCache::new(&self.restricted_cache, None)
+        } else {
+            self.main_cache()
+        };
+        self.search_in(start, end, heuristic, &cache, &blocked, &zones)
+    }
+
+    /// `search` with the given cache and the blocked nodes and cost zones read by the caller,
+    /// who holds the locks until the result is stored.
+    fn search_in(
+        &self,
+        start: usize,
+        end: usize,
+        heuristic: impl Fn(usize) -> Coord,
+        cache: &impl PathCache,
+        blocked: &HashSet<usize>,
+        zones: &CostZones,
+    ) -> Result<PathResult, RePathError> {
+        let edge_cost = |edge: &Edge| {
+            (!blocked.contains(&edge.to)).then(|| self.graph.traversal_cost(edge) * zones.multiplier(edge.to))
+        };
+        let limit = SearchLimit {
+            max_nodes: self.settings.max_search_nodes,
+            ..SearchLimit::default()
+        };
+
+        let path = self
+            .graph
+            .a_star_limited(start, end, cache, heuristic, edge_cost, limit)
+            .ok_or(RePathError::NoPath)?;
+        let result = PathResult::new(path, &self.graph);
+        if zones.zones.is_empty() {
+            return Ok(result);
+        }
+        let total_cost = result
+            .nodes
+            .windows(2)
+            .map(|pair| {
+                self.graph.edges[pair[0].id]
+                    .iter()
+                    .filter(|edge| edge.to == pair[1].id)
+                    .map(|edge| self.graph.traversal_cost(edge) * zones.multiplier(edge.to))
+                    .fold(Coord::INFINITY, Coord::min)
+            })
+            .sum();
+        Ok(PathResult { total_cost, ..result })
+    }
+
+    fn nodes_within_radius(&self, center: (Coord, Coord, Coord), radius: Coord) -> Vec<usize> {
+        let (x, y, z) = self.to_graph_coords(center);
+        let center = Node::new(usize::MAX, x, y, z);
+        self.spatial_index.nodes_within_radius_fast(&center, radius)
+    }
+
+    /// Uses the given zones with the nodes within them in the current graph, without sharing
+    /// them with clones.
+    fn set_cost_zones(&mut self, mut zones: CostZones) {
+        for zone in zones.zones.values_mut() {
+            zone.node_ids = self.nodes_within_radius(zone.center, zone.radius);
+        }
+        zones.rebuild_multipliers();
+        self.cost_zones = Arc::new(RwLock::new(zones));
+    }
+
+    /// Resolves the node closest to the given coordinates.
+    fn nearest_node(&self, coords: (Coord, Coord, Coord)) -> Result<usize, RePathError> {
+        let (x, y, z) = self.to_graph_coords(coords);
+        self.spatial_index
+            .nearest_node_fast(x, y, z)
+            .ok_or(RePathError::EmptyGraph)
+    }
+
+    /// Moves queried coordinates onto the flattened navmesh in `GraphMode::TwoD`.
+    fn to_graph_coords(&self, coords: (Coord, Coord, Coord)) -> (Coord, Coord, Coord) {
+        match self.settings.mode {
+            GraphMode::ThreeD => coords,
+            GraphMode::TwoD => (coords.0, 0.0, coords.2),
+        }
+    }
+}
+
+/// Creates a `RePathfinder` step by step, e.g. from a graph built in code instead of a navmesh
+/// file or with a cache warmed by the caller:
+///
+/// ```no_run
+/// use repath::graph::Graph;
+/// use repath::settings::RePathSettings;
+/// use repath::RePathfinderBuilder;
+///
+/// let graph = Graph::build_from_point_cloud(&[(0.0, 0.0, 0.0), (1.0, 0.0, 0.0)], 1.5);
+/// let pathfinder = RePathfinderBuilder::new(RePathSettings::default())
+///     .with_graph(graph)
+///     .skip_precomputation()
+///     .build()
+///     .expect("Invalid graph");
+/// ```
+///
+/// `new` comes first and `build` last, the calls in between may come in any order. Without
+/// `with_graph` the graph is loaded from the `navmesh_filename` setting, without `with_cache` an
+/// empty cache with the `cache_capacity` and `thread_local_cache_capacity` settings is used, and
+/// without `skip_precomputation` the cache is loaded from `cache_file` or precomputed like in
+/// `RePathfinder::new`, unless `with_disk_cache` opens a disk cache that already holds paths.
+pub struct RePathfinderBuilder {
+    settings: RePathSettings,
+    graph: Option<Graph>,
+    cache: Option<LruPathCache>,
+    disk_cache: Option<std::path::PathBuf>,
+    precompute: bool,
+}
+
+impl RePathfinderBuilder {
+    pub fn new(settings: RePathSettings) -> Self {
+        RePathfinderBuilder {
+            settings,
+            graph: None,
+            cache: None,
+            disk_cache: None,
+            precompute: true,
+        }
+    }
+
+    /// Uses the given graph instead of loading `navmesh_filename`, which is then ignored. The
+    /// `slope_factor` setting is still applied to it.
+    pub fn with_graph(mut self, graph: Graph) -> Self {
+        self.graph = Some(graph);
+        self
+    }
+
+    /// Uses the given cache, e.g. populated with paths of a previous run, instead of an empty one.
+    /// Its capacity is kept, `cache_capacity` only applies to the cache of `find_path_custom_cost`.
+    /// The cache must hold paths of the same graph.
+    pub fn with_cache(mut self, cache: LruPathCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Backs the cache with a `DiskPathCache` stored in `file`, which `build` opens or creates.
+    /// See `RePathfinder::with_disk_cache`.
+    pub fn with_disk_cache(mut self, file: impl Into<std::path::PathBuf>) -> Self {
+        self.disk_cache = Some(file.into());
+        self
+    }
+
+    /// Neither precomputes paths nor loads or saves `cache_file`, so `build` returns without
+    /// searching any path.
+    pub fn skip_precomputation(mut self) -> Self {
+        self.precompute = false;
+        self
+    }
+
+    /// Creates the pathfinder. Returns an error if the navmesh cannot be read or parsed, if the
+    /// graph contains no nodes or fails `Graph::validate`, if the cache file cannot be read or
+    /// written, or if the disk cache cannot be opened, see `DiskPathCache::open`.
+    pub fn build(self) -> Result<RePathfinder, RePathError> {
+        self.build_with(&|_, _| {}, &AtomicBool::new(false))
+    }
+
+    fn build_with(
+        self,
+        on_progress: &(impl Fn(usize, usize) + Sync),
+        cancel: &AtomicBool,
+    ) -> Result<RePathfinder, RePathError> {
+        let settings = self.settings;
+        let mut graph = match self.graph {
+            Some(graph) => graph,
+            None => parse_navmesh(&settings.navmesh_filename)?,
+        };
+        if settings.mode == GraphMode::TwoD {
+            graph = graph.flatten();
+        }
+        if settings.slope_factor != 0.0 {
+            graph.apply_slope_factor(settings.slope_factor);
+        }
+        if graph.nodes.is_empty() {
+            return Err(RePathError::EmptyGraph);
+        }
+        let (fatal, issues): (Vec<_>, Vec<_>) = graph.validate().into_iter().partition(ValidationError::is_fatal);
+        if !fatal.is_empty() {
+            return Err(RePathError::InvalidGraph(fatal));
+        }
+        warn_about_issues(&issues);
+        // The edges of lazily loaded graphs are still missing
+        if graph.lazy.is_none() {
+            warn_if_disconnected(&graph);
+        }
+
+        let cache = Arc::new(self.cache.unwrap_or_else(|| {
+            LruPathCache::new(settings.cache_capacity).with_thread_local_capacity(settings.thread_local_cache_capacity)
+        }));
+        let disk_cache = match self.disk_cache {
+            Some(file) => Some(Arc::new(DiskPathCache::open(file, &graph)?)),
+            None => None,
+        };
+        let grid_layout = GridLayout::detect(&graph).map(Arc::new);
+        let spatial_index = Arc::new(SpatialIndex::new(&graph));
+        let graph_generation = graph.generation();
+
+        let state = PathfinderState {
+            graph: Arc::new(graph),
+            cache,
+            disk_cache,
+            custom_cost_cache: Arc::new(LruPathCache::new(settings.cache_capacity)),
+            avoiding_cache: Arc::new(LruPathCache::new(settings.cache_capacity)),
+            budget_cache: Arc::new(LruPathCache::new(settings.cache_capacity)),
+            filter_cache: Arc::new(LruPathCache::new(settings.cache_capacity)),
+            restricted_cache: Arc::new(LruPathCache::new(settings.cache_capacity)),
+            heuristic_cache: Arc::new(LruPathCache::new(settings.cache_capacity)),
+            blocked_nodes: Arc::default(),
+            cost_zones: Arc::default(),
+            settings,
+            grid_layout,
+            spatial_index,
+            triangle_mesh: Arc::new(OnceLock::new()),
+            graph_generation,
+        };
+        let pathfinder = RePathfinder {
+            state: Arc::new(RwLock::new(Arc::new(state))),
+            counters: Arc::default(),
+        };
+        let state = pathfinder.state();
+        // The paths on disk take the place of the precomputed ones
+        if !self.precompute || state.disk_cache.as_ref().is_some_and(|disk| !disk.is_empty()) {
+            return Ok(pathfinder);
+        }
+
+        match state.settings.cache_file.as_deref() {
+            Some(cache_file) if std::path::Path::new(cache_file).exists() => {
+                pathfinder.load_cache(cache_file)?;
+            }
+            cache_file => {
+                state.precompute(&pathfinder.counters, on_progress, cancel);
+                if let Some(cache_file) = cache_file.filter(|_| !cancel.load(Ordering::Relaxed)) {
+                    pathfinder.save_cache(cache_file)?;
+                }
+            }
+        }
+
+        // Only lookups of actual queries should count towards the hit rate
+        state.cache.reset_stats();
+
+        Ok(pathfinder)
+    }
+}
+
+/// A search that runs in slices, see `RePathfinder::find_path_sliced`. It keeps the graph it
+/// was started on, so `RePathfinder::update_graph` does not affect it.
+#[derive(Debug, Clone)]
+pub struct SlicedPathfinder {
+    graph: Arc<Graph>,
+    state: AStarState,
+    max_steps_per_call: usize,
+}
+
+impl SlicedPathfinder {
+    /// Continues the search for up to `max_steps_per_call` steps, e.g. once per frame. Returns
+    /// `None` while the search is still in progress, and the result on every call after it is
+    /// complete.
+    pub fn advance(&mut self) -> Option<Result<PathResult, RePathError>> {
+        for _ in 0..self.max_steps_per_call {
+            if let StepResult::Complete(path) = self.graph.a_star_step(&mut self.state) {
+                return Some(path.map(|path| PathResult::new(path, &self.graph)).ok_or(RePathError::NoPath));
+            }
+        }
+        None
+    }
+}
+
+/// Identifies a zone added by `RePathfinder::add_cost_zone`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CostZoneId(u64);
+
+/// A sphere in which entering a node costs more, see `RePathfinder::add_cost_zone`.
+#[derive(Debug, Clone)]
+struct CostZone {
+    center: (Coord, Coord, Coord),
+    radius: Coord,
+    multiplier: Coord,
+    /// The nodes within the sphere.
+    node_ids: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct CostZones {
+    zones: HashMap<CostZoneId, CostZone>,
+    /// The product of the multipliers of all zones containing a node, for nodes in any zone.
+    multipliers: HashMap<usize, Coord>,
+    next_id: u64,
+}
+
+impl CostZones {
+    fn multiplier(&self, node_id: usize) -> Coord {
+        self.multipliers.get(&node_id).copied().unwrap_or(1.0)
+    }
+
+    fn rebuild_multipliers(&mut self) {
+        self.multipliers.clear();
+        for zone in self.zones.values() {
+            for &node_id in &zone.node_ids {
+                *self.multipliers.entry(node_id).or_insert(1.0) *= zone.multiplier;
+            }
+        }
+    }
+}
+
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    // The locked sets stay consistent even if a panic interrupted an update, so keep using them
+    lock.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Warns on stderr about the anomalies of the navmesh that do not prevent pathfinding.
+fn warn_about_issues(issues: &[ValidationError]) {
+    let self_loops = issues.iter().filter(|issue| matches!(issue, ValidationError::SelfLoop(_))).count();
+    let duplicates = issues.len() - self_loops;
+    if !issues.is_empty() {
+        eprintln!(
+            "Warning: the navmesh has {} self-loops and {} duplicate edges, e.g. {}",
+            self_loops, duplicates, issues[0]
+        );
+    }
+}
+
+/// The number of component sizes reported by `warn_if_disconnected`.
+const REPORTED_COMPONENTS: usize = 10;
+
+/// Warns on stderr if the navmesh consists of several islands, between which no path exists.
+fn warn_if_disconnected(graph: &Graph) {
+    let mut sizes: Vec<usize> = graph.connected_components().iter().map(Vec::len).collect();
+    if sizes.len() <= 1 {
+        return;
+    }
+
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    let omitted = sizes.len().saturating_sub(REPORTED_COMPONENTS);
+    sizes.truncate(REPORTED_COMPONENTS);
+    eprint!(
+        "Warning: the navmesh has {} disconnected components with {:?} nodes",
+        sizes.len() + omitted,
+        sizes
+    );
+    if omitted > 0 {
+        eprint!(" and {} smaller ones", omitted);
+    }
+    eprintln!();
+}
+
+#[cfg(feature = "metrics")]
+impl RePathfinder {
+    /// Returns the `metrics` and the cache size in the Prometheus text exposition format, e.g. to
+    /// serve them to a Prometheus scraper from an HTTP handler. The counters are atomics, so this
+    /// can be called while other threads are finding paths.
+    pub fn metrics_to_prometheus(&self) -> String {
+        let metrics = self.metrics();
+        let average_path_nodes = match metrics.calls {
+            0 => 0.0,
+            calls => metrics.total_nodes_in_paths as f64 / calls as f64,
+        };
+        // The name, type, description and value of every metric
+        let samples: [(&str, &str, &str, f64); 7] = [
+            (
+                "repath_pathfinding_calls_total",
+                "counter",
+                "Number of find_path calls.",
+                metrics.calls as f64,
+            ),
+            (
+                "repath_cache_hits_total",
+                "counter",
+                "Number of cache lookups that found a path.",
+                metrics.cache_hits as f64,
+            ),
+            (
+                "repath_cache_misses_total",
+                "counter",
+                "Number of cache lookups that had to search.",
+                metrics.cache_misses as f64,
+            ),
+            (
+                "repath_cached_paths",
+                "gauge",
+                "Number of paths in the cache.",
+                self.cache_len() as f64,
+            ),
+            (
+                "repath_precomputed_paths",
+                "gauge",
+                "Number of paths searched to fill the cache.",
+                metrics.precomputed_paths as f64,
+            ),
+            (
+                "repath_average_path_nodes",
+                "gauge",
+                "Average number of nodes of the paths found per call.",
+                average_path_nodes,
+            ),
+            (
+                "repath_average_call_duration_seconds",
+                "gauge",
+                "Average duration of a find_path call.",
+                metrics.average_duration.as_secs_f64(),
+            ),
+        ];
+
+        let mut output = String::new();
+        for (name, kind, help, value) in samples {
+            output.push_str(&format!("# HELP {} {}\n# TYPE {} {}\n", name, help, name, kind));
+            output.push_str(&format!("{} {}\n", name, value));
+        }
+        output
+    }
+}
+
+#[cfg(feature = "async")]
+impl RePathfinder {
+    /// Async version of `find_path`. The search runs on tokio's blocking thread pool, so it does
+    /// not block the executor. Must be called from within a tokio runtime.
+    pub async fn find_path_async(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+    ) -> Result<PathResult, RePathError> {
+        let pathfinder = self.clone();
+        Self::run_blocking(move || pathfinder.find_path(start_coords, end_coords)).await
+    }
+
+    /// Async version of `find_path_multithreaded`. The segments are still searched concurrently
+    /// by rayon, the whole call runs on tokio's blocking thread pool.
+    pub async fn find_path_multithreaded_async(
+        &self,
+        start_coords: (Coord, Coord, Coord),
+        end_coords: (Coord, Coord, Coord),
+        segment_count: u8,
+    ) -> Result<PathResult, RePathError> {
+        let pathfinder = self.clone();
+        Self::run_blocking(move || {
+            pathfinder.find_path_multithreaded(start_coords, end_coords, segment_count)
+        })
+        .await
+    }
+
+    async fn run_blocking<F>(search: F) -> Result<PathResult, RePathError>
+    where
+        F: FnOnce() -> Result<PathResult, RePathError> + Send + 'static,
+    {
+        match tokio::task::spawn_blocking(search).await {
+            Ok(result) => result,
+            // Propagate panics of the search to the caller like the synchronous API would
+            Err(error) => std::panic::resume_unwind(error.into_panic()),
+        }
     }
 }
@@ -0,0 +1,372 @@
+use std::collections::{HashSet, VecDeque};
+use crate::graph::Graph;
+use crate::node::Node;
+use crate::Coord;
+
+/// Graphs with more nodes than this are only checked against Euler's formula by
+/// `Graph::is_planar` instead of being tested exactly.
+pub const EXACT_PLANARITY_LIMIT: usize = 1000;
+
+/// Returns the edges of the graph as a simple undirected graph: both directions of an edge are
+/// one neighbor pair, self-loops and parallel edges are left out.
+fn simple_adjacency(graph: &Graph) -> Vec<Vec<usize>> {
+    let mut adjacency: Vec<HashSet<usize>> = vec![HashSet::new(); graph.nodes.len()];
+    for (from, edges) in graph.edges.iter().enumerate() {
+        for edge in edges {
+            if edge.to != from && edge.to < graph.nodes.len() {
+                adjacency[from].insert(edge.to);
+                adjacency[edge.to].insert(from);
+            }
+        }
+    }
+    adjacency
+        .into_iter()
+        .map(|neighbors| {
+            let mut neighbors: Vec<usize> = neighbors.into_iter().collect();
+            neighbors.sort_unstable();
+            neighbors
+        })
+        .collect()
+}
+
+/// Returns whether a simple graph with this many nodes and undirected edges can be planar by
+/// Euler's formula, which limits planar graphs with at least three nodes to `3 * |V| - 6` edges.
+fn within_euler_bound(node_count: usize, edge_count: usize) -> bool {
+    node_count < 3 || edge_count <= 3 * node_count - 6
+}
+
+/// Splits a simple graph into its biconnected components with Tarjan's algorithm, each as a
+/// list of undirected edges. A graph is planar if and only if all of them are.
+fn biconnected_components(adjacency: &[Vec<usize>]) -> Vec<Vec<(usize, usize)>> {
+    let node_count = adjacency.len();
+    let mut discovery = vec![usize::MAX; node_count];
+    let mut low = vec![0; node_count];
+    let mut time = 0;
+    let mut edge_stack = Vec::new();
+    let mut components = Vec::new();
+
+    for root in 0..node_count {
+        if discovery[root] != usize::MAX {
+            continue;
+        }
+        discovery[root] = time;
+        low[root] = time;
+        time += 1;
+        // The node, its parent and the index of the next neighbor to visit
+        let mut stack = vec![(root, usize::MAX, 0)];
+        while let Some(&mut (node, parent, ref mut next)) = stack.last_mut() {
+            if let Some(&neighbor) = adjacency[node].get(*next) {
+                *next += 1;
+                if discovery[neighbor] == usize::MAX {
+                    edge_stack.push((node, neighbor));
+                    discovery[neighbor] = time;
+                    low[neighbor] = time;
+                    time += 1;
+                    stack.push((neighbor, node, 0));
+                } else if neighbor != parent && discovery[neighbor] < discovery[node] {
+                    edge_stack.push((node, neighbor));
+                    low[node] = low[node].min(discovery[neighbor]);
+                }
+                continue;
+            }
+
+            stack.pop();
+            if parent != usize::MAX {
+                low[parent] = low[parent].min(low[node]);
+                if low[node] >= discovery[parent] {
+                    // The parent separates the component of this tree edge from the rest
+                    let mut component = Vec::new();
+                    while let Some(edge) = edge_stack.pop() {
+                        component.push(edge);
+                        if edge == (parent, node) {
+                            break;
+                        }
+                    }
+                    components.push(component);
+                }
+            }
+        }
+    }
+    components
+}
+
+/// A part of the graph that is not embedded yet, see `is_biconnected_planar`.
+struct Fragment {
+    /// The embedded nodes the fragment is attached to.
+    attachments: Vec<usize>,
+    /// A node of the fragment that is not embedded yet, or `None` for a single edge between
+    /// two embedded nodes.
+    inner: Option<usize>,
+}
+
+/// Tests a biconnected simple graph with at least three nodes for planarity with the algorithm
+/// of Demoucron, Malgrange and Pertuiset: starting from a cycle, it repeatedly embeds a path of
+/// a not yet embedded fragment into a face that contains all nodes the fragment is attached
+/// to, which succeeds for every fragment if and only if the graph is planar. Takes O(|V| * |E|)
+/// per embedded path.
+fn is_biconnected_planar(adjacency: &[Vec<usize>]) -> bool {
+    let node_count = adjacency.len();
+    let mut embedded_nodes = vec![false; node_count];
+    let mut embedded_edges: HashSet<(usize, usize)> = HashSet::new();
+    let key = |a: usize, b: usize| (a.min(b), a.max(b));
+
+    // Any edge lies on a cycle of a biconnected graph, close one through the rest of the graph
+    let (u, v) = (0, adjacency[0][0]);
+    let Some(mut cycle) = shortest_path(adjacency, v, |node| node == u, |a, b| key(a, b) != key(u, v)) else {
+        return false;
+    };
+    cycle.reverse();
+    for pair in cycle.windows(2) {
+        embedded_edges.insert(key(pair[0], pair[1]));
+    }
+    embedded_edges.insert(key(u, v));
+    for &node in &cycle {
+        embedded_nodes[node] = true;
+    }
+    let mut faces = vec![cycle.clone(), cycle];
+
+    loop {
+        let fragments = fragments(adjacency, &embedded_nodes, &embedded_edges);
+        if fragments.is_empty() {
+            return true;
+        }
+
+        // The faces every fragment fits into
+        let admissible: Vec<Vec<usize>> = fragments
+            .iter()
+            .map(|fragment| {
+                (0..faces.len())
+                    .filter(|&face| fragment.attachments.iter().all(|node| faces[face].contains(node)))
+                    .collect()
+            })
+            .collect();
+        if admissible.iter().any(Vec::is_empty) {
+            return false;
+        }
+        let chosen = admissible.iter().position(|candidates| candidates.len() == 1).unwrap_or(0);
+        let fragment = &fragments[chosen];
+        let face = admissible[chosen][0];
+
+        // A path through the fragment between two of its attachments
+        let start = fragment.attachments[0];
+        let path = match fragment.inner {
+            None => vec![start, fragment.attachments[1]],
+            Some(inner) => {
+                let first = adjacency[start]
+                    .iter()
+                    .copied()
+                    .find(|&node| !embedded_nodes[node] && reaches(adjacency, &embedded_nodes, inner, node))
+                    .expect("Every attachment has a neighbor in the fragment");
+                let ends_at_attachment =
+                    |node: usize| adjacency[node].iter().any(|&next| embedded_nodes[next] && next != start);
+                let inner_path = shortest_path(adjacency, first, ends_at_attachment, |_, b| !embedded_nodes[b])
+                    .expect("The fragment is connected");
+                let last = *inner_path.last().expect("The path is not empty");
+                let end = adjacency[last]
+                    .iter()
+                    .copied()
+                    .find(|&next| embedded_nodes[next] && next != start)
+                    .expect("The path ends next to an attachment");
+                let mut path = vec![start];
+                path.extend(inner_path);
+                path.push(end);
+                path
+            }
+        };
+
+        for pair in path.windows(2) {
+            embedded_edges.insert(key(pair[0], pair[1]));
+        }
+        for &node in &path {
+            embedded_nodes[node] = true;
+        }
+
+        // The path splits the face in two
+        let boundary = &faces[face];
+        let position = |node: usize| boundary.iter().position(|&other| other == node).expect("Attachment on the face");
+        let (from, to) = (position(path[0]), position(path[path.len() - 1]));
+        let arc = |from: usize, to: usize| {
+            let length = (to + boundary.len() - from) % boundary.len();
+            (0..=length).map(|offset| boundary[(from + offset) % boundary.len()]).collect::<Vec<_>>()
+        };
+        let interior = &path[1..path.len() - 1];
+        let mut first = arc(from, to);
+        first.extend(interior.iter().rev());
+        let mut second = arc(to, from);
+        second.extend(interior);
+        faces[face] = first;
+        faces.push(second);
+    }
+}
+
+/// Returns the fragments of the graph relative to the embedded part: the edges between two
+/// embedded nodes that are not embedded yet and the connected groups of nodes that are not
+/// embedded yet.
+fn fragments(adjacency: &[Vec<usize>], embedded_nodes: &[bool], embedded_edges: &HashSet<(usize, usize)>) -> Vec<Fragment> {
+    let mut fragments = Vec::new();
+    let mut visited = vec![false; adjacency.len()];
+    for (node, neighbors) in adjacency.iter().enumerate() {
+        if embedded_nodes[node] {
+            for &neighbor in neighbors {
+                if node < neighbor && embedded_nodes[neighbor] && !embedded_edges.contains(&(node, neighbor)) {
+                    fragments.push(Fragment { attachments: vec![node, neighbor], inner: None });
+                }
+            }
+            continue;
+        }
+        if visited[node] {
+            continue;
+        }
+
+        let mut attachments = Vec::new();
+        let mut queue = VecDeque::from([node]);
+        visited[node] = true;
+        while let Some(current) = queue.pop_front() {
+            for &neighbor in &adjacency[current] {
+                if embedded_nodes[neighbor] {
+                    attachments.push(neighbor);
+                } else if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    queue.push_back(neighbor);
+                }
+            }
+        }
+        attachments.sort_unstable();
+        attachments.dedup();
+        fragments.push(Fragment { attachments, inner: Some(node) });
+    }
+    fragments
+}
+
+/// Returns whether `to` can be reached from `from` without entering embedded nodes.
+fn reaches(adjacency: &[Vec<usize>], embedded_nodes: &[bool], from: usize, to: usize) -> bool {
+    shortest_path(adjacency, from, |node| node == to, |_, b| !embedded_nodes[b]).is_some()
+}
+
+/// Finds a path with the fewest edges from `start` to a node for which `is_end` returns true,
+/// only following the edges `allowed` returns true for, by breadth-first search.
+fn shortest_path(
+    adjacency: &[Vec<usize>],
+    start: usize,
+    is_end: impl Fn(usize) -> bool,
+    allowed: impl Fn(usize, usize) -> bool,
+) -> Option<Vec<usize>> {
+    let mut came_from = vec![usize::MAX; adjacency.len()];
+    came_from[start] = start;
+    let mut queue = VecDeque::from([start]);
+    while let Some(current) = queue.pop_front() {
+        if is_end(current) {
+            let mut path = vec![current];
+            while *path.last()? != start {
+                path.push(came_from[*path.last()?]);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for &neighbor in &adjacency[current] {
+            if came_from[neighbor] == usize::MAX && allowed(current, neighbor) {
+                came_from[neighbor] = current;
+                queue.push_back(neighbor);
+            }
+        }
+    }
+    None
+}
+
+/// The sign of the turn from `a` over `b` to `c` in the XZ plane: positive for a left turn,
+/// negative for a right turn and 0 if the points are collinear.
+fn orientation(a: &Node, b: &Node, c: &Node) -> Coord {
+    (b.x - a.x) * (c.z - a.z) - (b.z - a.z) * (c.x - a.x)
+}
+
+/// Returns whether the segments from `a` to `b` and from `c` to `d` cross in the XZ plane at a
+/// single point that is not an end of either.
+fn segments_cross(a: &Node, b: &Node, c: &Node, d: &Node) -> bool {
+    let opposite = |p: Coord, q: Coord| (p > 0.0 && q < 0.0) || (p < 0.0 && q > 0.0);
+    opposite(orientation(a, b, c), orientation(a, b, d)) && opposite(orientation(c, d, a), orientation(c, d, b))
+}
+
+impl Graph {
+    /// Returns whether the graph can be drawn in the plane without crossing edges, ignoring the
+    /// direction of edges, self-loops and parallel edges. Unlike `crossing_edges`, this is about
+    /// the connections only: a graph whose edges cross at their positions may still be planar.
+    ///
+    /// Graphs with up to `EXACT_PLANARITY_LIMIT` nodes are tested exactly, by splitting them into
+    /// biconnected components and embedding each with the algorithm of Demoucron, Malgrange and
+    /// Pertuiset. Larger graphs are only checked against Euler's formula `V - E + F = 2`, which
+    /// allows a planar graph with `|V| >= 3` nodes at most `3 * |V| - 6` edges. This rejects dense
+    /// graphs such as the complete graph of five nodes, but accepts sparse non-planar graphs such
+    /// as the complete bipartite graph of three and three nodes, so `true` is only an estimate
+    /// for large graphs.
+    pub fn is_planar(&self) -> bool {
+        let adjacency = simple_adjacency(self);
+        let edge_count = adjacency.iter().map(Vec::len).sum::<usize>() / 2;
+        if !within_euler_bound(adjacency.len(), edge_count) {
+            return false;
+        }
+        if adjacency.len() > EXACT_PLANARITY_LIMIT {
+            return true;
+        }
+
+        biconnected_components(&adjacency).into_iter().all(|edges| {
+            let mut nodes: Vec<usize> = edges.iter().flat_map(|&(a, b)| [a, b]).collect();
+            nodes.sort_unstable();
+            nodes.dedup();
+            // Every graph with fewer than five nodes or nine edges is planar
+            if nodes.len() < 5 || edges.len() < 9 {
+                return true;
+            }
+            if !within_euler_bound(nodes.len(), edges.len()) {
+                return false;
+            }
+            let index = |node: usize| nodes.binary_search(&node).expect("The node is in the component");
+            let mut component = vec![Vec::new(); nodes.len()];
+            for (a, b) in edges {
+                let (a, b) = (index(a), index(b));
+                component[a].push(b);
+                component[b].push(a);
+            }
+            is_biconnected_planar(&component)
+        })
+    }
+
+    /// Returns the pairs of edges that cross each other in the XZ plane, ignoring the heights of
+    /// the nodes, e.g. to find broken faces of an exported navmesh. Both directions of an edge
+    /// count as one edge `(a, b)` with `a < b`, and every pair is listed once in ascending order.
+    /// Edges only cross if they meet at a single point that is not an end of either, so edges
+    /// sharing a node, touching or lying on top of each other are not listed.
+    ///
+    /// The edges are swept along the X axis, so this is fast for navmeshes with short edges and
+    /// O(|E|^2) in the worst case.
+    pub fn crossing_edges(&self) -> Vec<((usize, usize), (usize, usize))> {
+        let adjacency = simple_adjacency(self);
+        let mut segments: Vec<(Coord, Coord, usize, usize)> = adjacency
+            .iter()
+            .enumerate()
+            .flat_map(|(a, neighbors)| neighbors.iter().filter(move |&&b| a < b).map(move |&b| (a, b)))
+            .map(|(a, b)| {
+                let (x_a, x_b) = (self.nodes[a].x, self.nodes[b].x);
+                (x_a.min(x_b), x_a.max(x_b), a, b)
+            })
+            .collect();
+        segments.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let mut crossings = Vec::new();
+        for (index, &(_, max_x, a, b)) in segments.iter().enumerate() {
+            for &(min_x, _, c, d) in &segments[index + 1..] {
+                if min_x > max_x {
+                    break;
+                }
+                if a == c || a == d || b == c || b == d {
+                    continue;
+                }
+                let nodes = &self.nodes;
+                if segments_cross(&nodes[a], &nodes[b], &nodes[c], &nodes[d]) {
+                    crossings.push(((a, b).min((c, d)), (a, b).max((c, d))));
+                }
+            }
+        }
+        crossings.sort_unstable();
+        crossings
+    }
+}
@@ -0,0 +1,136 @@
+use serde::de::Error as _;
+use serde::ser::SerializeSeq;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::edge::{Edge, EdgeKind};
+use crate::error::RePathError;
+use crate::graph::Graph;
+use crate::node::Node;
+use crate::Coord;
+
+/// `Graph` is serialized as `{ "nodes": [...], "edges": [...] }`, where every node is
+/// `{ "id", "x", "y", "z" }` and the edges are a flat list of `{ "from", "to", "cost" }` with the
+/// optional `"directed"`, `"kind"`, `"clearance"` and `"labels"` attributes.
+/// Deserialization checks the same constraints as `Graph::from_json`.
+impl Serialize for Graph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct GraphRef<'a> {
+            nodes: &'a [Node],
+            edges: FlatEdges<'a>,
+        }
+
+        GraphRef {
+            nodes: &self.nodes,
+            edges: FlatEdges(&self.edges),
+        }
+        .serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Graph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let document = GraphDocument::deserialize(deserializer)?;
+        Graph::try_from(document).map_err(D::Error::custom)
+    }
+}
+
+/// The deserialized form of a graph, see `impl Serialize for Graph`.
+#[derive(Deserialize)]
+struct GraphDocument {
+    nodes: Vec<Node>,
+    edges: Vec<EdgeRecord>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EdgeRecord {
+    from: usize,
+    to: usize,
+    cost: Coord,
+    #[serde(default)]
+    directed: bool,
+    #[serde(default)]
+    kind: EdgeKind,
+    /// `None` for an unlimited clearance, which JSON cannot represent as a number.
+    #[serde(default)]
+    clearance: Option<Coord>,
+    #[serde(default)]
+    labels: Vec<String>,
+}
+
+/// Serializes the adjacency lists as one flat list of edge records without copying them.
+struct FlatEdges<'a>(&'a [Vec<Edge>]);
+
+impl Serialize for FlatEdges<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Binary formats need the length up front
+        let count = self.0.iter().map(Vec::len).sum();
+        let mut seq = serializer.serialize_seq(Some(count))?;
+        for (from, edges) in self.0.iter().enumerate() {
+            for edge in edges {
+                seq.serialize_element(&EdgeRecord {
+                    from,
+                    to: edge.to,
+                    cost: edge.cost,
+                    directed: edge.directed,
+                    kind: edge.kind,
+                    clearance: edge.clearance.is_finite().then_some(edge.clearance),
+                    labels: edge.labels.clone(),
+                })?;
+            }
+        }
+        seq.end()
+    }
+}
+
+impl TryFrom<GraphDocument> for Graph {
+    type Error = RePathError;
+
+    fn try_from(document: GraphDocument) -> Result<Self, Self::Error> {
+        let mut graph = Graph::new();
+        for (index, node) in document.nodes.into_iter().enumerate() {
+            if node.id != index {
+                return Err(RePathError::InvalidNavmesh(format!(
+                    "Expected node id {} but found {}",
+                    index, node.id
+                )));
+            }
+            graph.add_node(node);
+        }
+
+        let node_count = graph.nodes.len();
+        for edge in document.edges {
+            if edge.from >= node_count || edge.to >= node_count {
+                return Err(RePathError::InvalidNavmesh(format!(
+                    "Edge from {} to {} refers to a missing node",
+                    edge.from, edge.to
+                )));
+            }
+            graph.push_edge(edge.from, Edge {
+                to: edge.to,
+                cost: edge.cost,
+                directed: edge.directed,
+                kind: edge.kind,
+                clearance: edge.clearance.unwrap_or(Coord::INFINITY),
+                labels: edge.labels,
+            });
+        }
+
+        Ok(graph)
+    }
+}
+
+impl Graph {
+    /// Serializes the graph as JSON, see `impl Serialize for Graph` for the schema.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string(self)
+    }
+
+    /// Parses a graph in the format written by `to_json`.
+    /// The nodes must be listed in order of their ids starting at 0, and every edge must connect
+    /// two of the listed nodes, otherwise `RePathError::InvalidNavmesh` is returned.
+    pub fn from_json(s: &str) -> Result<Graph, RePathError> {
+        let document: GraphDocument = serde_json::from_str(s)
+            .map_err(|err| RePathError::InvalidNavmesh(err.to_string()))?;
+        Graph::try_from(document)
+    }
+}
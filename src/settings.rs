@@ -1,14 +1,44 @@
+use std::fmt;
 use serde::{Serialize, Deserialize};
+use serde_json::{Map, Value};
+use crate::error::RePathError;
+use crate::Coord;
+
+/// The search algorithm used by `RePathfinder::find_path`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Algorithm {
+    /// A* with a Euclidean distance heuristic. Fast on navmeshes where edge costs are distances.
+    #[default]
+    AStar,
+
+    /// Dijkstra's algorithm without any heuristic.
+    /// Slower than A*, but optimal even when edge costs are not related to distances.
+    Dijkstra,
+}
+
+/// How `RePathfinder` treats the height of nodes and queried coordinates.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GraphMode {
+    /// Distances are measured along all three axes.
+    #[default]
+    ThreeD,
+
+    /// The navmesh is flattened onto the `y = 0` plane, see `Graph::flatten`, and the `y` of
+    /// queried coordinates is ignored, e.g. for top-down maps where small height differences are
+    /// only noise. Nodes added later with `RePathfinder::update_graph` should have a `y` of 0.
+    TwoD,
+}
 
 /// Configuration settings for the RePathfinder.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RePathSettings {
-    /// The filename of the navigation mesh in Wavefront OBJ format.
+    /// The filename of the navigation mesh in Wavefront OBJ format, or in glTF format if the
+    /// extension is `.gltf` or `.glb`.
     pub navmesh_filename: String,
 
     /// The radius within which to precompute paths between nodes.
     /// Higher values will result in longer precomputation times but faster pathfinding for long distances.
-    pub precompute_radius: f32,
+    pub precompute_radius: Coord,
 
     /// The total number of node pairs for which paths will be precomputed.
     /// Higher values will result in longer precomputation times but more efficient pathfinding.
@@ -17,4 +47,382 @@ pub struct RePathSettings {
     /// Whether to use the precomputed cache for pathfinding.
     /// Set to false to disable the use of precomputed paths.
     pub use_precomputed_cache: bool,
+
+    /// The algorithm used by `find_path` and `find_path_multithreaded`.
+    #[serde(default)]
+    pub algorithm: Algorithm,
+
+    /// The maximum number of paths kept in the cache, including precomputed ones.
+    /// When the cache is full, the least recently used path is evicted. 0 disables caching.
+    #[serde(default = "default_cache_capacity")]
+    pub cache_capacity: usize,
+
+    /// The maximum number of paths every thread keeps in a copy of the cache it can read without
+    /// locking, see `LruPathCache::with_thread_local_capacity`. 0 disables the copies.
+    #[serde(default = "default_thread_local_cache_capacity")]
+    pub thread_local_cache_capacity: usize,
+
+    /// A file to persist the cache in. If the file exists, the cache is loaded from it instead
+    /// of being precomputed, otherwise the precomputed cache is saved to it.
+    #[serde(default)]
+    pub cache_file: Option<String>,
+
+    /// The maximum number of nodes a search of `find_path` may take from its open set before it
+    /// gives up and returns `RePathError::NoPath`, see `Graph::a_star_bounded`. `None` searches
+    /// until the goal is found or proven unreachable.
+    #[serde(default)]
+    pub max_search_nodes: Option<usize>,
+
+    /// A CSV file to which `RePathfinder::record_path_metric` appends a row per call, see
+    /// `utils::append_path_metric_to_csv`.
+    #[serde(default)]
+    pub metrics_csv: Option<String>,
+
+    /// How much more expensive steep edges are for ground-based agents, see
+    /// `Graph::apply_slope_factor`. A vertical edge costs `1 + slope_factor` times its length.
+    /// 0 keeps the costs of the navmesh.
+    #[serde(default)]
+    pub slope_factor: Coord,
+
+    /// Whether distances and nearest nodes ignore the `y` axis.
+    #[serde(default)]
+    pub mode: GraphMode,
+
+    /// The edge length of the cubic chunks in which `RePathfinder::new_lazy` loads the faces of
+    /// the navmesh, see `Graph::load_region`.
+    #[serde(default = "default_lazy_chunk_size")]
+    pub lazy_chunk_size: Coord,
+}
+
+fn default_cache_capacity() -> usize {
+    100_000
+}
+
+fn default_thread_local_cache_capacity() -> usize {
+    1024
+}
+
+fn default_lazy_chunk_size() -> Coord {
+    100.0
+}
+
+impl Default for RePathSettings {
+    fn default() -> Self {
+        RePathSettings {
+            navmesh_filename: "NavMesh.obj".to_string(),
+            precompute_radius: 10000.0,
+            total_precompute_pairs: 5000,
+            use_precomputed_cache: true,
+            algorithm: Algorithm::default(),
+            cache_capacity: default_cache_capacity(),
+            thread_local_cache_capacity: default_thread_local_cache_capacity(),
+            cache_file: None,
+            max_search_nodes: None,
+            metrics_csv: None,
+            slope_factor: 0.0,
+            mode: GraphMode::default(),
+            lazy_chunk_size: default_lazy_chunk_size(),
+        }
+    }
+}
+
+impl RePathSettings {
+    /// Loads the settings from a TOML file with one `key = value` line per field, e.g.
+    /// `navmesh_filename = "NavMesh.obj"`. Fields with a default, such as `cache_capacity`, may
+    /// be left out. Only the flat subset of TOML the settings need is supported: bare keys, basic
+    /// strings, decimal integers, finite floats, booleans and comments. Everything else, e.g.
+    /// tables, dotted keys, arrays, inline tables, literal or multi-line strings and dates, is
+    /// rejected with a `ParseError` naming it rather than read differently than TOML would.
+    pub fn from_toml_file(path: &str) -> Result<RePathSettings, RePathError> {
+        Self::from_toml_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses settings in the format of `from_toml_file`.
+    pub fn from_toml_str(toml: &str) -> Result<RePathSettings, RePathError> {
+        let mut fields = Map::new();
+        for (index, line) in toml.lines().enumerate() {
+            let parse_error = |detail: &str| RePathError::ParseError {
+                line: index + 1,
+                detail: detail.to_string(),
+            };
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if line.starts_with("[[") {
+                return Err(parse_error("Arrays of tables are not supported"));
+            }
+            if line.starts_with('[') {
+                return Err(parse_error("Tables are not supported"));
+            }
+            let (key, value) = line.split_once('=').ok_or_else(|| parse_error("Expected `key = value`"))?;
+            let key = key.trim();
+            if key.starts_with(['"', '\'']) {
+                return Err(parse_error("Quoted keys are not supported"));
+            }
+            if key.contains('.') {
+                return Err(parse_error("Dotted keys are not supported"));
+            }
+            if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-') {
+                return Err(parse_error("Expected a bare key"));
+            }
+            let value = parse_toml_value(value.trim()).map_err(parse_error)?;
+            if fields.insert(key.to_string(), value).is_some() {
+                return Err(parse_error("Duplicate key"));
+            }
+        }
+        serde_json::from_value(Value::Object(fields)).map_err(|err| RePathError::SerializationError(err.to_string()))
+    }
+
+    /// Returns the settings in the format read by `from_toml_file`. Fields that are `None` are
+    /// left out.
+    pub fn to_toml_string(&self) -> String {
+        let Ok(Value::Object(fields)) = serde_json::to_value(self) else {
+            unreachable!("The settings serialize to a map");
+        };
+        fields
+            .iter()
+            .filter(|(_, value)| !value.is_null())
+            // JSON strings, numbers and booleans are written the same way in TOML
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect()
+    }
+}
+
+/// Parses a TOML basic string, decimal integer, float or boolean, followed by an optional
+/// comment. Returns why the value is not supported otherwise.
+fn parse_toml_value(value: &str) -> Result<Value, &'static str> {
+    if value.starts_with("\"\"\"") || value.starts_with("'''") {
+        return Err("Multi-line strings are not supported");
+    }
+    if value.starts_with('\'') {
+        return Err("Literal strings are not supported");
+    }
+    if value.starts_with('[') {
+        return Err("Arrays are not supported");
+    }
+    if value.starts_with('{') {
+        return Err("Inline tables are not supported");
+    }
+    if let Some(quoted) = value.strip_prefix('"') {
+        let (string, rest) = parse_toml_basic_string(quoted)?;
+        let rest = rest.trim_start();
+        if !rest.is_empty() && !rest.starts_with('#') {
+            return Err("Expected a comment or the end of the line after the string");
+        }
+        return Ok(Value::String(string));
+    }
+
+    let value = value.split('#').next().unwrap_or_default().trim();
+    match value {
+        "true" => return Ok(Value::Bool(true)),
+        "false" => return Ok(Value::Bool(false)),
+        _ => {}
+    }
+    if ["0x", "0o", "0b"].iter().any(|prefix| value.starts_with(prefix)) {
+        return Err("Hexadecimal, octal and binary integers are not supported");
+    }
+    // Underscores are only allowed between digits
+    let digits = value.replace('_', "");
+    let underscores_between_digits = value.match_indices('_').all(|(index, _)| {
+        let bytes = value.as_bytes();
+        index > 0 && bytes[index - 1].is_ascii_digit() && bytes.get(index + 1).is_some_and(u8::is_ascii_digit)
+    });
+    if !underscores_between_digits || digits.is_empty() {
+        return Err("Invalid value");
+    }
+    if let Ok(integer) = digits.parse::<i64>() {
+        return Ok(integer.into());
+    }
+    if digits.trim_start_matches(['+', '-']).starts_with(char::is_alphabetic) {
+        // Dates, bare words and `inf` or `nan`, which Rust parses as floats as well
+        return Err("Invalid value, strings must be quoted and only finite numbers are supported");
+    }
+    let float = digits.parse::<f64>().map_err(|_| "Invalid value")?;
+    serde_json::Number::from_f64(float).map(Value::Number).ok_or("Invalid value")
+}
+
+/// Parses the rest of a TOML basic string after its opening quote and returns the string and
+/// what follows the closing quote.
+fn parse_toml_basic_string(quoted: &str) -> Result<(String, &str), &'static str> {
+    let mut string = String::new();
+    let mut chars = quoted.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match c {
+            '"' => return Ok((string, &quoted[index + 1..])),
+            '\\' => {
+                let escaped = match chars.next().map(|(_, c)| c) {
+                    Some('b') => '\u{8}',
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some('f') => '\u{c}',
+                    Some('r') => '\r',
+                    Some('"') => '"',
+                    Some('\\') => '\\',
+                    Some(kind @ ('u' | 'U')) => {
+                        let len = if kind == 'u' { 4 } else { 8 };
+                        let hex: String = chars.by_ref().take(len).map(|(_, c)| c).collect();
+                        (hex.len() == len)
+                            .then(|| u32::from_str_radix(&hex, 16).ok())
+                            .flatten()
+                            .and_then(char::from_u32)
+                            .ok_or("Invalid unicode escape in string")?
+                    }
+                    _ => return Err("Invalid escape in string"),
+                };
+                string.push(escaped);
+            }
+            c if c.is_control() && c != '\t' => return Err("Control characters must be escaped in strings"),
+            c => string.push(c),
+        }
+    }
+    Err("Unterminated string")
+}
+
+/// A reason why `RePathSettingsBuilder::build` rejected the settings.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SettingsError {
+    /// The navmesh filename is empty.
+    EmptyNavmeshFilename,
+
+    /// The precompute radius is zero, negative or NaN.
+    InvalidPrecomputeRadius(Coord),
+
+    /// The number of precomputed pairs is zero.
+    NoPrecomputePairs,
+
+    /// The slope factor is negative or NaN, which would make climbing cheaper than walking.
+    InvalidSlopeFactor(Coord),
+
+    /// The lazy chunk size is zero, negative, infinite or NaN.
+    InvalidLazyChunkSize(Coord),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsError::EmptyNavmeshFilename => write!(f, "The navmesh filename is empty"),
+            SettingsError::InvalidPrecomputeRadius(radius) => {
+                write!(f, "The precompute radius must be positive, got {}", radius)
+            }
+            SettingsError::NoPrecomputePairs => write!(f, "The number of precompute pairs must be positive"),
+            SettingsError::InvalidSlopeFactor(slope_factor) => {
+                write!(f, "The slope factor must not be negative, got {}", slope_factor)
+            }
+            SettingsError::InvalidLazyChunkSize(chunk_size) => {
+                write!(f, "The lazy chunk size must be positive and finite, got {}", chunk_size)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SettingsError {}
+
+/// Builds `RePathSettings` step by step, starting from the defaults, and validates them:
+///
+/// ```no_run
+/// use repath::settings::RePathSettingsBuilder;
+///
+/// let settings = RePathSettingsBuilder::new()
+///     .navmesh_filename("NavMesh.obj")
+///     .precompute_radius(5000.0)
+///     .total_precompute_pairs(1000)
+///     .build()
+///     .expect("Invalid settings");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct RePathSettingsBuilder {
+    settings: RePathSettings,
+}
+
+impl RePathSettingsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn navmesh_filename(mut self, navmesh_filename: impl Into<String>) -> Self {
+        self.settings.navmesh_filename = navmesh_filename.into();
+        self
+    }
+
+    pub fn precompute_radius(mut self, precompute_radius: Coord) -> Self {
+        self.settings.precompute_radius = precompute_radius;
+        self
+    }
+
+    pub fn total_precompute_pairs(mut self, total_precompute_pairs: usize) -> Self {
+        self.settings.total_precompute_pairs = total_precompute_pairs;
+        self
+    }
+
+    pub fn use_precomputed_cache(mut self, use_precomputed_cache: bool) -> Self {
+        self.settings.use_precomputed_cache = use_precomputed_cache;
+        self
+    }
+
+    pub fn algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.settings.algorithm = algorithm;
+        self
+    }
+
+    pub fn cache_capacity(mut self, cache_capacity: usize) -> Self {
+        self.settings.cache_capacity = cache_capacity;
+        self
+    }
+
+    pub fn thread_local_cache_capacity(mut self, thread_local_cache_capacity: usize) -> Self {
+        self.settings.thread_local_cache_capacity = thread_local_cache_capacity;
+        self
+    }
+
+    pub fn cache_file(mut self, cache_file: impl Into<String>) -> Self {
+        self.settings.cache_file = Some(cache_file.into());
+        self
+    }
+
+    pub fn max_search_nodes(mut self, max_search_nodes: usize) -> Self {
+        self.settings.max_search_nodes = Some(max_search_nodes);
+        self
+    }
+
+    pub fn metrics_csv(mut self, metrics_csv: impl Into<String>) -> Self {
+        self.settings.metrics_csv = Some(metrics_csv.into());
+        self
+    }
+
+    pub fn slope_factor(mut self, slope_factor: Coord) -> Self {
+        self.settings.slope_factor = slope_factor;
+        self
+    }
+
+    pub fn mode(mut self, mode: GraphMode) -> Self {
+        self.settings.mode = mode;
+        self
+    }
+
+    pub fn lazy_chunk_size(mut self, lazy_chunk_size: Coord) -> Self {
+        self.settings.lazy_chunk_size = lazy_chunk_size;
+        self
+    }
+
+    /// Returns the settings, or the first reason why they are invalid.
+    pub fn build(self) -> Result<RePathSettings, SettingsError> {
+        let settings = self.settings;
+        if settings.navmesh_filename.is_empty() {
+            return Err(SettingsError::EmptyNavmeshFilename);
+        }
+        if settings.precompute_radius.is_nan() || settings.precompute_radius <= 0.0 {
+            return Err(SettingsError::InvalidPrecomputeRadius(settings.precompute_radius));
+        }
+        if settings.total_precompute_pairs == 0 {
+            return Err(SettingsError::NoPrecomputePairs);
+        }
+        if settings.slope_factor.is_nan() || settings.slope_factor < 0.0 {
+            return Err(SettingsError::InvalidSlopeFactor(settings.slope_factor));
+        }
+        if !settings.lazy_chunk_size.is_finite() || settings.lazy_chunk_size <= 0.0 {
+            return Err(SettingsError::InvalidLazyChunkSize(settings.lazy_chunk_size));
+        }
+        Ok(settings)
+    }
 }
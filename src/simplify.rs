@@ -0,0 +1,125 @@
+use crate::edge::{Edge, EdgeKind};
+use crate::graph::Graph;
+use crate::node::Node;
+use crate::Coord;
+
+/// Returns the two neighbors of a node that can be merged away by `Graph::simplify`: a node with
+/// exactly two distinct neighbors, connected to both in both directions.
+fn chain_neighbors(graph: &Graph, incoming: &[Vec<usize>], id: usize) -> Option<[usize; 2]> {
+    let mut outgoing: Vec<usize> = graph.edges[id].iter().map(|edge| edge.to).collect();
+    outgoing.sort_unstable();
+    outgoing.dedup();
+    let mut sources = incoming[id].clone();
+    sources.sort_unstable();
+    sources.dedup();
+
+    match outgoing[..] {
+        [a, b] if outgoing == sources && a != id && b != id => Some([a, b]),
+        _ => None,
+    }
+}
+
+impl Graph {
+    /// Returns a smaller graph in which chains of nodes with exactly two neighbors, e.g. a
+    /// corridor represented as a polyline, are merged into single edges. An edge replacing a
+    /// chain costs as much as traversing the chain did, including the weights of the removed
+    /// nodes, so the cheapest paths between the remaining nodes keep their cost.
+    ///
+    /// Only nodes connected to both neighbors in both directions are removed, and the positions
+    /// of removed nodes are lost. A cycle made up only of such nodes shrinks to its smallest node.
+    /// An edge replacing a chain only fits agents that fit through the whole chain, see
+    /// `Edge::clearance`. The remaining nodes keep their order but get new ids, so paths and
+    /// caches of the original graph do not apply to the result.
+    pub fn simplify(&self) -> Graph {
+        let node_count = self.nodes.len();
+        let mut incoming = vec![Vec::new(); node_count];
+        for (from, edges) in self.edges.iter().enumerate() {
+            for edge in edges {
+                incoming[edge.to].push(from);
+            }
+        }
+        let chains: Vec<Option<[usize; 2]>> =
+            (0..node_count).map(|id| chain_neighbors(self, &incoming, id)).collect();
+
+        let mut kept: Vec<bool> = chains.iter().map(Option::is_none).collect();
+        // Cycles of chain nodes have no node to start from, keep their smallest node
+        let mut visited = vec![false; node_count];
+        for id in 0..node_count {
+            if kept[id] || visited[id] {
+                continue;
+            }
+            let (mut previous, mut current) = (id, chains[id].unwrap()[0]);
+            visited[id] = true;
+            while chains[current].is_some() && !visited[current] {
+                visited[current] = true;
+                let [a, b] = chains[current].unwrap();
+                let next = if a == previous { b } else { a };
+                (previous, current) = (current, next);
+            }
+            if current == id {
+                kept[id] = true;
+            }
+        }
+
+        let mut new_ids = vec![usize::MAX; node_count];
+        let mut simplified = Graph::new();
+        for id in (0..node_count).filter(|&id| kept[id]) {
+            new_ids[id] = simplified.nodes.len();
+            simplified.add_node(Node {
+                id: simplified.nodes.len(),
+                ..self.nodes[id].clone()
+            });
+        }
+
+        for from in (0..node_count).filter(|&id| kept[id]) {
+            for edge in &self.edges[from] {
+                if kept[edge.to] {
+                    simplified.edges[new_ids[from]].push(Edge {
+                        to: new_ids[edge.to],
+                        ..edge.clone()
+                    });
+                    continue;
+                }
+
+                // Follow the chain to the next kept node, summing up the cost of traversing it
+                let (mut previous, mut current) = (from, edge.to);
+                let mut cost = 0.0;
+                let mut clearance = edge.clearance;
+                // The chain needs whatever any of its edges needs, e.g. swimming
+                let mut labels = edge.labels.clone();
+                let mut step_cost = edge.cost;
+                while !kept[current] {
+                    cost += step_cost * self.nodes[current].weight;
+                    let [a, b] = chains[current].unwrap();
+                    let next = if a == previous { b } else { a };
+                    step_cost = self.edge_cost(current, next).unwrap();
+                    let widest = self.edges[current]
+                        .iter()
+                        .filter(|edge| edge.to == next)
+                        .map(|edge| edge.clearance)
+                        .fold(0.0, Coord::max);
+                    clearance = clearance.min(widest);
+                    for edge in self.edges[current].iter().filter(|edge| edge.to == next) {
+                        for label in &edge.labels {
+                            if !labels.contains(label) {
+                                labels.push(label.clone());
+                            }
+                        }
+                    }
+                    (previous, current) = (current, next);
+                }
+                if current != from {
+                    simplified.push_edge(new_ids[from], Edge {
+                        to: new_ids[current],
+                        cost: cost + step_cost,
+                        directed: false,
+                        kind: EdgeKind::Walk,
+                        clearance,
+                        labels,
+                    });
+                }
+            }
+        }
+        simplified
+    }
+}
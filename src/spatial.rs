@@ -0,0 +1,350 @@
+use crate::graph::Graph;
+use crate::node::Node;
+use crate::path::project_onto_segment;
+use crate::utils::distance;
+use crate::Coord;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+/// A k-d tree over the node positions of a graph for fast nearest node and radius queries.
+///
+/// The index is built once in O(n log n) and answers `nearest_node_fast` in O(log n) on average
+/// instead of the linear scan of `Graph::nearest_node`. It is a snapshot of the positions, so it
+/// must be rebuilt after nodes are added or moved.
+#[derive(Debug, Clone)]
+pub struct SpatialIndex {
+    /// Node ids arranged as an implicit balanced tree: the middle element of every range splits
+    /// it, the elements before it form the left subtree and the elements after it the right one.
+    /// The splitting axis cycles through x, y and z with the depth.
+    ids: Vec<usize>,
+    /// The position of every node by id.
+    positions: Vec<[Coord; 3]>,
+}
+
+impl SpatialIndex {
+    /// Builds the index over all nodes of the graph.
+    pub fn new(graph: &Graph) -> Self {
+        let positions: Vec<[Coord; 3]> = graph.nodes.iter().map(|node| [node.x, node.y, node.z]).collect();
+        let mut ids: Vec<usize> = (0..positions.len()).collect();
+        build(&mut ids, &positions, 0);
+
+        SpatialIndex { ids, positions }
+    }
+
+    /// Returns the id of the node closest to the given coordinates, or `None` if the graph has no
+    /// nodes. Returns the same node as `Graph::nearest_node`, including the lowest id on ties.
+    pub fn nearest_node_fast(&self, x: Coord, y: Coord, z: Coord) -> Option<usize> {
+        let mut best = None;
+        self.nearest(0, self.ids.len(), 0, [x, y, z], &mut best);
+        best.map(|(_, id)| id)
+    }
+
+    /// Returns the ids of all nodes within `radius` of the given node in ascending order, the
+    /// same as `utils::nodes_within_radius` but in O(k log n) for k results instead of O(n).
+    pub fn nodes_within_radius_fast(&self, node: &Node, radius: Coord) -> Vec<usize> {
+        let mut result = Vec::new();
+        self.within_radius(0, self.ids.len(), 0, [node.x, node.y, node.z], radius, &mut result);
+        result.sort_unstable();
+        result
+    }
+
+    /// Returns the ids of the `k` nodes closest to the given coordinates, closest first and with
+    /// the lower id first on ties. Returns all nodes if the graph has fewer than `k`.
+    pub fn k_nearest_fast(&self, x: Coord, y: Coord, z: Coord, k: usize) -> Vec<usize> {
+        let mut best = Vec::with_capacity(k + 1);
+        if k > 0 {
+            self.k_nearest(0, self.ids.len(), 0, [x, y, z], k, &mut best);
+        }
+        best.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Returns the ids of all nodes inside the box, including its faces, in ascending order, the
+    /// same as `Graph::nodes_in_aabb` but only visiting the parts of the tree that overlap it.
+    pub fn nodes_in_aabb_fast(&self, min: (Coord, Coord, Coord), max: (Coord, Coord, Coord)) -> Vec<usize> {
+        let mut result = Vec::new();
+        self.in_aabb(0, self.ids.len(), 0, [min.0, min.1, min.2], [max.0, max.1, max.2], &mut result);
+        result.sort_unstable();
+        result
+    }
+
+    fn in_aabb(&self, start: usize, end: usize, depth: usize, min: [Coord; 3], max: [Coord; 3], result: &mut Vec<usize>) {
+        if start >= end {
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        let id = self.ids[middle];
+        let position = self.positions[id];
+
+        if (0..3).all(|axis| min[axis] <= position[axis] && position[axis] <= max[axis]) {
+            result.push(id);
+        }
+
+        let axis = depth % 3;
+        if min[axis] <= position[axis] {
+            self.in_aabb(start, middle, depth + 1, min, max, result);
+        }
+        if max[axis] >= position[axis] {
+            self.in_aabb(middle + 1, end, depth + 1, min, max, result);
+        }
+    }
+
+    fn within_radius(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        center: [Coord; 3],
+        radius: Coord,
+        result: &mut Vec<usize>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        let id = self.ids[middle];
+        let position = self.positions[id];
+
+        if distance(&to_tuple(center), &to_tuple(position)) <= radius {
+            result.push(id);
+        }
+
+        let axis = depth % 3;
+        let offset = center[axis] - position[axis];
+        // Only descend into the sides the sphere reaches
+        if offset <= radius {
+            self.within_radius(start, middle, depth + 1, center, radius, result);
+        }
+        if offset >= -radius {
+            self.within_radius(middle + 1, end, depth + 1, center, radius, result);
+        }
+    }
+
+    fn nearest(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: [Coord; 3],
+        best: &mut Option<(Coord, usize)>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        let id = self.ids[middle];
+        let position = self.positions[id];
+
+        let d = distance(&to_tuple(position), &to_tuple(target));
+        if best.is_none_or(|(best_distance, best_id)| d < best_distance || (d == best_distance && id < best_id)) {
+            *best = Some((d, id));
+        }
+
+        let axis = depth % 3;
+        let offset = target[axis] - position[axis];
+        let (near, far) = if offset < 0.0 {
+            ((start, middle), (middle + 1, end))
+        } else {
+            ((middle + 1, end), (start, middle))
+        };
+
+        self.nearest(near.0, near.1, depth + 1, target, best);
+        // The other side can only contain a closer node if the splitting plane is close enough
+        if best.is_none_or(|(best_distance, _)| offset.abs() <= best_distance) {
+            self.nearest(far.0, far.1, depth + 1, target, best);
+        }
+    }
+
+    /// Collects the `k` closest nodes into `best`, which is kept sorted by distance and id.
+    fn k_nearest(
+        &self,
+        start: usize,
+        end: usize,
+        depth: usize,
+        target: [Coord; 3],
+        k: usize,
+        best: &mut Vec<(Coord, usize)>,
+    ) {
+        if start >= end {
+            return;
+        }
+        let middle = start + (end - start) / 2;
+        let id = self.ids[middle];
+        let position = self.positions[id];
+
+        let candidate = (distance(&to_tuple(position), &to_tuple(target)), id);
+        let index = best.partition_point(|&(d, other)| d < candidate.0 || (d == candidate.0 && other < id));
+        if index < k {
+            best.insert(index, candidate);
+            best.truncate(k);
+        }
+
+        let axis = depth % 3;
+        let offset = target[axis] - position[axis];
+        let (near, far) = if offset < 0.0 {
+            ((start, middle), (middle + 1, end))
+        } else {
+            ((middle + 1, end), (start, middle))
+        };
+
+        self.k_nearest(near.0, near.1, depth + 1, target, k, best);
+        if best.len() < k || offset.abs() <= best[best.len() - 1].0 {
+            self.k_nearest(far.0, far.1, depth + 1, target, k, best);
+        }
+    }
+}
+
+impl Graph {
+    /// Returns the ids of the `k` nodes closest to the given coordinates, closest first and with
+    /// the lower id first on ties, or all nodes if the graph has fewer than `k`. Scans all nodes,
+    /// `SpatialIndex::k_nearest_fast` answers the same query in O(k log n).
+    pub fn k_nearest_nodes(&self, x: Coord, y: Coord, z: Coord, k: usize) -> Vec<usize> {
+        let mut candidates: Vec<(Coord, usize)> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(id, node)| (distance(&(node.x, node.y, node.z), &(x, y, z)), id))
+            .collect();
+        let compare = |a: &(Coord, usize), b: &(Coord, usize)| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1));
+        if k < candidates.len() {
+            candidates.select_nth_unstable_by(k, compare);
+            candidates.truncate(k);
+        }
+        candidates.sort_unstable_by(compare);
+        candidates.into_iter().map(|(_, id)| id).collect()
+    }
+
+    /// Returns the ids of all nodes inside the axis-aligned box from `min` to `max`, including
+    /// its faces, in ascending order. Scans all nodes, see `SpatialIndex::nodes_in_aabb_fast`.
+    pub fn nodes_in_aabb(&self, min: (Coord, Coord, Coord), max: (Coord, Coord, Coord)) -> Vec<usize> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                (min.0..=max.0).contains(&node.x) && (min.1..=max.1).contains(&node.y) && (min.2..=max.2).contains(&node.z)
+            })
+            .map(|(id, _)| id)
+            .collect()
+    }
+
+    /// Returns the ids of all nodes a sphere of `radius` overlaps while moving in a straight line
+    /// from `start` to `end`, in ascending order. These are the nodes within `radius` of the
+    /// segment, the capsule swept by the sphere, including its surface. Scans all nodes,
+    /// `RePathfinder::sphere_cast` only checks those near the segment.
+    pub fn sphere_cast(&self, start: (Coord, Coord, Coord), end: (Coord, Coord, Coord), radius: Coord) -> Vec<usize> {
+        let sweep = Sweep::new(start, end, radius);
+        (0..self.nodes.len()).filter(|&id| sweep.contains(&self.nodes[id])).collect()
+    }
+
+    /// Builds a graph from a point cloud, e.g. LIDAR data or procedurally generated terrain, with
+    /// one node per point and edges in both directions between all points within
+    /// `connect_radius` of each other. The node ids are the indices of the points and the edge
+    /// costs their distances.
+    pub fn build_from_point_cloud(points: &[(Coord, Coord, Coord)], connect_radius: Coord) -> Graph {
+        let graph = graph_from_points(points);
+        let index = SpatialIndex::new(&graph);
+        let neighbors: Vec<Vec<usize>> = (0..points.len())
+            .into_par_iter()
+            .map(|id| index.nodes_within_radius_fast(&graph.nodes[id], connect_radius))
+            .collect();
+
+        // The neighborhoods are symmetric, so every edge gets its reverse as well
+        let pairs = neighbors
+            .iter()
+            .enumerate()
+            .flat_map(|(id, ids)| ids.iter().map(move |&to| (id, to)));
+        connect(graph, points, pairs)
+    }
+
+    /// Builds a graph from a point cloud like `build_from_point_cloud`, but connects every point
+    /// to its `k` nearest other points instead of all points within a radius. The edges are added
+    /// in both directions, so points may end up with more than `k` neighbors.
+    pub fn build_from_point_cloud_knn(points: &[(Coord, Coord, Coord)], k: usize) -> Graph {
+        let graph = graph_from_points(points);
+        let index = SpatialIndex::new(&graph);
+        let mut pairs: Vec<(usize, usize)> = (0..points.len())
+            .into_par_iter()
+            .flat_map_iter(|id| {
+                let (x, y, z) = points[id];
+                // One more, since the point itself is the closest
+                index
+                    .k_nearest_fast(x, y, z, k + 1)
+                    .into_iter()
+                    .filter(move |&other| other != id)
+                    .take(k)
+                    .flat_map(move |other| [(id, other), (other, id)])
+            })
+            .collect();
+        pairs.sort_unstable();
+        pairs.dedup();
+
+        connect(graph, points, pairs)
+    }
+}
+
+/// The capsule a moving sphere sweeps, see `Graph::sphere_cast`.
+pub(crate) struct Sweep {
+    start: Node,
+    end: Node,
+    radius: Coord,
+}
+
+impl Sweep {
+    pub(crate) fn new(start: (Coord, Coord, Coord), end: (Coord, Coord, Coord), radius: Coord) -> Self {
+        Sweep {
+            start: Node::new(usize::MAX, start.0, start.1, start.2),
+            end: Node::new(usize::MAX, end.0, end.1, end.2),
+            radius,
+        }
+    }
+
+    /// The corners of the axis-aligned box around the capsule.
+    pub(crate) fn bounds(&self) -> ((Coord, Coord, Coord), (Coord, Coord, Coord)) {
+        let (start, end, radius) = (&self.start, &self.end, self.radius);
+        (
+            (start.x.min(end.x) - radius, start.y.min(end.y) - radius, start.z.min(end.z) - radius),
+            (start.x.max(end.x) + radius, start.y.max(end.y) + radius, start.z.max(end.z) + radius),
+        )
+    }
+
+    pub(crate) fn contains(&self, node: &Node) -> bool {
+        let position = (node.x, node.y, node.z);
+        let (_, closest) = project_onto_segment(position, &self.start, &self.end);
+        distance(&position, &closest) <= self.radius
+    }
+}
+
+/// A graph with one node per point and no edges, see `Graph::build_from_point_cloud`.
+fn graph_from_points(points: &[(Coord, Coord, Coord)]) -> Graph {
+    let mut graph = Graph::new();
+    for (id, &(x, y, z)) in points.iter().enumerate() {
+        graph.add_node(Node::new(id, x, y, z));
+    }
+    graph
+}
+
+/// Adds an edge for every pair of distinct points, costing their distance.
+fn connect(mut graph: Graph, points: &[(Coord, Coord, Coord)], pairs: impl IntoIterator<Item = (usize, usize)>) -> Graph {
+    for (from, to) in pairs {
+        if from != to {
+            graph.add_edge(from, to, distance(&points[from], &points[to]));
+        }
+    }
+    graph
+}
+
+/// Arranges `ids` as an implicit k-d tree, see `SpatialIndex::ids`.
+fn build(ids: &mut [usize], positions: &[[Coord; 3]], depth: usize) {
+    if ids.len() <= 1 {
+        return;
+    }
+    let axis = depth % 3;
+    let middle = ids.len() / 2;
+    ids.select_nth_unstable_by(middle, |&a, &b| positions[a][axis].total_cmp(&positions[b][axis]));
+
+    let (left, right) = ids.split_at_mut(middle);
+    build(left, positions, depth + 1);
+    build(&mut right[1..], positions, depth + 1);
+}
+
+fn to_tuple(position: [Coord; 3]) -> (Coord, Coord, Coord) {
+    (position[0], position[1], position[2])
+}
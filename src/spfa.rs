@@ -0,0 +1,98 @@
+use std::collections::VecDeque;
+use crate::cache::PathCache;
+use crate::error::RePathError;
+use crate::graph::Graph;
+use crate::path::Path;
+use crate::Coord;
+
+impl Graph {
+    /// Finds the cheapest path from start to goal with the Shortest Path Faster Algorithm, a
+    /// queue-based variant of Bellman-Ford that, unlike `a_star` and `dijkstra`, handles negative
+    /// edge costs, e.g. for rewards collected along the way. Results share the cache with `a_star`.
+    ///
+    /// Returns `Err(RePathError::NegativeCycle)` if a cycle with a negative total cost can be
+    /// reached from start, since paths could get cheaper forever by going around it. Every node
+    /// reachable from start is visited, so this is much slower than `dijkstra` on large graphs.
+    pub fn spfa(&self, start: usize, goal: usize, cache: &impl PathCache) -> Result<Option<Path>, RePathError> {
+        let cache_key = (start, goal);
+        if let Some(result) = cache.get_path(&cache_key) {
+            return Ok(result);
+        }
+
+        let num_nodes = self.nodes.len();
+        let mut dist = vec![Coord::INFINITY; num_nodes];
+        let mut came_from = vec![None; num_nodes];
+        // The number of edges of the cheapest path found to every node, a path with as many
+        // edges as there are nodes repeats a node and therefore contains a negative cycle
+        let mut edge_counts = vec![0; num_nodes];
+        let mut queued = vec![false; num_nodes];
+        let mut queue = VecDeque::from([start]);
+        dist[start] = 0.0;
+        queued[start] = true;
+
+        while let Some(current) = queue.pop_front() {
+            queued[current] = false;
+            for edge in &self.edges[current] {
+                let tentative_dist = dist[current] + self.traversal_cost(edge);
+                if tentative_dist < dist[edge.to] {
+                    dist[edge.to] = tentative_dist;
+                    came_from[edge.to] = Some(current);
+                    edge_counts[edge.to] = edge_counts[current] + 1;
+                    if edge_counts[edge.to] >= num_nodes {
+                        return Err(RePathError::NegativeCycle);
+                    }
+                    if !queued[edge.to] {
+                        queued[edge.to] = true;
+                        queue.push_back(edge.to);
+                    }
+                }
+            }
+        }
+
+        let result = dist[goal].is_finite().then(|| self.reconstruct_path(&came_from, goal));
+        cache.insert_path(cache_key, result.clone());
+        Ok(result)
+    }
+
+    /// Returns the cost of the cheapest path from start to every node with the Bellman-Ford
+    /// algorithm, which allows negative edge costs, or `None` for nodes that cannot be reached.
+    /// Nodes reachable through a cycle with a negative total cost get negative infinity, since
+    /// their paths can get cheaper forever.
+    pub fn bellman_ford_all_paths(&self, start: usize) -> Vec<Option<Coord>> {
+        let num_nodes = self.nodes.len();
+        let mut dist = vec![Coord::INFINITY; num_nodes];
+        dist[start] = 0.0;
+        let relax = |dist: &mut Vec<Coord>| {
+            let mut changed = Vec::new();
+            for (from, edges) in self.edges.iter().enumerate() {
+                if !dist[from].is_finite() {
+                    continue;
+                }
+                for edge in edges {
+                    let tentative_dist = dist[from] + self.traversal_cost(edge);
+                    if tentative_dist < dist[edge.to] {
+                        dist[edge.to] = tentative_dist;
+                        changed.push(edge.to);
+                    }
+                }
+            }
+            changed
+        };
+
+        for _ in 1..num_nodes {
+            if relax(&mut dist).is_empty() {
+                break;
+            }
+        }
+        // Whatever still improves lies on or behind a negative cycle, as does everything it reaches
+        let mut unbounded = relax(&mut dist);
+        while let Some(node) = unbounded.pop() {
+            if dist[node] != Coord::NEG_INFINITY {
+                dist[node] = Coord::NEG_INFINITY;
+                unbounded.extend(self.edges[node].iter().map(|edge| edge.to));
+            }
+        }
+
+        dist.into_iter().map(|dist| (dist != Coord::INFINITY).then_some(dist)).collect()
+    }
+}
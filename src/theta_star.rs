@@ -0,0 +1,105 @@
+use std::collections::BinaryHeap;
+use crate::cache::PathCache;
+use crate::graph::{Graph, State};
+use crate::path::Path;
+use crate::triangles::TriangleMesh;
+use crate::Coord;
+
+impl Graph {
+    /// Returns true if the straight segment between nodes `a` and `b` stays on the navmesh
+    /// surface, see `TriangleMesh::line_of_sight`.
+    ///
+    /// This reconstructs the triangle faces of the graph on every call, build a `TriangleMesh`
+    /// once and use `TriangleMesh::line_of_sight` when checking many segments.
+    pub fn line_of_sight(&self, a: usize, b: usize) -> bool {
+        TriangleMesh::new(self).line_of_sight(self, a, b)
+    }
+
+    /// Finds an any-angle path from start to goal using Theta*.
+    /// Whenever a node is visible from the parent of the node being expanded, the parent is linked
+    /// to it directly, so the path cuts across triangles instead of following their edges.
+    /// Consecutive nodes of the returned path are therefore not necessarily connected by an edge.
+    ///
+    /// Paths are cached under `(start, goal)` like `a_star`, but since they differ from A* paths
+    /// the cache must not be shared with the other search methods.
+    pub fn theta_star(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+    ) -> Option<Path> {
+        self.theta_star_with_mesh(start, goal, cache, &TriangleMesh::new(self))
+    }
+
+    /// Finds an any-angle path from start to goal using Theta* on a previously built mesh.
+    /// The mesh must have been built from this graph and the graph must not have changed since.
+    pub fn theta_star_with_mesh(
+        &self,
+        start: usize,
+        goal: usize,
+        cache: &impl PathCache,
+        mesh: &TriangleMesh,
+    ) -> Option<Path> {
+        let cache_key = (start, goal);
+
+        // Check if the path is already in cache
+        if let Some(result) = cache.get_path(&cache_key) {
+            return result;
+        }
+
+        let num_nodes = self.nodes.len();
+        let mut open_set = BinaryHeap::new();
+        let mut came_from: Vec<Option<usize>> = vec![None; num_nodes];
+        let mut g_score = vec![Coord::INFINITY; num_nodes];
+        let mut closed_set = vec![false; num_nodes];
+
+        g_score[start] = 0.0;
+        open_set.push(State {
+            cost: self.heuristic(start, goal),
+            position: start,
+        });
+
+        let mut result = None;
+
+        while let Some(State { cost: _, position: current }) = open_set.pop() {
+            if current == goal {
+                result = Some(self.reconstruct_path(&came_from, current));
+                break;
+            }
+
+            if closed_set[current] {
+                continue;
+            }
+            closed_set[current] = true;
+
+            for edge in &self.edges[current] {
+                let neighbor = edge.to;
+
+                if closed_set[neighbor] {
+                    continue;
+                }
+
+                // Link the neighbor straight to the current node's parent when it is visible
+                let (parent, tentative_g_score) = match came_from[current] {
+                    Some(parent) if mesh.line_of_sight(self, parent, neighbor) => {
+                        (parent, g_score[parent] + self.heuristic(parent, neighbor))
+                    }
+                    _ => (current, g_score[current] + self.traversal_cost(edge)),
+                };
+
+                if tentative_g_score < g_score[neighbor] {
+                    came_from[neighbor] = Some(parent);
+                    g_score[neighbor] = tentative_g_score;
+                    open_set.push(State {
+                        cost: tentative_g_score + self.heuristic(neighbor, goal),
+                        position: neighbor,
+                    });
+                }
+            }
+        }
+
+        cache.insert_path(cache_key, result.clone());
+
+        result
+    }
+}
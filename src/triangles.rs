@@ -0,0 +1,335 @@
+use std::collections::{HashMap, HashSet};
+use crate::graph::Graph;
+use crate::Coord;
+
+/// The triangle faces of a navmesh graph together with the adjacency needed to walk across them.
+///
+/// Faces are not stored on the graph, they are reconstructed from edge cycles `a -> b -> c -> a`,
+/// which is how `parse_obj` adds the edges of every face.
+#[derive(Debug, Clone)]
+pub struct TriangleMesh {
+    /// The triangles of the mesh as node ids.
+    pub triangles: Vec<[usize; 3]>,
+    /// Ids of the triangles each node belongs to.
+    node_triangles: Vec<Vec<usize>>,
+    /// Ids of the triangles sharing each undirected edge, keyed by `(min, max)` node id.
+    edge_triangles: HashMap<(usize, usize), Vec<usize>>,
+    /// The axis treated as "up" (0 = x, 1 = y, 2 = z). Line of sight is tested in the plane of
+    /// the other two axes.
+    up_axis: usize,
+}
+
+impl TriangleMesh {
+    /// Reconstructs the triangle faces of the graph.
+    /// The up axis is the one along which the nodes have the smallest extent.
+    pub fn new(graph: &Graph) -> Self {
+        let triangles = graph.triangles();
+        let mut node_triangles = vec![Vec::new(); graph.nodes.len()];
+        let mut edge_triangles: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+
+        for (id, triangle) in triangles.iter().enumerate() {
+            for i in 0..3 {
+                let (a, b) = (triangle[i], triangle[(i + 1) % 3]);
+                node_triangles[a].push(id);
+                edge_triangles.entry((a.min(b), a.max(b))).or_default().push(id);
+            }
+        }
+
+        let mut min = [Coord::MAX; 3];
+        let mut max = [Coord::MIN; 3];
+        for node in &graph.nodes {
+            for (axis, value) in [node.x, node.y, node.z].into_iter().enumerate() {
+                min[axis] = min[axis].min(value);
+                max[axis] = max[axis].max(value);
+            }
+        }
+        let up_axis = (0..3)
+            .min_by(|&a, &b| (max[a] - min[a]).total_cmp(&(max[b] - min[b])))
+            .unwrap();
+
+        TriangleMesh {
+            triangles,
+            node_triangles,
+            edge_triangles,
+            up_axis,
+        }
+    }
+
+    /// Returns true if the straight segment between nodes `a` and `b` stays on the mesh surface,
+    /// i.e. it never leaves the area covered by triangles when projected onto the ground plane.
+    ///
+    /// The test walks from triangle to triangle along the segment, so it is exact and its cost is
+    /// proportional to the number of triangles crossed.
+    pub fn line_of_sight(&self, graph: &Graph, a: usize, b: usize) -> bool {
+        if a == b {
+            return true;
+        }
+
+        let target = self.project(graph, b);
+        let scale = distance_squared(self.project(graph, a), target);
+        if scale == 0.0 {
+            return true;
+        }
+        let epsilon = scale * 1e-9;
+
+        let mut from = a;
+        // Each step either crosses a triangle or moves to another vertex, so the number of
+        // steps is bounded by the number of triangles plus the number of vertices
+        let mut remaining_steps = self.triangles.len() + self.node_triangles.len();
+
+        'vertex: loop {
+            let origin = self.project(graph, from);
+
+            // Find the triangle around the current vertex that the segment enters
+            let mut crossing = None;
+            for &triangle_id in &self.node_triangles[from] {
+                let triangle = self.triangles[triangle_id];
+                if triangle.contains(&b) {
+                    return true;
+                }
+
+                // Order the other two vertices so that `right, left` runs counter-clockwise
+                // around the current vertex
+                let others: Vec<usize> = triangle.iter().copied().filter(|&id| id != from).collect();
+                let (mut left, mut right) = (others[0], others[1]);
+                if orient(origin, self.project(graph, right), self.project(graph, left)) < 0.0 {
+                    std::mem::swap(&mut left, &mut right);
+                }
+
+                let left_side = orient(origin, target, self.project(graph, left));
+                let right_side = orient(origin, target, self.project(graph, right));
+
+                // The segment runs along one of the triangle's edges through another vertex
+                for (vertex, side) in [(left, left_side), (right, right_side)] {
+                    let position = self.project(graph, vertex);
+                    if side.abs() <= epsilon && dot(origin, position, target) > 0.0 {
+                        if distance_squared(origin, position) >= distance_squared(origin, target) {
+                            // The target lies on this edge
+                            return true;
+                        }
+                        if remaining_steps == 0 {
+                            return false;
+                        }
+                        remaining_steps -= 1;
+                        from = vertex;
+                        continue 'vertex;
+                    }
+                }
+
+                if left_side > 0.0 && right_side < 0.0 {
+                    crossing = Some((triangle_id, left, right));
+                    break;
+                }
+            }
+
+            let Some((mut triangle_id, mut left, mut right)) = crossing else {
+                return false;
+            };
+
+            // Walk across the triangles until the target is reached or the segment leaves the mesh
+            loop {
+                if remaining_steps == 0 {
+                    return false;
+                }
+                remaining_steps -= 1;
+
+                // The target is inside the current triangle if it is not beyond the exit edge
+                if orient(self.project(graph, right), self.project(graph, left), target) >= -epsilon {
+                    return true;
+                }
+
+                let key = (left.min(right), left.max(right));
+                let Some(next_id) = self.edge_triangles[&key].iter().copied().find(|&id| id != triangle_id) else {
+                    return false;
+                };
+                let next = self.triangles[next_id];
+                let Some(&apex) = next.iter().find(|&&id| id != left && id != right) else {
+                    return false;
+                };
+                if apex == b {
+                    return true;
+                }
+
+                let side = orient(origin, target, self.project(graph, apex));
+                if side.abs() <= epsilon {
+                    // The segment passes exactly through the apex
+                    from = apex;
+                    continue 'vertex;
+                }
+                if side > 0.0 {
+                    left = apex;
+                } else {
+                    right = apex;
+                }
+                triangle_id = next_id;
+            }
+        }
+    }
+
+    /// Projects a node onto the ground plane.
+    fn project(&self, graph: &Graph, id: usize) -> (Coord, Coord) {
+        let node = &graph.nodes[id];
+        match self.up_axis {
+            0 => (node.y, node.z),
+            1 => (node.x, node.z),
+            _ => (node.x, node.y),
+        }
+    }
+}
+
+impl TriangleMesh {
+    /// Casts a ray against the triangles of the mesh, see `Graph::raycast`.
+    pub fn raycast(
+        &self,
+        graph: &Graph,
+        origin: (Coord, Coord, Coord),
+        direction: (Coord, Coord, Coord),
+    ) -> Option<(usize, Coord)> {
+        raycast_triangles(graph, &self.triangles, origin, direction)
+    }
+}
+
+/// Returns the node of the hit triangle closest to the nearest hit and the distance to the hit.
+fn raycast_triangles(
+    graph: &Graph,
+    triangles: &[[usize; 3]],
+    origin: (Coord, Coord, Coord),
+    direction: (Coord, Coord, Coord),
+) -> Option<(usize, Coord)> {
+    let length = dot3(direction, direction).sqrt();
+    if length == 0.0 || !length.is_finite() {
+        return None;
+    }
+    let direction = scale3(direction, 1.0 / length);
+    let position = |id: usize| {
+        let node = &graph.nodes[id];
+        (node.x, node.y, node.z)
+    };
+
+    let (distance, triangle) = triangles
+        .iter()
+        .filter_map(|triangle| {
+            let corners = triangle.map(position);
+            ray_triangle_intersection(origin, direction, corners).map(|distance| (distance, triangle))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))?;
+    let hit = add3(origin, scale3(direction, distance));
+    let node = triangle
+        .iter()
+        .copied()
+        .min_by(|&a, &b| {
+            let to_hit = |id| {
+                let offset = sub3(position(id), hit);
+                dot3(offset, offset)
+            };
+            to_hit(a).total_cmp(&to_hit(b)).then(a.cmp(&b))
+        })?;
+    Some((node, distance))
+}
+
+/// The distance along the normalized `direction` at which the ray hits the triangle, from either
+/// side, with the Möller-Trumbore algorithm. Degenerate triangles and rays parallel to the
+/// triangle never hit.
+fn ray_triangle_intersection(
+    origin: (Coord, Coord, Coord),
+    direction: (Coord, Coord, Coord),
+    [p0, p1, p2]: [(Coord, Coord, Coord); 3],
+) -> Option<Coord> {
+    let edge1 = sub3(p1, p0);
+    let edge2 = sub3(p2, p0);
+    let p = cross3(direction, edge2);
+    let determinant = dot3(edge1, p);
+    // Zero for rays in the plane of the triangle and for triangles without area
+    if determinant.abs() < Coord::EPSILON {
+        return None;
+    }
+    let inverse = 1.0 / determinant;
+    let s = sub3(origin, p0);
+    let u = dot3(s, p) * inverse;
+    if !(0.0..=1.0).contains(&u) {
+        return None;
+    }
+    let q = cross3(s, edge1);
+    let v = dot3(direction, q) * inverse;
+    if v < 0.0 || u + v > 1.0 {
+        return None;
+    }
+    let distance = dot3(edge2, q) * inverse;
+    (distance >= 0.0).then_some(distance)
+}
+
+fn add3(a: (Coord, Coord, Coord), b: (Coord, Coord, Coord)) -> (Coord, Coord, Coord) {
+    (a.0 + b.0, a.1 + b.1, a.2 + b.2)
+}
+
+fn sub3(a: (Coord, Coord, Coord), b: (Coord, Coord, Coord)) -> (Coord, Coord, Coord) {
+    (a.0 - b.0, a.1 - b.1, a.2 - b.2)
+}
+
+fn scale3(a: (Coord, Coord, Coord), factor: Coord) -> (Coord, Coord, Coord) {
+    (a.0 * factor, a.1 * factor, a.2 * factor)
+}
+
+fn dot3(a: (Coord, Coord, Coord), b: (Coord, Coord, Coord)) -> Coord {
+    a.0 * b.0 + a.1 * b.1 + a.2 * b.2
+}
+
+fn cross3(a: (Coord, Coord, Coord), b: (Coord, Coord, Coord)) -> (Coord, Coord, Coord) {
+    (a.1 * b.2 - a.2 * b.1, a.2 * b.0 - a.0 * b.2, a.0 * b.1 - a.1 * b.0)
+}
+
+/// Twice the signed area of the triangle `p, q, r`. Positive if `r` is left of `p -> q`.
+fn orient(p: (Coord, Coord), q: (Coord, Coord), r: (Coord, Coord)) -> Coord {
+    (q.0 - p.0) * (r.1 - p.1) - (q.1 - p.1) * (r.0 - p.0)
+}
+
+/// Dot product of the vectors `p -> q` and `p -> r`.
+fn dot(p: (Coord, Coord), q: (Coord, Coord), r: (Coord, Coord)) -> Coord {
+    (q.0 - p.0) * (r.0 - p.0) + (q.1 - p.1) * (r.1 - p.1)
+}
+
+fn distance_squared(p: (Coord, Coord), q: (Coord, Coord)) -> Coord {
+    (q.0 - p.0) * (q.0 - p.0) + (q.1 - p.1) * (q.1 - p.1)
+}
+
+impl Graph {
+    /// Reconstructs the triangle faces from edge cycles `a -> b -> c -> a`.
+    /// Each triangle is reported once, starting with its smallest node id and following the
+    /// direction of the edges.
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = Vec::new();
+        let mut seen = HashSet::new();
+
+        for (a, edges) in self.edges.iter().enumerate() {
+            for edge_ab in edges {
+                let b = edge_ab.to;
+                if b <= a {
+                    continue;
+                }
+                for edge_bc in &self.edges[b] {
+                    let c = edge_bc.to;
+                    if c <= a || c == b {
+                        continue;
+                    }
+                    let closes = self.edges[c].iter().any(|edge| edge.to == a);
+                    if closes && seen.insert([a, b.min(c), b.max(c)]) {
+                        triangles.push([a, b, c]);
+                    }
+                }
+            }
+        }
+
+        triangles
+    }
+
+    /// Casts a ray from `origin` along `direction` against the triangle faces, reconstructed like
+    /// `triangles`, e.g. to check line of sight or pick the navmesh under a cursor. Returns the
+    /// node of the first triangle hit that is closest to the hit point, together with the
+    /// distance from `origin` to the hit, or `None` if nothing is hit. `direction` does not have
+    /// to be normalized. Triangles are hit from both sides, degenerate ones are ignored.
+    ///
+    /// The faces are reconstructed on every call, `RePathfinder::raycast` reuses them.
+    pub fn raycast(&self, origin: (Coord, Coord, Coord), direction: (Coord, Coord, Coord)) -> Option<(usize, Coord)> {
+        raycast_triangles(self, &self.triangles(), origin, direction)
+    }
+}
@@ -1,57 +1,1059 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
 use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
 
+use crate::edge::{Edge, EdgeKind};
+use crate::error::RePathError;
 use crate::graph::Graph;
-use crate::metrics::Metrics;
+use crate::metrics::{Metrics, PathMetric};
 use crate::node::Node;
+use crate::Coord;
 
-pub fn parse_obj(filename: &str) -> Graph {
-    let file = File::open(filename).expect("Unable to open file");
+/// Loads a navmesh, choosing the parser by the file extension: `.gltf` and `.glb` files are
+/// parsed with `parse_gltf`, `.rpnav` files with `parse_graph_binary`, `.rpmap` files with
+/// `parse_graph_mmap` and everything else with `parse_obj`.
+pub fn parse_navmesh(filename: &str) -> Result<Graph, RePathError> {
+    let extension = std::path::Path::new(filename)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+
+    match extension.as_deref() {
+        Some("gltf" | "glb") => parse_gltf(filename),
+        Some("rpnav") => parse_graph_binary(filename),
+        Some("rpmap") => parse_graph_mmap(filename),
+        _ => parse_obj(filename),
+    }
+}
+
+/// The prefix of OBJ material names that set the clearance of the following edges.
+const CLEARANCE_MATERIAL_PREFIX: &str = "clearance_";
+
+/// Parses a Wavefront OBJ navmesh. Every vertex becomes a node, every face `f a b c` the edges
+/// `a -> b -> c -> a` and every line element `l a b` edges in both directions, or only from `a`
+/// to `b` if it follows a `# directed` comment. Edge costs are the distances between the nodes.
+///
+/// The node weight (see `Node::weight`) is read from an optional 4th vertex component
+/// `v x y z weight`, or from a 7th one after vertex colors `v x y z r g b weight`. Vertices
+/// without it have a weight of 1. Face vertex references may take any of the forms `v`, `v/vt`,
+/// `v//vn` and `v/vt/vn`, and the `vn` normal referenced by the latter two is stored as
+/// `Node::normal` of the vertex.
+///
+/// Group names set the kind of the edges of the faces and line elements that follow, see
+/// `EdgeKind::from_group_name`, e.g. `g stairs_north` for stairs. Edges before the first group
+/// are `Walk` edges. Likewise, a material named `clearance_<radius>`, e.g. `usemtl clearance_1.5`,
+/// sets the clearance of the edges that follow, see `Edge::clearance`, and any other material
+/// makes it unlimited again.
+///
+/// The name of the last `o` or `g` line is stored as `Node::group` of the vertices that follow
+/// it, see `Graph::nodes_in_group`.
+///
+/// The file is parsed in a single pass: every vertex is added to the graph as soon as it is
+/// read, and since faces and line elements can only refer to earlier vertices, their edges are
+/// added right away with costs measured between the nodes of the graph. Apart from the current
+/// line and the `vn` normals, nothing but the graph itself is kept in memory.
+pub fn parse_obj(filename: &str) -> Result<Graph, RePathError> {
+    let file = File::open(filename)?;
     let reader = BufReader::new(file);
 
     let mut graph = Graph::new();
-    let mut vertices: Vec<(f32, f32, f32)> = Vec::new();
-    let mut vertex_id = 0;
+    let mut normals: Vec<(Coord, Coord, Coord)> = Vec::new();
+    let mut attributes = ObjElementAttributes::default();
+    let mut group = None;
 
-    for line in reader.lines() {
-        let line = line.expect("Unable to read line");
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = line_index + 1;
         let parts: Vec<&str> = line.split_whitespace().collect();
         if parts.is_empty() {
             continue;
         }
 
         match parts[0] {
-            "v" => {
-                let x: f32 = parts[1].parse().unwrap();
-                let y: f32 = parts[2].parse().unwrap();
-                let z: f32 = parts[3].parse().unwrap();
-                vertices.push((x, y, z));
-                graph.add_node(Node::new(vertex_id, x, y, z));
-                vertex_id += 1;
+            "v" => graph.add_node(parse_obj_vertex(&parts, graph.nodes.len(), group.clone(), line_number)?),
+            "vn" => normals.push(parse_obj_position(&parts, line_number)?),
+            "f" | "l" => {
+                let vertex_count = graph.nodes.len();
+                add_obj_element(&mut graph, &parts, &attributes, vertex_count, &normals, line_number)?;
+                attributes.directed = false;
+            }
+            "o" | "g" => {
+                group = parse_obj_group(&parts);
+                attributes.update(&parts, line_number)?;
             }
-            "f" => {
-                let v1 = parts[1].parse::<usize>().unwrap() - 1;
-                let v2 = parts[2].parse::<usize>().unwrap() - 1;
-                let v3 = parts[3].parse::<usize>().unwrap() - 1;
-                graph.add_edge(v1, v2, distance(&vertices[v1], &vertices[v2]));
-                graph.add_edge(v2, v3, distance(&vertices[v2], &vertices[v3]));
-                graph.add_edge(v3, v1, distance(&vertices[v3], &vertices[v1]));
+            _ => attributes.update(&parts, line_number)?,
+        }
+    }
+
+    Ok(graph)
+}
+
+/// An alias of `parse_obj`, which already parses the file in a single pass and keeps nothing
+/// but the graph, the current line and the `vn` normals in memory.
+pub fn parse_obj_streaming(filename: &str) -> Result<Graph, RePathError> {
+    parse_obj(filename)
+}
+
+/// The state set by earlier lines of an OBJ file that applies to a face or line element.
+#[derive(Debug, Clone)]
+pub(crate) struct ObjElementAttributes {
+    /// Set by a `# directed` comment for the face or line element that follows it.
+    pub directed: bool,
+    /// Set by the last `g` line for all elements that follow it.
+    pub kind: EdgeKind,
+    /// Set by the last `usemtl` line for all elements that follow it.
+    pub clearance: Coord,
+    /// The name of the last `usemtl` line for all elements that follow it, unless it sets the
+    /// clearance.
+    pub labels: Vec<String>,
+}
+
+impl Default for ObjElementAttributes {
+    fn default() -> Self {
+        ObjElementAttributes {
+            directed: false,
+            kind: EdgeKind::Walk,
+            clearance: Coord::INFINITY,
+            labels: Vec::new(),
+        }
+    }
+}
+
+impl ObjElementAttributes {
+    /// Applies a comment, `g` or `usemtl` line, other lines are ignored.
+    pub(crate) fn update(&mut self, parts: &[&str], line_number: usize) -> Result<(), RePathError> {
+        match parts[0] {
+            "#" => self.directed = parts.get(1) == Some(&"directed"),
+            "g" => self.kind = parts.get(1).map_or(EdgeKind::Walk, |name| EdgeKind::from_group_name(name)),
+            "usemtl" => {
+                let material = parts.get(1).copied();
+                self.clearance = parse_clearance(material, line_number)?;
+                self.labels = material
+                    .filter(|material| !material.starts_with(CLEARANCE_MATERIAL_PREFIX))
+                    .map(|material| vec![material.to_string()])
+                    .unwrap_or_default();
             }
             _ => {}
         }
+        Ok(())
     }
+}
 
-    graph
+/// Parses a `v x y z [weight]` line into the node with the given id and group.
+pub(crate) fn parse_obj_vertex(
+    parts: &[&str],
+    id: usize,
+    group: Option<String>,
+    line_number: usize,
+) -> Result<Node, RePathError> {
+    let (x, y, z) = parse_obj_position(parts, line_number)?;
+    let weight = match parts.len() {
+        5 => parse_component::<Coord>(parts, 4, line_number)?,
+        8.. => parse_component::<Coord>(parts, 7, line_number)?,
+        _ => 1.0,
+    };
+    Ok(Node {
+        group,
+        ..Node::with_weight(id, x, y, z, weight)
+    })
+}
+
+/// Returns the name of an `o` or `g` line, or `None` if it has none.
+pub(crate) fn parse_obj_group(parts: &[&str]) -> Option<String> {
+    (parts.len() > 1).then(|| parts[1..].join(" "))
+}
+
+/// Parses the three components following the keyword of a `v` or `vn` line.
+pub(crate) fn parse_obj_position(parts: &[&str], line_number: usize) -> Result<(Coord, Coord, Coord), RePathError> {
+    Ok((
+        parse_component::<Coord>(parts, 1, line_number)?,
+        parse_component::<Coord>(parts, 2, line_number)?,
+        parse_component::<Coord>(parts, 3, line_number)?,
+    ))
+}
+
+/// Adds the edges of an `f` or `l` line to the graph, as described in `parse_obj`, with costs
+/// measured between the nodes of the graph. `vertex_count` is the number of vertices before the
+/// line, which limits the vertices it may refer to, and `normals` are the `vn` lines before it.
+pub(crate) fn add_obj_element(
+    graph: &mut Graph,
+    parts: &[&str],
+    attributes: &ObjElementAttributes,
+    vertex_count: usize,
+    normals: &[(Coord, Coord, Coord)],
+    line_number: usize,
+) -> Result<(), RePathError> {
+    let add_edge = |graph: &mut Graph, from: usize, to: usize| {
+        let (start, end) = (&graph.nodes[from], &graph.nodes[to]);
+        let cost = distance(&(start.x, start.y, start.z), &(end.x, end.y, end.z));
+        graph.push_edge(from, Edge {
+            to,
+            cost,
+            directed: attributes.directed,
+            kind: attributes.kind,
+            clearance: attributes.clearance,
+            labels: attributes.labels.clone(),
+        });
+    };
+
+    if parts[0] == "l" {
+        // Lines connect both ways unless they are marked as directed
+        let v1 = parse_vertex_index(parts, 1, vertex_count, line_number)?;
+        let v2 = parse_vertex_index(parts, 2, vertex_count, line_number)?;
+        add_edge(graph, v1, v2);
+        if !attributes.directed {
+            add_edge(graph, v2, v1);
+        }
+        return Ok(());
+    }
+
+    let v1 = parse_vertex_index(parts, 1, vertex_count, line_number)?;
+    let v2 = parse_vertex_index(parts, 2, vertex_count, line_number)?;
+    let v3 = parse_vertex_index(parts, 3, vertex_count, line_number)?;
+    for (index, vertex) in [(1, v1), (2, v2), (3, v3)] {
+        if let Some(normal) = parse_normal_index(parts, index, normals.len(), line_number)? {
+            graph.nodes[vertex].normal = Some(normals[normal]);
+        }
+    }
+    add_edge(graph, v1, v2);
+    add_edge(graph, v2, v3);
+    add_edge(graph, v3, v1);
+    Ok(())
+}
+
+/// Writes the graph as a Wavefront OBJ file that `parse_obj` reads back with the same nodes,
+/// including their weights, and edges. Edges forming a triangle `a -> b -> c -> a` are written as face lines `f a b c`, pairs
+/// of opposite edges as line elements `l a b`, and all other edges as line elements preceded by
+/// a `# directed` comment. Edges without a reverse are therefore read back as directed edges
+/// even if they were not marked as directed. Edges that are not `Walk` edges or have a limited
+/// clearance are written as line elements after the group of their kind, see
+/// `EdgeKind::group_name`, and the material of their clearance.
+///
+/// Edge costs, edge labels and vertex normals are not stored, `parse_obj` recomputes the costs
+/// as the distance between the nodes.
+pub fn write_obj(graph: &Graph, filename: &str) -> Result<(), RePathError> {
+    let mut writer = BufWriter::new(File::create(filename)?);
+
+    for node in &graph.nodes {
+        if node.weight == 1.0 {
+            writeln!(writer, "v {} {} {}", node.x, node.y, node.z)?;
+        } else {
+            writeln!(writer, "v {} {} {} {}", node.x, node.y, node.z, node.weight)?;
+        }
+    }
+
+    // Count the edges that are not directed so that every edge is written exactly once, either as
+    // part of a face or as a line element. Edges are told apart by their attributes as well, which
+    // apply to whole elements.
+    let mut remaining: HashMap<(usize, usize, EdgeAttributes), usize> = HashMap::new();
+    for (from, edges) in graph.edges.iter().enumerate() {
+        for edge in edges.iter().filter(|edge| !edge.directed) {
+            *remaining.entry((from, edge.to, EdgeAttributes::of(edge))).or_default() += 1;
+        }
+    }
+
+    for [a, b, c] in graph.triangles() {
+        // Both windings of the same three nodes may be present
+        for face in [[a, b, c], [a, c, b]] {
+            let sides = [
+                (face[0], face[1], EdgeAttributes::DEFAULT),
+                (face[1], face[2], EdgeAttributes::DEFAULT),
+                (face[2], face[0], EdgeAttributes::DEFAULT),
+            ];
+            if sides.iter().all(|side| remaining.get(side).is_some_and(|&count| count > 0)) {
+                for side in sides {
+                    if let Some(count) = remaining.get_mut(&side) {
+                        *count -= 1;
+                    }
+                }
+                writeln!(writer, "f {} {} {}", face[0] + 1, face[1] + 1, face[2] + 1)?;
+            }
+        }
+    }
+
+    // The default attributes come first, so they need neither a group nor a material line
+    let mut all_attributes: Vec<EdgeAttributes> = graph.edges.iter().flatten().map(EdgeAttributes::of).collect();
+    all_attributes.push(EdgeAttributes::DEFAULT);
+    all_attributes.sort_unstable_by(|a, b| {
+        (a != &EdgeAttributes::DEFAULT).cmp(&(b != &EdgeAttributes::DEFAULT)).then(a.cmp(b))
+    });
+    all_attributes.dedup();
+
+    let mut current = EdgeAttributes::DEFAULT;
+    for attributes in all_attributes {
+        if attributes.kind != current.kind {
+            writeln!(writer, "g {}", attributes.kind.group_name())?;
+        }
+        if attributes.clearance.to_bits() != current.clearance.to_bits() {
+            if attributes.clearance.is_finite() {
+                writeln!(writer, "usemtl {}{}", CLEARANCE_MATERIAL_PREFIX, attributes.clearance)?;
+            } else {
+                writeln!(writer, "usemtl default")?;
+            }
+        }
+        current = attributes;
+
+        for (from, edges) in graph.edges.iter().enumerate() {
+            for edge in edges.iter().filter(|edge| EdgeAttributes::of(edge) == attributes) {
+                if !edge.directed {
+                    let Some(count) = remaining.get_mut(&(from, edge.to, attributes)).filter(|count| **count > 0) else {
+                        continue;
+                    };
+                    *count -= 1;
+                    // A line element stands for the edge and its reverse
+                    if let Some(reverse) = remaining.get_mut(&(edge.to, from, attributes)).filter(|count| **count > 0) {
+                        *reverse -= 1;
+                        writeln!(writer, "l {} {}", from + 1, edge.to + 1)?;
+                        continue;
+                    }
+                }
+                writeln!(writer, "# directed")?;
+                writeln!(writer, "l {} {}", from + 1, edge.to + 1)?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// The attributes `write_obj` writes as group and material lines.
+#[derive(Debug, Clone, Copy)]
+struct EdgeAttributes {
+    kind: EdgeKind,
+    clearance: Coord,
+}
+
+impl EdgeAttributes {
+    const DEFAULT: EdgeAttributes = EdgeAttributes {
+        kind: EdgeKind::Walk,
+        clearance: Coord::INFINITY,
+    };
+
+    fn of(edge: &Edge) -> EdgeAttributes {
+        EdgeAttributes {
+            kind: edge.kind,
+            clearance: edge.clearance,
+        }
+    }
+
+    fn cmp(&self, other: &EdgeAttributes) -> std::cmp::Ordering {
+        let index = |kind: EdgeKind| EdgeKind::ALL.iter().position(|&other| other == kind);
+        index(self.kind)
+            .cmp(&index(other.kind))
+            .then(self.clearance.total_cmp(&other.clearance))
+    }
+}
+
+// Compared bitwise so that the attributes can be used as keys
+impl PartialEq for EdgeAttributes {
+    fn eq(&self, other: &Self) -> bool {
+        self.kind == other.kind && self.clearance.to_bits() == other.clearance.to_bits()
+    }
+}
+
+impl Eq for EdgeAttributes {}
+
+impl std::hash::Hash for EdgeAttributes {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.kind.hash(state);
+        self.clearance.to_bits().hash(state);
+    }
+}
+
+/// Parses the first mesh of a `.gltf` or `.glb` file, as exported by e.g. Godot or Bevy.
+/// Every triangle becomes three edges like an OBJ face in `parse_obj`. The vertices of all
+/// triangle primitives of the mesh become nodes in order, node transforms are not applied.
+pub fn parse_gltf(filename: &str) -> Result<Graph, RePathError> {
+    let gltf = gltf::Gltf::open(filename)?;
+    let base = std::path::Path::new(filename).parent();
+    let buffers = gltf::import_buffers(&gltf.document, base, gltf.blob.clone())?;
+
+    let mesh = gltf
+        .document
+        .meshes()
+        .next()
+        .ok_or_else(|| RePathError::InvalidNavmesh("The file does not contain a mesh".to_string()))?;
+
+    let mut graph = Graph::new();
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(|data| &data[..]));
+        let positions = reader.read_positions().ok_or_else(|| {
+            RePathError::InvalidNavmesh("A mesh primitive has no vertex positions".to_string())
+        })?;
+
+        let offset = graph.nodes.len();
+        for (index, [x, y, z]) in positions.enumerate() {
+            graph.add_node(Node::new(offset + index, x as Coord, y as Coord, z as Coord));
+        }
+        let vertex_count = graph.nodes.len() - offset;
+
+        // Primitives without indices list the vertices of each triangle in order
+        let indices: Vec<usize> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().map(|index| index as usize).collect(),
+            None => (0..vertex_count).collect(),
+        };
+
+        for triangle in indices.chunks_exact(3) {
+            if let Some(&index) = triangle.iter().find(|&&index| index >= vertex_count) {
+                return Err(RePathError::InvalidNavmesh(format!(
+                    "Vertex index {} is out of range",
+                    index
+                )));
+            }
+            let [v1, v2, v3] = [triangle[0] + offset, triangle[1] + offset, triangle[2] + offset];
+            graph.add_edge(v1, v2, graph.heuristic(v1, v2));
+            graph.add_edge(v2, v3, graph.heuristic(v2, v3));
+            graph.add_edge(v3, v1, graph.heuristic(v3, v1));
+        }
+    }
+
+    Ok(graph)
+}
+
+/// The first bytes of every binary navmesh file.
+const BINARY_MAGIC: &[u8; 4] = b"RPNV";
+
+/// The version of the binary navmesh format written by `save_graph_binary`.
+/// Increase it whenever the layout changes, older readers then reject the file.
+/// Version 1 files, which lack the edge flags, version 2 files, which lack the node weights,
+/// version 3 files, which lack the edge kinds, version 4 files, which lack the edge clearances,
+/// version 5 files, which always store `f32` values, and version 6 files, which lack the edge
+/// labels, are still read.
+const BINARY_VERSION: u32 = 7;
+
+/// The bit of the edge flags byte set for directed edges, see `Edge::directed`.
+const BINARY_EDGE_DIRECTED: u8 = 1;
+
+/// The offset of the bits of the edge flags byte holding the index of the edge kind in
+/// `EdgeKind::ALL`.
+const BINARY_EDGE_KIND_SHIFT: u8 = 1;
+
+/// Writes the graph in the compact binary navmesh format, which loads much faster than OBJ.
+/// The conventional extension is `.rpnav`, which `parse_navmesh` recognizes.
+///
+/// All values are little-endian: the magic `RPNV`, the format version as `u32`, the size of the
+/// floating point values in bytes as `u8`, which is 4 for `f32` and 8 for `f64` depending on
+/// `Coord`, and the node count as `u32` followed by the `x, y, z` coordinates and the weight of
+/// every node as floats, then for every node its edge count as `u32` followed by the target node
+/// id as `u32`, the cost as float and a flags byte of every edge. Bit 0 of the flags marks
+/// directed edges, bits 1 and 2 hold the index of the edge kind in `EdgeKind::ALL`, the other
+/// bits are 0. The flags are followed by the clearance as float, which is infinite for
+/// unrestricted edges, and the label count as `u32` followed by the length in bytes as `u32` and
+/// the UTF-8 bytes of every label. Node ids and normals are not stored, the nodes must be numbered by their
+/// index as `parse_obj` does. Files written with the `precision-f64` feature can be read without
+/// it, at the loss of the extra precision.
+pub fn save_graph_binary(graph: &Graph, filename: &str) -> Result<(), RePathError> {
+    let mut writer = BufWriter::new(File::create(filename)?);
+    writer.write_all(BINARY_MAGIC)?;
+    writer.write_all(&BINARY_VERSION.to_le_bytes())?;
+    writer.write_all(&[std::mem::size_of::<Coord>() as u8])?;
+
+    writer.write_all(&binary_count(graph.nodes.len())?)?;
+    for node in &graph.nodes {
+        for value in [node.x, node.y, node.z, node.weight] {
+            writer.write_all(&value.to_le_bytes())?;
+        }
+    }
+
+    for edges in &graph.edges {
+        writer.write_all(&binary_count(edges.len())?)?;
+        for edge in edges {
+            writer.write_all(&binary_count(edge.to)?)?;
+            writer.write_all(&edge.cost.to_le_bytes())?;
+            let kind = EdgeKind::ALL.iter().position(|&kind| kind == edge.kind).unwrap_or(0) as u8;
+            let mut flags = kind << BINARY_EDGE_KIND_SHIFT;
+            if edge.directed {
+                flags |= BINARY_EDGE_DIRECTED;
+            }
+            writer.write_all(&[flags])?;
+            writer.write_all(&edge.clearance.to_le_bytes())?;
+            writer.write_all(&binary_count(edge.labels.len())?)?;
+            for label in &edge.labels {
+                writer.write_all(&binary_count(label.len())?)?;
+                writer.write_all(label.as_bytes())?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a graph written by `save_graph_binary`.
+/// Returns `RePathError::InvalidNavmesh` if the file is not a binary navmesh, was written by a
+/// newer version of the format, is truncated or refers to nodes that do not exist.
+pub fn parse_graph_binary(filename: &str) -> Result<Graph, RePathError> {
+    read_graph_binary(BufReader::new(File::open(filename)?))
+}
+
+/// Reads a graph in the format of `save_graph_binary` from `reader`, e.g. a byte slice of a
+/// navmesh that is already in memory. The values are decoded one by one, see `read_graph_mmap`
+/// for a format that can be read in place. Returns the same errors as `parse_graph_binary`.
+pub fn read_graph_binary(mut reader: impl Read) -> Result<Graph, RePathError> {
+    let mut magic = [0; 4];
+    read_binary(&mut reader, &mut magic)?;
+    if &magic != BINARY_MAGIC {
+        return Err(RePathError::InvalidNavmesh(
+            "The file is not a binary navmesh".to_string(),
+        ));
+    }
+    let version = read_binary_u32(&mut reader)?;
+    if version == 0 || version > BINARY_VERSION {
+        return Err(RePathError::InvalidNavmesh(format!(
+            "Unsupported binary navmesh version {}",
+            version
+        )));
+    }
+    let mut float_size = [4];
+    if version >= 6 {
+        read_binary(&mut reader, &mut float_size)?;
+    }
+    let [float_size] = float_size;
+    if float_size != 4 && float_size != 8 {
+        return Err(RePathError::InvalidNavmesh(format!(
+            "Unsupported float size {}",
+            float_size
+        )));
+    }
+
+    let mut graph = Graph::new();
+    let node_count = read_binary_u32(&mut reader)? as usize;
+    for id in 0..node_count {
+        let x = read_binary_float(&mut reader, float_size)?;
+        let y = read_binary_float(&mut reader, float_size)?;
+        let z = read_binary_float(&mut reader, float_size)?;
+        let weight = if version >= 3 { read_binary_float(&mut reader, float_size)? } else { 1.0 };
+        graph.add_node(Node::with_weight(id, x, y, z, weight));
+    }
+
+    for from in 0..node_count {
+        let edge_count = read_binary_u32(&mut reader)?;
+        for _ in 0..edge_count {
+            let to = read_binary_u32(&mut reader)? as usize;
+            let cost = read_binary_float(&mut reader, float_size)?;
+            let mut flags = [0];
+            if version >= 2 {
+                read_binary(&mut reader, &mut flags)?;
+            }
+            let clearance = if version >= 5 { read_binary_float(&mut reader, float_size)? } else { Coord::INFINITY };
+            let label_count = if version >= 7 { read_binary_u32(&mut reader)? } else { 0 };
+            let labels = (0..label_count).map(|_| read_binary_string(&mut reader)).collect::<Result<_, _>>()?;
+            if to >= node_count {
+                return Err(RePathError::InvalidNavmesh(format!(
+                    "Node index {} is out of range",
+                    to
+                )));
+            }
+            graph.push_edge(from, Edge {
+                to,
+                cost,
+                directed: flags[0] & BINARY_EDGE_DIRECTED != 0,
+                kind: EdgeKind::ALL[usize::from(flags[0] >> BINARY_EDGE_KIND_SHIFT) & 3],
+                clearance,
+                labels,
+            });
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Encodes a count or node id as the `u32` stored in the binary navmesh format.
+fn binary_count(value: usize) -> Result<[u8; 4], RePathError> {
+    u32::try_from(value).map(u32::to_le_bytes).map_err(|_| {
+        RePathError::InvalidNavmesh(format!("{} does not fit the binary navmesh format", value))
+    })
+}
+
+fn read_binary(reader: &mut impl Read, buffer: &mut [u8]) -> Result<(), RePathError> {
+    reader.read_exact(buffer).map_err(|err| match err.kind() {
+        std::io::ErrorKind::UnexpectedEof => {
+            RePathError::InvalidNavmesh("The binary navmesh is truncated".to_string())
+        }
+        _ => RePathError::IoError(err),
+    })
+}
+
+fn read_binary_u32(reader: &mut impl Read) -> Result<u32, RePathError> {
+    let mut bytes = [0; 4];
+    read_binary(reader, &mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads a string of the binary navmesh format, stored as its length and its UTF-8 bytes.
+fn read_binary_string(reader: &mut impl Read) -> Result<String, RePathError> {
+    let length = read_binary_u32(reader)? as usize;
+    let mut bytes = Vec::new();
+    reader.take(length as u64).read_to_end(&mut bytes)?;
+    if bytes.len() < length {
+        return Err(RePathError::InvalidNavmesh("The binary navmesh is truncated".to_string()));
+    }
+    String::from_utf8(bytes).map_err(|_| RePathError::InvalidNavmesh("A label is not valid UTF-8".to_string()))
+}
+
+/// Reads a float of the binary navmesh format with the given size in bytes.
+fn read_binary_float(reader: &mut impl Read, size: u8) -> Result<Coord, RePathError> {
+    if size == 8 {
+        let mut bytes = [0; 8];
+        read_binary(reader, &mut bytes)?;
+        return Ok(f64::from_le_bytes(bytes) as Coord);
+    }
+    let mut bytes = [0; 4];
+    read_binary(reader, &mut bytes)?;
+    Ok(f32::from_le_bytes(bytes) as Coord)
+}
+
+/// The first bytes of every mapped navmesh file.
+const MAPPED_MAGIC: &[u8; 4] = b"RPMM";
+
+/// The version of the mapped navmesh format written by `save_graph_mmap`. Unlike
+/// `BINARY_VERSION` older versions are not read, rewrite the file with `save_graph_mmap` instead.
+const MAPPED_VERSION: u32 = 1;
+
+/// The header at the start of a mapped navmesh file, see `save_graph_mmap`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MappedHeader {
+    /// `RPMM`.
+    pub magic: [u8; 4],
+    pub version: u32,
+    pub node_count: u32,
+    pub edge_count: u32,
+    pub label_count: u32,
+    /// The total length of the UTF-8 bytes of all labels.
+    pub label_bytes: u32,
+}
+
+/// A node of a mapped navmesh file, see `save_graph_mmap`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MappedNode {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+    pub weight: f64,
+    /// The index of the first outgoing edge in the edge section.
+    pub first_edge: u32,
+    pub edge_count: u32,
+}
+
+/// An edge of a mapped navmesh file, see `save_graph_mmap`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MappedEdge {
+    pub cost: f64,
+    /// Infinite for unrestricted edges, see `Edge::clearance`.
+    pub clearance: f64,
+    pub to: u32,
+    /// The index of the first label in the label section.
+    pub first_label: u32,
+    pub label_count: u32,
+    /// The same flags as in the binary navmesh format: bit 0 marks directed edges, bits 1 and 2
+    /// hold the index of the edge kind in `EdgeKind::ALL`.
+    pub flags: u8,
+    /// Always 0, makes the padding before the next edge explicit.
+    pub padding: [u8; 3],
+}
+
+/// A label of a mapped navmesh file: the range of its UTF-8 bytes in the label bytes section.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MappedLabel {
+    pub offset: u32,
+    pub len: u32,
+}
+
+// The layout of the file, which must not change without increasing `MAPPED_VERSION`
+const _: () = assert!(std::mem::size_of::<MappedHeader>() == 24);
+const _: () = assert!(std::mem::size_of::<MappedNode>() == 40 && std::mem::align_of::<MappedNode>() == 8);
+const _: () = assert!(std::mem::size_of::<MappedEdge>() == 32 && std::mem::align_of::<MappedEdge>() == 8);
+const _: () = assert!(std::mem::size_of::<MappedLabel>() == 8);
+
+// SAFETY: the structs are `repr(C)` and made of integers, floats and byte arrays only, for which
+// every bit pattern is valid. They have no padding bytes: the fields are ordered by alignment,
+// the sizes above are the sums of the field sizes, and `MappedEdge` pads itself with a field.
+unsafe impl bytemuck::Zeroable for MappedHeader {}
+unsafe impl bytemuck::Pod for MappedHeader {}
+unsafe impl bytemuck::Zeroable for MappedNode {}
+unsafe impl bytemuck::Pod for MappedNode {}
+unsafe impl bytemuck::Zeroable for MappedEdge {}
+unsafe impl bytemuck::Pod for MappedEdge {}
+unsafe impl bytemuck::Zeroable for MappedLabel {}
+unsafe impl bytemuck::Pod for MappedLabel {}
+
+/// Writes the graph in the mapped navmesh format, which `parse_graph_mmap` reads by mapping the
+/// file into memory and viewing its sections as slices of the records, without decoding the
+/// values one by one as `parse_graph_binary` does. The conventional extension is `.rpmap`, which
+/// `parse_navmesh` recognizes. Files are about twice the size of `.rpnav` files.
+///
+/// The file is a `MappedHeader` followed by the sections `[MappedNode; node_count]`,
+/// `[MappedEdge; edge_count]`, `[MappedLabel; label_count]` and the label bytes. All values are
+/// little-endian, and floats are `f64` whatever `Coord` is. The edges of every node are stored
+/// one after another in node order. Node ids and normals are not stored, the nodes must be
+/// numbered by their index as `parse_obj` does.
+// The casts are no-ops with the `precision-f64` feature
+#[allow(clippy::unnecessary_cast)]
+pub fn save_graph_mmap(graph: &Graph, filename: &str) -> Result<(), RePathError> {
+    let edge_count: usize = graph.edges.iter().map(Vec::len).sum();
+    let labels = graph.edges.iter().flatten().flat_map(|edge| &edge.labels);
+    let header = MappedHeader {
+        magic: *MAPPED_MAGIC,
+        version: MAPPED_VERSION,
+        node_count: mapped_index(graph.nodes.len())?,
+        edge_count: mapped_index(edge_count)?,
+        label_count: mapped_index(labels.clone().count())?,
+        label_bytes: mapped_index(labels.map(String::len).sum())?,
+    };
+
+    let mut writer = BufWriter::new(File::create(filename)?);
+    write_mapped(&mut writer, &header)?;
+    let mut first_edge = 0;
+    for (node, edges) in graph.nodes.iter().zip(&graph.edges) {
+        write_mapped(&mut writer, &MappedNode {
+            x: node.x as f64,
+            y: node.y as f64,
+            z: node.z as f64,
+            weight: node.weight as f64,
+            first_edge: mapped_index(first_edge)?,
+            edge_count: mapped_index(edges.len())?,
+        })?;
+        first_edge += edges.len();
+    }
+
+    let mut first_label = 0;
+    for edge in graph.edges.iter().flatten() {
+        let kind = EdgeKind::ALL.iter().position(|&kind| kind == edge.kind).unwrap_or(0) as u8;
+        write_mapped(&mut writer, &MappedEdge {
+            cost: edge.cost as f64,
+            clearance: edge.clearance as f64,
+            to: mapped_index(edge.to)?,
+            first_label: mapped_index(first_label)?,
+            label_count: mapped_index(edge.labels.len())?,
+            flags: (kind << BINARY_EDGE_KIND_SHIFT) | (u8::from(edge.directed) * BINARY_EDGE_DIRECTED),
+            padding: [0; 3],
+        })?;
+        first_label += edge.labels.len();
+    }
+
+    let mut offset = 0;
+    for label in graph.edges.iter().flatten().flat_map(|edge| &edge.labels) {
+        write_mapped(&mut writer, &MappedLabel { offset: mapped_index(offset)?, len: mapped_index(label.len())? })?;
+        offset += label.len();
+    }
+    for label in graph.edges.iter().flatten().flat_map(|edge| &edge.labels) {
+        writer.write_all(label.as_bytes())?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Reads a graph written by `save_graph_mmap` by mapping the file into memory, see
+/// `read_graph_mmap`. Other than `parse_graph_binary` the file is neither copied into a buffer
+/// nor decoded value by value, only the `Graph` itself is built from the mapped records. On
+/// targets other than Unix the file is read into an aligned buffer instead.
+///
+/// The file must not be truncated or written to while it is parsed: the mapping is only held
+/// during this call, but the operating system does not protect it against other processes, so
+/// a concurrent change may be read as a corrupt navmesh, and reading a page that was truncated
+/// away terminates the process with `SIGBUS`. Replace navmesh files by renaming a new file over
+/// them instead of rewriting them in place.
+/// Returns `RePathError::InvalidNavmesh` for the same reasons as `read_graph_mmap`.
+pub fn parse_graph_mmap(filename: &str) -> Result<Graph, RePathError> {
+    let file = File::open(filename)?;
+    let len = usize::try_from(file.metadata()?.len())
+        .map_err(|_| RePathError::InvalidNavmesh("The mapped navmesh is too large".to_string()))?;
+    if len == 0 {
+        // Empty files cannot be mapped
+        return read_graph_mmap(&[]);
+    }
+
+    #[cfg(unix)]
+    {
+        let mapping = FileMapping::new(&file, len)?;
+        read_graph_mmap(mapping.bytes())
+    }
+    #[cfg(not(unix))]
+    {
+        let mut words = vec![0u64; len.div_ceil(8)];
+        let bytes = &mut bytemuck::cast_slice_mut::<u64, u8>(&mut words)[..len];
+        { file }.read_exact(bytes)?;
+        read_graph_mmap(bytes)
+    }
+}
+
+/// Reads a graph in the format of `save_graph_mmap` from `bytes`, e.g. a memory-mapped file.
+/// The sections are viewed in place as slices of `MappedNode`, `MappedEdge` and `MappedLabel`
+/// if `bytes` starts at an address aligned to 8 bytes, as mapped memory always does, and are
+/// copied record by record otherwise.
+/// Returns `RePathError::InvalidNavmesh` if the bytes are not a mapped navmesh, were written by
+/// another version of the format, are truncated, refer to edges, labels or nodes that do not
+/// exist or hold labels that are not UTF-8, and on big-endian targets, which cannot view the
+/// little-endian records in place.
+pub fn read_graph_mmap(bytes: &[u8]) -> Result<Graph, RePathError> {
+    if cfg!(target_endian = "big") {
+        return Err(RePathError::InvalidNavmesh(
+            "Mapped navmeshes can only be read on little-endian targets".to_string(),
+        ));
+    }
+    let mut offset = 0;
+    let header: MappedHeader = mapped_section(bytes, &mut offset, 1)?[0];
+    if &header.magic != MAPPED_MAGIC {
+        return Err(RePathError::InvalidNavmesh("The file is not a mapped navmesh".to_string()));
+    }
+    if header.version != MAPPED_VERSION {
+        return Err(RePathError::InvalidNavmesh(format!(
+            "Unsupported mapped navmesh version {}",
+            header.version
+        )));
+    }
+    let nodes: Cow<[MappedNode]> = mapped_section(bytes, &mut offset, header.node_count as usize)?;
+    let edges: Cow<[MappedEdge]> = mapped_section(bytes, &mut offset, header.edge_count as usize)?;
+    let labels: Cow<[MappedLabel]> = mapped_section(bytes, &mut offset, header.label_count as usize)?;
+    let label_bytes = bytes
+        .get(offset..offset + header.label_bytes as usize)
+        .ok_or_else(|| RePathError::InvalidNavmesh("The mapped navmesh is truncated".to_string()))?;
+
+    let mut graph = Graph::new();
+    for (id, node) in nodes.iter().enumerate() {
+        graph.add_node(Node::with_weight(id, node.x as Coord, node.y as Coord, node.z as Coord, node.weight as Coord));
+    }
+    for (from, node) in nodes.iter().enumerate() {
+        let node_edges = mapped_range(&edges, node.first_edge, node.edge_count, "Edge")?;
+        for edge in node_edges {
+            if edge.to >= header.node_count {
+                return Err(RePathError::InvalidNavmesh(format!(
+                    "Node index {} is out of range",
+                    edge.to
+                )));
+            }
+            let labels = mapped_range(&labels, edge.first_label, edge.label_count, "Label")?
+                .iter()
+                .map(|label| {
+                    let bytes = mapped_range(label_bytes, label.offset, label.len, "Label byte")?;
+                    std::str::from_utf8(bytes)
+                        .map(str::to_string)
+                        .map_err(|_| RePathError::InvalidNavmesh("A label is not valid UTF-8".to_string()))
+                })
+                .collect::<Result<_, _>>()?;
+            graph.push_edge(from, Edge {
+                to: edge.to as usize,
+                cost: edge.cost as Coord,
+                directed: edge.flags & BINARY_EDGE_DIRECTED != 0,
+                kind: EdgeKind::ALL[usize::from(edge.flags >> BINARY_EDGE_KIND_SHIFT) & 3],
+                clearance: edge.clearance as Coord,
+                labels,
+            });
+        }
+    }
+
+    Ok(graph)
+}
+
+/// Converts a count or index to the `u32` stored in the mapped navmesh format.
+fn mapped_index(value: usize) -> Result<u32, RePathError> {
+    u32::try_from(value).map_err(|_| {
+        RePathError::InvalidNavmesh(format!("{} does not fit the mapped navmesh format", value))
+    })
+}
+
+fn write_mapped(writer: &mut impl Write, record: &impl bytemuck::Pod) -> Result<(), RePathError> {
+    writer.write_all(bytemuck::bytes_of(record))?;
+    Ok(())
+}
+
+/// The next `count` records of a mapped navmesh starting at `offset`, which is moved past them.
+/// Borrowed from `bytes` if they are aligned, see `read_graph_mmap`.
+fn mapped_section<'a, T: bytemuck::Pod>(
+    bytes: &'a [u8],
+    offset: &mut usize,
+    count: usize,
+) -> Result<Cow<'a, [T]>, RePathError> {
+    let section = count
+        .checked_mul(std::mem::size_of::<T>())
+        .and_then(|len| bytes.get(*offset..offset.checked_add(len)?))
+        .ok_or_else(|| RePathError::InvalidNavmesh("The mapped navmesh is truncated".to_string()))?;
+    *offset += section.len();
+    Ok(match bytemuck::try_cast_slice(section) {
+        Ok(records) => Cow::Borrowed(records),
+        Err(_) => Cow::Owned(
+            section.chunks_exact(std::mem::size_of::<T>()).map(bytemuck::pod_read_unaligned).collect(),
+        ),
+    })
+}
+
+/// The `count` items of `items` starting at `first`, as referred to by a mapped record.
+fn mapped_range<'a, T>(items: &'a [T], first: u32, count: u32, what: &str) -> Result<&'a [T], RePathError> {
+    let (first, count) = (first as usize, count as usize);
+    items.get(first..first + count).ok_or_else(|| {
+        RePathError::InvalidNavmesh(format!("{} range {}..{} is out of range", what, first, first + count))
+    })
+}
+
+/// A read-only memory map of a whole file, unmapped when dropped.
+#[cfg(unix)]
+struct FileMapping {
+    address: *mut libc::c_void,
+    len: usize,
+}
+
+#[cfg(unix)]
+impl FileMapping {
+    /// Maps the first `len` bytes of the file, which must not be 0.
+    fn new(file: &File, len: usize) -> Result<Self, RePathError> {
+        use std::os::unix::io::AsRawFd;
+        // SAFETY: mapping an open file read-only at an address chosen by the kernel does not
+        // touch any memory of the process, the result is checked for failure below
+        let address = unsafe {
+            libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_PRIVATE, file.as_raw_fd(), 0)
+        };
+        if address == libc::MAP_FAILED {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(Self { address, len })
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: the mapping spans `len` readable bytes until `self` is dropped. Its contents
+        // may change if another process writes to the file, which `parse_graph_mmap` documents
+        // and `read_graph_mmap` tolerates, since it validates every record it reads.
+        unsafe { std::slice::from_raw_parts(self.address.cast::<u8>(), self.len) }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for FileMapping {
+    fn drop(&mut self) {
+        // SAFETY: the address and length are those of a mapping created by `new`, and the slices
+        // returned by `bytes` cannot outlive `self`
+        unsafe {
+            libc::munmap(self.address, self.len);
+        }
+    }
+}
+
+/// Parses the component at `index` of an OBJ line.
+fn parse_component<T: std::str::FromStr>(
+    parts: &[&str],
+    index: usize,
+    line: usize,
+) -> Result<T, RePathError> {
+    let part = parts.get(index).ok_or_else(|| RePathError::ParseError {
+        line,
+        detail: format!("Expected at least {} components", index),
+    })?;
+
+    part.parse().map_err(|_| RePathError::ParseError {
+        line,
+        detail: format!("Invalid value '{}'", part),
+    })
+}
+
+/// Parses the clearance of a `usemtl clearance_<radius>` line, other materials do not restrict
+/// the clearance.
+fn parse_clearance(material: Option<&str>, line: usize) -> Result<Coord, RePathError> {
+    let Some(radius) = material.and_then(|material| material.strip_prefix(CLEARANCE_MATERIAL_PREFIX)) else {
+        return Ok(Coord::INFINITY);
+    };
+    radius
+        .parse()
+        .ok()
+        .filter(|radius: &Coord| *radius >= 0.0)
+        .ok_or_else(|| RePathError::ParseError {
+            line,
+            detail: format!("Invalid clearance '{}'", radius),
+        })
+}
+
+/// Parses a 1-based OBJ vertex reference of a face and converts it to a 0-based node id.
+/// References of the form `v/vt/vn` are accepted, see `parse_face_index`.
+pub(crate) fn parse_vertex_index(
+    parts: &[&str],
+    index: usize,
+    vertex_count: usize,
+    line: usize,
+) -> Result<usize, RePathError> {
+    let part = parts.get(index).ok_or_else(|| RePathError::ParseError {
+        line,
+        detail: format!("Expected at least {} components", index),
+    })?;
+    let vertex = parse_face_index(part, line)?;
+    if vertex == 0 || vertex > vertex_count {
+        return Err(RePathError::ParseError {
+            line,
+            detail: format!("Vertex index {} is out of range", vertex),
+        });
+    }
+    Ok(vertex - 1)
+}
+
+/// Parses the 1-based vertex index of a face vertex reference in any of the OBJ formats `v`,
+/// `v/vt`, `v//vn` and `v/vt/vn`, as exported e.g. by Blender and Maya. The vertex index is the
+/// component before the first `/`, the others are ignored.
+fn parse_face_index(part: &str, line: usize) -> Result<usize, RePathError> {
+    let vertex = part.split('/').next().unwrap_or(part);
+    parse_component::<usize>(&[vertex], 0, line)
 }
 
-pub fn distance(p1: &(f32, f32, f32), p2: &(f32, f32, f32)) -> f32 {
+/// Parses the normal of a face vertex reference `v//vn` or `v/vt/vn` and converts it to a 0-based
+/// index into the `vn` lines. Returns `None` if the reference has no normal.
+fn parse_normal_index(
+    parts: &[&str],
+    index: usize,
+    normal_count: usize,
+    line: usize,
+) -> Result<Option<usize>, RePathError> {
+    let Some(normal) = parts.get(index).and_then(|part| part.split('/').nth(2)).filter(|normal| !normal.is_empty()) else {
+        return Ok(None);
+    };
+    let normal = parse_component::<usize>(&[normal], 0, line)?;
+    if normal == 0 || normal > normal_count {
+        return Err(RePathError::ParseError {
+            line,
+            detail: format!("Normal index {} is out of range", normal),
+        });
+    }
+    Ok(Some(normal - 1))
+}
+
+pub fn distance(p1: &(Coord, Coord, Coord), p2: &(Coord, Coord, Coord)) -> Coord {
     let dx = p1.0 - p2.0;
     let dy = p1.1 - p2.1;
     let dz = p1.2 - p2.2;
     (dx * dx + dy * dy + dz * dz).sqrt()
 }
 
-pub fn nodes_within_radius(graph: &Graph, node: &Node, radius: f32) -> Vec<usize> {
+/// Like `distance`, but subtracts and squares the three components in one SSE instruction each
+/// on x86_64. Other targets use the scalar code.
+#[cfg(feature = "simd")]
+pub fn distance_simd(p1: (f32, f32, f32), p2: (f32, f32, f32)) -> f32 {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::*;
+        // SAFETY: SSE is part of every x86_64 target
+        unsafe {
+            let difference = _mm_sub_ps(_mm_setr_ps(p1.0, p1.1, p1.2, 0.0), _mm_setr_ps(p2.0, p2.1, p2.2, 0.0));
+            let squares = _mm_mul_ps(difference, difference);
+            // Adds lanes 2 and 3 to lanes 0 and 1, then lane 1 to lane 0
+            let pairs = _mm_add_ps(squares, _mm_movehl_ps(squares, squares));
+            let sum = _mm_add_ss(pairs, _mm_shuffle_ps::<0b01>(pairs, pairs));
+            _mm_cvtss_f32(_mm_sqrt_ss(sum))
+        }
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        let (dx, dy, dz) = (p1.0 - p2.0, p1.1 - p2.1, p1.2 - p2.2);
+        (dx * dx + dy * dy + dz * dz).sqrt()
+    }
+}
+
+pub fn nodes_within_radius(graph: &Graph, node: &Node, radius: Coord) -> Vec<usize> {
     graph
         .nodes
         .iter()
@@ -67,36 +1069,76 @@ pub fn nodes_within_radius(graph: &Graph, node: &Node, radius: f32) -> Vec<usize
         .collect()
 }
 
+/// Appends a row with the results of a benchmark run to a CSV file, writing the header first if
+/// the file does not exist yet.
 pub fn save_metrics_to_csv(
     filename: &str,
     metrics: &Metrics,
 ) -> Result<(), Box<dyn std::error::Error>> {
+    let header = [
+        "navmesh_filename",
+        "use_precomputed_cache",
+        "precompute_radius",
+        "total_paths_precomputed",
+        "total_precompute_pairs",
+        "precomputation_time",
+        "pathfinding_time",
+    ];
+    let row = [
+        metrics.settings.navmesh_filename.clone(),
+        metrics.settings.use_precomputed_cache.to_string(),
+        metrics.settings.precompute_radius.to_string(),
+        metrics.total_paths_precomputed.to_string(),
+        metrics.settings.total_precompute_pairs.to_string(),
+        metrics.precomputation_time.to_string(),
+        metrics.pathfinding_time.to_string(),
+    ];
+    append_csv_row(filename, &header, &row)?;
+    Ok(())
+}
+
+/// Appends a row with the timing of a single pathfinding call to a CSV file, writing the header
+/// first if the file does not exist yet. The columns are `timestamp` in milliseconds since the
+/// Unix epoch, `start_x`, `start_y`, `start_z`, `end_x`, `end_y`, `end_z`, `path_len` in nodes,
+/// `duration_us` in microseconds and `cache_hit`.
+pub fn append_path_metric_to_csv(filename: &str, metric: &PathMetric) -> Result<(), RePathError> {
+    let header = [
+        "timestamp",
+        "start_x",
+        "start_y",
+        "start_z",
+        "end_x",
+        "end_y",
+        "end_z",
+        "path_len",
+        "duration_us",
+        "cache_hit",
+    ];
+    let row = [
+        metric.timestamp.to_string(),
+        metric.start.0.to_string(),
+        metric.start.1.to_string(),
+        metric.start.2.to_string(),
+        metric.end.0.to_string(),
+        metric.end.1.to_string(),
+        metric.end.2.to_string(),
+        metric.path_len.to_string(),
+        metric.duration.as_micros().to_string(),
+        metric.cache_hit.to_string(),
+    ];
+    append_csv_row(filename, &header, &row)
+}
+
+fn append_csv_row(filename: &str, header: &[&str], row: &[String]) -> Result<(), RePathError> {
     let file_exists = std::path::Path::new(filename).exists();
     let mut wtr = csv::WriterBuilder::new()
-        .has_headers(!file_exists)
+        .has_headers(false)
         .from_writer(OpenOptions::new().create(true).append(true).open(filename)?);
 
     if !file_exists {
-        wtr.write_record(&[
-            "navmesh_filename",
-            "use_precomputed_cache",
-            "precompute_radius",
-            "total_paths_precomputed",
-            "total_precompute_pairs",
-            "precomputation_time",
-            "pathfinding_time",
-        ])?;
-    }
-
-    wtr.write_record(&[
-        &metrics.settings.navmesh_filename,
-        &metrics.settings.use_precomputed_cache.to_string(),
-        &metrics.settings.precompute_radius.to_string(),
-        &metrics.total_paths_precomputed.to_string(),
-        &metrics.settings.total_precompute_pairs.to_string(),
-        &metrics.precomputation_time.to_string(),
-        &metrics.pathfinding_time.to_string(),
-    ])?;
+        wtr.write_record(header)?;
+    }
+    wtr.write_record(row)?;
     wtr.flush()?;
     Ok(())
 }
@@ -0,0 +1,76 @@
+use std::fmt;
+use crate::graph::Graph;
+use crate::Coord;
+
+/// An anomaly of a graph found by `Graph::validate`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationError {
+    /// The node has an edge to itself, which never helps a path.
+    SelfLoop(usize),
+
+    /// There is more than one edge from the first node to the second, searches use the cheapest.
+    DuplicateEdge(usize, usize),
+
+    /// The edge from the first node to the second has a negative cost, which breaks the
+    /// guarantees of A* and Dijkstra's algorithm.
+    NegativeCost(usize, usize, Coord),
+
+    /// The edge from the first node leads to a node id that does not exist.
+    DanglingEdge(usize, usize),
+}
+
+impl ValidationError {
+    /// Returns whether searches on a graph with this anomaly can panic or return wrong paths.
+    /// `RePathfinder::new` rejects such graphs and only warns about the others.
+    pub fn is_fatal(&self) -> bool {
+        matches!(self, ValidationError::NegativeCost(..) | ValidationError::DanglingEdge(..))
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::SelfLoop(node) => write!(f, "Node {} has an edge to itself", node),
+            ValidationError::DuplicateEdge(from, to) => {
+                write!(f, "Node {} has several edges to node {}", from, to)
+            }
+            ValidationError::NegativeCost(from, to, cost) => {
+                write!(f, "The edge from {} to {} has the negative cost {}", from, to, cost)
+            }
+            ValidationError::DanglingEdge(from, to) => {
+                write!(f, "Node {} has an edge to the missing node {}", from, to)
+            }
+        }
+    }
+}
+
+impl Graph {
+    /// Checks the graph for anomalies, e.g. after loading it or building it with `add_edge`.
+    /// Returns an empty list for a valid graph, otherwise the issues ordered by the node they
+    /// start at. Several edges between the same nodes are reported once.
+    pub fn validate(&self) -> Vec<ValidationError> {
+        let node_count = self.nodes.len();
+        let mut errors = Vec::new();
+
+        for (from, edges) in self.edges.iter().enumerate() {
+            let mut targets: Vec<usize> = Vec::with_capacity(edges.len());
+            for edge in edges {
+                if edge.to >= node_count {
+                    errors.push(ValidationError::DanglingEdge(from, edge.to));
+                } else if edge.to == from {
+                    errors.push(ValidationError::SelfLoop(from));
+                }
+                if edge.cost < 0.0 {
+                    errors.push(ValidationError::NegativeCost(from, edge.to, edge.cost));
+                }
+                targets.push(edge.to);
+            }
+
+            targets.sort_unstable();
+            for duplicates in targets.chunk_by(|a, b| a == b).filter(|group| group.len() > 1) {
+                errors.push(ValidationError::DuplicateEdge(from, duplicates[0]));
+            }
+        }
+        errors
+    }
+}
@@ -0,0 +1,49 @@
+#![cfg(feature = "async")]
+
+use repath::settings::RePathSettings;
+use repath::utils::parse_obj;
+use repath::{Coord, RePathfinder};
+
+/// Creates a pathfinder for the bundled fully connected navmesh with little precomputation.
+fn create_pathfinder() -> RePathfinder {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        precompute_radius: 100.0,
+        total_precompute_pairs: 10,
+        use_precomputed_cache: true,
+        ..RePathSettings::default()
+    };
+    RePathfinder::new(settings).expect("Failed to create pathfinder")
+}
+
+/// Returns the coordinates of the given nodes of the bundled navmesh.
+fn node_coords(ids: &[usize]) -> Vec<(Coord, Coord, Coord)> {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    ids.iter()
+        .map(|&id| (graph.nodes[id].x, graph.nodes[id].y, graph.nodes[id].z))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_find_path_async_matches_find_path() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+
+    let path = pathfinder
+        .find_path_async(coords[0], coords[1])
+        .await
+        .expect("No path found");
+    assert_eq!(path, pathfinder.find_path(coords[0], coords[1]).unwrap());
+}
+
+#[tokio::test]
+async fn test_find_path_multithreaded_async_matches_find_path_multithreaded() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+
+    let path = pathfinder
+        .find_path_multithreaded_async(coords[0], coords[1], 3)
+        .await
+        .expect("No path found");
+    assert_eq!(path, pathfinder.find_path_multithreaded(coords[0], coords[1], 3).unwrap());
+}
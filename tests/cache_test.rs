@@ -0,0 +1,223 @@
+use std::sync::Arc;
+use repath::cache::{DiskPathCache, LruPathCache, PathCache};
+use repath::error::RePathError;
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::path::Path;
+use repath::Coord;
+
+fn path_to(id: usize) -> Option<Path> {
+    Some(Arc::new(vec![Node::new(id, id as Coord, 0.0, 0.0)]))
+}
+
+/// A line of `nodes` nodes one unit apart.
+fn line_graph(nodes: usize) -> Graph {
+    let mut graph = Graph::new();
+    for id in 0..nodes {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for id in 1..nodes {
+        graph.add_edge(id - 1, id, 1.0);
+    }
+    graph
+}
+
+#[test]
+fn test_lru_evicts_least_recently_used() {
+    let cache = LruPathCache::new(2);
+    cache.insert_path((0, 1), path_to(1));
+    cache.insert_path((0, 2), path_to(2));
+
+    // Using (0, 1) makes (0, 2) the least recently used entry
+    assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+    cache.insert_path((0, 3), None);
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get_path(&(0, 2)), None);
+    assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+    assert_eq!(cache.get_path(&(0, 3)), Some(None));
+}
+
+#[test]
+fn test_lru_replacing_entry_does_not_evict() {
+    let cache = LruPathCache::new(2);
+    cache.insert_path((0, 1), None);
+    cache.insert_path((0, 2), path_to(2));
+    cache.insert_path((0, 1), path_to(1));
+
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+    assert_eq!(cache.get_path(&(0, 2)), Some(path_to(2)));
+}
+
+#[test]
+fn test_lru_capacity_is_respected_across_shards() {
+    let cache = LruPathCache::new(5000);
+    assert_eq!(cache.capacity(), 5000);
+    for id in 0..20000 {
+        cache.insert_path((id, id + 1), None);
+    }
+    assert!(cache.len() <= 5000);
+    assert!(cache.len() > 4000);
+}
+
+#[test]
+fn test_lru_zero_capacity_disables_caching() {
+    let cache = LruPathCache::new(0);
+    cache.insert_path((0, 1), path_to(1));
+    assert!(cache.is_empty());
+    assert_eq!(cache.get_path(&(0, 1)), None);
+}
+
+#[test]
+fn test_lru_hit_rate() {
+    let cache = LruPathCache::new(10);
+    assert_eq!(cache.hit_rate(), 0.0);
+
+    cache.insert_path((0, 1), path_to(1));
+    cache.get_path(&(0, 1));
+    cache.get_path(&(0, 1));
+    cache.get_path(&(0, 1));
+    cache.get_path(&(1, 0));
+    assert_eq!(cache.hit_rate(), 0.75);
+
+    cache.reset_stats();
+    assert_eq!(cache.hit_rate(), 0.0);
+}
+
+#[test]
+fn test_lru_retain_and_clear() {
+    let cache = LruPathCache::new(10);
+    for id in 0..5 {
+        cache.insert_path((0, id), path_to(id));
+    }
+
+    cache.retain(|&(_, goal), _| goal % 2 == 0);
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.get_path(&(0, 1)), None);
+    assert_eq!(cache.get_path(&(0, 2)), Some(path_to(2)));
+
+    // Removed entries no longer take part in eviction
+    for id in 10..17 {
+        cache.insert_path((0, id), None);
+    }
+    assert_eq!(cache.len(), 10);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    cache.insert_path((0, 1), path_to(1));
+    assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+}
+
+#[test]
+fn test_lru_thread_local_layer() {
+    let cache = LruPathCache::new(10).with_thread_local_capacity(2);
+    cache.insert_path((0, 1), path_to(1));
+    assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+
+    // Other threads see the shared results, and their lookups count as well
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+            assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+            cache.insert_path((0, 2), path_to(2));
+        });
+    });
+    assert_eq!(cache.get_path(&(0, 2)), Some(path_to(2)));
+    assert_eq!(cache.hits(), 4);
+
+    // Removing or replacing results outdates the copies of all threads
+    cache.retain(|&(_, goal), _| goal != 1);
+    assert_eq!(cache.get_path(&(0, 1)), None);
+    cache.insert_path((0, 2), None);
+    assert_eq!(cache.get_path(&(0, 2)), Some(None));
+    cache.clear();
+    assert_eq!(cache.get_path(&(0, 2)), None);
+
+    // A full layer starts over, which does not lose results of the shared cache
+    for id in 0..5 {
+        cache.insert_path((1, id), path_to(id));
+    }
+    assert!((0..5).all(|id| cache.get_path(&(1, id)) == Some(path_to(id))));
+}
+
+#[test]
+fn test_disk_cache_survives_reopening() {
+    let file = std::env::temp_dir().join("repath_disk_cache.rpdc");
+    let _ = std::fs::remove_file(&file);
+    let graph = line_graph(10);
+
+    let cache = DiskPathCache::open(&file, &graph).expect("Failed to create disk cache");
+    assert!(cache.is_empty());
+    cache.insert_path((0, 1), path_to(1));
+    cache.insert_path((0, 2), path_to(2));
+    cache.insert_path((0, 3), None);
+    cache.insert_path((0, 2), path_to(4));
+    assert_eq!(cache.get_path(&(0, 2)), Some(path_to(4)));
+    drop(cache);
+
+    let cache = DiskPathCache::open(&file, &graph).expect("Failed to reopen disk cache");
+    assert_eq!(cache.len(), 3);
+    assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+    assert_eq!(cache.get_path(&(0, 2)), Some(path_to(4)));
+    assert_eq!(cache.get_path(&(0, 3)), Some(None));
+    assert_eq!(cache.get_path(&(1, 0)), None);
+
+    // Removals are stored as well
+    cache.retain(|&(_, goal), _| goal != 1);
+    drop(cache);
+    let cache = DiskPathCache::open(&file, &graph).unwrap();
+    assert!(!cache.contains(&(0, 1)));
+    assert_eq!(cache.len(), 2);
+
+    cache.clear();
+    assert!(cache.is_empty());
+    cache.insert_path((5, 6), path_to(6));
+    drop(cache);
+    let cache = DiskPathCache::open(&file, &graph).unwrap();
+    assert_eq!(cache.len(), 1);
+    assert_eq!(cache.get_path(&(5, 6)), Some(path_to(6)));
+}
+
+#[test]
+fn test_disk_cache_drops_incomplete_record() {
+    let file = std::env::temp_dir().join("repath_disk_cache_incomplete.rpdc");
+    let _ = std::fs::remove_file(&file);
+    let graph = line_graph(10);
+    let cache = DiskPathCache::open(&file, &graph).unwrap();
+    cache.insert_path((0, 1), path_to(1));
+    cache.insert_path((0, 2), path_to(2));
+    drop(cache);
+
+    // A crash while the last record was written
+    let len = std::fs::metadata(&file).unwrap().len();
+    std::fs::OpenOptions::new().write(true).open(&file).unwrap().set_len(len - 3).unwrap();
+    let cache = DiskPathCache::open(&file, &graph).expect("Failed to reopen disk cache");
+    assert_eq!(cache.get_path(&(0, 1)), Some(path_to(1)));
+    assert_eq!(cache.get_path(&(0, 2)), None);
+    cache.insert_path((0, 3), path_to(3));
+    drop(cache);
+    assert_eq!(DiskPathCache::open(&file, &graph).unwrap().get_path(&(0, 3)), Some(path_to(3)));
+}
+
+#[test]
+fn test_disk_cache_rejects_other_navmesh() {
+    let file = std::env::temp_dir().join("repath_disk_cache_other.rpdc");
+    let _ = std::fs::remove_file(&file);
+    let graph = line_graph(10);
+    DiskPathCache::open(&file, &graph).unwrap().insert_path((0, 9), path_to(9));
+    assert_eq!(DiskPathCache::open(&file, &graph).unwrap().len(), 1);
+
+    assert!(matches!(DiskPathCache::open(&file, &line_graph(20)), Err(RePathError::SerializationError(_))));
+    // Same number of nodes, but another cost or position makes other paths the shortest
+    let mut other_cost = line_graph(10);
+    other_cost.edges[4][0].cost = 3.0;
+    assert!(matches!(DiskPathCache::open(&file, &other_cost), Err(RePathError::SerializationError(_))));
+    let mut other_position = line_graph(10);
+    other_position.nodes[9].y = 1.0;
+    assert!(matches!(DiskPathCache::open(&file, &other_position), Err(RePathError::SerializationError(_))));
+
+    let other = std::env::temp_dir().join("repath_disk_cache_other.txt");
+    std::fs::write(&other, "not a cache").unwrap();
+    assert!(matches!(DiskPathCache::open(&other, &graph), Err(RePathError::SerializationError(_))));
+}
@@ -0,0 +1,496 @@
+use dashmap::DashMap;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::path::Path;
+use repath::Coord;
+
+/// Builds two islands, a triangle of nodes 0, 2 and 4 and a pair of nodes 1 and 3, plus the
+/// isolated node 5.
+fn build_two_islands() -> Graph {
+    let mut graph = Graph::new();
+    for id in 0..6 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    graph.add_edge(0, 2, 2.0);
+    graph.add_edge(2, 4, 2.0);
+    graph.add_edge(4, 0, 4.0);
+    // A one-way edge still connects its nodes
+    graph.add_edge_directed(3, 1, 2.0);
+    graph
+}
+
+#[test]
+fn test_connected_components() {
+    let graph = build_two_islands();
+    assert_eq!(graph.connected_components(), vec![vec![0, 2, 4], vec![1, 3], vec![5]]);
+    assert_eq!(graph.component_of(4), 0);
+    assert_eq!(graph.component_of(3), 1);
+    assert_eq!(graph.component_of(5), 2);
+    assert!(graph.are_connected(0, 4));
+    assert!(graph.are_connected(1, 3));
+    assert!(!graph.are_connected(0, 1));
+}
+
+#[test]
+fn test_connected_components_after_changes() {
+    let mut graph = build_two_islands();
+    assert!(!graph.are_connected(4, 1));
+
+    graph.connect_nodes(4, 1, 3.0);
+    assert!(graph.are_connected(4, 1));
+    assert_eq!(graph.connected_components(), vec![vec![0, 1, 2, 3, 4], vec![5]]);
+
+    // Removing the bridge splits the islands again, with the node ids moved down
+    graph.remove_node(4);
+    assert_eq!(graph.connected_components(), vec![vec![0, 2], vec![1, 3], vec![4]]);
+}
+
+#[test]
+fn test_strongly_connected_components() {
+    let mut graph = Graph::new();
+    for id in 0..4 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    graph.add_edge_directed(0, 1, 1.0);
+    graph.add_edge_directed(1, 2, 1.0);
+    graph.add_edge_directed(2, 0, 2.0);
+    graph.add_edge_directed(2, 3, 1.0);
+
+    assert_eq!(graph.strongly_connected_components(), vec![vec![0, 1, 2], vec![3]]);
+    assert!(!graph.is_strongly_connected());
+    // The graph is still connected when ignoring the edge directions
+    assert_eq!(graph.connected_components().len(), 1);
+
+    graph.add_edge_directed(3, 1, 1.0);
+    assert_eq!(graph.strongly_connected_components(), vec![vec![0, 1, 2, 3]]);
+    assert!(graph.is_strongly_connected());
+}
+
+#[test]
+fn test_strongly_connected_components_chain() {
+    // A long one-way chain, deep enough to overflow a recursive implementation
+    let mut graph = Graph::new();
+    let len = 200_000;
+    for id in 0..len {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for id in 1..len {
+        graph.add_edge_directed(id - 1, id, 1.0);
+    }
+
+    let components = graph.strongly_connected_components();
+    assert_eq!(components.len(), len);
+    assert!(components.iter().enumerate().all(|(id, component)| component == &vec![id]));
+}
+
+#[test]
+fn test_articulation_points_and_bridges_of_path() {
+    let mut graph = Graph::new();
+    for id in 0..5 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for id in 1..5 {
+        graph.connect_nodes(id - 1, id, 1.0);
+    }
+
+    assert_eq!(graph.articulation_points(), vec![1, 2, 3]);
+    assert_eq!(graph.bridges(), vec![(0, 1), (1, 2), (2, 3), (3, 4)]);
+}
+
+#[test]
+fn test_articulation_points_and_bridges() {
+    // Two triangles 0-1-2 and 3-4-5 joined by the edge 2-3, whose direction does not matter
+    let mut graph = Graph::new();
+    for id in 0..7 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for (a, b) in [(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3)] {
+        graph.connect_nodes(a, b, 1.0);
+    }
+    graph.add_edge_directed(3, 2, 1.0);
+
+    assert_eq!(graph.articulation_points(), vec![2, 3]);
+    assert_eq!(graph.bridges(), vec![(2, 3)]);
+
+    // Closing the cycle removes all cuts, the isolated node 6 has none either
+    graph.connect_nodes(0, 5, 1.0);
+    assert!(graph.articulation_points().is_empty());
+    assert!(graph.bridges().is_empty());
+}
+
+#[test]
+fn test_degree_statistics_complete_graph() {
+    let mut graph = Graph::new();
+    for id in 0..4 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for a in 0..4 {
+        for b in (a + 1)..4 {
+            graph.connect_nodes(a, b, 1.0);
+        }
+    }
+
+    assert_eq!(graph.density(), 1.0);
+    assert!((0..4).all(|id| graph.out_degree(id) == 3 && graph.in_degree(id) == 3));
+    assert_eq!(graph.average_degree(), 3.0);
+    assert_eq!(graph.degree_histogram(), vec![(3, 4)]);
+}
+
+#[test]
+fn test_degree_statistics_after_changes() {
+    let mut graph = Graph::new();
+    assert_eq!((graph.density(), graph.average_degree()), (0.0, 0.0));
+    assert!(graph.degree_histogram().is_empty());
+
+    // A one-way star: node 0 leads to the three others
+    for id in 0..4 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for to in 1..4 {
+        graph.add_edge(0, to, 1.0);
+    }
+    assert_eq!((graph.out_degree(0), graph.in_degree(0)), (3, 0));
+    assert_eq!((graph.out_degree(1), graph.in_degree(1)), (0, 1));
+    assert_eq!(graph.degree_histogram(), vec![(0, 3), (3, 1)]);
+    assert_eq!(graph.density(), 0.25);
+
+    // The reverse index follows changes of the graph
+    graph.add_edge(2, 0, 1.0);
+    assert_eq!(graph.in_degree(0), 1);
+    graph.remove_node(1);
+    assert_eq!((graph.in_degree(0), graph.in_degree(1)), (1, 1));
+}
+
+/// A star of three leaves around node 0 whose edges cost 1, 2 and 4, with a cycle between the
+/// leaves 1 and 2 and node 4 connected to nothing.
+fn build_walk_graph() -> Graph {
+    let mut graph = Graph::new();
+    for id in 0..5 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for (leaf, cost) in [(1, 1.0), (2, 2.0), (3, 4.0)] {
+        graph.add_edge(0, leaf, cost);
+        graph.add_edge(leaf, 0, cost);
+    }
+    graph.add_edge(1, 2, 1.0);
+    graph.add_edge(2, 1, 1.0);
+    graph
+}
+
+#[test]
+fn test_random_walk() {
+    let graph = build_walk_graph();
+    let mut rng = StdRng::seed_from_u64(7);
+
+    for _ in 0..20 {
+        let walk = graph.random_walk(0, 10, &mut rng);
+        assert_eq!(walk.len(), 11);
+        assert_eq!(walk[0], 0);
+        assert!(walk.iter().all(|&id| id < 4));
+        assert!(walk.windows(2).all(|pair| graph.edge_cost(pair[0], pair[1]).is_some()));
+    }
+
+    // A walk from an isolated node ends right away
+    assert_eq!(graph.random_walk(4, 10, &mut rng), vec![4]);
+}
+
+#[test]
+fn test_random_walk_prefers_cheap_edges() {
+    let graph = build_walk_graph();
+    let mut rng = StdRng::seed_from_u64(7);
+
+    let mut counts = [0; 4];
+    for _ in 0..7000 {
+        counts[graph.random_walk(0, 1, &mut rng)[1]] += 1;
+    }
+    // The edges to 1, 2 and 4 are taken with the probabilities 4/7, 2/7 and 1/7
+    assert!(counts[1] > counts[2] && counts[2] > counts[3], "{:?}", counts);
+    assert!((3500..4500).contains(&counts[1]), "{:?}", counts);
+}
+
+#[test]
+fn test_random_walk_unique() {
+    let graph = build_walk_graph();
+    let mut rng = StdRng::seed_from_u64(7);
+
+    for _ in 0..20 {
+        let walk = graph.random_walk_unique(0, 10, &mut rng);
+        assert_eq!(walk[0], 0);
+        // At most the four connected nodes can be visited once each
+        assert!((2..=4).contains(&walk.len()), "{:?}", walk);
+        let mut distinct = walk.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(distinct.len(), walk.len());
+    }
+}
+
+#[test]
+fn test_random_weighted_path() {
+    // A ring of four nodes where the way from 0 to 2 through 1 costs 2 and through 3 costs 10,
+    // plus the isolated node 4
+    let mut graph = Graph::new();
+    for id in 0..5 {
+        graph.add_node(Node::new(id, 0.0, 0.0, 0.0));
+    }
+    for (a, b, cost) in [(0, 1, 1.0), (1, 2, 1.0), (2, 3, 5.0), (3, 0, 5.0)] {
+        graph.connect_nodes(a, b, cost);
+    }
+    let mut rng = StdRng::seed_from_u64(7);
+    let ids = |path: Path| path.iter().map(|node| node.id).collect::<Vec<_>>();
+
+    let cheapest = graph.a_star(0, 2, &DashMap::new()).map(ids);
+    assert_eq!(cheapest, Some(vec![0, 1, 2]));
+    for _ in 0..10 {
+        assert_eq!(graph.random_weighted_path(0, 2, &mut rng, 0.0).map(ids), cheapest);
+    }
+
+    // Hot paths take both ways and never visit a node twice
+    let mut detours = 0;
+    for _ in 0..200 {
+        let path = ids(graph.random_weighted_path(0, 2, &mut rng, 100.0).unwrap());
+        assert_eq!((path[0], path[path.len() - 1]), (0, 2));
+        assert!(path.windows(2).all(|pair| graph.edge_cost(pair[0], pair[1]).is_some()));
+        let mut distinct = path.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        assert_eq!(distinct.len(), path.len());
+        detours += usize::from(path == vec![0, 3, 2]);
+    }
+    assert!((20..180).contains(&detours), "{}", detours);
+
+    assert_eq!(graph.random_weighted_path(0, 4, &mut rng, 1.0), None);
+    assert_eq!(graph.random_weighted_path(3, 3, &mut rng, 1.0).map(ids), Some(vec![3]));
+}
+
+#[test]
+fn test_minimum_spanning_tree() {
+    // A complete graph of four nodes with different costs
+    let mut graph = Graph::new();
+    for id in 0..4 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    let edges = [(0, 1, 4.0), (0, 2, 1.0), (0, 3, 5.0), (1, 2, 2.0), (1, 3, 3.0), (2, 3, 6.0)];
+    for (a, b, cost) in edges {
+        graph.connect_nodes(a, b, cost);
+    }
+
+    let tree = graph.minimum_spanning_tree();
+    assert_eq!(tree.nodes, graph.nodes);
+    assert_eq!(tree.edges.iter().map(Vec::len).sum::<usize>(), 2 * 3);
+    assert_eq!(tree.connected_components().len(), 1);
+    assert_eq!((tree.edge_cost(0, 2), tree.edge_cost(2, 1), tree.edge_cost(3, 1)), (Some(1.0), Some(2.0), Some(3.0)));
+    assert_eq!(graph.minimum_spanning_tree_cost(), 6.0);
+
+    // No other spanning tree of three edges is cheaper
+    for first in 0..edges.len() {
+        for second in first + 1..edges.len() {
+            for third in second + 1..edges.len() {
+                let mut subset = Graph::new();
+                for id in 0..4 {
+                    subset.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+                }
+                for index in [first, second, third] {
+                    let (a, b, cost) = edges[index];
+                    subset.connect_nodes(a, b, cost);
+                }
+                if subset.connected_components().len() == 1 {
+                    let cost: Coord = [first, second, third].iter().map(|&index| edges[index].2).sum();
+                    assert!(graph.minimum_spanning_tree_cost() <= cost);
+                }
+            }
+        }
+    }
+
+    // Separate islands get one tree each, one-way edges count as two-way
+    let islands = build_two_islands();
+    let forest = islands.minimum_spanning_tree();
+    assert_eq!(forest.connected_components(), islands.connected_components());
+    assert_eq!(forest.edges.iter().map(Vec::len).sum::<usize>(), 2 * 3);
+    assert_eq!((forest.edge_cost(1, 3), forest.edge_cost(3, 1)), (Some(2.0), Some(2.0)));
+    assert_eq!(islands.minimum_spanning_tree_cost(), 6.0);
+    assert_eq!(Graph::new().minimum_spanning_tree_cost(), 0.0);
+}
+
+/// `len` nodes in a row connected both ways by edges of cost 1.
+fn build_line(len: usize) -> Graph {
+    let mut graph = Graph::new();
+    for id in 0..len {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for id in 1..len {
+        graph.connect_nodes(id - 1, id, 1.0);
+    }
+    graph
+}
+
+#[test]
+fn test_reachable_nodes_bfs() {
+    let graph = build_line(6);
+
+    assert_eq!(graph.reachable_nodes_bfs(2, 2.0), vec![(2, 0.0), (1, 1.0), (3, 1.0), (0, 2.0), (4, 2.0)]);
+    assert_eq!(graph.reachable_nodes_bfs(0, 3.5), vec![(0, 0.0), (1, 1.0), (2, 2.0), (3, 3.0)]);
+    assert_eq!(graph.reachable_nodes_bfs(5, 0.0), vec![(5, 0.0)]);
+    assert!(graph.reachable_nodes_bfs(5, -1.0).is_empty());
+    assert_eq!(graph.reachable_nodes_bfs(0, Coord::INFINITY).len(), 6);
+}
+
+#[test]
+fn test_eccentricity_of_line() {
+    let graph = build_line(5);
+
+    assert_eq!(graph.diameter(None), 4.0);
+    assert_eq!(graph.radius(), 2.0);
+    assert_eq!(graph.center_node(), Some(2));
+    assert_eq!(graph.peripheral_nodes(), vec![0, 4]);
+
+    // Sampling every node gives the exact diameter, fewer nodes at most the exact one
+    assert_eq!(graph.diameter(Some(5)), 4.0);
+    let sampled = graph.diameter(Some(2));
+    assert!((2.0..=4.0).contains(&sampled), "{}", sampled);
+}
+
+#[test]
+fn test_eccentricity_ignores_unreachable_nodes() {
+    // A line of three nodes, an isolated node and a one-way edge into the line
+    let mut graph = build_line(3);
+    graph.add_node(Node::new(3, 10.0, 0.0, 0.0));
+    graph.add_node(Node::new(4, -1.0, 0.0, 0.0));
+    graph.add_edge_directed(4, 0, 5.0);
+
+    assert_eq!(graph.diameter(None), 7.0);
+    assert_eq!(graph.radius(), 1.0);
+    assert_eq!(graph.center_node(), Some(1));
+    assert_eq!(graph.peripheral_nodes(), vec![4]);
+
+    assert_eq!(Graph::new().diameter(None), 0.0);
+    assert_eq!(Graph::new().center_node(), None);
+    assert!(Graph::new().peripheral_nodes().is_empty());
+}
+
+#[test]
+fn test_clustering_coefficient() {
+    // A complete graph of four nodes
+    let mut complete = Graph::new();
+    for id in 0..4 {
+        complete.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for a in 0..4 {
+        for b in (a + 1)..4 {
+            complete.connect_nodes(a, b, 1.0);
+        }
+    }
+    assert!((0..4).all(|id| complete.local_clustering_coefficient(id) == 1.0));
+    assert_eq!(complete.average_clustering_coefficient(), 1.0);
+
+    // A binary tree of seven nodes, where 0, 1 and 2 are not leaves
+    let mut tree = Graph::new();
+    for id in 0..7 {
+        tree.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for child in 1..7 {
+        tree.connect_nodes((child - 1) / 2, child, 1.0);
+    }
+    assert!((0..3).all(|id| tree.local_clustering_coefficient(id) == 0.0));
+    assert_eq!(tree.average_clustering_coefficient(), 0.0);
+    assert_eq!(Graph::new().average_clustering_coefficient(), 0.0);
+}
+
+#[test]
+fn test_clustering_coefficient_directed() {
+    // One-way edges 0 -> 1, 0 -> 2 and 1 -> 2: of the two ordered pairs of neighbors of every
+    // node, one is linked
+    let mut graph = Graph::new();
+    for id in 0..3 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    graph.add_edge_directed(0, 1, 1.0);
+    graph.add_edge_directed(0, 2, 1.0);
+    graph.add_edge_directed(1, 2, 1.0);
+
+    assert!((0..3).all(|id| graph.local_clustering_coefficient(id) == 0.5));
+    assert_eq!(graph.average_clustering_coefficient(), 0.5);
+
+    // Closing the triangle in both directions makes it a clique
+    for (a, b) in [(1, 0), (2, 0), (2, 1)] {
+        graph.add_edge_directed(a, b, 1.0);
+    }
+    assert_eq!(graph.local_clustering_coefficient(0), 1.0);
+}
+
+#[test]
+fn test_subgraph() {
+    // A line of ten nodes with an extra one-way edge from 2 to 7
+    let mut graph = build_line(10);
+    graph.add_edge_directed(2, 7, 5.0);
+    let subgraph = graph.subgraph(&[7, 2, 3, 2]);
+
+    assert_eq!(subgraph.nodes.len(), 3);
+    assert_eq!(subgraph.nodes.iter().map(|node| node.x).collect::<Vec<_>>(), vec![7.0, 2.0, 3.0]);
+    assert!(subgraph.nodes.iter().enumerate().all(|(id, node)| node.id == id));
+    // Only the edges 2 <-> 3 and 2 -> 7 lie inside the set
+    assert_eq!(subgraph.edges.iter().map(Vec::len).sum::<usize>(), 3);
+    assert_eq!(subgraph.edge_cost(1, 2), Some(1.0));
+    assert_eq!(subgraph.edge_cost(2, 1), Some(1.0));
+    assert_eq!(subgraph.edge_cost(1, 0), Some(5.0));
+    assert!(subgraph.edges[0].is_empty());
+
+    // The subgraph is independent of the original
+    graph.set_edge_cost(2, 3, 9.0);
+    assert_eq!(subgraph.edge_cost(1, 2), Some(1.0));
+}
+
+#[test]
+fn test_subgraph_in_aabb() {
+    let graph = build_line(10);
+    let subgraph = graph.subgraph_in_aabb(2.0, -1.0, -1.0, 4.5, 1.0, 1.0);
+
+    assert_eq!(subgraph.nodes.iter().map(|node| node.x).collect::<Vec<_>>(), vec![2.0, 3.0, 4.0]);
+    assert_eq!(subgraph.edges.iter().map(Vec::len).sum::<usize>(), 4);
+    assert!(graph.subgraph_in_aabb(20.0, 0.0, 0.0, 30.0, 0.0, 0.0).nodes.is_empty());
+}
+
+#[test]
+fn test_cluster() {
+    let graph = build_line(6);
+    let (abstract_graph, clusters) = graph.cluster(2.0);
+    assert_eq!(clusters, vec![0, 0, 1, 1, 2, 2]);
+    let positions: Vec<Coord> = abstract_graph.nodes.iter().map(|node| node.x).collect();
+    assert_eq!(positions, vec![0.5, 2.5, 4.5]);
+    // Only edges between different cells are kept, once per direction
+    assert_eq!(abstract_graph.edges.iter().map(Vec::len).sum::<usize>(), 4);
+    assert_eq!(abstract_graph.edge_cost(0, 1), Some(2.0));
+    assert_eq!(abstract_graph.edge_cost(2, 1), Some(2.0));
+    assert_eq!(abstract_graph.edge_cost(0, 2), None);
+}
+
+#[test]
+fn test_merge() {
+    // A chain of three nodes and a cycle of four nodes further along the x axis
+    let chain = build_line(3);
+    let mut cycle = Graph::new();
+    for id in 0..4 {
+        cycle.add_node(Node::new(id, 10.0 + id as Coord, 0.0, 0.0));
+    }
+    for id in 0..4 {
+        cycle.add_edge_directed(id, (id + 1) % 4, 2.0);
+    }
+
+    let merged = chain.clone().merge(cycle.clone());
+    assert_eq!(merged.nodes.len(), 7);
+    assert!(merged.nodes.iter().enumerate().all(|(id, node)| node.id == id));
+    assert_eq!(merged.nodes[3].x, 10.0);
+    assert_eq!(merged.edges.iter().map(Vec::len).sum::<usize>(), 4 + 4);
+    assert_eq!(merged.edge_cost(6, 3), Some(2.0));
+    assert!(merged.edges[3].iter().all(|edge| edge.directed));
+    assert_eq!(merged.connected_components().len(), 2);
+
+    // Bridge the end of the chain to the closest node of the cycle
+    let bridged = chain.merge_with_bridges(cycle, |a, b| (b.x - a.x < 9.0).then_some(8.0));
+    assert_eq!(bridged.nodes.len(), 7);
+    assert_eq!(bridged.edges.iter().map(Vec::len).sum::<usize>(), 4 + 4 + 2);
+    assert_eq!((bridged.edge_cost(2, 3), bridged.edge_cost(3, 2)), (Some(8.0), Some(8.0)));
+    assert_eq!(bridged.connected_components().len(), 1);
+}
@@ -0,0 +1,105 @@
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::settings::RePathSettings;
+use repath::{Coord, RePathfinderBuilder};
+
+/// A line of three nodes with two-way edges and a directed edge from the last node to the first.
+fn build_graph() -> Graph {
+    let mut graph = Graph::new();
+    for id in 0..3 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    graph.add_edge(0, 1, 1.0);
+    graph.add_edge(1, 0, 1.0);
+    graph.add_edge(1, 2, 1.5);
+    graph.add_edge(2, 1, 1.5);
+    graph.add_edge_directed(2, 0, 2.0);
+    graph
+}
+
+#[test]
+fn test_to_dot_declares_nodes_and_edges() {
+    let graph = build_graph();
+    let mut output = Vec::new();
+    graph.to_dot(&mut output).unwrap();
+    let dot = String::from_utf8(output).unwrap();
+
+    assert!(dot.starts_with("digraph"));
+    let node_lines = dot.lines().filter(|line| line.contains("[label=") && !line.contains("->"));
+    assert_eq!(node_lines.count(), 3);
+    assert_eq!(dot.lines().filter(|line| line.contains("->")).count(), 5);
+    assert!(dot.contains("1 [label=\"1(1,0,0)\"]"));
+    assert!(dot.contains("1 -> 2 [label=\"1.5\"]"));
+    assert!(!dot.contains("color=red"));
+}
+
+#[test]
+fn test_export_dot_highlights_path() {
+    let pathfinder = RePathfinderBuilder::new(RePathSettings::default())
+        .with_graph(build_graph())
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    let path = pathfinder.find_path((0.0, 0.0, 0.0), (2.0, 0.0, 0.0)).unwrap().nodes;
+
+    let filename = std::env::temp_dir().join("repath_export_test.dot");
+    pathfinder.export_dot(&filename, Some(&path)).unwrap();
+    let dot = std::fs::read_to_string(&filename).unwrap();
+    std::fs::remove_file(&filename).unwrap();
+
+    assert_eq!(dot.lines().filter(|line| line.contains("color=red")).count(), 5);
+    assert!(dot.contains("0 -> 1 [label=\"1\", color=red]"));
+    assert!(dot.contains("1 -> 2 [label=\"1.5\", color=red]"));
+    assert!(dot.contains("2 -> 0 [label=\"2\"]"));
+}
+
+#[test]
+fn test_to_geojson_features() {
+    let graph = build_graph();
+    let mut output = Vec::new();
+    graph.to_geojson(&mut output, false).unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(document["type"], "FeatureCollection");
+    let features = document["features"].as_array().unwrap();
+    let count = |geometry: &str| features.iter().filter(|f| f["geometry"]["type"] == geometry).count();
+    assert_eq!(features.len(), 8);
+    assert!(features.iter().all(|feature| feature["type"] == "Feature"));
+    assert_eq!((count("Point"), count("LineString")), (3, 5));
+    assert_eq!(features[1]["geometry"]["coordinates"], serde_json::json!([1.0, 0.0, 0.0]));
+    assert_eq!(features[1]["properties"]["id"], 1);
+    assert_eq!(features[3]["properties"]["from"], 0);
+    assert_eq!(features[3]["properties"]["to"], 1);
+}
+
+#[test]
+fn test_to_geojson_lat_lon_mode() {
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 13.5, 40.0, 52.5));
+    let mut output = Vec::new();
+    graph.to_geojson(&mut output, true).unwrap();
+    let document: serde_json::Value = serde_json::from_slice(&output).unwrap();
+
+    assert_eq!(document["features"][0]["geometry"]["coordinates"], serde_json::json!([13.5, 52.5]));
+}
+
+#[test]
+fn test_export_geojson_adds_path() {
+    let pathfinder = RePathfinderBuilder::new(RePathSettings::default())
+        .with_graph(build_graph())
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    let path = pathfinder.find_path((0.0, 0.0, 0.0), (2.0, 0.0, 0.0)).unwrap().nodes;
+
+    let filename = std::env::temp_dir().join("repath_export_test.geojson");
+    pathfinder.export_geojson(&filename, Some(&path)).unwrap();
+    let document: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(&filename).unwrap()).unwrap();
+    std::fs::remove_file(&filename).unwrap();
+
+    let features = document["features"].as_array().unwrap();
+    assert_eq!(features.len(), 9);
+    let path_feature = features.last().unwrap();
+    assert_eq!(path_feature["properties"]["path"], true);
+    assert_eq!(path_feature["geometry"]["coordinates"].as_array().unwrap().len(), 3);
+}
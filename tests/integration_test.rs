@@ -1,7 +1,16 @@
 use std::collections::VecDeque;
+use repath::edge::EdgeKind;
+use repath::error::RePathError;
 use repath::graph::Graph;
+use repath::lazy::parse_obj_lazy;
+use repath::node::Node;
 use repath::settings::RePathSettings;
-use repath::utils::parse_obj;
+use repath::utils::{
+    parse_graph_binary, parse_graph_mmap, parse_gltf, parse_navmesh, parse_obj,
+    parse_obj_streaming, read_graph_binary, read_graph_mmap, save_graph_binary, save_graph_mmap,
+    write_obj,
+};
+use repath::Coord;
 use dashmap::DashMap;
 
 #[test]
@@ -11,10 +20,11 @@ fn test_pathfinding_connected_nodes() {
         precompute_radius: 5000.0,
         total_precompute_pairs: 100,
         use_precomputed_cache: true,
+        ..RePathSettings::default()
     };
 
     // Parse the navmesh file into a graph
-    let graph = parse_obj(&settings.navmesh_filename);
+    let graph = parse_obj(&settings.navmesh_filename).expect("Failed to parse navmesh");
 
     // Initialize the cache using DashMap for concurrent access
     let cache = DashMap::new();
@@ -71,3 +81,585 @@ fn are_nodes_connected(graph: &Graph, start: usize, goal: usize) -> bool {
     }
     false
 }
+
+#[test]
+fn test_parse_obj_missing_file() {
+    let result = parse_obj("does_not_exist.obj");
+    assert!(matches!(result, Err(RePathError::IoError(_))));
+}
+
+#[test]
+fn test_parse_obj_invalid_line() {
+    let filename = std::env::temp_dir().join("repath_invalid_line.obj");
+    std::fs::write(&filename, "v 0.0 0.0 0.0\nv 1.0 abc 0.0\n").unwrap();
+
+    let result = parse_obj(filename.to_str().unwrap());
+    assert!(matches!(result, Err(RePathError::ParseError { line: 2, .. })));
+}
+
+#[test]
+fn test_parse_obj_face_out_of_range() {
+    let filename = std::env::temp_dir().join("repath_face_out_of_range.obj");
+    std::fs::write(&filename, "v 0.0 0.0 0.0\nv 1.0 0.0 0.0\nf 1 2 3\n").unwrap();
+
+    let result = parse_obj(filename.to_str().unwrap());
+    assert!(matches!(result, Err(RePathError::ParseError { line: 3, .. })));
+}
+
+/// A glTF document with a single quad made of two triangles in the XZ plane. The buffer holds
+/// four positions followed by six `u16` indices, either inline as `uri` or in a GLB chunk.
+fn gltf_quad_json(uri: Option<&str>) -> String {
+    let uri = uri.map(|uri| format!(r#""uri": "{}","#, uri)).unwrap_or_default();
+    format!(
+        r#"{{
+            "asset": {{ "version": "2.0" }},
+            "buffers": [{{ {} "byteLength": 60 }}],
+            "bufferViews": [
+                {{ "buffer": 0, "byteOffset": 0, "byteLength": 48 }},
+                {{ "buffer": 0, "byteOffset": 48, "byteLength": 12 }}
+            ],
+            "accessors": [
+                {{ "bufferView": 0, "componentType": 5126, "count": 4, "type": "VEC3",
+                   "min": [0.0, 0.0, 0.0], "max": [1.0, 0.0, 1.0] }},
+                {{ "bufferView": 1, "componentType": 5123, "count": 6, "type": "SCALAR" }}
+            ],
+            "meshes": [{{ "primitives": [{{ "attributes": {{ "POSITION": 0 }}, "indices": 1 }}] }}]
+        }}"#,
+        uri
+    )
+}
+
+const GLTF_QUAD_BUFFER: &str =
+    "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAACAPwAAAAAAAIA/AAAAAAAAAAAAAIA/AAABAAIAAAACAAMA";
+
+fn assert_is_gltf_quad(graph: &Graph) {
+    assert_eq!(graph.nodes.len(), 4);
+    assert_eq!((graph.nodes[2].x, graph.nodes[2].y, graph.nodes[2].z), (1.0, 0.0, 1.0));
+    assert_eq!(graph.edges.iter().map(Vec::len).sum::<usize>(), 6);
+
+    let diagonal = graph.edges[2].iter().find(|edge| edge.to == 0).expect("Missing edge");
+    assert!((diagonal.cost - Coord::sqrt(2.0)).abs() < 1e-6);
+    assert!(graph.a_star(1, 3, &DashMap::new()).is_some());
+}
+
+#[test]
+fn test_parse_gltf() {
+    let filename = std::env::temp_dir().join("repath_quad.gltf");
+    std::fs::write(&filename, gltf_quad_json(Some(GLTF_QUAD_BUFFER))).unwrap();
+
+    let graph = parse_gltf(filename.to_str().unwrap()).expect("Failed to parse glTF");
+    assert_is_gltf_quad(&graph);
+}
+
+#[test]
+fn test_parse_glb() {
+    let mut json = gltf_quad_json(None).into_bytes();
+    while !json.len().is_multiple_of(4) {
+        json.push(b' ');
+    }
+    let mut bin = Vec::new();
+    for value in [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 1.0, 0.0, 1.0, 0.0, 0.0, 1.0] {
+        bin.extend_from_slice(&value.to_le_bytes());
+    }
+    for index in [0u16, 1, 2, 0, 2, 3] {
+        bin.extend_from_slice(&index.to_le_bytes());
+    }
+
+    let mut glb = Vec::new();
+    glb.extend_from_slice(b"glTF");
+    glb.extend_from_slice(&2u32.to_le_bytes());
+    glb.extend_from_slice(&((12 + 8 + json.len() + 8 + bin.len()) as u32).to_le_bytes());
+    glb.extend_from_slice(&(json.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"JSON");
+    glb.extend_from_slice(&json);
+    glb.extend_from_slice(&(bin.len() as u32).to_le_bytes());
+    glb.extend_from_slice(b"BIN\0");
+    glb.extend_from_slice(&bin);
+
+    let filename = std::env::temp_dir().join("repath_quad.glb");
+    std::fs::write(&filename, glb).unwrap();
+
+    // The parser is chosen by the extension
+    let graph = parse_navmesh(filename.to_str().unwrap()).expect("Failed to parse GLB");
+    assert_is_gltf_quad(&graph);
+}
+
+#[test]
+fn test_parse_gltf_invalid() {
+    let filename = std::env::temp_dir().join("repath_invalid.gltf");
+    std::fs::write(&filename, "{ not json").unwrap();
+    assert!(matches!(
+        parse_navmesh(filename.to_str().unwrap()),
+        Err(RePathError::InvalidNavmesh(_))
+    ));
+
+    assert!(matches!(parse_gltf("does_not_exist.gltf"), Err(RePathError::IoError(_))));
+}
+
+#[test]
+fn test_graph_binary_round_trip() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let filename = std::env::temp_dir().join("repath_round_trip.rpnav");
+    let filename = filename.to_str().unwrap();
+    save_graph_binary(&graph, filename).expect("Failed to save binary navmesh");
+
+    let loaded = parse_navmesh(filename).expect("Failed to parse binary navmesh");
+    assert_eq!(loaded.nodes.len(), graph.nodes.len());
+    for (loaded_node, node) in loaded.nodes.iter().zip(&graph.nodes) {
+        assert_eq!(loaded_node.id, node.id);
+        assert_eq!((loaded_node.x, loaded_node.y, loaded_node.z), (node.x, node.y, node.z));
+    }
+    for (loaded_edges, edges) in loaded.edges.iter().zip(&graph.edges) {
+        let loaded_edges: Vec<_> = loaded_edges.iter().map(|edge| (edge.to, edge.cost)).collect();
+        let edges: Vec<_> = edges.iter().map(|edge| (edge.to, edge.cost)).collect();
+        assert_eq!(loaded_edges, edges);
+    }
+}
+
+#[test]
+fn test_parse_graph_binary_invalid() {
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::new(1, 1.0, 0.0, 0.0));
+    graph.add_edge(0, 1, 1.0);
+    let filename = std::env::temp_dir().join("repath_invalid.rpnav");
+    let filename = filename.to_str().unwrap();
+    save_graph_binary(&graph, filename).unwrap();
+    let bytes = std::fs::read(filename).unwrap();
+
+    let parse = |bytes: &[u8]| {
+        std::fs::write(filename, bytes).unwrap();
+        parse_graph_binary(filename)
+    };
+    assert!(parse(&bytes).is_ok());
+    // Bytes already in memory are read the same way
+    assert_eq!(read_graph_binary(&bytes[..]).unwrap(), graph);
+    assert!(matches!(read_graph_binary(&bytes[..10]), Err(RePathError::InvalidNavmesh(_))));
+
+    // Wrong magic, newer version and truncated files are rejected
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(matches!(parse(&wrong_magic), Err(RePathError::InvalidNavmesh(_))));
+
+    let mut newer_version = bytes.clone();
+    newer_version[4..8].copy_from_slice(&8u32.to_le_bytes());
+    assert!(matches!(parse(&newer_version), Err(RePathError::InvalidNavmesh(_))));
+
+    let mut float_size = bytes.clone();
+    float_size[8] = 2;
+    assert!(matches!(parse(&float_size), Err(RePathError::InvalidNavmesh(_))));
+
+    assert!(matches!(parse(&bytes[..bytes.len() - 2]), Err(RePathError::InvalidNavmesh(_))));
+
+    // The edge target of node 0 points past the last node
+    let mut out_of_range = bytes.clone();
+    let target = 13 + 2 * 4 * std::mem::size_of::<Coord>() + 4;
+    out_of_range[target..target + 4].copy_from_slice(&5u32.to_le_bytes());
+    assert!(matches!(parse(&out_of_range), Err(RePathError::InvalidNavmesh(_))));
+}
+
+#[test]
+fn test_graph_json_round_trip() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let json = graph.to_json().expect("Failed to serialize graph");
+
+    let loaded = Graph::from_json(&json).expect("Failed to parse JSON");
+    assert_eq!(loaded, graph);
+    // Node equality only compares ids, so check the positions as well
+    assert!(loaded
+        .nodes
+        .iter()
+        .zip(&graph.nodes)
+        .all(|(a, b)| (a.x, a.y, a.z) == (b.x, b.y, b.z)));
+}
+
+#[test]
+fn test_graph_from_json_schema() {
+    let json = r#"{
+        "nodes": [
+            { "id": 0, "x": 0.0, "y": 0.0, "z": 0.0 },
+            { "id": 1, "x": 3.0, "y": 4.0, "z": 0.0 }
+        ],
+        "edges": [{ "from": 0, "to": 1, "cost": 5.0 }]
+    }"#;
+    let graph = Graph::from_json(json).expect("Failed to parse JSON");
+    assert_eq!(graph.nodes.len(), 2);
+    assert_eq!(graph.edges[0].len(), 1);
+    assert_eq!((graph.edges[0][0].to, graph.edges[0][0].cost), (1, 5.0));
+    assert!(graph.edges[1].is_empty());
+
+    let missing_node = r#"{ "nodes": [{ "id": 0, "x": 0, "y": 0, "z": 0 }], "edges": [{ "from": 0, "to": 1, "cost": 1 }] }"#;
+    assert!(matches!(Graph::from_json(missing_node), Err(RePathError::InvalidNavmesh(_))));
+    let unordered_ids = r#"{ "nodes": [{ "id": 1, "x": 0, "y": 0, "z": 0 }], "edges": [] }"#;
+    assert!(matches!(Graph::from_json(unordered_ids), Err(RePathError::InvalidNavmesh(_))));
+    assert!(matches!(Graph::from_json("{}"), Err(RePathError::InvalidNavmesh(_))));
+}
+
+/// Returns the sorted edge targets of every node.
+fn sorted_edges(graph: &Graph) -> Vec<Vec<usize>> {
+    graph
+        .edges
+        .iter()
+        .map(|edges| {
+            let mut targets: Vec<usize> = edges.iter().map(|edge| edge.to).collect();
+            targets.sort_unstable();
+            targets
+        })
+        .collect()
+}
+
+#[test]
+fn test_write_obj_round_trip() {
+    let graph = parse_obj("NavMesh.obj").expect("Failed to parse navmesh");
+    let filename = std::env::temp_dir().join("repath_write_obj_round_trip.obj");
+    let filename = filename.to_str().unwrap();
+    write_obj(&graph, filename).expect("Failed to write OBJ");
+
+    let written = parse_obj(filename).expect("Failed to parse written OBJ");
+    assert_eq!(written.nodes.len(), graph.nodes.len());
+    assert_eq!(
+        written.edges.iter().map(Vec::len).sum::<usize>(),
+        graph.edges.iter().map(Vec::len).sum::<usize>()
+    );
+    assert!(written
+        .nodes
+        .iter()
+        .zip(&graph.nodes)
+        .all(|(a, b)| (a.x, a.y, a.z) == (b.x, b.y, b.z)));
+    assert_eq!(sorted_edges(&written), sorted_edges(&graph));
+}
+
+#[test]
+fn test_write_obj_edges_outside_faces() {
+    let mut graph = Graph::new();
+    for (id, (x, z)) in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (5.0, 5.0)].into_iter().enumerate() {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    graph.add_edge(0, 1, 1.0);
+    graph.add_edge(1, 2, Coord::sqrt(2.0));
+    graph.add_edge(2, 0, 1.0);
+    // A one-way edge added at runtime that is not part of any face
+    graph.add_edge(2, 3, graph.heuristic(2, 3));
+
+    let filename = std::env::temp_dir().join("repath_write_obj_edges.obj");
+    let filename = filename.to_str().unwrap();
+    write_obj(&graph, filename).expect("Failed to write OBJ");
+
+    let contents = std::fs::read_to_string(filename).unwrap();
+    assert_eq!(contents.lines().filter(|line| line.starts_with("f ")).count(), 1);
+    // The edge has no reverse, so it is written as a directed line element
+    let lines: Vec<&str> = contents.lines().collect();
+    assert!(lines.windows(2).any(|pair| pair == ["# directed", "l 3 4"]));
+
+    let written = parse_obj(filename).expect("Failed to parse written OBJ");
+    assert_eq!(sorted_edges(&written), sorted_edges(&graph));
+    assert!((written.edges[2][1].cost - graph.edges[2][1].cost).abs() < 1e-6);
+    assert!(written.edges[2][1].directed);
+}
+
+#[test]
+fn test_parse_obj_directed_lines() {
+    let filename = std::env::temp_dir().join("repath_directed_lines.obj");
+    let filename = filename.to_str().unwrap();
+    std::fs::write(filename, "v 0 0 0\nv 1 0 0\nv 2 0 0\nl 1 2\n# directed\nl 2 3\n").unwrap();
+
+    let graph = parse_obj(filename).expect("Failed to parse OBJ");
+    assert_eq!(sorted_edges(&graph), vec![vec![1], vec![0, 2], vec![]]);
+    assert!(!graph.edges[0][0].directed && !graph.edges[1][0].directed);
+    assert!(graph.edges[1].iter().any(|edge| edge.to == 2 && edge.directed));
+
+    // Directed edges survive writing and binary round trips
+    write_obj(&graph, filename).expect("Failed to write OBJ");
+    let written = parse_obj(filename).expect("Failed to parse written OBJ");
+    assert_eq!(written, graph);
+    save_graph_binary(&graph, filename).expect("Failed to save binary navmesh");
+    assert_eq!(parse_graph_binary(filename).expect("Failed to parse binary navmesh"), graph);
+    save_graph_mmap(&graph, filename).expect("Failed to save mapped navmesh");
+    assert_eq!(parse_graph_mmap(filename).expect("Failed to parse mapped navmesh"), graph);
+}
+
+#[test]
+fn test_graph_mmap_round_trip() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let filename = std::env::temp_dir().join("repath_round_trip.rpmap");
+    let filename = filename.to_str().unwrap();
+    save_graph_mmap(&graph, filename).expect("Failed to save mapped navmesh");
+
+    let loaded = parse_navmesh(filename).expect("Failed to parse mapped navmesh");
+    assert_eq!(loaded, graph);
+    for (loaded_node, node) in loaded.nodes.iter().zip(&graph.nodes) {
+        assert_eq!((loaded_node.x, loaded_node.y, loaded_node.z), (node.x, node.y, node.z));
+    }
+
+    // Bytes that are not aligned for the records are copied instead of viewed in place
+    let bytes = std::fs::read(filename).unwrap();
+    let mut unaligned = vec![0u8];
+    unaligned.extend_from_slice(&bytes);
+    assert_eq!(read_graph_mmap(&unaligned[1..]).unwrap(), graph);
+}
+
+#[test]
+fn test_parse_graph_mmap_invalid() {
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::new(1, 1.0, 0.0, 0.0));
+    graph.add_edge_with_labels(0, 1, 1.0, vec!["door".to_string()]);
+    let filename = std::env::temp_dir().join("repath_invalid.rpmap");
+    let filename = filename.to_str().unwrap();
+    save_graph_mmap(&graph, filename).unwrap();
+    let bytes = std::fs::read(filename).unwrap();
+
+    let parse = |bytes: &[u8]| {
+        std::fs::write(filename, bytes).unwrap();
+        parse_graph_mmap(filename)
+    };
+    assert_eq!(parse(&bytes).unwrap(), graph);
+    assert!(matches!(parse(&[]), Err(RePathError::InvalidNavmesh(_))));
+
+    // Wrong magic, other versions and truncated files are rejected
+    let mut wrong_magic = bytes.clone();
+    wrong_magic[0] = b'X';
+    assert!(matches!(parse(&wrong_magic), Err(RePathError::InvalidNavmesh(_))));
+
+    let mut other_version = bytes.clone();
+    other_version[4..8].copy_from_slice(&2u32.to_le_bytes());
+    assert!(matches!(parse(&other_version), Err(RePathError::InvalidNavmesh(_))));
+
+    assert!(matches!(parse(&bytes[..bytes.len() - 2]), Err(RePathError::InvalidNavmesh(_))));
+    assert!(matches!(read_graph_mmap(&bytes[..30]), Err(RePathError::InvalidNavmesh(_))));
+
+    // The edge count of node 0, the edge target and the label length point past their sections
+    let header = 24;
+    let edges = header + 2 * 40;
+    let labels = edges + 32;
+    for (offset, value) in [(header + 36, 2u32), (edges + 16, 5), (labels + 4, 9)] {
+        let mut out_of_range = bytes.clone();
+        out_of_range[offset..offset + 4].copy_from_slice(&value.to_le_bytes());
+        assert!(matches!(parse(&out_of_range), Err(RePathError::InvalidNavmesh(_))), "{}", offset);
+    }
+
+    let mut not_utf8 = bytes.clone();
+    let len = not_utf8.len();
+    not_utf8[len - 1] = 0xff;
+    assert!(matches!(parse(&not_utf8), Err(RePathError::InvalidNavmesh(_))));
+}
+
+#[test]
+fn test_parse_obj_edge_kinds_and_clearance() {
+    let filename = std::env::temp_dir().join("repath_edge_kinds.obj");
+    let filename = filename.to_str().unwrap();
+    std::fs::write(
+        filename,
+        "v 0 0 0\nv 1 0 0\nv 1 0 1\nv 1 3 1\nv 1 6 1\nf 1 2 3\n\
+         g Stairs_North\nl 3 4\ng elevator_up\nusemtl clearance_0.75\n# directed\nl 4 5\n\
+         g default\nusemtl concrete\nl 1 5\n",
+    )
+    .unwrap();
+
+    let graph = parse_obj(filename).expect("Failed to parse OBJ");
+    let kind = |from: usize, to: usize| graph.edges[from].iter().find(|edge| edge.to == to).unwrap().kind;
+    assert_eq!(kind(0, 1), EdgeKind::Walk);
+    assert_eq!((kind(2, 3), kind(3, 2)), (EdgeKind::Stairs, EdgeKind::Stairs));
+    assert_eq!(kind(3, 4), EdgeKind::Elevator);
+    assert_eq!((kind(0, 4), kind(4, 0)), (EdgeKind::Walk, EdgeKind::Walk));
+    assert_eq!(graph.edges[3].iter().find(|edge| edge.to == 4).unwrap().clearance, 0.75);
+    assert!(graph.edges[0].iter().all(|edge| edge.clearance == Coord::INFINITY));
+
+    // The kinds and clearances survive writing and binary round trips
+    write_obj(&graph, filename).expect("Failed to write OBJ");
+    let contents = std::fs::read_to_string(filename).unwrap();
+    assert!(contents.lines().any(|line| line == "g stairs"));
+    let written = parse_obj(filename).expect("Failed to parse written OBJ");
+    assert_eq!(sorted_edges(&written), sorted_edges(&graph));
+    let kind = |from: usize, to: usize| written.edges[from].iter().find(|edge| edge.to == to).unwrap().kind;
+    assert_eq!((kind(2, 3), kind(3, 2), kind(3, 4)), (EdgeKind::Stairs, EdgeKind::Stairs, EdgeKind::Elevator));
+    assert_eq!(written.edges[3].iter().find(|edge| edge.to == 4).unwrap().clearance, 0.75);
+    assert!(written.edges[0].iter().all(|edge| edge.clearance == Coord::INFINITY));
+    assert_eq!(Graph::from_json(&graph.to_json().unwrap()).unwrap(), graph);
+    save_graph_binary(&graph, filename).expect("Failed to save binary navmesh");
+    assert_eq!(parse_graph_binary(filename).expect("Failed to parse binary navmesh"), graph);
+    save_graph_mmap(&graph, filename).expect("Failed to save mapped navmesh");
+    assert_eq!(parse_graph_mmap(filename).expect("Failed to parse mapped navmesh"), graph);
+}
+
+#[test]
+fn test_parse_obj_material_labels() {
+    let filename = std::env::temp_dir().join("repath_material_labels.obj");
+    let filename = filename.to_str().unwrap();
+    std::fs::write(
+        filename,
+        "v 0 0 0\nv 1 0 0\nv 1 0 1\nv 2 0 1\nf 1 2 3\nusemtl swimming\nl 3 4\nusemtl clearance_0.5\nl 4 1\n",
+    )
+    .unwrap();
+
+    let graph = parse_obj(filename).expect("Failed to parse OBJ");
+    let labels = |from: usize, to: usize| graph.edges[from].iter().find(|edge| edge.to == to).unwrap().labels.clone();
+    assert!(labels(0, 1).is_empty());
+    assert_eq!((labels(2, 3), labels(3, 2)), (vec!["swimming".to_string()], vec!["swimming".to_string()]));
+    // Clearance materials restrict the edge instead of labelling it
+    assert!(labels(3, 0).is_empty());
+
+    // The labels survive JSON and binary round trips
+    assert_eq!(Graph::from_json(&graph.to_json().unwrap()).unwrap(), graph);
+    save_graph_binary(&graph, filename).expect("Failed to save binary navmesh");
+    assert_eq!(parse_graph_binary(filename).expect("Failed to parse binary navmesh"), graph);
+    save_graph_mmap(&graph, filename).expect("Failed to save mapped navmesh");
+    assert_eq!(parse_graph_mmap(filename).expect("Failed to parse mapped navmesh"), graph);
+}
+
+#[test]
+fn test_parse_obj_vertex_normals() {
+    let filename = std::env::temp_dir().join("repath_vertex_normals.obj");
+    let filename = filename.to_str().unwrap();
+    std::fs::write(
+        filename,
+        "v 0 0 0\nv 1 0 0\nv 0 0 1\nv 1 1 1\nvn 0 1 0\nvn 0.6 0.8 0\nf 1//1 2//1 3//2\nf 2/1/2 4 3\n",
+    )
+    .unwrap();
+
+    let graph = parse_obj(filename).expect("Failed to parse OBJ");
+    let normals: Vec<_> = graph.nodes.iter().map(|node| node.normal).collect();
+    assert_eq!(normals, vec![Some((0.0, 1.0, 0.0)), Some((0.6, 0.8, 0.0)), Some((0.6, 0.8, 0.0)), None]);
+    assert_eq!(sorted_edges(&graph), vec![vec![1], vec![2, 3], vec![0, 1], vec![2]]);
+
+    std::fs::write(filename, "v 0 0 0\nv 1 0 0\nv 0 0 1\nvn 0 1 0\nf 1//1 2//2 3//1\n").unwrap();
+    assert!(matches!(parse_obj(filename), Err(RePathError::ParseError { line: 5, .. })));
+}
+
+#[test]
+fn test_parse_obj_compound_face_syntax() {
+    // A quad as exported by Blender with texture coordinates and normals, split into two faces
+    // that use different formats for their vertex references
+    let filename = std::env::temp_dir().join("repath_compound_faces.obj");
+    let filename = filename.to_str().unwrap();
+    let obj = "\
+o Plane
+v 0.0 0.0 0.0
+v 1.0 0.0 0.0
+v 1.0 0.0 1.0
+v 0.0 0.0 1.0
+vt 0.0 0.0
+vt 1.0 0.0
+vt 1.0 1.0
+vn 0.0 1.0 0.0
+s off
+f 1/1 2/2 3/3
+f 1/1/1 3/3/1 4//1
+";
+    std::fs::write(filename, obj).unwrap();
+
+    let graph = parse_obj(filename).expect("Failed to parse OBJ");
+    assert_eq!(graph.nodes.len(), 4);
+    assert_eq!(sorted_edges(&graph), vec![vec![1, 2], vec![2], vec![0, 3], vec![0]]);
+    assert_eq!(graph.edge_cost(0, 1), Some(1.0));
+    assert_eq!(graph.nodes[3].normal, Some((0.0, 1.0, 0.0)));
+
+    // The vertex index must come first
+    std::fs::write(filename, "v 0 0 0\nv 1 0 0\nv 0 0 1\nf /1 2 3\n").unwrap();
+    assert!(matches!(parse_obj(filename), Err(RePathError::ParseError { line: 4, .. })));
+}
+
+/// Asserts that both graphs have the same nodes and edges.
+fn assert_same_graph(a: &Graph, b: &Graph) {
+    assert_eq!(a.nodes.len(), b.nodes.len());
+    for (a, b) in a.nodes.iter().zip(&b.nodes) {
+        assert_eq!((a.id, a.x, a.y, a.z, a.weight), (b.id, b.x, b.y, b.z, b.weight));
+        assert_eq!((&a.group, a.normal), (&b.group, b.normal));
+    }
+    assert_eq!(a.edges, b.edges);
+}
+
+#[test]
+fn test_parse_obj_streaming() {
+    for filename in ["NavMesh.obj", "navmesh_varied.obj"] {
+        let graph = parse_obj_streaming(filename).expect("Failed to parse navmesh");
+        assert_same_graph(&graph, &parse_obj(filename).unwrap());
+    }
+
+    assert!(matches!(parse_obj_streaming("does_not_exist.obj"), Err(RePathError::IoError(_))));
+}
+
+#[test]
+fn test_parse_obj_lazy_loads_regions() {
+    let filename = std::env::temp_dir().join("repath_lazy.obj");
+    let filename = filename.to_str().unwrap();
+    std::fs::write(
+        filename,
+        "v 0 0 0\nv 1 0 0\nv 0 0 1\nv 10 0 0\nv 11 0 0\nvn 0 1 0\ng stairs\nf 1//1 2//1 3//1\n\
+         usemtl clearance_0.5\n# directed\nl 4 5\n",
+    )
+    .unwrap();
+
+    let mut graph = parse_obj_lazy(filename, 5.0).expect("Failed to parse OBJ");
+    assert_eq!(graph.nodes.len(), 5);
+    assert!(graph.edges.iter().all(Vec::is_empty));
+    let face_region = ((0.0, 0.0, 0.0), (1.0, 1.0, 1.0));
+    assert!(!graph.is_region_loaded(face_region));
+
+    // Only the face lies in the chunk of the region
+    graph.load_region(face_region).expect("Failed to load region");
+    assert!(graph.is_region_loaded(face_region));
+    assert!(!graph.is_region_loaded(((0.0, 0.0, 0.0), (20.0, 1.0, 1.0))));
+    assert_eq!(graph.generation(), 1);
+    assert_eq!(sorted_edges(&graph), vec![vec![1], vec![2], vec![0], vec![], vec![]]);
+    assert!(graph.edges[0].iter().all(|edge| edge.kind == EdgeKind::Stairs));
+    assert_eq!(graph.nodes[0].normal, Some((0.0, 1.0, 0.0)));
+
+    // Loading a region again changes nothing
+    graph.load_region(face_region).expect("Failed to load region");
+    assert_eq!(graph.generation(), 1);
+
+    graph.load_region(((-100.0, -100.0, -100.0), (100.0, 100.0, 100.0))).expect("Failed to load region");
+    assert_eq!(graph, parse_obj(filename).expect("Failed to parse OBJ"));
+    assert!(graph.edges[3][0].directed && graph.edges[3][0].clearance == 0.5);
+    assert!(graph.edges[4].is_empty());
+}
+
+#[test]
+fn test_graph_serde_round_trip() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+
+    let json = serde_json::to_string(&graph).unwrap();
+    assert_eq!(serde_json::from_str::<Graph>(&json).unwrap(), graph);
+
+    // The edges are stored as a flat list of records
+    let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+    let edge = &value["edges"][0];
+    assert_eq!(edge["from"], 0);
+    assert!(edge["to"].is_u64() && edge["cost"].is_f64());
+
+    let bytes = bincode::serialize(&graph).unwrap();
+    let decoded: Graph = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(decoded, graph);
+    assert!(decoded
+        .nodes
+        .iter()
+        .zip(&graph.nodes)
+        .all(|(a, b)| (a.x, a.y, a.z) == (b.x, b.y, b.z)));
+}
+
+#[test]
+fn test_graph_deserialize_rejects_missing_nodes() {
+    let json = r#"{ "nodes": [{ "id": 0, "x": 0, "y": 0, "z": 0 }], "edges": [{ "from": 0, "to": 3, "cost": 1 }] }"#;
+    let error = serde_json::from_str::<Graph>(json).unwrap_err();
+    assert!(error.to_string().contains("missing node"));
+}
+
+#[test]
+fn test_parse_obj_node_weights() {
+    let filename = std::env::temp_dir().join("repath_node_weights.obj");
+    let filename = filename.to_str().unwrap();
+    std::fs::write(filename, "v 0 0 0\nv 1 0 0 2.5\nv 2 0 0 0.5 0.5 0.5 4\nv 3 0 0 1 0 0\nl 1 2\n").unwrap();
+
+    let graph = parse_obj(filename).expect("Failed to parse OBJ");
+    let weights: Vec<Coord> = graph.nodes.iter().map(|node| node.weight).collect();
+    assert_eq!(weights, vec![1.0, 2.5, 4.0, 1.0]);
+
+    // Weights survive writing and binary round trips
+    write_obj(&graph, filename).expect("Failed to write OBJ");
+    let written = parse_obj(filename).expect("Failed to parse written OBJ");
+    assert!(written.nodes.iter().zip(&graph.nodes).all(|(a, b)| a.weight == b.weight));
+    save_graph_binary(&graph, filename).expect("Failed to save binary navmesh");
+    let loaded = parse_graph_binary(filename).expect("Failed to parse binary navmesh");
+    assert!(loaded.nodes.iter().zip(&graph.nodes).all(|(a, b)| a.weight == b.weight));
+}
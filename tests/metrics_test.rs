@@ -0,0 +1,31 @@
+#![cfg(feature = "metrics")]
+
+use repath::settings::RePathSettings;
+use repath::RePathfinder;
+
+#[test]
+fn test_metrics_to_prometheus() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        precompute_radius: 100.0,
+        total_precompute_pairs: 50,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    let (start, end) = (&pathfinder.graph().nodes[1000], &pathfinder.graph().nodes[1203]);
+    for _ in 0..2 {
+        pathfinder.find_path((start.x, start.y, start.z), (end.x, end.y, end.z)).unwrap();
+    }
+
+    let output = pathfinder.metrics_to_prometheus();
+    let value = |name: &str| -> f64 {
+        let line = output.lines().find(|line| line.starts_with(&format!("{} ", name))).unwrap();
+        line.split(' ').nth(1).unwrap().parse().unwrap()
+    };
+    assert_eq!(value("repath_pathfinding_calls_total"), 2.0);
+    assert_eq!(value("repath_cache_hits_total"), 1.0);
+    assert!(value("repath_precomputed_paths") > 0.0);
+    assert!(value("repath_average_path_nodes") > 1.0);
+    assert!(output.contains("# TYPE repath_pathfinding_calls_total counter\n"));
+    assert!(output.lines().all(|line| line.starts_with('#') || line.split(' ').count() == 2));
+}
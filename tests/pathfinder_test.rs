@@ -0,0 +1,1373 @@
+use dashmap::DashMap;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use repath::cache::{DiskPathCache, LruPathCache, PathCache};
+use repath::edge::EdgeKind;
+use repath::graph::Graph;
+use repath::lazy::parse_obj_lazy;
+use repath::metrics::PathfindingMetrics;
+use repath::node::Node;
+use repath::path::{path_cost, PathResult};
+use repath::settings::{Algorithm, GraphMode, RePathSettings};
+use repath::utils::parse_obj;
+use repath::{Coord, RePathError, RePathfinder, RePathfinderBuilder};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Creates a pathfinder for the bundled fully connected navmesh with little precomputation.
+fn create_pathfinder() -> RePathfinder {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        precompute_radius: 100.0,
+        total_precompute_pairs: 10,
+        use_precomputed_cache: true,
+        ..RePathSettings::default()
+    };
+    RePathfinder::new(settings).expect("Failed to create pathfinder")
+}
+
+/// Returns the coordinates of the given nodes of the bundled navmesh.
+fn node_coords(ids: &[usize]) -> Vec<(Coord, Coord, Coord)> {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    ids.iter()
+        .map(|&id| (graph.nodes[id].x, graph.nodes[id].y, graph.nodes[id].z))
+        .collect()
+}
+
+#[test]
+fn test_find_k_paths() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 1650]);
+
+    let paths = pathfinder.find_k_paths(coords[0], coords[1], 4);
+    assert_eq!(paths.len(), 4);
+    assert!(paths[1].total_cost >= paths[0].total_cost);
+    for pair in paths.windows(2) {
+        assert!(pair[1].total_cost >= pair[0].total_cost - 1e-3);
+        assert_ne!(pair[0], pair[1]);
+    }
+
+    let shortest = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert_eq!(paths[0], shortest);
+}
+
+#[test]
+fn test_find_path_from_nearest() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[100, 39000, 20500, 20000]);
+
+    let path = pathfinder
+        .find_path_from_nearest(&coords[..3], coords[3])
+        .expect("No path found");
+    let cheapest = coords[..3]
+        .iter()
+        .map(|&start| pathfinder.find_path(start, coords[3]).unwrap().total_cost)
+        .fold(Coord::INFINITY, Coord::min);
+    assert!((path.total_cost - cheapest).abs() < 1e-2);
+
+    assert!(matches!(
+        pathfinder.find_path_from_nearest(&[], coords[3]),
+        Err(RePathError::NoPath)
+    ));
+}
+
+#[test]
+fn test_find_path_to_nearest() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[20000, 100, 20500, 39000]);
+
+    let path = pathfinder
+        .find_path_to_nearest(coords[0], &coords[1..])
+        .expect("No path found");
+    let cheapest = coords[1..]
+        .iter()
+        .map(|&end| pathfinder.find_path(coords[0], end).unwrap().total_cost)
+        .fold(Coord::INFINITY, Coord::min);
+    assert!((path.total_cost - cheapest).abs() < 1e-2);
+}
+
+#[test]
+fn test_find_path_partial_complete() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 1650]);
+
+    let partial = pathfinder
+        .find_path_partial(coords[0], coords[1])
+        .expect("Failed to find path");
+    assert!(partial.complete);
+    assert_eq!(partial.path, pathfinder.find_path(coords[0], coords[1]).unwrap().nodes);
+}
+
+#[test]
+fn test_cache_capacity_and_hit_rate() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        precompute_radius: 100.0,
+        total_precompute_pairs: 50,
+        use_precomputed_cache: true,
+        cache_capacity: 5,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    assert!(pathfinder.cache_len() <= 5);
+    assert_eq!(pathfinder.cache_hit_rate(), 0.0);
+
+    let coords = node_coords(&[1000, 1650]);
+    let first = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    let second = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(pathfinder.cache_hit_rate(), 0.5);
+    assert!(pathfinder.cache_len() <= 5);
+}
+
+#[test]
+fn test_save_and_load_cache() {
+    let cache_file = std::env::temp_dir().join("repath_test_save_and_load_cache.bin");
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 1650]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    pathfinder.save_cache(&cache_file).expect("Failed to save cache");
+
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        total_precompute_pairs: 0,
+        ..RePathSettings::default()
+    };
+    let restored = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    assert_eq!(restored.cache_len(), 0);
+    restored.load_cache(&cache_file).expect("Failed to load cache");
+    assert_eq!(restored.cache_len(), pathfinder.cache_len());
+
+    // The path is served from the loaded cache
+    assert_eq!(restored.find_path(coords[0], coords[1]).unwrap(), path);
+    assert_eq!(restored.cache_hit_rate(), 1.0);
+
+    std::fs::write(&cache_file, b"not a cache").unwrap();
+    assert!(matches!(
+        restored.load_cache(&cache_file),
+        Err(RePathError::SerializationError(_))
+    ));
+    std::fs::remove_file(&cache_file).unwrap();
+}
+
+#[test]
+fn test_cache_file_setting() {
+    let cache_file = std::env::temp_dir().join("repath_test_cache_file_setting.bin");
+    let _ = std::fs::remove_file(&cache_file);
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        precompute_radius: 100.0,
+        total_precompute_pairs: 10,
+        cache_file: Some(cache_file.to_string_lossy().into_owned()),
+        ..RePathSettings::default()
+    };
+
+    // The first pathfinder precomputes and saves the cache, the second one loads it
+    let precomputed = RePathfinder::new(settings.clone()).expect("Failed to create pathfinder");
+    assert!(cache_file.exists());
+    let loaded = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    assert_eq!(loaded.cache_len(), precomputed.cache_len());
+
+    std::fs::remove_file(&cache_file).unwrap();
+}
+
+#[test]
+fn test_cache_invalidation() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        total_precompute_pairs: 0,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    let coords = node_coords(&[1000, 1650, 30000]);
+
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap().nodes;
+    pathfinder.find_path(coords[0], coords[2]).unwrap();
+    pathfinder.find_path(coords[2], coords[1]).unwrap();
+    assert_eq!(pathfinder.cache_len(), 3);
+
+    pathfinder.invalidate_cache_for_node(path[0].id);
+    assert_eq!(pathfinder.cache_len(), 1);
+
+    // Only the cached path through the edge is removed
+    pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert_eq!(pathfinder.cache_len(), 2);
+    pathfinder.invalidate_cache_for_edge(path[1].id, path[2].id);
+    assert_eq!(pathfinder.cache_len(), 1);
+    // The reverse direction is a different edge
+    pathfinder.find_path(coords[0], coords[1]).unwrap();
+    pathfinder.invalidate_cache_for_edge(path[2].id, path[1].id);
+    assert_eq!(pathfinder.cache_len(), 2);
+
+    pathfinder.clear_cache();
+    assert_eq!(pathfinder.cache_len(), 0);
+}
+
+#[test]
+fn test_find_path_smooth() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    let smoothed = pathfinder.find_path_smooth(coords[0], coords[1]).unwrap();
+    assert_eq!(smoothed.nodes.first(), path.nodes.first());
+    assert_eq!(smoothed.nodes.last(), path.nodes.last());
+    assert!(smoothed.nodes.len() < path.nodes.len());
+    assert!(smoothed.euclidean_length <= path.euclidean_length + 1e-3);
+}
+
+#[test]
+fn test_find_path_decimated() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert_eq!(pathfinder.find_path_decimated(coords[0], coords[1], 0.0).unwrap(), path);
+    let decimated = pathfinder.find_path_decimated(coords[0], coords[1], 1e9).unwrap();
+    assert_eq!(*decimated.nodes, vec![path.nodes[0].clone(), path.nodes[path.nodes.len() - 1].clone()]);
+}
+
+#[test]
+fn test_find_path_iter() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    let nodes: Vec<_> = pathfinder.find_path_iter(coords[0], coords[1]).unwrap().collect();
+    assert_eq!(nodes, *path.nodes);
+}
+
+#[test]
+fn test_reroute_if_deviated() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000, 40000]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap().nodes;
+
+    // An agent between two waypoints is on the path, so no search runs
+    let (a, b) = (&path[3], &path[4]);
+    let on_path = ((a.x + b.x) / 2.0, (a.y + b.y) / 2.0, (a.z + b.z) / 2.0);
+    let calls = pathfinder.metrics().calls;
+    let same = pathfinder.reroute_if_deviated(on_path, &path, 0.01, coords[1]).unwrap();
+    assert!(Arc::ptr_eq(&same, &path));
+    assert_eq!(pathfinder.metrics().calls, calls);
+
+    // An agent pushed far away gets a new path from where it is now
+    let rerouted = pathfinder.reroute_if_deviated(coords[2], &path, 0.01, coords[1]).unwrap();
+    assert!(!Arc::ptr_eq(&rerouted, &path));
+    assert_eq!(rerouted.first().unwrap().id, 40000);
+    assert_eq!(rerouted.last().unwrap().id, 30000);
+    assert_eq!(pathfinder.metrics().calls, calls + 1);
+}
+
+#[test]
+fn test_find_path_interpolated() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    let step_size = 0.001;
+    let points = pathfinder.find_path_interpolated(coords[0], coords[1], step_size).unwrap();
+    let expected = path.euclidean_length / step_size;
+    assert!((points.len() as Coord - expected).abs() < expected * 0.01);
+
+    let (first, last) = (&path.nodes[0], &path.nodes[path.nodes.len() - 1]);
+    assert_eq!(points[0], (first.x, first.y, first.z));
+    assert_eq!(points[points.len() - 1], (last.x, last.y, last.z));
+}
+
+#[test]
+fn test_find_path_bezier() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    let curve = pathfinder.find_path_bezier(coords[0], coords[1], 1000).unwrap();
+    assert_eq!(curve.len(), 1000);
+
+    let (first, last) = (&path.nodes[0], &path.nodes[path.nodes.len() - 1]);
+    assert_eq!(curve[0], (first.x, first.y, first.z));
+    assert_eq!(curve[curve.len() - 1], (last.x, last.y, last.z));
+}
+
+#[test]
+fn test_path_result_cost_and_length() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    let edge_costs: Coord = path
+        .nodes
+        .windows(2)
+        .map(|pair| graph.edge_cost(pair[0].id, pair[1].id).expect("Missing edge"))
+        .sum();
+    assert!((path.total_cost - edge_costs).abs() < 1e-3);
+    assert!((path.euclidean_length - path_cost(&path.nodes)).abs() < 1e-3);
+    // Edge costs of OBJ navmeshes are the distances between the nodes
+    assert!((path.total_cost - path.euclidean_length).abs() < 1e-2);
+
+    // Smoothed segments without an edge count with their length
+    let smoothed = pathfinder.find_path_smooth(coords[0], coords[1]).unwrap();
+    assert!((smoothed.total_cost - smoothed.euclidean_length).abs() < 1e-2);
+    assert!(smoothed.total_cost <= path.total_cost + 1e-3);
+}
+
+#[test]
+fn test_update_graph_clears_cache() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap().nodes;
+    assert!(pathfinder.cache_len() > 0);
+
+    // Changes that keep the generation keep the cache
+    let cache_len = pathfinder.cache_len();
+    pathfinder.update_graph(|graph| graph.edge_cost(0, 1));
+    assert_eq!(pathfinder.cache_len(), cache_len);
+
+    // Clones see the change, graphs taken before keep the previous nodes
+    let removed = path[path.len() / 2].id;
+    let clone = pathfinder.clone();
+    let previous_graph = pathfinder.graph();
+    assert!(pathfinder.update_graph(|graph| graph.remove_node(removed)));
+    assert_eq!(pathfinder.cache_len(), 0);
+    assert_eq!(clone.graph().nodes.len(), previous_graph.nodes.len() - 1);
+    assert!(Arc::ptr_eq(&pathfinder.graph(), &clone.graph()));
+
+    // The new path avoids the removed node, whose position is now covered by other nodes
+    let removed = &previous_graph.nodes[removed];
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap().nodes;
+    assert!(path
+        .iter()
+        .all(|node| (node.x, node.y, node.z) != (removed.x, removed.y, removed.z)));
+}
+
+#[test]
+fn test_reload() {
+    let dir = std::env::temp_dir();
+    let first = dir.join("repath_reload_first.obj");
+    let second = dir.join("repath_reload_second.obj");
+    std::fs::write(&first, "v 0 0 0\nv 1 0 0\nv 0 0 1\nf 1 2 3\n").unwrap();
+    std::fs::write(&second, "v 0 0 0\nv 1 0 0\nv 0 0 1\nv 1 0 1\nf 1 2 3\nf 2 4 3\n").unwrap();
+    let settings = |navmesh: &std::path::Path| RePathSettings {
+        navmesh_filename: navmesh.to_str().unwrap().to_string(),
+        total_precompute_pairs: 10,
+        ..RePathSettings::default()
+    };
+
+    let pathfinder = RePathfinder::new(settings(&first)).expect("Failed to create pathfinder");
+    let zone = pathfinder.add_cost_zone((1.0, 0.0, 1.0), 0.5, 3.0);
+    pathfinder.find_path((0.0, 0.0, 0.0), (1.0, 0.0, 0.0)).unwrap();
+    let clone = pathfinder.clone();
+    let previous_graph = pathfinder.graph();
+    // Changing the graph keeps the clones together
+    pathfinder.update_graph(|graph| graph.set_edge_cost(0, 1, 2.0));
+
+    // Clones see the reload, graphs taken before keep the previous navmesh
+    clone.reload(settings(&second)).expect("Failed to reload");
+    assert_eq!(pathfinder.graph().nodes.len(), 4);
+    assert_eq!(clone.graph().nodes.len(), 4);
+    assert_eq!(previous_graph.nodes.len(), 3);
+    let path = pathfinder.find_path((0.0, 0.0, 0.0), (1.0, 0.0, 1.0)).unwrap();
+    assert_eq!(path.nodes.last().unwrap().id, 3);
+    // The metrics and the cost zone carry over
+    assert_eq!(pathfinder.metrics().calls, 2);
+    assert!(pathfinder.remove_cost_zone(zone));
+
+    // A failed reload keeps the current navmesh
+    let missing = dir.join("repath_reload_missing.obj");
+    assert!(matches!(pathfinder.reload(settings(&missing)), Err(RePathError::IoError(_))));
+    assert_eq!(pathfinder.graph().nodes.len(), 4);
+}
+
+#[test]
+fn test_reload_while_finding_paths() {
+    let dir = std::env::temp_dir();
+    let first = dir.join("repath_concurrent_reload_first.obj");
+    let second = dir.join("repath_concurrent_reload_second.obj");
+    std::fs::write(&first, "v 0 0 0\nv 1 0 0\nv 0 0 1\nf 1 2 3\n").unwrap();
+    std::fs::write(&second, "v 0 0 0\nv 1 0 0\nv 0 0 1\nv 1 0 1\nf 1 2 3\nf 2 4 3\n").unwrap();
+    let settings = |navmesh: &std::path::Path| RePathSettings {
+        navmesh_filename: navmesh.to_str().unwrap().to_string(),
+        total_precompute_pairs: 10,
+        ..RePathSettings::default()
+    };
+
+    let pathfinder = Arc::new(RePathfinder::new(settings(&first)).expect("Failed to create pathfinder"));
+    let done = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        let workers: Vec<_> = (0..4)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut queries = 0;
+                    while !done.load(Ordering::Relaxed) || queries == 0 {
+                        // Both navmeshes have a path between these nodes
+                        let path = pathfinder.find_path((0.0, 0.0, 0.0), (1.0, 0.0, 0.0)).unwrap();
+                        assert_eq!(path.nodes.len(), 2);
+                        queries += 1;
+                    }
+                })
+            })
+            .collect();
+
+        for navmesh in [&second, &first, &second] {
+            pathfinder.reload(settings(navmesh)).expect("Failed to reload");
+        }
+        done.store(true, Ordering::Relaxed);
+        for worker in workers {
+            worker.join().unwrap();
+        }
+    });
+
+    assert_eq!(pathfinder.graph().nodes.len(), 4);
+    let path = pathfinder.find_path((0.0, 0.0, 0.0), (1.0, 0.0, 1.0)).unwrap();
+    assert_eq!(path.nodes.last().unwrap().id, 3);
+}
+
+#[test]
+fn test_disk_cache_survives_restart() {
+    let dir = std::env::temp_dir();
+    let navmesh = dir.join("repath_disk_cache_navmesh.obj");
+    let db = dir.join("repath_disk_cache_pathfinder.rpdc");
+    let db = db.to_str().unwrap();
+    let _ = std::fs::remove_file(db);
+    std::fs::write(&navmesh, "v 0 0 0\nv 1 1 0\nv 0 0 1\nv 1 0 1\nf 1 2 3\nf 2 4 3\n").unwrap();
+    let settings = RePathSettings {
+        navmesh_filename: navmesh.to_str().unwrap().to_string(),
+        total_precompute_pairs: 0,
+        ..RePathSettings::default()
+    };
+
+    let pathfinder = RePathfinder::with_disk_cache(settings.clone(), db).expect("Failed to create pathfinder");
+    let path = pathfinder.find_path((0.0, 0.0, 0.0), (1.0, 0.0, 1.0)).unwrap();
+    let graph = pathfinder.graph();
+    drop(pathfinder);
+    let disk = DiskPathCache::open(db, &graph).expect("Failed to open disk cache");
+    assert_eq!(disk.get_path(&(0, 3)), Some(Some(Arc::clone(&path.nodes))));
+    drop(disk);
+
+    // The restarted pathfinder finds the path on disk and copies it into memory
+    let pathfinder = RePathfinder::with_disk_cache(settings.clone(), db).expect("Failed to reopen pathfinder");
+    assert_eq!(pathfinder.cache_len(), 0);
+    assert_eq!(pathfinder.find_path((0.0, 0.0, 0.0), (1.0, 0.0, 1.0)).unwrap().nodes, path.nodes);
+    assert_eq!(pathfinder.cache_len(), 1);
+
+    pathfinder.clear_cache();
+    drop(pathfinder);
+    assert!(DiskPathCache::open(db, &graph).unwrap().is_empty());
+    RePathfinder::with_disk_cache(settings.clone(), db).unwrap().find_path((0.0, 0.0, 0.0), (1.0, 0.0, 1.0)).unwrap();
+
+    // A disk cache of the same navmesh with other settings or of another navmesh is rejected
+    let steep = RePathSettings { slope_factor: 2.0, ..settings.clone() };
+    assert!(matches!(RePathfinder::with_disk_cache(steep, db), Err(RePathError::SerializationError(_))));
+    std::fs::write(&navmesh, "v 0 0 0\nv 1 1 0\nv 0 0 1\nv 1 0 2\nf 1 2 3\nf 2 4 3\n").unwrap();
+    assert!(matches!(RePathfinder::with_disk_cache(settings, db), Err(RePathError::SerializationError(_))));
+}
+
+#[test]
+fn test_new_with_progress() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        precompute_radius: 100.0,
+        total_precompute_pairs: 50,
+        ..RePathSettings::default()
+    };
+    let calls = AtomicUsize::new(0);
+    let max_completed = AtomicUsize::new(0);
+    RePathfinder::new_with_progress(settings, |completed, total| {
+        assert_eq!(total, 50);
+        calls.fetch_add(1, Ordering::Relaxed);
+        max_completed.fetch_max(completed, Ordering::Relaxed);
+    })
+    .expect("Failed to create pathfinder");
+
+    assert_eq!(calls.into_inner(), 50);
+    assert_eq!(max_completed.into_inner(), 50);
+}
+
+#[test]
+fn test_new_cancellable() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        precompute_radius: 100.0,
+        total_precompute_pairs: 100_000_000,
+        ..RePathSettings::default()
+    };
+    let cancel = Arc::new(AtomicBool::new(false));
+    let handle = {
+        let cancel = Arc::clone(&cancel);
+        std::thread::spawn(move || RePathfinder::new_cancellable(settings, cancel))
+    };
+    std::thread::sleep(Duration::from_millis(10));
+    cancel.store(true, Ordering::Relaxed);
+
+    let pathfinder = handle.join().unwrap().expect("Failed to create pathfinder");
+    assert!(pathfinder.cache_len() < 100_000_000);
+
+    // The partially warmed pathfinder is usable right away
+    let coords = node_coords(&[1000, 1650]);
+    assert!(pathfinder.find_path(coords[0], coords[1]).is_ok());
+}
+
+#[test]
+fn test_precompute_pairs_and_region() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        total_precompute_pairs: 0,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    assert_eq!(pathfinder.cache_len(), 0);
+
+    pathfinder.precompute_pairs(&[(1000, 1650), (1650, 1000), (1000, 1650)]);
+    assert_eq!(pathfinder.cache_len(), 2);
+    let coords = node_coords(&[1000, 1650]);
+    pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert_eq!(pathfinder.cache_len(), 2);
+
+    // Every ordered pair of the nodes in the region is cached
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let center = node_coords(&[20000])[0];
+    let region = repath::utils::nodes_within_radius(&graph, &graph.nodes[20000], 30.0).len();
+    assert!(region > 2);
+    pathfinder.precompute_region(center, 30.0);
+    assert_eq!(pathfinder.cache_len(), 2 + region * (region - 1));
+}
+
+#[test]
+fn test_max_search_nodes_setting() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        total_precompute_pairs: 0,
+        max_search_nodes: Some(10),
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new(settings.clone()).expect("Failed to create pathfinder");
+    let coords = node_coords(&[1000, 30000]);
+    assert!(matches!(pathfinder.find_path(coords[0], coords[1]), Err(RePathError::NoPath)));
+    assert_eq!(pathfinder.cache_len(), 0);
+
+    let settings = RePathSettings {
+        algorithm: Algorithm::Dijkstra,
+        ..settings
+    };
+    let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    assert!(matches!(pathfinder.find_path(coords[0], coords[1]), Err(RePathError::NoPath)));
+    assert!(create_pathfinder().find_path(coords[0], coords[1]).is_ok());
+}
+
+#[test]
+fn test_find_path_custom_cost() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+
+    // Doubling every cost keeps the route and doubles its cost
+    let double = pathfinder
+        .find_path_custom_cost(coords[0], coords[1], |edge| edge.cost * 2.0, 1)
+        .unwrap();
+    assert_eq!(double.nodes, path.nodes);
+    assert!((double.total_cost - path.total_cost * 2.0).abs() < 1e-2);
+
+    // Making the second node of the path prohibitively expensive to enter forces a detour
+    let avoided = path.nodes[1].id;
+    let cache_len = pathfinder.cache_len();
+    let detour = pathfinder
+        .find_path_custom_cost(
+            coords[0],
+            coords[1],
+            |edge| if edge.to == avoided { 1e6 } else { edge.cost * 2.0 },
+            2,
+        )
+        .unwrap();
+    assert!(detour.nodes.iter().all(|node| node.id != avoided));
+    assert!(detour.total_cost < 1e6);
+    assert_eq!(pathfinder.cache_len(), cache_len + 1);
+}
+
+#[test]
+fn test_block_and_unblock_nodes() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+
+    // Blocking a node of the cached path forces a detour around it
+    let blocked = path.nodes[path.nodes.len() / 2].id;
+    pathfinder.block_node(blocked);
+    let detour = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert!(detour.nodes.iter().all(|node| node.id != blocked));
+    assert!(detour.total_cost >= path.total_cost);
+
+    pathfinder.unblock_node(blocked);
+    let restored = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert_eq!(restored.nodes, path.nodes);
+
+    // Blocking a region around the goal makes it unreachable
+    pathfinder.block_region(coords[1], 30.0);
+    assert!(pathfinder.find_path(coords[0], coords[1]).is_err());
+}
+
+/// Creates a pathfinder for a diamond from node 0 to node 3, whose shorter side leads over node 1
+/// and longer side over node 2.
+fn create_diamond_pathfinder() -> RePathfinder {
+    let mut graph = Graph::new();
+    for (id, x, z) in [(0, 0.0, 0.0), (1, 5.0, 1.0), (2, 5.0, -3.0), (3, 10.0, 0.0)] {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    for (a, b) in [(0, 1), (1, 3), (0, 2), (2, 3)] {
+        let cost = graph.heuristic(a, b);
+        graph.add_edge(a, b, cost);
+        graph.add_edge(b, a, cost);
+    }
+    let settings = RePathSettings {
+        navmesh_filename: "does_not_exist.obj".to_string(),
+        ..RePathSettings::default()
+    };
+    RePathfinderBuilder::new(settings).with_graph(graph).skip_precomputation().build().unwrap()
+}
+
+#[test]
+fn test_blocked_nodes_with_searches_ignoring_them() {
+    let pathfinder = create_diamond_pathfinder();
+    let ids = |nodes: &[Node]| nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+    let (start, end) = ((0.0, 0.0, 0.0), (10.0, 0.0, 0.0));
+
+    pathfinder.block_node(1);
+    pathfinder.clear_cache();
+    // These searches ignore blocked nodes, their results must not reach `find_path`
+    assert_eq!(ids(&pathfinder.find_path_partial(start, end).unwrap().path), vec![0, 1, 3]);
+    assert_eq!(ids(&pathfinder.find_path(start, end).unwrap().nodes), vec![0, 2, 3]);
+
+    // Nor the other way around
+    assert_eq!(ids(&pathfinder.find_path_dijkstra(end, start).unwrap().nodes), vec![3, 2, 0]);
+    assert_eq!(ids(&pathfinder.find_path_partial(end, start).unwrap().path), vec![3, 1, 0]);
+
+    pathfinder.unblock_node(1);
+    assert_eq!(ids(&pathfinder.find_path(start, end).unwrap().nodes), vec![0, 1, 3]);
+}
+
+#[test]
+fn test_find_path_with_heuristic() {
+    let pathfinder = create_diamond_pathfinder();
+    let graph = pathfinder.graph();
+    let ids = |nodes: &[Node]| nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+    let (start, end) = ((0.0, 0.0, 0.0), (10.0, 0.0, 0.0));
+    let calls = AtomicUsize::new(0);
+    let manhattan = |a: usize, b: usize| {
+        calls.fetch_add(1, Ordering::Relaxed);
+        repath::heuristics::manhattan(&graph.nodes[a], &graph.nodes[b])
+    };
+
+    let path = pathfinder.find_path_with_heuristic(start, end, manhattan, 1).unwrap();
+    assert_eq!(ids(&path.nodes), vec![0, 1, 3]);
+    // Cached under the heuristic id, apart from `find_path`
+    let evaluated = calls.load(Ordering::Relaxed);
+    assert!(evaluated > 0);
+    assert_eq!(pathfinder.find_path_with_heuristic(start, end, manhattan, 1).unwrap().nodes, path.nodes);
+    assert_eq!(calls.load(Ordering::Relaxed), evaluated);
+    assert_eq!(pathfinder.cache_len(), 1);
+    pathfinder.find_path_with_heuristic(start, end, |_, _| 0.0, 2).unwrap();
+    assert_eq!(pathfinder.cache_len(), 2);
+
+    // Blocked nodes are avoided and the cached paths through them removed
+    pathfinder.block_node(1);
+    let path = pathfinder.find_path_with_heuristic(start, end, manhattan, 1).unwrap();
+    assert_eq!(ids(&path.nodes), vec![0, 2, 3]);
+    pathfinder.unblock_node(1);
+    assert_eq!(ids(&pathfinder.find_path_with_heuristic(start, end, manhattan, 1).unwrap().nodes), vec![0, 1, 3]);
+}
+
+#[test]
+fn test_blocked_nodes_follow_removed_nodes() {
+    // The diamond after an unconnected node 0
+    let mut graph = Graph::new();
+    for (id, x, z) in [(0, -50.0, 0.0), (1, 0.0, 0.0), (2, 5.0, 1.0), (3, 5.0, -3.0), (4, 10.0, 0.0)] {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    for (a, b) in [(1, 2), (2, 4), (1, 3), (3, 4)] {
+        let cost = graph.heuristic(a, b);
+        graph.add_edge(a, b, cost);
+        graph.add_edge(b, a, cost);
+    }
+    let settings = RePathSettings {
+        navmesh_filename: "does_not_exist.obj".to_string(),
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinderBuilder::new(settings).with_graph(graph).skip_precomputation().build().unwrap();
+    let clone = pathfinder.clone();
+    let positions = |nodes: &[Node]| nodes.iter().map(|node| (node.x, node.z)).collect::<Vec<_>>();
+    let (start, end) = ((0.0, 0.0, 0.0), (10.0, 0.0, 0.0));
+
+    pathfinder.block_node(2);
+    let detour = vec![(0.0, 0.0), (5.0, -3.0), (10.0, 0.0)];
+    assert_eq!(positions(&pathfinder.find_path(start, end).unwrap().nodes), detour);
+
+    // The node at (5, 1) is now node 1 and stays blocked, node 2 at (5, -3) does not
+    assert!(pathfinder.update_graph(|graph| graph.remove_node(0)));
+    assert_eq!(positions(&pathfinder.find_path(start, end).unwrap().nodes), detour);
+    assert_eq!(positions(&clone.find_path(start, end).unwrap().nodes), detour);
+
+    clone.unblock_node(1);
+    let shortest = vec![(0.0, 0.0), (5.0, 1.0), (10.0, 0.0)];
+    assert_eq!(positions(&pathfinder.find_path(start, end).unwrap().nodes), shortest);
+}
+
+#[test]
+fn test_cost_zones_with_searches_ignoring_them() {
+    let pathfinder = create_diamond_pathfinder();
+    let ids = |nodes: &[Node]| nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+    let (start, end) = ((0.0, 0.0, 0.0), (10.0, 0.0, 0.0));
+
+    // A swamp on the shorter side makes the longer side cheaper for `find_path`
+    let zone = pathfinder.add_cost_zone((5.0, 0.0, 1.0), 0.5, 10.0);
+    assert_eq!(ids(&pathfinder.find_path_partial(start, end).unwrap().path), vec![0, 1, 3]);
+    assert_eq!(ids(&pathfinder.find_path(start, end).unwrap().nodes), vec![0, 2, 3]);
+    assert_eq!(ids(&pathfinder.find_path_partial(start, end).unwrap().path), vec![0, 1, 3]);
+
+    assert!(pathfinder.remove_cost_zone(zone));
+    assert_eq!(ids(&pathfinder.find_path(start, end).unwrap().nodes), vec![0, 1, 3]);
+}
+
+#[test]
+fn test_cost_zones() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 30000]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+
+    // A costly zone in the middle of the path is cheaper to walk around than to cross
+    let middle = &path.nodes[path.nodes.len() / 2];
+    let zone = pathfinder.add_cost_zone((middle.x, middle.y, middle.z), 50.0, 100.0);
+    let detour = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert!(detour.nodes.iter().all(|node| node.id != middle.id));
+    assert!(detour.total_cost >= path.total_cost);
+    assert!(detour.total_cost < path.total_cost * 2.0);
+
+    // A zone around the goal cannot be avoided, so it is crossed and counted in the cost
+    let goal_zone = pathfinder.add_cost_zone(coords[1], 1.0, 10.0);
+    let crossing = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert_eq!(crossing.nodes.last().unwrap().id, 30000);
+    assert!(crossing.total_cost > detour.total_cost);
+
+    assert!(pathfinder.remove_cost_zone(zone));
+    assert!(pathfinder.remove_cost_zone(goal_zone));
+    assert!(!pathfinder.remove_cost_zone(zone));
+    let restored = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert!((restored.total_cost - path.total_cost).abs() < 1e-2);
+}
+
+#[test]
+fn test_metrics() {
+    let pathfinder = create_pathfinder();
+    pathfinder.reset_metrics();
+    // The precomputed paths are not reset
+    let precomputed_paths = pathfinder.metrics().precomputed_paths;
+    assert_eq!(
+        pathfinder.metrics(),
+        PathfindingMetrics {
+            precomputed_paths,
+            ..PathfindingMetrics::default()
+        }
+    );
+
+    let coords = node_coords(&[1000, 30000]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    pathfinder.find_path(coords[0], coords[1]).unwrap();
+
+    let metrics = pathfinder.metrics();
+    assert_eq!(metrics.calls, 2);
+    assert_eq!(metrics.cache_hits, 1);
+    assert_eq!(metrics.cache_misses, 1);
+    assert_eq!(metrics.total_nodes_in_paths, 2 * path.nodes.len() as u64);
+    assert!(metrics.average_duration > Duration::ZERO);
+
+    // Clones share the statistics
+    pathfinder.clone().reset_metrics();
+    assert_eq!(pathfinder.metrics().calls, 0);
+}
+
+#[test]
+fn test_record_path_metric() {
+    let metrics_csv = std::env::temp_dir().join("repath_path_metrics.csv");
+    let _ = std::fs::remove_file(&metrics_csv);
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        total_precompute_pairs: 0,
+        metrics_csv: Some(metrics_csv.to_str().unwrap().to_string()),
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new(settings).expect("Failed to create pathfinder");
+    let coords = node_coords(&[1000, 30000]);
+
+    let read_rows = || -> Vec<csv::StringRecord> {
+        let mut reader = csv::Reader::from_path(&metrics_csv).unwrap();
+        assert_eq!(reader.headers().unwrap().len(), 10);
+        reader.records().map(Result::unwrap).collect()
+    };
+    let path = pathfinder.record_path_metric(coords[0], coords[1]).unwrap();
+    assert_eq!(read_rows().len(), 1);
+    pathfinder.record_path_metric(coords[0], coords[1]).unwrap();
+
+    // The columns are timestamp, start, end, path_len, duration_us and cache_hit
+    let rows = read_rows();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0][7].parse::<usize>().unwrap(), path.nodes.len());
+    assert_eq!((&rows[0][9], &rows[1][9]), ("false", "true"));
+    assert_eq!(rows[1][1].parse::<Coord>().unwrap(), coords[0].0);
+    assert!(rows[0][0].parse::<u64>().unwrap() > 0);
+}
+
+#[test]
+fn test_builder_with_graph_and_cache() {
+    // A line of five nodes, built without any navmesh file
+    let points: Vec<(Coord, Coord, Coord)> = (0..5).map(|x| (x as Coord, 0.0, 0.0)).collect();
+    let graph = Graph::build_from_point_cloud(&points, 1.0);
+    let settings = RePathSettings {
+        navmesh_filename: "does_not_exist.obj".to_string(),
+        ..RePathSettings::default()
+    };
+
+    let pathfinder = RePathfinderBuilder::new(settings.clone())
+        .with_graph(graph.clone())
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    assert_eq!(pathfinder.cache_len(), 0);
+    let path = pathfinder.find_path((0.0, 0.0, 0.0), (4.0, 0.0, 0.0)).unwrap();
+    assert_eq!(path.nodes.len(), 5);
+
+    // An injected cache is used as it is, even with a made up path
+    let cache = LruPathCache::new(10);
+    let shortcut = Arc::new(vec![graph.nodes[0].clone(), graph.nodes[4].clone()]);
+    cache.insert_path((0, 4), Some(shortcut.clone()));
+    let pathfinder = RePathfinderBuilder::new(settings.clone())
+        .with_graph(graph)
+        .with_cache(cache)
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    assert_eq!(pathfinder.find_path((0.0, 0.0, 0.0), (4.0, 0.0, 0.0)).unwrap().nodes, shortcut);
+
+    // Without a graph the navmesh file is loaded
+    assert!(matches!(RePathfinderBuilder::new(settings).build(), Err(RePathError::IoError(_))));
+    assert!(matches!(
+        RePathfinderBuilder::new(RePathSettings::default()).with_graph(Graph::new()).build(),
+        Err(RePathError::EmptyGraph)
+    ));
+}
+
+#[test]
+fn test_find_path_avoiding_group() {
+    // Two routes from west to east, the shorter one through the north
+    let filename = std::env::temp_dir().join("repath_groups.obj");
+    let filename = filename.to_str().unwrap();
+    let obj = "\
+o west
+v 0 0 0
+v 0 0 0.5
+g north
+v 1 0 1
+g south
+v 1 0 -2
+o east
+v 2 0 0
+l 1 2
+l 1 3
+l 3 5
+l 1 4
+l 4 5
+";
+    std::fs::write(filename, obj).unwrap();
+
+    let graph = parse_obj(filename).expect("Failed to parse OBJ");
+    assert_eq!(graph.nodes_in_group("west"), vec![0, 1]);
+    assert_eq!(graph.nodes_in_group("north"), vec![2]);
+    assert_eq!(graph.nodes_in_group("east"), vec![4]);
+    assert!(graph.nodes_in_group("Plane").is_empty());
+    assert_eq!(parse_obj_lazy(filename, 10.0).unwrap().nodes_in_group("south"), vec![3]);
+
+    let settings = RePathSettings {
+        navmesh_filename: filename.to_string(),
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinderBuilder::new(settings).with_graph(graph).skip_precomputation().build().unwrap();
+    let ids = |path: PathResult| path.nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+    let (west, east) = ((0.0, 0.0, 0.0), (2.0, 0.0, 0.0));
+    assert_eq!(ids(pathfinder.find_path(west, east).unwrap()), vec![0, 2, 4]);
+    assert_eq!(ids(pathfinder.find_path_avoiding_group(west, east, "north").unwrap()), vec![0, 3, 4]);
+    assert!(matches!(pathfinder.find_path_avoiding_group(west, east, "east"), Err(RePathError::NoPath)));
+}
+
+#[test]
+fn test_find_path_filtered() {
+    // Two square floors, one above the other, connected by a single flight of stairs
+    let navmesh = std::env::temp_dir().join("repath_two_floors.obj");
+    std::fs::write(
+        &navmesh,
+        "v 0 0 0\nv 10 0 0\nv 10 0 10\nv 0 0 10\nv 0 5 0\nv 10 5 0\nv 10 5 10\nv 0 5 10\n\
+         f 1 2 3\nf 1 3 4\nf 5 6 7\nf 5 7 8\ng stairs\nl 3 7\n",
+    )
+    .unwrap();
+    let settings = RePathSettings {
+        navmesh_filename: navmesh.to_str().unwrap().to_string(),
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinderBuilder::new(settings).skip_precomputation().build().unwrap();
+
+    let (ground, upstairs) = ((0.0, 0.0, 0.0), (0.0, 5.0, 10.0));
+    assert!(matches!(
+        pathfinder.find_path_filtered(ground, upstairs, &[EdgeKind::Walk, EdgeKind::Elevator]),
+        Err(RePathError::NoPath)
+    ));
+    let path = pathfinder
+        .find_path_filtered(ground, upstairs, &[EdgeKind::Walk, EdgeKind::Stairs])
+        .unwrap();
+    let ids: Vec<usize> = path.nodes.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 2, 6, 7]);
+
+    // The results are cached per set of kinds, regardless of their order
+    assert_eq!(pathfinder.cache_len(), 2);
+    assert!(pathfinder.find_path_filtered(ground, upstairs, &[EdgeKind::Stairs, EdgeKind::Walk]).is_ok());
+    assert_eq!(pathfinder.cache_len(), 2);
+    assert!(matches!(
+        pathfinder.find_path_filtered(ground, upstairs, &[EdgeKind::Walk]),
+        Err(RePathError::NoPath)
+    ));
+
+    // Each floor can still be crossed without the stairs
+    assert!(pathfinder.find_path_filtered(ground, (10.0, 0.0, 10.0), &[EdgeKind::Walk]).is_ok());
+}
+
+#[test]
+fn test_find_path_with_label_filter() {
+    // A short swim across a lake and a longer walk around it
+    let mut graph = Graph::new();
+    for (id, x, z) in [(0, 0.0, 0.0), (1, 10.0, 0.0), (2, 5.0, 5.0)] {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    let swimming = vec!["swimming".to_string()];
+    graph.add_edge_with_labels(0, 1, 10.0, swimming.clone());
+    graph.add_edge_with_labels(1, 0, 10.0, swimming);
+    for (from, to) in [(0, 2), (2, 0), (2, 1), (1, 2)] {
+        graph.add_edge(from, to, 7.5);
+    }
+    let settings = RePathSettings {
+        navmesh_filename: "does_not_exist.obj".to_string(),
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinderBuilder::new(settings).with_graph(graph).skip_precomputation().build().unwrap();
+    let ids = |path: PathResult| path.nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+
+    let (start, end) = ((0.0, 0.0, 0.0), (10.0, 0.0, 0.0));
+    assert_eq!(ids(pathfinder.find_path_with_label_filter(start, end, &[], &[]).unwrap()), vec![0, 1]);
+    assert_eq!(ids(pathfinder.find_path_with_label_filter(start, end, &[], &["swimming"]).unwrap()), vec![0, 2, 1]);
+    assert_eq!(ids(pathfinder.find_path_with_label_filter(start, end, &["swimming"], &[]).unwrap()), vec![0, 1]);
+    assert!(matches!(
+        pathfinder.find_path_with_label_filter(start, end, &["flying"], &[]),
+        Err(RePathError::NoPath)
+    ));
+
+    // Every set of labels is cached on its own
+    assert_eq!(pathfinder.cache_len(), 4);
+    assert_eq!(ids(pathfinder.find_path_with_label_filter(start, end, &[], &["swimming"]).unwrap()), vec![0, 2, 1]);
+    assert_eq!(pathfinder.cache_len(), 4);
+}
+
+#[test]
+fn test_find_path_with_radius() {
+    // A direct line element through a narrow gap and a three times longer detour around it
+    let navmesh = std::env::temp_dir().join("repath_bottleneck.obj");
+    std::fs::write(
+        &navmesh,
+        "v 0 0 0\nv 10 0 0\nv 0 0 10\nv 10 0 10\nl 1 3\nl 3 4\nl 4 2\nusemtl clearance_1.0\nl 1 2\n",
+    )
+    .unwrap();
+    let settings = RePathSettings {
+        navmesh_filename: navmesh.to_str().unwrap().to_string(),
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinderBuilder::new(settings).skip_precomputation().build().unwrap();
+
+    let (start, end) = ((0.0, 0.0, 0.0), (10.0, 0.0, 0.0));
+    let small = pathfinder.find_path_with_radius(start, end, 0.5).unwrap();
+    assert_eq!(small.nodes.iter().map(|node| node.id).collect::<Vec<_>>(), vec![0, 1]);
+    let large = pathfinder.find_path_with_radius(start, end, 2.0).unwrap();
+    assert_eq!(large.nodes.iter().map(|node| node.id).collect::<Vec<_>>(), vec![0, 2, 3, 1]);
+    assert_eq!(large.total_cost, 30.0);
+
+    // An agent exactly as wide as the gap still fits through
+    assert_eq!(pathfinder.find_path_with_radius(start, end, 1.0).unwrap().nodes.len(), 2);
+}
+
+#[test]
+fn test_slope_factor_setting() {
+    // From the middle node, a flat edge and a vertical edge of the same length
+    let points = [(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (1.0, 1.0, 0.0)];
+    let graph = Graph::build_from_point_cloud(&points, 1.0);
+    let settings = RePathSettings {
+        navmesh_filename: "does_not_exist.obj".to_string(),
+        slope_factor: 1.0,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinderBuilder::new(settings).with_graph(graph).skip_precomputation().build().unwrap();
+
+    assert_eq!(pathfinder.graph().edge_cost(1, 0), Some(1.0));
+    assert_eq!(pathfinder.graph().edge_cost(1, 2), Some(2.0));
+    let climb = pathfinder.find_path((1.0, 0.0, 0.0), (1.0, 1.0, 0.0)).unwrap();
+    assert_eq!(climb.total_cost, 2.0);
+}
+
+#[test]
+fn test_two_d_mode() {
+    // Two routes around a square, one over a node on a high pillar and one around a node that is
+    // further away on the ground
+    let mut graph = Graph::new();
+    for (id, (x, y, z)) in [(0.0, 0.0, 0.0), (1.0, 5.0, 0.0), (2.0, 0.0, 0.0), (1.0, 0.0, 1.2)].into_iter().enumerate() {
+        graph.add_node(Node::new(id, x, y, z));
+    }
+    for (a, b) in [(0, 1), (1, 2), (0, 3), (3, 2)] {
+        graph.add_edge(a, b, graph.heuristic(a, b));
+        graph.add_edge(b, a, graph.heuristic(a, b));
+    }
+    let build = |mode| {
+        let settings = RePathSettings {
+            navmesh_filename: "does_not_exist.obj".to_string(),
+            mode,
+            ..RePathSettings::default()
+        };
+        RePathfinderBuilder::new(settings).with_graph(graph.clone()).skip_precomputation().build().unwrap()
+    };
+    let ids = |path: PathResult| path.nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+
+    let three_d = build(GraphMode::ThreeD);
+    assert_eq!(ids(three_d.find_path((0.0, 0.0, 0.0), (2.0, 0.0, 0.0)).unwrap()), vec![0, 3, 2]);
+
+    // Without heights the pillar is the shorter route, at a cost of 1 per edge
+    let two_d = build(GraphMode::TwoD);
+    let path = two_d.find_path((0.0, 0.0, 0.0), (2.0, 0.0, 0.0)).unwrap();
+    assert_eq!(ids(path.clone()), vec![0, 1, 2]);
+    assert_eq!(path.total_cost, 2.0);
+    assert!(path.nodes.iter().all(|node| node.y == 0.0));
+    // The height of the query is ignored when looking for the nearest node
+    let path = two_d.find_path((0.1, -40.0, 0.0), (1.1, 80.0, 1.0)).unwrap();
+    assert_eq!(ids(path), vec![0, 3]);
+}
+
+#[test]
+fn test_find_path_hierarchical() {
+    let pathfinder = create_pathfinder();
+    let graph = pathfinder.graph();
+    let mut rng = StdRng::seed_from_u64(5);
+    for _ in 0..10 {
+        let coords = node_coords(&[rng.gen_range(0..graph.nodes.len()), rng.gen_range(0..graph.nodes.len())]);
+        let expected = pathfinder.find_path_a_star(coords[0], coords[1]).unwrap();
+        let path = pathfinder.find_path_hierarchical(coords[0], coords[1], 200.0).unwrap();
+        assert_eq!(path.nodes.first(), expected.nodes.first());
+        assert_eq!(path.nodes.last(), expected.nodes.last());
+        assert!(path.nodes.windows(2).all(|pair| graph.edge_cost(pair[0].id, pair[1].id).is_some()));
+        assert!(path.total_cost >= expected.total_cost - 1e-2);
+    }
+
+    // Without any path, there is no hierarchical path either
+    let mut islands = Graph::new();
+    for id in 0..4 {
+        islands.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    islands.connect_nodes(0, 1, 1.0);
+    islands.connect_nodes(2, 3, 1.0);
+    let pathfinder = RePathfinderBuilder::new(RePathSettings::default())
+        .with_graph(islands)
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    let result = pathfinder.find_path_hierarchical((0.0, 0.0, 0.0), (3.0, 0.0, 0.0), 2.0);
+    assert!(matches!(result, Err(RePathError::NoPath)));
+}
+
+#[test]
+fn test_find_path_through_waypoints() {
+    let pathfinder = create_pathfinder();
+    let ids = [1000, 5030, 3400, 1650];
+    let coords = node_coords(&ids);
+    let path = pathfinder.find_path_through_waypoints(coords[0], &coords[1..3], coords[3]).unwrap();
+
+    // The waypoints are visited in order and the segments are joined without repeating nodes
+    let positions: Vec<usize> = ids
+        .iter()
+        .map(|&id| path.nodes.iter().position(|node| node.id == id).unwrap())
+        .collect();
+    assert_eq!(positions[0], 0);
+    assert_eq!(positions[3], path.nodes.len() - 1);
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(path.nodes.windows(2).all(|pair| pair[0].id != pair[1].id));
+    let segments = [(0, 1), (1, 2), (2, 3)].map(|(a, b)| pathfinder.find_path(coords[a], coords[b]).unwrap());
+    assert_eq!(path.nodes.len(), segments.iter().map(|segment| segment.nodes.len()).sum::<usize>() - 2);
+
+    // Without waypoints this is a plain path
+    let direct = pathfinder.find_path_through_waypoints(coords[0], &[], coords[3]).unwrap();
+    assert_eq!(direct.nodes, pathfinder.find_path(coords[0], coords[3]).unwrap().nodes);
+
+    // A waypoint on another island cannot be reached
+    let mut islands = Graph::new();
+    for id in 0..4 {
+        islands.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    islands.connect_nodes(0, 1, 1.0);
+    islands.connect_nodes(2, 3, 1.0);
+    let pathfinder = RePathfinderBuilder::new(RePathSettings::default())
+        .with_graph(islands)
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    let result = pathfinder.find_path_through_waypoints((0.0, 0.0, 0.0), &[(3.0, 0.0, 0.0)], (1.0, 0.0, 0.0));
+    assert!(matches!(result, Err(RePathError::NoPath)));
+}
+
+#[test]
+fn test_find_path_sliced() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 5030]);
+    let mut sliced = pathfinder.find_path_sliced(coords[0], coords[1], 10).unwrap();
+    let mut calls = 1;
+    let path = loop {
+        if let Some(result) = sliced.advance() {
+            break result.unwrap();
+        }
+        calls += 1;
+    };
+    assert!(calls > 1);
+    assert_eq!(path.nodes, pathfinder.find_path(coords[0], coords[1]).unwrap().nodes);
+    assert_eq!(sliced.advance().unwrap().unwrap().nodes, path.nodes);
+}
+
+#[test]
+fn test_raycast() {
+    let pathfinder = create_pathfinder();
+    let node = pathfinder.graph().nodes[1000].clone();
+    let (hit, distance) = pathfinder.raycast((node.x, node.y + 50.0, node.z), (0.0, -1.0, 0.0)).expect("No hit");
+    // The node is a corner of the hit triangle, and the mesh is close to it below the origin
+    assert_eq!(hit, node.id);
+    assert!((distance - 50.0).abs() < 5.0);
+    assert_eq!(pathfinder.raycast((node.x, node.y + 50.0, node.z), (0.0, 1.0, 0.0)), None);
+}
+
+#[test]
+fn test_sphere_cast() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[1000, 1650]);
+    let nodes = pathfinder.sphere_cast(coords[0], coords[1], 40.0);
+    assert!(nodes.contains(&1000) && nodes.contains(&1650));
+    assert_eq!(nodes, pathfinder.graph().sphere_cast(coords[0], coords[1], 40.0));
+}
+
+#[test]
+fn test_find_path_avoiding() {
+    // Two pairs of nodes joined by the single corridor node 2
+    let mut graph = Graph::new();
+    for id in 0..5 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, (id % 2) as Coord));
+    }
+    for (a, b) in [(0, 1), (0, 2), (1, 2), (2, 3), (2, 4), (3, 4)] {
+        graph.connect_nodes(a, b, 1.0);
+    }
+    let cache = DashMap::new();
+    assert!(graph.a_star_avoiding(0, 4, &[2], 7, &cache).is_none());
+    assert!(graph.a_star_avoiding(0, 4, &[1, 3], 8, &cache).is_some());
+    assert_eq!(cache.len(), 2);
+
+    let pathfinder = RePathfinderBuilder::new(RePathSettings::default())
+        .with_graph(graph)
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    let (start, end, corridor) = ((0.0, 0.0, 0.0), (4.0, 0.0, 0.0), (2.1, 0.0, 0.1));
+    assert!(matches!(pathfinder.find_path_avoiding(start, end, &[corridor]), Err(RePathError::NoPath)));
+    // Other queries are not affected, and neither is the cached failure by other avoided nodes
+    assert_eq!(pathfinder.find_path(start, end).unwrap().nodes.len(), 3);
+    assert_eq!(pathfinder.find_path_avoiding(start, end, &[(1.0, 0.0, 1.0)]).unwrap().nodes.len(), 3);
+    assert!(pathfinder.find_path_avoiding(start, end, &[corridor, corridor]).is_err());
+    assert_eq!(pathfinder.cache_len(), 3);
+}
+
+#[test]
+fn test_find_paths_batch() {
+    let pathfinder = create_pathfinder();
+    let sequential = create_pathfinder();
+    let mut rng = StdRng::seed_from_u64(3);
+    let node_count = pathfinder.graph().nodes.len();
+    // Random pairs at most ten rows of the grid apart, to keep the searches short
+    let mut queries: Vec<_> = (0..100)
+        .map(|_| {
+            let start = rng.gen_range(0..node_count - 2000);
+            let coords = node_coords(&[start, start + rng.gen_range(0..2000)]);
+            (coords[0], coords[1])
+        })
+        .collect();
+    // Repeated queries are searched once but answered in every position
+    queries.push(queries[0]);
+
+    let results = pathfinder.find_paths_batch(&queries);
+    assert_eq!(results.len(), queries.len());
+    for (result, &(start, end)) in results.iter().zip(&queries) {
+        let expected = sequential.find_path(start, end).unwrap();
+        assert_eq!(result.as_ref().unwrap().nodes, expected.nodes);
+    }
+
+    let results = pathfinder.find_paths_batch_multithreaded(&queries[..10], 2);
+    for (result, &(start, end)) in results.iter().zip(&queries) {
+        let expected = sequential.find_path_multithreaded(start, end, 2).unwrap();
+        assert_eq!(result.as_ref().unwrap().nodes, expected.nodes);
+    }
+}
+
+#[test]
+fn test_new_lazy_loads_regions_on_demand() {
+    let settings = RePathSettings {
+        navmesh_filename: "navmesh_varied.obj".to_string(),
+        lazy_chunk_size: 200.0,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinder::new_lazy(settings).expect("Failed to create pathfinder");
+    let clone = pathfinder.clone();
+    let coords = node_coords(&[0, 5 * 201 + 5]);
+    assert_eq!(pathfinder.graph().nodes.len(), 201 * 201);
+    assert!(pathfinder.graph().edges.iter().all(Vec::is_empty));
+    // Searches between node ids do not load regions
+    assert!(matches!(pathfinder.find_path_by_id(0, 5 * 201 + 5), Err(RePathError::NoPath)));
+
+    let path = pathfinder.find_path(coords[0], coords[1]).expect("No path found");
+    assert!(pathfinder.graph().is_region_loaded((coords[0], coords[1])));
+    assert!(clone.graph().is_region_loaded((coords[0], coords[1])));
+    assert!(pathfinder.find_path_by_id(0, 5 * 201 + 5).is_ok());
+    let loaded_edges: usize = pathfinder.graph().edges.iter().map(Vec::len).sum();
+    let all_edges: usize = parse_obj("navmesh_varied.obj").unwrap().edges.iter().map(Vec::len).sum();
+    assert!(loaded_edges > 0 && loaded_edges < all_edges / 10);
+
+    // The path stays within the loaded region and is as cheap as on the fully loaded navmesh
+    let expected = create_pathfinder().find_path(coords[0], coords[1]).expect("No path found");
+    assert!((path.total_cost - expected.total_cost).abs() < 1e-3);
+}
+
+#[test]
+fn test_diagnostic_report() {
+    // A triangle with a one-way shortcut and two isolated nodes
+    let mut graph = Graph::new();
+    for id in 0..5 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for (from, to, cost) in [(0, 1, 1.0), (1, 0, 1.0), (1, 2, 3.0), (2, 1, 3.0), (0, 2, 5.0)] {
+        graph.add_edge(from, to, cost);
+    }
+    let settings = RePathSettings {
+        navmesh_filename: "does_not_exist.obj".to_string(),
+        cache_capacity: 8,
+        ..RePathSettings::default()
+    };
+    let pathfinder = RePathfinderBuilder::new(settings).with_graph(graph).skip_precomputation().build().unwrap();
+    pathfinder.find_path((0.0, 0.0, 0.0), (2.0, 0.0, 0.0)).unwrap();
+    pathfinder.find_path((2.0, 0.0, 0.0), (0.0, 0.0, 0.0)).unwrap();
+
+    let report = pathfinder.diagnostic_report();
+    assert_eq!(report.node_count, 5);
+    assert_eq!(report.edge_count, 5);
+    assert_eq!(report.isolated_node_count, 2);
+    assert_eq!(report.component_count, 3);
+    assert_eq!(report.density, 0.25);
+    assert_eq!((report.min_edge_cost, report.max_edge_cost), (1.0, 5.0));
+    assert!((report.average_edge_cost - 2.6).abs() < 1e-5);
+    assert_eq!(report.cache_warm_percentage, 25.0);
+    let expensive: Vec<(usize, usize, Coord)> =
+        report.most_expensive_edges.iter().map(|edge| (edge.from, edge.to, edge.cost)).collect();
+    assert_eq!(expensive, vec![(0, 2, 5.0), (1, 2, 3.0), (2, 1, 3.0), (0, 1, 1.0), (1, 0, 1.0)]);
+
+    let text = report.to_string();
+    assert!(text.contains("Isolated nodes: 2"));
+    assert!(text.contains("Cache warm: 25.0%"));
+    assert!(text.contains("0 -> 2: 5"));
+    let json: serde_json::Value = serde_json::to_value(&report).unwrap();
+    assert_eq!(json["component_count"], 3);
+    assert_eq!(json["most_expensive_edges"][0]["to"], 2);
+
+    // Only the ten most expensive of the twelve edges of a complete graph are listed
+    let points = [(0.0, 0.0, 0.0), (1.0, 0.0, 0.0), (0.0, 0.0, 1.0), (1.0, 0.0, 1.0)];
+    let settings = RePathSettings {
+        navmesh_filename: "does_not_exist.obj".to_string(),
+        ..RePathSettings::default()
+    };
+    let complete = RePathfinderBuilder::new(settings)
+        .with_graph(Graph::build_from_point_cloud(&points, 2.0))
+        .skip_precomputation()
+        .build()
+        .unwrap();
+    let report = complete.diagnostic_report();
+    assert_eq!((report.edge_count, report.isolated_node_count, report.component_count), (12, 0, 1));
+    assert_eq!(report.density, 1.0);
+    assert_eq!(report.most_expensive_edges.len(), 10);
+    assert!(report.most_expensive_edges[..4].iter().all(|edge| edge.cost == report.max_edge_cost));
+}
+
+#[test]
+fn test_find_path_by_id() {
+    let pathfinder = create_pathfinder();
+    let graph = pathfinder.graph();
+    let mut rng = StdRng::seed_from_u64(94);
+    for _ in 0..20 {
+        let (start, end) = (rng.gen_range(0..graph.nodes.len()), rng.gen_range(0..graph.nodes.len()));
+        let coords = |id: usize| (graph.nodes[id].x, graph.nodes[id].y, graph.nodes[id].z);
+        let (start_coords, end_coords) = (coords(start), coords(end));
+        assert_eq!(pathfinder.nearest_node_id(start_coords.0, start_coords.1, start_coords.2), Some(start));
+
+        let ids = |path: PathResult| path.nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+        let by_id = pathfinder.find_path_by_id(start, end).map(ids);
+        assert_eq!(by_id.ok(), pathfinder.find_path(start_coords, end_coords).map(ids).ok());
+    }
+    assert!(matches!(pathfinder.find_path_by_id(0, graph.nodes.len()), Err(RePathError::NoPath)));
+}
+
+#[test]
+fn test_find_path_within_budget() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[0, 40]);
+    let path = pathfinder.find_path(coords[0], coords[1]).unwrap();
+    assert!(path.total_cost > 0.0);
+
+    let ids = |path: PathResult| path.nodes.iter().map(|node| node.id).collect::<Vec<_>>();
+    let within = pathfinder.find_path_within_budget(coords[0], coords[1], path.total_cost).unwrap();
+    assert_eq!(ids(within), ids(path.clone()));
+    assert!(matches!(
+        pathfinder.find_path_within_budget(coords[0], coords[1], path.total_cost * 0.9),
+        Err(RePathError::NoPath)
+    ));
+}
+
+#[test]
+fn test_find_path_stochastic() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[0, 40]);
+    let cheapest = pathfinder.find_path(coords[0], coords[1]).unwrap();
+
+    let cold = pathfinder.find_path_stochastic(coords[0], coords[1], 0.0).unwrap();
+    assert!((cold.total_cost - cheapest.total_cost).abs() < 1e-3);
+    let hot = pathfinder.find_path_stochastic(coords[0], coords[1], 10.0).unwrap();
+    assert_eq!(hot.nodes.first().unwrap().id, cheapest.nodes.first().unwrap().id);
+    assert_eq!(hot.nodes.last().unwrap().id, cheapest.nodes.last().unwrap().id);
+    assert!(hot.total_cost >= cheapest.total_cost - 1e-3);
+}
+
+#[test]
+fn test_reachable_from() {
+    let pathfinder = create_pathfinder();
+    let coords = node_coords(&[0])[0];
+    let reachable = pathfinder.reachable_from(coords, 5.0);
+    assert_eq!(reachable[0], (0, 0.0));
+    assert!(reachable.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+    assert!(reachable.iter().all(|&(_, cost)| cost <= 5.0));
+
+    // Every reachable node agrees with the cost of a search
+    let graph = pathfinder.graph();
+    for &(id, cost) in reachable.iter().take(10) {
+        let path = graph.dijkstra(0, id, &DashMap::new()).unwrap();
+        let result = PathResult::new(path, &graph);
+        assert!((result.total_cost - cost).abs() < 1e-3);
+    }
+    assert!(reachable.len() < pathfinder.reachable_from(coords, 50.0).len());
+}
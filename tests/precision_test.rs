@@ -0,0 +1,39 @@
+#![cfg(feature = "precision-f64")]
+
+use dashmap::DashMap;
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::utils::{parse_graph_binary, save_graph_binary};
+
+/// Two nodes 0.1 apart, a million units away from the origin.
+fn build_distant_pair() -> Graph {
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 1_000_000.0, 0.0, -1_000_000.0));
+    graph.add_node(Node::new(1, 1_000_000.1, 0.0, -1_000_000.0));
+    graph.add_edge(0, 1, graph.heuristic(0, 1));
+    graph
+}
+
+#[test]
+fn test_distance_far_from_origin() {
+    let graph = build_distant_pair();
+    assert!((graph.heuristic(0, 1) - 0.1).abs() < 1e-9);
+    let path = graph.a_star(0, 1, &DashMap::new()).unwrap();
+    assert_eq!(path.len(), 2);
+
+    // At this distance from the origin f32 cannot tell positions 0.1 apart
+    let error = ((1_000_000.1f32 - 1_000_000.0f32) - 0.1).abs();
+    assert!(error > 1e-3);
+}
+
+#[test]
+fn test_binary_round_trip_keeps_precision() {
+    let graph = build_distant_pair();
+    let filename = std::env::temp_dir().join("repath_precision.rpnav");
+    let filename = filename.to_str().unwrap();
+    save_graph_binary(&graph, filename).unwrap();
+
+    let loaded = parse_graph_binary(filename).unwrap();
+    assert_eq!(loaded.nodes[1].x, 1_000_000.1);
+    assert_eq!(loaded, graph);
+}
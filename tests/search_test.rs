@@ -0,0 +1,1343 @@
+use std::collections::BinaryHeap;
+use dashmap::DashMap;
+use repath::contraction_hierarchy::ContractionHierarchy;
+use repath::d_star::DStarLite;
+use repath::error::RePathError;
+use repath::graph::{Graph, State, StepResult};
+use repath::heap::IndexedBinaryHeap;
+use repath::heuristics;
+use repath::jps::GridLayout;
+use repath::node::Node;
+use repath::path::{self, Path};
+use repath::triangles::TriangleMesh;
+use repath::utils::parse_obj;
+use repath::Coord;
+
+/// Builds a graph where the cheapest route detours through a node that is far away in space,
+/// so the Euclidean heuristic overestimates the remaining cost.
+fn build_non_geometric_graph() -> Graph {
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::new(1, 100.0, 0.0, 0.0));
+    graph.add_node(Node::new(2, 2.0, 0.0, 0.0));
+
+    graph.add_edge(0, 2, 10.0);
+    graph.add_edge(0, 1, 1.0);
+    graph.add_edge(1, 2, 1.0);
+    graph
+}
+
+#[test]
+fn test_a_star_budgeted() {
+    let graph = build_non_geometric_graph();
+    let cache = DashMap::new();
+    let ids = |path: Path| path.iter().map(|node| node.id).collect::<Vec<_>>();
+
+    // The cheapest path costs 2, the direct edge 10
+    assert_eq!(graph.a_star_budgeted(0, 2, 2.0, &cache).map(ids), Some(vec![0, 1, 2]));
+    // 1.5 shares the cache entry of 2, whose path is too expensive for it
+    assert_eq!(graph.a_star_budgeted(0, 2, 1.5, &cache), None);
+    assert_eq!(graph.a_star_budgeted(0, 2, 3.0, &cache).map(ids), Some(vec![0, 1, 2]));
+    assert_eq!(cache.len(), 2);
+
+    assert_eq!(graph.a_star_budgeted(0, 0, 0.0, &cache).map(ids), Some(vec![0]));
+    assert_eq!(graph.a_star_budgeted(0, 2, -1.0, &cache), None);
+    // The edges are one-way, so no budget is enough to go back
+    assert_eq!(graph.a_star_budgeted(2, 0, Coord::INFINITY, &cache), None);
+}
+
+#[test]
+fn test_a_star_directed() {
+    // From the start in the south west to the goal in the north east, a shorter route that first
+    // heads south and a longer one that first heads north
+    let mut graph = Graph::new();
+    for (id, x, z) in [(0, 0.0, 0.0), (1, 10.0, 5.0), (2, 5.0, -2.0), (3, 0.0, 5.0)] {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    for (a, b) in [(0, 2), (2, 1), (0, 3), (3, 1)] {
+        let cost = graph.heuristic(a, b);
+        graph.add_edge(a, b, cost);
+        graph.add_edge(b, a, cost);
+    }
+    let ids = |path: Path| path.iter().map(|node| node.id).collect::<Vec<_>>();
+    let north = (0.0, 0.0, 1.0);
+    let cone = std::f64::consts::FRAC_PI_3 as Coord;
+
+    // Searches in different directions share the cache
+    let cache = DashMap::new();
+    let shortcut = Some(vec![0, 2, 1]);
+    assert_eq!(graph.a_star(0, 1, &DashMap::new()).map(ids), shortcut);
+    assert_eq!(graph.a_star_directed(0, 1, north, cone, 0.0, &cache).map(ids), shortcut);
+    assert_eq!(graph.a_star_directed(0, 1, north, cone, 10.0, &cache).map(ids), Some(vec![0, 3, 1]));
+    // Heading south makes the shortcut even more attractive
+    assert_eq!(graph.a_star_directed(0, 1, (0.0, 0.0, -1.0), cone, 10.0, &cache).map(ids), shortcut);
+    assert_eq!(graph.a_star_directed(0, 1, north, cone, 10.0, &cache).map(ids), Some(vec![0, 3, 1]));
+    assert_eq!(cache.len(), 3);
+}
+
+#[test]
+fn test_dijkstra_finds_cheapest_path() {
+    let graph = build_non_geometric_graph();
+    let cache = DashMap::new();
+
+    let path = graph.dijkstra(0, 2, &cache).expect("No path found");
+    let ids: Vec<usize> = path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 1, 2]);
+
+    // The result should be cached under the same key scheme as A*
+    assert!(cache.contains_key(&(0, 2)));
+}
+
+#[test]
+fn test_dijkstra_no_path() {
+    let graph = build_non_geometric_graph();
+    let cache = DashMap::new();
+
+    assert!(graph.dijkstra(2, 0, &cache).is_none());
+    assert!(cache.get(&(2, 0)).unwrap().is_none());
+}
+
+#[test]
+fn test_a_star_with_zero_heuristic_matches_dijkstra() {
+    let graph = build_non_geometric_graph();
+
+    let path = graph
+        .a_star_with_heuristic(0, 2, 0, &DashMap::new(), |a, b| {
+            heuristics::zero(&graph.nodes[a], &graph.nodes[b])
+        })
+        .expect("No path found");
+    let expected = graph.dijkstra(0, 2, &DashMap::new()).expect("No path found");
+    assert_eq!(path, expected);
+}
+
+#[test]
+fn test_a_star_with_heuristic_caches_per_heuristic() {
+    let graph = build_non_geometric_graph();
+    let cache = DashMap::new();
+    let ids = |path: Path| path.iter().map(|node| node.id).collect::<Vec<_>>();
+    let euclidean = |a: usize, b: usize| heuristics::euclidean(&graph.nodes[a], &graph.nodes[b]);
+
+    // The distance of 98 from node 1 overestimates its edge cost of 1, so the detour is missed
+    assert_eq!(graph.a_star_with_heuristic(0, 2, 1, &cache, euclidean).map(ids), Some(vec![0, 2]));
+    // Another heuristic does not get the suboptimal path from the cache
+    assert_eq!(graph.a_star_with_heuristic(0, 2, 2, &cache, |_, _| 0.0).map(ids), Some(vec![0, 1, 2]));
+    assert_eq!(graph.a_star_with_heuristic(0, 2, 1, &cache, |_, _| 0.0).map(ids), Some(vec![0, 2]));
+}
+
+#[test]
+fn test_heuristics() {
+    let a = Node::new(0, 0.0, 0.0, 0.0);
+    let b = Node::new(1, 3.0, -4.0, 12.0);
+
+    assert_eq!(heuristics::euclidean(&a, &b), 13.0);
+    assert_eq!(heuristics::manhattan(&a, &b), 19.0);
+    assert_eq!(heuristics::chebyshev(&a, &b), 12.0);
+    assert_eq!(heuristics::zero(&a, &b), 0.0);
+}
+
+/// Sums the cheapest edge between each pair of consecutive nodes, panicking if the path is not
+/// contiguous.
+fn path_cost(graph: &Graph, path: &[Node]) -> Coord {
+    path.windows(2)
+        .map(|pair| {
+            graph.edges[pair[0].id]
+                .iter()
+                .filter(|edge| edge.to == pair[1].id)
+                .map(|edge| edge.cost)
+                .min_by(|a, b| a.total_cmp(b))
+                .unwrap_or_else(|| panic!("No edge from {} to {}", pair[0].id, pair[1].id))
+        })
+        .sum()
+}
+
+#[test]
+fn test_a_star_bidirectional_grid() {
+    // 5x5 grid with a wall in the middle column that is only open at the top
+    let mut graph = Graph::new();
+    for y in 0..5 {
+        for x in 0..5 {
+            graph.add_node(Node::new(y * 5 + x, x as Coord, y as Coord, 0.0));
+        }
+    }
+    for y in 0..5 {
+        for x in 0..5 {
+            let id = y * 5 + x;
+            let wall = |x: usize, y: usize| x == 2 && y < 4;
+            if wall(x, y) {
+                continue;
+            }
+            if x + 1 < 5 && !wall(x + 1, y) {
+                graph.add_edge(id, id + 1, 1.0);
+                graph.add_edge(id + 1, id, 1.0);
+            }
+            if y + 1 < 5 && !wall(x, y + 1) {
+                graph.add_edge(id, id + 5, 1.0);
+                graph.add_edge(id + 5, id, 1.0);
+            }
+        }
+    }
+
+    let path = graph.a_star_bidirectional(0, 4, &DashMap::new()).expect("No path found");
+    let expected = graph.a_star(0, 4, &DashMap::new()).expect("No path found");
+
+    assert_eq!(path.first().unwrap().id, 0);
+    assert_eq!(path.last().unwrap().id, 4);
+    assert_eq!(path_cost(&graph, &path), path_cost(&graph, &expected));
+    assert_eq!(path_cost(&graph, &path), 12.0);
+
+    assert!(graph.a_star_bidirectional(0, 12, &DashMap::new()).is_none());
+}
+
+#[test]
+fn test_a_star_bidirectional_navmesh() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let node_count = graph.nodes.len();
+
+    for start in (0..node_count).step_by(node_count / 10) {
+        let goal = (start * 7919 + 13) % node_count;
+
+        let forward = graph.a_star(start, goal, &DashMap::new());
+        let bidirectional = graph.a_star_bidirectional(start, goal, &DashMap::new());
+        assert_eq!(forward.is_some(), bidirectional.is_some());
+
+        if let (Some(forward), Some(bidirectional)) = (forward, bidirectional) {
+            assert_eq!(bidirectional.first().unwrap().id, start);
+            assert_eq!(bidirectional.last().unwrap().id, goal);
+            assert!(path_cost(&graph, &bidirectional) <= path_cost(&graph, &forward) + 1e-3);
+        }
+    }
+}
+
+/// Builds an 8-connected grid where cells rejected by `blocked` are left out.
+fn build_grid(size: usize, blocked: impl Fn(usize, usize) -> bool) -> Graph {
+    let mut graph = Graph::new();
+    let mut ids = vec![None; size * size];
+
+    for y in 0..size {
+        for x in 0..size {
+            if !blocked(x, y) {
+                ids[y * size + x] = Some(graph.nodes.len());
+                graph.add_node(Node::new(graph.nodes.len(), x as Coord * 2.0, 5.0, y as Coord * 2.0));
+            }
+        }
+    }
+
+    for y in 0..size as i64 {
+        for x in 0..size as i64 {
+            let Some(from) = ids[(y * size as i64 + x) as usize] else {
+                continue;
+            };
+            for (dx, dy) in [(1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+                let (nx, ny) = (x + dx, y + dy);
+                if nx < 0 || ny < 0 || nx >= size as i64 || ny >= size as i64 {
+                    continue;
+                }
+                if let Some(to) = ids[(ny * size as i64 + nx) as usize] {
+                    let cost = if dx != 0 && dy != 0 { 2.0 * std::f64::consts::SQRT_2 as Coord } else { 2.0 };
+                    graph.add_edge(from, to, cost);
+                }
+            }
+        }
+    }
+
+    graph
+}
+
+#[test]
+fn test_jps_matches_a_star_on_grid() {
+    let graph = build_grid(30, |x, y| (x == 10 && y < 25) || (x == 20 && y > 4) || (y == 15 && x > 22));
+    assert!(GridLayout::detect(&graph).is_some());
+
+    let last = graph.nodes.len() - 1;
+    for (start, goal) in [(0, last), (5, last - 40), (last / 2, 3), (100, 101)] {
+        let (a_star_path, a_star_stats) = graph.a_star_with_stats(start, goal);
+        let (jps_path, jps_stats) = graph.jps_with_stats(start, goal);
+        let (a_star_path, jps_path) = (a_star_path.unwrap(), jps_path.unwrap());
+
+        assert_eq!(jps_path.first().unwrap().id, start);
+        assert_eq!(jps_path.last().unwrap().id, goal);
+        assert!((path_cost(&graph, &jps_path) - path_cost(&graph, &a_star_path)).abs() < 1e-3);
+        assert!(jps_stats.nodes_expanded <= a_star_stats.nodes_expanded);
+    }
+
+    let cache = DashMap::new();
+    assert!(graph.jps(0, last, &cache).is_some());
+    assert!(cache.contains_key(&(0, last)));
+}
+
+#[test]
+fn test_jps_falls_back_to_a_star() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    assert!(GridLayout::detect(&graph).is_none());
+
+    let (jps_path, jps_stats) = graph.jps_with_stats(0, 500);
+    let (a_star_path, a_star_stats) = graph.a_star_with_stats(0, 500);
+    assert_eq!(jps_path, a_star_path);
+    assert_eq!(jps_stats, a_star_stats);
+}
+
+/// Builds a flat triangulated grid in the XZ plane, leaving out the faces of cells rejected by
+/// `hole`. Faces are wound consistently like in an OBJ file.
+fn build_triangulated_grid(size: usize, hole: impl Fn(usize, usize) -> bool) -> Graph {
+    let mut graph = Graph::new();
+    for z in 0..=size {
+        for x in 0..=size {
+            graph.add_node(Node::new(graph.nodes.len(), x as Coord, 0.0, z as Coord));
+        }
+    }
+
+    let mut add_face = |a: usize, b: usize, c: usize| {
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            let (p, q) = (&graph.nodes[from], &graph.nodes[to]);
+            graph.add_edge(from, to, ((p.x - q.x).powi(2) + (p.z - q.z).powi(2)).sqrt());
+        }
+    };
+
+    for z in 0..size {
+        for x in 0..size {
+            if hole(x, z) {
+                continue;
+            }
+            let a = z * (size + 1) + x;
+            let (b, c, d) = (a + 1, a + size + 2, a + size + 1);
+            add_face(a, b, c);
+            add_face(a, c, d);
+        }
+    }
+
+    graph
+}
+
+fn euclidean_length(path: &[Node]) -> Coord {
+    path.windows(2).map(|pair| heuristics::euclidean(&pair[0], &pair[1])).sum()
+}
+
+#[test]
+fn test_line_of_sight() {
+    // 10x10 cells with a hole in the middle
+    let graph = build_triangulated_grid(10, |x, z| (4..6).contains(&x) && (3..7).contains(&z));
+    let mesh = TriangleMesh::new(&graph);
+    let id = |x: usize, z: usize| z * 11 + x;
+
+    assert!(mesh.line_of_sight(&graph, id(0, 0), id(10, 1)));
+    assert!(graph.line_of_sight(id(0, 0), id(10, 2)));
+    // Straight along grid lines and through vertices
+    assert!(mesh.line_of_sight(&graph, id(0, 1), id(10, 1)));
+    assert!(mesh.line_of_sight(&graph, id(0, 0), id(2, 2)));
+    // Across the hole
+    assert!(!mesh.line_of_sight(&graph, id(0, 5), id(10, 5)));
+    assert!(!mesh.line_of_sight(&graph, id(3, 2), id(7, 8)));
+    // Along the edge of the hole
+    assert!(mesh.line_of_sight(&graph, id(4, 0), id(4, 10)));
+}
+
+#[test]
+fn test_theta_star_is_shorter_than_a_star() {
+    let graph = build_triangulated_grid(10, |x, z| (4..6).contains(&x) && (3..7).contains(&z));
+    let mesh = TriangleMesh::new(&graph);
+
+    for (start, goal) in [(0, 120), (11, 64), (5 * 11, 5 * 11 + 10), (3, 117)] {
+        let a_star_path = graph.a_star(start, goal, &DashMap::new()).expect("No A* path found");
+        let theta_path = graph.theta_star(start, goal, &DashMap::new()).expect("No Theta* path found");
+
+        assert_eq!(theta_path.first().unwrap().id, start);
+        assert_eq!(theta_path.last().unwrap().id, goal);
+        assert!(euclidean_length(&theta_path) <= euclidean_length(&a_star_path) + 1e-3);
+        for pair in theta_path.windows(2) {
+            assert!(mesh.line_of_sight(&graph, pair[0].id, pair[1].id));
+        }
+    }
+
+    // On an open grid the any-angle path is a single straight segment
+    let open_graph = build_triangulated_grid(10, |_, _| false);
+    let path = open_graph.theta_star(0, 4 * 11 + 9, &DashMap::new()).expect("No path found");
+    assert_eq!(path.len(), 2);
+}
+
+#[test]
+fn test_theta_star_navmesh() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let mesh = TriangleMesh::new(&graph);
+
+    for (start, goal) in [(0, 5000), (1234, 20345), (40000, 17)] {
+        let a_star_path = graph.a_star(start, goal, &DashMap::new()).expect("No A* path found");
+        let theta_path = graph
+            .theta_star_with_mesh(start, goal, &DashMap::new(), &mesh)
+            .expect("No Theta* path found");
+        assert!(euclidean_length(&theta_path) <= euclidean_length(&a_star_path) + 1e-3);
+        assert!(theta_path.len() <= a_star_path.len());
+    }
+}
+
+#[test]
+fn test_ida_star_matches_a_star_cost() {
+    let graph = build_triangulated_grid(5, |x, z| x == 2 && z < 4);
+    let cache = DashMap::new();
+
+    for (start, goal) in [(0, 35), (0, 5), (7, 28), (12, 12)] {
+        let a_star_path = graph.a_star(start, goal, &DashMap::new()).expect("No A* path found");
+        let ida_path = graph.ida_star(start, goal, &cache).expect("No IDA* path found");
+
+        assert_eq!(ida_path.first().unwrap().id, start);
+        assert_eq!(ida_path.last().unwrap().id, goal);
+        assert!((path_cost(&graph, &ida_path) - path_cost(&graph, &a_star_path)).abs() < 1e-4);
+    }
+
+    // The cache is not used
+    assert!(cache.is_empty());
+
+    // An isolated node can never be reached
+    let mut isolated = build_triangulated_grid(2, |_, _| false);
+    isolated.add_node(Node::new(9, 10.0, 0.0, 10.0));
+    assert!(isolated.ida_star(0, 9, &cache).is_none());
+}
+
+#[test]
+fn test_contraction_hierarchy_matches_a_star_cost() {
+    let navmesh = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let graphs = [
+        build_triangulated_grid(12, |x, z| x == 6 && z < 10),
+        navmesh.subgraph_in_aabb(0.0, -1000.0, 0.0, 600.0, 1000.0, 600.0),
+    ];
+    for graph in &graphs {
+        let hierarchy = ContractionHierarchy::build(graph);
+        assert!(hierarchy.shortcut_count() > 0);
+        let node_count = graph.nodes.len();
+        for start in (0..node_count).step_by(node_count / 10) {
+            let goal = (start * 7919 + 13) % node_count;
+            let expected = graph.a_star(start, goal, &DashMap::new()).expect("No A* path found");
+            let path = hierarchy.query(start, goal).expect("No CH path found");
+            assert_eq!(path.first().unwrap().id, start);
+            assert_eq!(path.last().unwrap().id, goal);
+            assert!((path_cost(graph, &path) - path_cost(graph, &expected)).abs() < 1e-2);
+        }
+    }
+
+    // The hierarchy survives a round trip through bincode
+    let hierarchy = ContractionHierarchy::build(&graphs[0]);
+    let bytes = bincode::serialize(&hierarchy).unwrap();
+    let restored: ContractionHierarchy = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(restored.query(0, 168), hierarchy.query(0, 168));
+    assert_eq!(restored.query(5, 5).unwrap().len(), 1);
+
+    // One-way edges are kept and unreachable nodes have no path
+    let mut graph = build_non_geometric_graph();
+    graph.add_node(Node::new(3, 3.0, 0.0, 0.0));
+    graph.add_edge_directed(2, 3, 1.0);
+    let hierarchy = ContractionHierarchy::build(&graph);
+    let ids: Vec<usize> = hierarchy.query(0, 3).unwrap().iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 1, 2, 3]);
+    assert!(hierarchy.query(3, 0).is_none());
+}
+
+#[test]
+fn test_spfa_with_negative_edge() {
+    // The detour through node 1 pays off thanks to the negative edge into node 3
+    let mut graph = build_non_geometric_graph();
+    graph.add_node(Node::new(3, 3.0, 0.0, 0.0));
+    graph.add_edge_directed(2, 3, 5.0);
+    graph.add_edge_directed(1, 3, -2.0);
+    let cache = DashMap::new();
+
+    let path = graph.spfa(0, 3, &cache).unwrap().expect("No path found");
+    let ids: Vec<usize> = path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 1, 3]);
+    assert!(cache.contains_key(&(0, 3)));
+    assert_eq!(graph.bellman_ford_all_paths(0), vec![Some(0.0), Some(1.0), Some(2.0), Some(-1.0)]);
+
+    // Node 3 has no edges back
+    assert!(graph.spfa(3, 0, &cache).unwrap().is_none());
+    assert_eq!(graph.bellman_ford_all_paths(3), vec![None, None, None, Some(0.0)]);
+}
+
+#[test]
+fn test_spfa_negative_cycle() {
+    // Going around 1 -> 2 -> 1 costs -1, and node 3 is only reachable from it
+    let mut graph = Graph::new();
+    for id in 0..5 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    graph.add_edge_directed(0, 1, 1.0);
+    graph.add_edge_directed(1, 2, 1.0);
+    graph.add_edge_directed(2, 1, -2.0);
+    graph.add_edge_directed(2, 3, 1.0);
+    graph.add_edge_directed(4, 0, 1.0);
+    let cache = DashMap::new();
+
+    assert!(matches!(graph.spfa(0, 3, &cache), Err(RePathError::NegativeCycle)));
+    assert!(cache.is_empty());
+    let dist = graph.bellman_ford_all_paths(4);
+    assert_eq!(dist[0], Some(1.0));
+    assert!(dist[1..4].iter().all(|&dist| dist == Some(Coord::NEG_INFINITY)));
+    assert_eq!(dist[4], Some(0.0));
+    // The cycle cannot be reached from node 3
+    assert_eq!(graph.spfa(3, 3, &cache).unwrap().unwrap().len(), 1);
+}
+
+#[test]
+fn test_floyd_warshall_matches_a_star() {
+    let graph = build_triangulated_grid(8, |x, z| x == 4 && z < 6);
+    let all_pairs = graph.floyd_warshall().unwrap();
+    for start in (0..graph.nodes.len()).step_by(7) {
+        for goal in (0..graph.nodes.len()).step_by(5) {
+            let expected = graph.a_star(start, goal, &DashMap::new()).expect("No A* path found");
+            let path = all_pairs.path(start, goal).expect("No path found");
+            assert_eq!(path.first().unwrap().id, start);
+            assert_eq!(path.last().unwrap().id, goal);
+            assert!((path_cost(&graph, &path) - path_cost(&graph, &expected)).abs() < 1e-4);
+            assert!((all_pairs.distance(start, goal) - path_cost(&graph, &expected)).abs() < 1e-4);
+        }
+    }
+    assert_eq!(all_pairs.distance(3, 3), 0.0);
+
+    // Unreachable nodes have no path
+    let mut graph = build_non_geometric_graph();
+    graph.add_node(Node::new(3, 3.0, 0.0, 0.0));
+    let all_pairs = graph.floyd_warshall().unwrap();
+    assert_eq!(all_pairs.distance(0, 2), 2.0);
+    assert_eq!(all_pairs.distance(0, 3), Coord::INFINITY);
+    assert!(all_pairs.path(3, 0).is_none());
+
+    assert!(matches!(graph.floyd_warshall_with_limit(3), Err(RePathError::GraphTooLarge(4))));
+}
+
+#[test]
+fn test_d_star_lite_replans_around_expensive_edge() {
+    let mut graph = build_triangulated_grid(6, |x, z| x == 3 && z > 0);
+    let (start, goal) = (3 * 7, 3 * 7 + 6);
+
+    let mut planner = DStarLite::new(&graph, start, goal);
+    let path = planner.find_path().expect("No path found");
+    let expected = graph.a_star(start, goal, &DashMap::new()).unwrap();
+    assert!((path_cost(&graph, &path) - path_cost(&graph, &expected)).abs() < 1e-4);
+
+    // Every route has to pass node 3 at the gap below the wall, make its next edge expensive
+    let from = 3;
+    let to = path
+        .windows(2)
+        .find(|pair| pair[0].id == from)
+        .map(|pair| pair[1].id)
+        .expect("Path does not pass through the gap");
+
+    planner.update_edge(from, to, 1000.0);
+    let replanned = planner.find_path().expect("No path found after update");
+    assert!(!replanned.windows(2).any(|pair| pair[0].id == from && pair[1].id == to));
+
+    // The replanned path must be optimal under the new costs
+    for edge in graph.edges[from].iter_mut().filter(|edge| edge.to == to) {
+        edge.cost = 1000.0;
+    }
+    let expected = graph.a_star(start, goal, &DashMap::new()).unwrap();
+    assert!((path_cost(&graph, &replanned) - path_cost(&graph, &expected)).abs() < 1e-3);
+
+    // Restoring the edge makes it attractive again
+    let mut planner = graph.d_star_lite(start, goal);
+    planner.update_edge(from, to, 1.0);
+    let restored = planner.find_path().expect("No path found after restoring");
+    assert!(restored.windows(2).any(|pair| pair[0].id == from && pair[1].id == to));
+}
+
+#[test]
+fn test_k_shortest_paths() {
+    // The example graph from the description of Yen's algorithm, C=0, D=1, E=2, F=3, G=4, H=5
+    let mut graph = Graph::new();
+    for id in 0..6 {
+        graph.add_node(Node::new(id, 0.0, 0.0, 0.0));
+    }
+    for (from, to, cost) in [
+        (0, 1, 3.0),
+        (0, 2, 2.0),
+        (1, 3, 4.0),
+        (2, 1, 1.0),
+        (2, 3, 2.0),
+        (2, 4, 3.0),
+        (3, 4, 2.0),
+        (3, 5, 1.0),
+        (4, 5, 2.0),
+    ] {
+        graph.add_edge(from, to, cost);
+    }
+
+    let paths = graph.k_shortest_paths(0, 5, 3, &DashMap::new());
+    let ids: Vec<Vec<usize>> = paths
+        .iter()
+        .map(|path| path.iter().map(|node| node.id).collect())
+        .collect();
+    assert_eq!(ids, vec![vec![0, 2, 3, 5], vec![0, 2, 4, 5], vec![0, 1, 3, 5]]);
+
+    // There are only 7 loopless paths in total
+    assert_eq!(graph.k_shortest_paths(0, 5, 100, &DashMap::new()).len(), 7);
+    assert!(graph.k_shortest_paths(5, 0, 3, &DashMap::new()).is_empty());
+}
+
+#[test]
+fn test_a_star_multi_source_picks_cheapest_source() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let starts = [100, 15000, 20500, 39000];
+    let goal = 20000;
+
+    let cache = DashMap::new();
+    let path = graph.a_star_multi_source(&starts, goal, &cache).expect("No path found");
+    let winner = path.first().unwrap().id;
+    assert!(starts.contains(&winner));
+    assert_eq!(path.last().unwrap().id, goal);
+    assert!(cache.contains_key(&(winner, goal)));
+
+    let cheapest = starts
+        .iter()
+        .map(|&start| path_cost(&graph, &graph.a_star(start, goal, &DashMap::new()).unwrap()))
+        .fold(Coord::INFINITY, Coord::min);
+    assert!((path_cost(&graph, &path) - cheapest).abs() < 1e-2);
+
+    assert!(graph.a_star_multi_source(&[], goal, &DashMap::new()).is_none());
+}
+
+#[test]
+fn test_a_star_nearest_goal() {
+    // Diamond: start 0 in the middle, a close goal 3 via 1 and a far goal 4 via 2
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::new(1, 2.0, 1.0, 0.0));
+    graph.add_node(Node::new(2, -5.0, 1.0, 0.0));
+    graph.add_node(Node::new(3, 4.0, 0.0, 0.0));
+    graph.add_node(Node::new(4, -10.0, 0.0, 0.0));
+    for (a, b) in [(0, 1), (1, 3), (0, 2), (2, 4)] {
+        let cost = graph.heuristic(a, b);
+        graph.add_edge(a, b, cost);
+        graph.add_edge(b, a, cost);
+    }
+
+    let cache = DashMap::new();
+    let (goal_index, path) = graph.a_star_nearest_goal(0, &[4, 3], &cache).expect("No path found");
+    assert_eq!(goal_index, 1);
+    let ids: Vec<usize> = path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 1, 3]);
+    assert!(cache.contains_key(&(0, 3)));
+
+    // A goal that is close in a straight line but expensive to reach loses
+    graph.add_node(Node::new(5, -1.0, 0.0, 0.0));
+    graph.add_edge(4, 5, 50.0);
+    let (goal_index, _) = graph.a_star_nearest_goal(0, &[5, 3], &cache).expect("No path found");
+    assert_eq!(goal_index, 1);
+
+    assert!(graph.a_star_nearest_goal(0, &[], &cache).is_none());
+}
+
+#[test]
+fn test_a_star_partial() {
+    // Two islands on a line: 0 - 1 - 2 and 3 - 4, with 2 being the node closest to the goal 4
+    let mut graph = Graph::new();
+    for (id, x) in [0.0, 1.0, 2.0, 5.0, 6.0].into_iter().enumerate() {
+        graph.add_node(Node::new(id, x, 0.0, 0.0));
+    }
+    for (a, b) in [(0, 1), (1, 2), (3, 4)] {
+        graph.add_edge(a, b, 1.0);
+        graph.add_edge(b, a, 1.0);
+    }
+
+    let cache = DashMap::new();
+    let partial = graph.a_star_partial(0, 4, &cache);
+    assert!(!partial.complete);
+    let ids: Vec<usize> = partial.path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 1, 2]);
+    assert!(cache.get(&(0, 4)).unwrap().is_none());
+
+    // Failures are cached as `None`, the partial path is still computed on the next call
+    let partial = graph.a_star_partial(0, 4, &cache);
+    assert!(!partial.complete);
+    assert_eq!(partial.path.len(), 3);
+
+    let partial = graph.a_star_partial(3, 4, &cache);
+    assert!(partial.complete);
+    assert_eq!(partial.path, graph.a_star(3, 4, &DashMap::new()).unwrap());
+}
+
+#[test]
+fn test_smooth_path() {
+    let graph = build_triangulated_grid(10, |x, z| (4..6).contains(&x) && (3..7).contains(&z));
+    let mesh = TriangleMesh::new(&graph);
+
+    for (start, goal) in [(0, 120), (11, 64), (5 * 11, 5 * 11 + 10), (3, 117)] {
+        let path = graph.a_star(start, goal, &DashMap::new()).expect("No path found");
+        let smoothed = path::smooth(&path, &graph);
+
+        assert_eq!(smoothed.first(), path.first());
+        assert_eq!(smoothed.last(), path.last());
+        assert!(smoothed.len() <= path.len());
+        assert!(euclidean_length(&smoothed) <= euclidean_length(&path) + 1e-3);
+        for pair in smoothed.windows(2) {
+            assert!(mesh.line_of_sight(&graph, pair[0].id, pair[1].id));
+        }
+    }
+
+    // Around the hole the smoothed path keeps only the corners it must turn at
+    let path = graph.a_star(5 * 11, 5 * 11 + 10, &DashMap::new()).unwrap();
+    assert!(path::smooth(&path, &graph).len() <= 4);
+}
+
+#[test]
+fn test_smooth_removes_collinear_nodes() {
+    // Nodes along a straight line of the grid are all in sight of each other
+    let graph = build_triangulated_grid(10, |_, _| false);
+    let path: Vec<Node> = (0..=10).map(|x| graph.nodes[x].clone()).collect();
+    let smoothed = path::smooth(&path, &graph);
+    assert_eq!(smoothed.len(), 2);
+
+    // A corner around the hole is kept unless the turn is below the angle threshold
+    let graph = build_triangulated_grid(10, |x, z| (4..6).contains(&x) && (3..7).contains(&z));
+    let mesh = TriangleMesh::new(&graph);
+    let id = |x: usize, z: usize| z * 11 + x;
+    let corner = vec![graph.nodes[id(2, 5)].clone(), graph.nodes[id(5, 1)].clone(), graph.nodes[id(8, 5)].clone()];
+    assert_eq!(path::smooth_with_mesh(&corner, &graph, &mesh, 1.0).len(), 3);
+    assert_eq!(path::smooth_with_mesh(&corner, &graph, &mesh, 180.0).len(), 3);
+    assert_eq!(path::smooth_with_mesh(&corner[..2], &graph, &mesh, 1.0).len(), 2);
+    let open = build_triangulated_grid(10, |_, _| false);
+    assert_eq!(path::smooth_with_mesh(&corner, &open, &TriangleMesh::new(&open), 180.0).len(), 2);
+}
+
+#[test]
+fn test_smooth_keeps_slight_turn_around_obstacle() {
+    // Two triangles meeting at a corner that the path turns around by about 0.6 degrees, the
+    // straight line from the first to the last node passes just outside the mesh
+    let mut graph = Graph::new();
+    for (x, z) in [(0.0, 0.0), (100.0, 0.5), (200.0, 0.0), (100.0, 10.0)] {
+        graph.add_node(Node::new(graph.nodes.len(), x, 0.0, z));
+    }
+    for (a, b, c) in [(0, 1, 3), (1, 2, 3)] {
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            let (p, q) = (&graph.nodes[from], &graph.nodes[to]);
+            graph.add_edge(from, to, ((p.x - q.x).powi(2) + (p.z - q.z).powi(2)).sqrt());
+        }
+    }
+    let mesh = TriangleMesh::new(&graph);
+    assert!(!mesh.line_of_sight(&graph, 0, 2));
+
+    let path: Vec<Node> = [0, 1, 2].iter().map(|&id| graph.nodes[id].clone()).collect();
+    let smoothed = path::smooth_with_mesh(&path, &graph, &mesh, path::DEFAULT_SMOOTHING_ANGLE);
+    assert_eq!(smoothed.iter().map(|node| node.id).collect::<Vec<_>>(), vec![0, 1, 2]);
+    assert_eq!(path::smooth(&path, &graph).len(), 3);
+}
+
+#[test]
+fn test_decimate_path() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let path = graph.a_star(1234, 20345, &DashMap::new()).expect("No path found");
+
+    assert_eq!(path::decimate(&path, 0.0), *path);
+
+    let decimated = path::decimate(&path, 1e9);
+    assert_eq!(decimated, vec![path[0].clone(), path[path.len() - 1].clone()]);
+
+    let decimated = path::decimate(&path, 5.0);
+    assert!(decimated.len() > 2 && decimated.len() < path.len());
+    // The kept nodes stay in the original order
+    let positions: Vec<usize> = decimated
+        .iter()
+        .map(|node| path.iter().position(|other| other == node).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn test_decimate_splits_at_furthest_node() {
+    let nodes: Vec<Node> = [(0.0, 0.0), (1.0, 0.1), (2.0, 3.0), (3.0, 0.2), (4.0, 0.0)]
+        .into_iter()
+        .enumerate()
+        .map(|(id, (x, z))| Node::new(id, x, 0.0, z))
+        .collect();
+
+    let ids = |path: Vec<Node>| path.iter().map(|node| node.id).collect::<Vec<_>>();
+    assert_eq!(ids(path::decimate(&nodes, 1.0)), vec![0, 2, 4]);
+    assert_eq!(ids(path::decimate(&nodes, 0.05)), vec![0, 1, 2, 3, 4]);
+    assert_eq!(ids(path::decimate(&nodes[..2], 10.0)), vec![0, 1]);
+}
+
+#[test]
+fn test_interpolate_path() {
+    let nodes = vec![
+        Node::new(0, 0.0, 0.0, 0.0),
+        Node::new(1, 1.0, 0.0, 0.0),
+        Node::new(2, 1.0, 0.0, 1.5),
+    ];
+
+    // The step carries over the turn at the middle node
+    let points = path::interpolate(&nodes, 0.75);
+    assert_eq!(
+        points,
+        vec![(0.0, 0.0, 0.0), (0.75, 0.0, 0.0), (1.0, 0.0, 0.5), (1.0, 0.0, 1.25), (1.0, 0.0, 1.5)]
+    );
+
+    assert_eq!(path::interpolate(&nodes[..1], 1.0), vec![(0.0, 0.0, 0.0)]);
+    assert!(path::interpolate(&[], 1.0).is_empty());
+}
+
+#[test]
+fn test_to_bezier_passes_through_waypoints() {
+    let nodes = vec![
+        Node::new(0, 0.0, 0.0, 0.0),
+        Node::new(1, 2.0, 0.0, 0.0),
+        Node::new(2, 2.0, 0.0, 2.0),
+        Node::new(3, 4.0, 1.0, 2.0),
+    ];
+
+    // With one sample per waypoint plus two in between every segment, every third sample is a
+    // waypoint
+    let curve = path::to_bezier(&nodes, 10);
+    assert_eq!(curve.len(), 10);
+    for (index, node) in nodes.iter().enumerate() {
+        let point = curve[index * 3];
+        let offset = (point.0 - node.x, point.1 - node.y, point.2 - node.z);
+        assert!(offset.0.abs() < 1e-5 && offset.1.abs() < 1e-5 && offset.2.abs() < 1e-5);
+    }
+    assert_eq!(curve[0], (0.0, 0.0, 0.0));
+    assert_eq!(curve[9], (4.0, 1.0, 2.0));
+
+    // The corner at the second waypoint is rounded off instead of followed
+    let dense = path::to_bezier(&nodes, 301);
+    assert!(dense.iter().any(|point| point.0 < 2.0 - 1e-3 && point.2 > 1e-3));
+
+    assert_eq!(path::to_bezier(&nodes[..1], 5), vec![(0.0, 0.0, 0.0)]);
+    assert!(path::to_bezier(&nodes, 0).is_empty());
+}
+
+#[test]
+fn test_closest_point_on_path() {
+    let nodes = vec![
+        Node::new(0, 0.0, 0.0, 0.0),
+        Node::new(1, 4.0, 0.0, 0.0),
+        Node::new(2, 4.0, 0.0, 4.0),
+    ];
+
+    // Exactly at the endpoints
+    assert_eq!(path::closest_point_on_path(&nodes, (0.0, 0.0, 0.0)), Some((0, (0.0, 0.0, 0.0))));
+    assert_eq!(path::closest_point_on_path(&nodes, (4.0, 0.0, 4.0)), Some((1, (4.0, 0.0, 4.0))));
+    assert_eq!(path::progress_fraction(&nodes, (0.0, 0.0, 0.0)), 0.0);
+    assert_eq!(path::progress_fraction(&nodes, (4.0, 0.0, 4.0)), 1.0);
+
+    // Beside the middle of a segment, the perpendicular projection is between the nodes
+    assert_eq!(path::closest_point_on_path(&nodes, (1.0, 3.0, -2.0)), Some((0, (1.0, 0.0, 0.0))));
+    assert_eq!(path::closest_point_on_path(&nodes, (6.0, 0.0, 3.0)), Some((1, (4.0, 0.0, 3.0))));
+    assert_eq!(path::progress_fraction(&nodes, (6.0, 0.0, 3.0)), 7.0 / 8.0);
+
+    // Beyond the ends the endpoints are closest, at the corner the first segment wins
+    assert_eq!(path::closest_point_on_path(&nodes, (-3.0, 1.0, 0.0)), Some((0, (0.0, 0.0, 0.0))));
+    assert_eq!(path::closest_point_on_path(&nodes, (5.0, 0.0, -1.0)), Some((0, (4.0, 0.0, 0.0))));
+    assert_eq!(path::progress_fraction(&nodes, (5.0, 0.0, -1.0)), 0.5);
+
+    assert_eq!(path::closest_point_on_path(&nodes[..1], (1.0, 1.0, 1.0)), Some((0, (0.0, 0.0, 0.0))));
+    assert_eq!(path::progress_fraction(&nodes[..1], (1.0, 1.0, 1.0)), 0.0);
+    assert!(path::closest_point_on_path(&[], (1.0, 1.0, 1.0)).is_none());
+    assert_eq!(path::progress_fraction(&[], (1.0, 1.0, 1.0)), 0.0);
+}
+
+#[test]
+fn test_bounds_and_center() {
+    let mut graph = Graph::new();
+    assert!(graph.bounds().is_none());
+    assert_eq!(graph.center(), (0.0, 0.0, 0.0));
+
+    for (id, (x, y, z)) in [(-1.0, 2.0, 0.0), (3.0, -4.0, 1.0), (1.0, 5.0, 2.0)].into_iter().enumerate() {
+        graph.add_node(Node::new(id, x, y, z));
+    }
+    let (min, max) = graph.bounds().unwrap();
+    assert_eq!((min.x, min.y, min.z), (-1.0, -4.0, 0.0));
+    assert_eq!((max.x, max.y, max.z), (3.0, 5.0, 2.0));
+    assert_eq!(graph.center(), (1.0, 1.0, 1.0));
+}
+
+#[test]
+fn test_normalize_coordinates() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let normalized = graph.normalize_coordinates();
+
+    assert!(normalized
+        .nodes
+        .iter()
+        .all(|node| [node.x, node.y, node.z].iter().all(|value| (0.0..=1.0).contains(value))));
+    let center = normalized.center();
+    for value in [center.0, center.1, center.2] {
+        assert!((value - 0.5).abs() < 0.05, "Centroid {:?} is not near the middle", center);
+    }
+
+    // The topology is kept and the costs shrink by the same factor
+    let (min, max) = graph.bounds().unwrap();
+    let scale = (max.x - min.x).max(max.y - min.y).max(max.z - min.z);
+    assert_eq!(normalized.edges.len(), graph.edges.len());
+    for (edges, normalized_edges) in graph.edges.iter().zip(&normalized.edges) {
+        assert_eq!(edges.len(), normalized_edges.len());
+        for (edge, normalized_edge) in edges.iter().zip(normalized_edges) {
+            assert_eq!(edge.to, normalized_edge.to);
+            assert!((edge.cost / scale - normalized_edge.cost).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_flatten() {
+    let mut graph = Graph::new();
+    for (id, (x, y, z)) in [(0.0, 0.0, 0.0), (3.0, 4.0, 4.0), (0.0, 7.0, 0.0)].into_iter().enumerate() {
+        graph.add_node(Node::new(id, x, y, z));
+    }
+    graph.add_edge(0, 1, graph.heuristic(0, 1));
+    graph.add_edge(1, 0, 2.0 * graph.heuristic(0, 1));
+    graph.add_edge(0, 2, 3.0);
+
+    let flattened = graph.flatten();
+    assert!(flattened.nodes.iter().all(|node| node.y == 0.0));
+    assert_eq!(flattened.heuristic(0, 1), 5.0);
+    assert!((flattened.edge_cost(0, 1).unwrap() - 5.0).abs() < 1e-5);
+    assert!((flattened.edge_cost(1, 0).unwrap() - 10.0).abs() < 1e-5);
+    // A vertical edge collapses to a point and keeps its cost
+    assert_eq!(flattened.edge_cost(0, 2), Some(3.0));
+    assert_eq!(graph.nodes[1].y, 4.0);
+}
+
+#[test]
+fn test_a_star_iter() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let path = graph.a_star(1234, 20345, &DashMap::new()).expect("No path found");
+
+    let iter = graph.a_star_iter(1234, 20345).expect("No path found");
+    assert_eq!(iter.len(), path.len());
+    assert_eq!(iter.clone().collect::<Vec<_>>(), *path);
+    assert_eq!(iter.rev().collect::<Vec<_>>(), path.iter().rev().cloned().collect::<Vec<_>>());
+
+    let mut graph = graph;
+    let isolated = graph.nodes.len();
+    graph.add_node(Node::new(isolated, 0.0, 0.0, 0.0));
+    assert!(graph.a_star_iter(0, isolated).is_none());
+}
+
+#[test]
+fn test_a_star_directed_edge() {
+    let mut graph = Graph::new();
+    for id in 0..3 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    graph.add_edge(0, 1, 1.0);
+    graph.add_edge(1, 0, 1.0);
+    // A drop from node 1 to node 2 that cannot be climbed back up
+    graph.add_edge_directed(1, 2, 1.0);
+    assert!(graph.edges[1][1].directed);
+
+    let path = graph.a_star(0, 2, &DashMap::new()).expect("No path found");
+    let ids: Vec<usize> = path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 1, 2]);
+    assert!(graph.a_star(2, 0, &DashMap::new()).is_none());
+}
+
+#[test]
+fn test_set_edge_cost() {
+    let mut graph = build_non_geometric_graph();
+    graph.set_edge_cost(0, 1, 20.0);
+    let ids = |path: Vec<Node>| path.iter().map(|node| node.id).collect::<Vec<_>>();
+    let path = graph.a_star(0, 2, &DashMap::new()).expect("No path found");
+    assert_eq!(ids(path.to_vec()), vec![0, 2]);
+
+    // Lowering the cost again makes the detour the cheaper path
+    assert!(graph.set_edge_cost(0, 1, 1.0));
+    assert_eq!(graph.edge_cost(0, 1), Some(1.0));
+    let path = graph
+        .a_star_with_heuristic(0, 2, 0, &DashMap::new(), |_, _| 0.0)
+        .expect("No path found");
+    assert_eq!(ids(path.to_vec()), vec![0, 1, 2]);
+
+    assert!(!graph.set_edge_cost(2, 0, 1.0));
+    assert!(!graph.set_edge_cost(10, 0, 1.0));
+}
+
+#[test]
+fn test_scale_all_edge_costs() {
+    let mut graph = build_non_geometric_graph();
+    graph.scale_all_edge_costs(2.0);
+    assert_eq!(graph.edge_cost(0, 2), Some(20.0));
+    assert_eq!(graph.edge_cost(0, 1), Some(2.0));
+    assert_eq!(graph.edge_cost(1, 2), Some(2.0));
+}
+
+#[test]
+fn test_move_node() {
+    // A square with two routes of cost 20 from corner 0 to corner 2
+    let mut graph = Graph::new();
+    for (id, (x, z)) in [(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)].into_iter().enumerate() {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    for id in 0..4 {
+        graph.connect_nodes(id, (id + 1) % 4, 10.0);
+    }
+    let ids = |path: Path| path.iter().map(|node| node.id).collect::<Vec<_>>();
+
+    // Moving node 1 halfway towards node 0 shortens both of its edges, in both directions
+    let generation = graph.generation();
+    assert!(graph.move_node(1, 5.0, 0.0, 0.0));
+    assert_eq!(graph.generation(), generation + 1);
+    assert_eq!(graph.nodes[1].x, 5.0);
+    assert_eq!(graph.edge_cost(0, 1), Some(5.0));
+    assert_eq!(graph.edge_cost(1, 0), Some(5.0));
+    assert_eq!(graph.edge_cost(2, 1), graph.edge_cost(1, 2));
+    assert!((graph.edge_cost(1, 2).unwrap() - (125.0 as Coord).sqrt()).abs() < 1e-4);
+    assert_eq!(graph.edge_cost(2, 3), Some(10.0));
+    assert_eq!(ids(graph.a_star(0, 2, &DashMap::new()).expect("No path found")), vec![0, 1, 2]);
+
+    // Moving the other corners makes the route through node 3 the cheaper one
+    assert_eq!(graph.move_nodes_batch(&[(3, 2.0, 0.0, 8.0), (2, 8.0, 0.0, 10.0), (7, 0.0, 0.0, 0.0)]), 2);
+    assert!((graph.edge_cost(3, 2).unwrap() - (40.0 as Coord).sqrt()).abs() < 1e-4);
+    assert_eq!(ids(graph.a_star(0, 2, &DashMap::new()).expect("No path found")), vec![0, 3, 2]);
+
+    assert!(!graph.move_node(4, 0.0, 0.0, 0.0));
+    assert_eq!(graph.generation(), generation + 2);
+}
+
+#[test]
+fn test_move_node_keeps_cost_ratio() {
+    let mut graph = Graph::new();
+    for (id, x) in [(0, 0.0), (1, 10.0), (2, 10.0)] {
+        graph.add_node(Node::new(id, x, 0.0, 0.0));
+    }
+    // Three times as expensive as its length, e.g. through mud
+    graph.connect_nodes(0, 1, 30.0);
+    graph.add_edge(0, 2, 10.0);
+    graph.add_edge(1, 2, 0.0);
+
+    // Halving the length halves the cost, the extra cost stays
+    assert!(graph.move_node(1, 5.0, 0.0, 0.0));
+    assert_eq!(graph.edge_cost(0, 1), Some(15.0));
+    assert_eq!(graph.edge_cost(1, 0), Some(15.0));
+    // An edge between nodes at the same position has no length to scale
+    assert_eq!(graph.edge_cost(1, 2), Some(5.0));
+    assert_eq!(graph.edge_cost(0, 2), Some(10.0));
+
+    // A node moved twice in a batch is scaled from where it was before the batch
+    assert_eq!(graph.move_nodes_batch(&[(1, 40.0, 0.0, 0.0), (1, 20.0, 0.0, 0.0)]), 1);
+    assert_eq!(graph.nodes[1].x, 20.0);
+    assert_eq!(graph.edge_cost(0, 1), Some(60.0));
+    assert_eq!(graph.edge_cost(1, 2), Some(10.0));
+
+    // The slope factor of a flat edge is not recomputed when it becomes steep
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::new(1, 10.0, 0.0, 0.0));
+    graph.connect_nodes(0, 1, 10.0);
+    graph.apply_slope_factor(1.0);
+    graph.move_node(1, 0.0, 20.0, 0.0);
+    assert_eq!(graph.edge_cost(0, 1), Some(20.0));
+}
+
+#[test]
+fn test_add_node_with_edges() {
+    let mut graph = build_non_geometric_graph();
+    // A shortcut from node 0 to node 2 that is cheaper than both existing routes
+    let id = graph.add_node_with_edges(Node::new(99, 1.0, 0.0, 0.0), &[(0, 0.5), (2, 0.5)]);
+    assert_eq!(id, 3);
+    assert_eq!(graph.nodes[id].id, id);
+    assert_eq!(graph.nodes.len(), graph.edges.len());
+    assert_eq!(graph.edge_cost(2, id), Some(0.5));
+
+    let path = graph.a_star(0, 2, &DashMap::new()).expect("No path found");
+    let ids: Vec<usize> = path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, id, 2]);
+
+    // The reverse direction did not exist before
+    graph.connect_nodes(2, 1, 1.0);
+    assert_eq!(graph.edge_cost(2, 1), Some(1.0));
+    assert_eq!(graph.edge_cost(1, 2), Some(1.0));
+}
+
+#[test]
+#[should_panic]
+fn test_add_node_with_edges_to_missing_node() {
+    let mut graph = build_non_geometric_graph();
+    graph.add_node_with_edges(Node::new(3, 0.0, 0.0, 0.0), &[(3, 1.0)]);
+}
+
+#[test]
+fn test_remove_node() {
+    let mut graph = build_non_geometric_graph();
+    graph.add_node_with_edges(Node::new(3, 1.0, 0.0, 0.0), &[(0, 0.5), (2, 0.5)]);
+    let generation = graph.generation();
+
+    // Without the cheap detour through node 1, the direct edge is the cheapest path
+    assert!(graph.remove_node(1));
+    assert!(!graph.remove_node(10));
+    assert_eq!(graph.generation(), generation + 1);
+    assert_eq!(graph.nodes.len(), 3);
+    assert_eq!(graph.nodes.len(), graph.edges.len());
+    assert!(graph.nodes.iter().enumerate().all(|(index, node)| node.id == index));
+    assert!(graph.edges.iter().flatten().all(|edge| edge.to < 3));
+
+    // The node added last moved down to id 2
+    let path = graph.a_star(0, 1, &DashMap::new()).expect("No path found");
+    let ids: Vec<usize> = path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 2, 1]);
+
+    assert!(graph.remove_node(2));
+    let path = graph.a_star(0, 1, &DashMap::new()).expect("No path found");
+    let ids: Vec<usize> = path.iter().map(|node| node.id).collect();
+    assert_eq!(ids, vec![0, 1]);
+}
+
+/// Builds a large grid plus an isolated node, which A* can only rule out as a goal after
+/// expanding the whole grid.
+fn build_grid_with_island() -> (Graph, usize) {
+    let mut graph = build_grid(300, |_, _| false);
+    let island = graph.nodes.len();
+    graph.add_node(Node::new(island, -100.0, 5.0, -100.0));
+    (graph, island)
+}
+
+#[test]
+fn test_a_star_bounded() {
+    let (graph, island) = build_grid_with_island();
+    let cache = DashMap::new();
+
+    let start = std::time::Instant::now();
+    assert!(graph.a_star_bounded(0, island, &cache, 1000).is_none());
+    assert!(start.elapsed() < std::time::Duration::from_secs(1));
+    // A search that gave up is not cached, since a path may still exist
+    assert!(cache.is_empty());
+
+    // Close enough goals are still found
+    let path = graph.a_star_bounded(0, 301, &cache, 1000).expect("No path found");
+    assert_eq!(path.len(), 2);
+    assert!(cache.contains_key(&(0, 301)));
+
+    // Giving up on a reachable goal does not keep a later unlimited search from finding it
+    let far_corner = 300 * 300 - 1;
+    assert!(graph.a_star_bounded(0, far_corner, &cache, 10).is_none());
+    assert!(!cache.contains_key(&(0, far_corner)));
+    assert!(graph.a_star(0, far_corner, &cache).is_some());
+}
+
+#[test]
+fn test_a_star_with_timeout() {
+    let (graph, island) = build_grid_with_island();
+    let cache = DashMap::new();
+
+    assert!(graph.a_star_with_timeout(0, island, &cache, std::time::Instant::now()).is_none());
+    assert!(cache.is_empty());
+
+    let deadline = std::time::Instant::now() + std::time::Duration::from_secs(60);
+    assert!(graph.a_star_with_timeout(0, island, &cache, deadline).is_none());
+    // The search ran to completion, so the goal is known to be unreachable
+    assert!(cache.get(&(0, island)).unwrap().is_none());
+}
+
+#[test]
+fn test_a_star_with_cost() {
+    // A steep drop from node 0 straight to node 2 and a gentle detour over node 1
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::new(1, 1.0, 0.0, 1.0));
+    graph.add_node(Node::new(2, 2.0, 0.0, 0.0));
+    graph.add_edge_directed(0, 2, 2.0);
+    graph.add_edge(0, 1, 1.5);
+    graph.add_edge(1, 2, 1.5);
+
+    let scout = |edge: &repath::edge::Edge| edge.cost;
+    let heavy_vehicle = |edge: &repath::edge::Edge| if edge.directed { edge.cost * 10.0 } else { edge.cost };
+    let ids = |path: Option<repath::path::Path>| {
+        path.expect("No path found").iter().map(|node| node.id).collect::<Vec<_>>()
+    };
+
+    let cache = DashMap::new();
+    assert_eq!(ids(graph.a_star_with_cost(0, 2, 1, &cache, scout)), vec![0, 2]);
+    assert_eq!(ids(graph.a_star_with_cost(0, 2, 2, &cache, heavy_vehicle)), vec![0, 1, 2]);
+
+    // Each cost function has its own cache entries
+    assert_eq!(cache.len(), 2);
+    assert_eq!(ids(graph.a_star_with_cost(0, 2, 1, &cache, heavy_vehicle)), vec![0, 2]);
+}
+
+#[test]
+fn test_a_star_with_radius() {
+    // The direct edge from 0 to 1 is a narrow corridor, the detour through 2 is wide
+    let mut graph = Graph::new();
+    for (id, (x, z)) in [(0.0, 0.0), (4.0, 0.0), (2.0, 3.0)].into_iter().enumerate() {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    for (a, b) in [(0, 1), (0, 2), (2, 1)] {
+        graph.add_edge(a, b, graph.heuristic(a, b));
+        graph.add_edge(b, a, graph.heuristic(a, b));
+    }
+    assert!(graph.set_edge_clearance(0, 1, 1.0));
+    assert!(!graph.set_edge_clearance(0, 0, 1.0));
+
+    let ids = |path: Option<repath::path::Path>| path.unwrap().iter().map(|node| node.id).collect::<Vec<_>>();
+    // Searches for different radii share the cache
+    let cache = DashMap::new();
+    assert_eq!(ids(graph.a_star_with_radius(0, 1, &cache, 0.5)), vec![0, 1]);
+    assert_eq!(ids(graph.a_star_with_radius(0, 1, &cache, 2.0)), vec![0, 2, 1]);
+    assert_eq!(ids(graph.a_star_with_radius(0, 1, &cache, 0.5)), vec![0, 1]);
+    assert_eq!(cache.len(), 2);
+    // The reverse edge has no restriction
+    assert_eq!(ids(graph.a_star_with_radius(1, 0, &cache, 2.0)), vec![1, 0]);
+
+    assert!(graph.a_star_with_radius(0, 1, &cache, Coord::INFINITY).is_some());
+    graph.set_edge_clearance(2, 1, 1.5);
+    assert!(graph.a_star_with_radius(0, 1, &DashMap::new(), 2.0).is_none());
+}
+
+#[test]
+fn test_apply_slope_factor() {
+    // Three edges of length 2 from the origin: flat, straight up and at 30 degrees
+    let mut graph = Graph::new();
+    for (id, (x, y, z)) in [(0.0, 0.0, 0.0), (2.0, 0.0, 0.0), (0.0, 2.0, 0.0), (0.0, 1.0, Coord::sqrt(3.0))]
+        .into_iter()
+        .enumerate()
+    {
+        graph.add_node(Node::new(id, x, y, z));
+    }
+    for to in 1..4 {
+        graph.add_edge(0, to, graph.heuristic(0, to));
+        graph.add_edge(to, 0, graph.heuristic(0, to));
+    }
+
+    graph.apply_slope_factor(0.5);
+    assert_eq!(graph.edge_cost(0, 1), Some(2.0));
+    assert_eq!(graph.edge_cost(0, 2), Some(3.0));
+    assert!((graph.edge_cost(0, 3).unwrap() - 2.5).abs() < 1e-5);
+    // Going down is as steep as going up
+    assert_eq!(graph.edge_cost(2, 0), Some(3.0));
+    assert_eq!(graph.generation(), 1);
+}
+
+#[test]
+fn test_a_star_avoids_heavy_node() {
+    // The direct route over node 1 stands in fire, the detour over node 2 is longer
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::with_weight(1, 1.0, 0.0, 0.0, 10.0));
+    graph.add_node(Node::new(2, 1.0, 0.0, 1.0));
+    graph.add_node(Node::new(3, 2.0, 0.0, 0.0));
+    for (a, b) in [(0, 1), (1, 3), (0, 2), (2, 3)] {
+        graph.connect_nodes(a, b, graph.heuristic(a, b));
+    }
+    assert_eq!(graph.traversal_cost(&graph.edges[0][0]), 10.0);
+
+    let ids = |path: Option<repath::path::Path>| {
+        path.expect("No path found").iter().map(|node| node.id).collect::<Vec<_>>()
+    };
+    assert_eq!(ids(graph.a_star(0, 3, &DashMap::new())), vec![0, 2, 3]);
+    assert_eq!(ids(graph.dijkstra(0, 3, &DashMap::new())), vec![0, 2, 3]);
+
+    graph.nodes[1].weight = 1.0;
+    assert_eq!(ids(graph.a_star(0, 3, &DashMap::new())), vec![0, 1, 3]);
+}
+
+#[test]
+fn test_a_star_step_matches_a_star() {
+    let graph = build_triangulated_grid(20, |x, z| (5..15).contains(&x) && z == 10);
+    let goal = graph.nodes.len() - 1;
+    for start in [0, 20, 230] {
+        let mut state = graph.a_star_start(start, goal);
+        let mut steps = 0;
+        let path = loop {
+            steps += 1;
+            if let StepResult::Complete(path) = graph.a_star_step(&mut state) {
+                break path;
+            }
+        };
+        assert!(steps > 1);
+        assert_eq!(path, graph.a_star(start, goal, &DashMap::new()));
+        // The completed search keeps its result
+        assert_eq!(graph.a_star_step(&mut state), StepResult::Complete(path));
+    }
+
+    // Unreachable goals complete without a path
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::new(1, 1.0, 0.0, 0.0));
+    let mut state = graph.a_star_start(0, 1);
+    assert_eq!(graph.a_star_step(&mut state), StepResult::InProgress);
+    assert_eq!(graph.a_star_step(&mut state), StepResult::Complete(None));
+}
+
+#[test]
+fn test_indexed_binary_heap() {
+    let mut heap = IndexedBinaryHeap::new(5);
+    for (item, priority) in [(0, 5.0), (1, 3.0), (2, 4.0), (3, 1.0)] {
+        heap.push(item, priority);
+    }
+    assert!(heap.contains(2) && !heap.contains(4));
+    heap.decrease_key(0, 2.0);
+    // Higher priorities are ignored
+    heap.decrease_key(3, 6.0);
+    assert_eq!(heap.len(), 4);
+    let order: Vec<(usize, f32)> = std::iter::from_fn(|| heap.pop()).collect();
+    assert_eq!(order, [(3, 1.0), (0, 2.0), (1, 3.0), (2, 4.0)]);
+    assert!(heap.is_empty() && !heap.contains(0));
+}
+
+/// Counts the states an A* search with a plain `BinaryHeap` takes from the open set, which
+/// pushes a new state whenever a cheaper path to a node is found and skips the outdated ones.
+fn binary_heap_states_popped(graph: &Graph, start: usize, goal: usize) -> usize {
+    let mut g_score = vec![Coord::INFINITY; graph.nodes.len()];
+    let mut closed_set = vec![false; graph.nodes.len()];
+    let mut open_set = BinaryHeap::from([State { cost: graph.heuristic(start, goal), position: start }]);
+    g_score[start] = 0.0;
+    let mut popped = 0;
+    while let Some(State { position: current, .. }) = open_set.pop() {
+        popped += 1;
+        if current == goal {
+            break;
+        }
+        if std::mem::replace(&mut closed_set[current], true) {
+            continue;
+        }
+        for edge in &graph.edges[current] {
+            let tentative_g_score = g_score[current] + graph.traversal_cost(edge);
+            if !closed_set[edge.to] && tentative_g_score < g_score[edge.to] {
+                g_score[edge.to] = tentative_g_score;
+                let cost = tentative_g_score + graph.heuristic(edge.to, goal);
+                open_set.push(State { cost, position: edge.to });
+            }
+        }
+    }
+    popped
+}
+
+#[test]
+fn test_a_star_pops_every_node_once() {
+    // The wall forces a detour on which many nodes are reached again on cheaper paths
+    let graph = build_triangulated_grid(40, |x, z| x == 20 && z < 35);
+    let goal = 40 * 41 + 40;
+    for start in [0, 20 * 41] {
+        let (path, stats) = graph.a_star_with_stats(start, goal);
+        assert!(path.is_some());
+        assert_eq!(stats.states_popped, stats.nodes_expanded + 1);
+        assert!(stats.states_popped < binary_heap_states_popped(&graph, start, goal));
+    }
+}
+
+#[test]
+fn test_raycast() {
+    let graph = build_triangulated_grid(4, |x, z| x == 3 && z == 3);
+    let id = |x: usize, z: usize| z * 5 + x;
+
+    // Straight down onto the flat mesh, the direction does not have to be normalized
+    let (node, distance) = graph.raycast((1.2, 5.0, 1.3), (0.0, -2.0, 0.0)).expect("No hit");
+    assert_eq!(node, id(1, 1));
+    assert!((distance - 5.0).abs() < 1e-4);
+    // Diagonally from below, the faces are hit from both sides
+    let (node, distance) = graph.raycast((0.0, -3.0, 0.0), (2.0, 3.0, 2.8)).expect("No hit");
+    assert_eq!(node, id(2, 3));
+    assert!((distance - (4.0 as Coord + 9.0 + 2.8 * 2.8).sqrt()).abs() < 1e-4);
+
+    // Rays pointing away, passing the hole or the edge of the mesh, or lying in its plane miss
+    assert_eq!(graph.raycast((1.2, 5.0, 1.3), (0.0, 1.0, 0.0)), None);
+    assert_eq!(graph.raycast((3.5, 5.0, 3.5), (0.0, -1.0, 0.0)), None);
+    assert_eq!(graph.raycast((5.5, 5.0, 1.0), (0.0, -1.0, 0.0)), None);
+    assert_eq!(graph.raycast((-1.0, 0.0, 1.5), (1.0, 0.0, 0.0)), None);
+    assert_eq!(graph.raycast((1.2, 5.0, 1.3), (0.0, 0.0, 0.0)), None);
+
+    // A triangle without area is never hit
+    let mut graph = Graph::new();
+    for id in 0..3 {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    graph.add_edge(0, 1, 1.0);
+    graph.add_edge(1, 2, 1.0);
+    graph.add_edge(2, 0, 2.0);
+    assert_eq!(graph.triangles().len(), 1);
+    assert_eq!(graph.raycast((1.0, 1.0, 0.0), (0.0, -1.0, 0.0)), None);
+}
@@ -0,0 +1,132 @@
+use repath::settings::{Algorithm, RePathSettings, RePathSettingsBuilder, SettingsError};
+use repath::{Coord, RePathError};
+
+#[test]
+fn test_settings_builder() {
+    let settings = RePathSettingsBuilder::new()
+        .navmesh_filename("navmesh_varied.obj")
+        .precompute_radius(5000.0)
+        .total_precompute_pairs(1000)
+        .algorithm(Algorithm::Dijkstra)
+        .max_search_nodes(500)
+        .build()
+        .unwrap();
+    assert_eq!(settings.navmesh_filename, "navmesh_varied.obj");
+    assert_eq!(settings.precompute_radius, 5000.0);
+    assert_eq!(settings.total_precompute_pairs, 1000);
+    assert_eq!(settings.algorithm, Algorithm::Dijkstra);
+    assert_eq!(settings.max_search_nodes, Some(500));
+    assert_eq!(settings.cache_capacity, RePathSettings::default().cache_capacity);
+}
+
+#[test]
+fn test_settings_builder_rejects_invalid_settings() {
+    let builder = RePathSettingsBuilder::new;
+    assert_eq!(builder().navmesh_filename("").build().unwrap_err(), SettingsError::EmptyNavmeshFilename);
+    assert_eq!(
+        builder().precompute_radius(-1.0).build().unwrap_err(),
+        SettingsError::InvalidPrecomputeRadius(-1.0)
+    );
+    assert_eq!(builder().precompute_radius(0.0).build().unwrap_err(), SettingsError::InvalidPrecomputeRadius(0.0));
+    assert!(matches!(
+        builder().precompute_radius(Coord::NAN).build(),
+        Err(SettingsError::InvalidPrecomputeRadius(_))
+    ));
+    assert_eq!(builder().total_precompute_pairs(0).build().unwrap_err(), SettingsError::NoPrecomputePairs);
+    assert_eq!(builder().slope_factor(-0.5).build().unwrap_err(), SettingsError::InvalidSlopeFactor(-0.5));
+    assert_eq!(builder().slope_factor(2.0).build().unwrap().slope_factor, 2.0);
+    assert_eq!(builder().lazy_chunk_size(0.0).build().unwrap_err(), SettingsError::InvalidLazyChunkSize(0.0));
+    assert!(matches!(
+        builder().lazy_chunk_size(Coord::INFINITY).build(),
+        Err(SettingsError::InvalidLazyChunkSize(_))
+    ));
+}
+
+#[test]
+fn test_settings_toml_round_trip() {
+    let settings = RePathSettingsBuilder::new()
+        .navmesh_filename("maps/\"quoted\" level.obj")
+        .precompute_radius(2500.5)
+        .cache_file("cache.bin")
+        .build()
+        .unwrap();
+    let filename = std::env::temp_dir().join("repath_settings.toml");
+    std::fs::write(&filename, settings.to_toml_string()).unwrap();
+
+    let loaded = RePathSettings::from_toml_file(filename.to_str().unwrap()).unwrap();
+    assert_eq!(loaded.navmesh_filename, settings.navmesh_filename);
+    assert_eq!(loaded.precompute_radius, settings.precompute_radius);
+    assert_eq!(loaded.total_precompute_pairs, settings.total_precompute_pairs);
+    assert_eq!(loaded.cache_file, settings.cache_file);
+    assert_eq!(loaded.max_search_nodes, None);
+}
+
+#[test]
+fn test_settings_from_toml_str() {
+    let toml = r#"
+        # Settings of the test server
+        navmesh_filename = "NavMesh.obj" # the level
+        precompute_radius = 10_000
+        total_precompute_pairs = 50
+        use_precomputed_cache = false
+        algorithm = "Dijkstra"
+    "#;
+    let settings = RePathSettings::from_toml_str(toml).unwrap();
+    assert_eq!(settings.navmesh_filename, "NavMesh.obj");
+    assert_eq!(settings.precompute_radius, 10000.0);
+    assert!(!settings.use_precomputed_cache);
+    assert_eq!(settings.algorithm, Algorithm::Dijkstra);
+
+    let error = RePathSettings::from_toml_str("navmesh_filename = \"a.obj\"\n[table]\n").unwrap_err();
+    assert!(matches!(error, RePathError::ParseError { line: 2, .. }));
+    let error = RePathSettings::from_toml_str("precompute_radius = \"far\"").unwrap_err();
+    assert!(matches!(error, RePathError::SerializationError(_)));
+    assert!(RePathSettings::from_toml_file("does_not_exist.toml").is_err());
+}
+
+#[test]
+fn test_settings_toml_escapes() {
+    let toml = r#"
+        navmesh_filename = "levels\\a \"b\"\tc\u00e9\U0001F600.obj" # quoted
+        precompute_radius = 1e2
+        total_precompute_pairs = 1_000
+        use_precomputed_cache = true
+    "#;
+    let settings = RePathSettings::from_toml_str(toml).unwrap();
+    assert_eq!(settings.navmesh_filename, "levels\\a \"b\"\tc\u{e9}\u{1F600}.obj");
+
+    for invalid in [r#""a\/b""#, r#""a\x41""#, r#""\u00""#, r#""\uD800""#, r#""open"#] {
+        let toml = format!("navmesh_filename = {}", invalid);
+        let error = RePathSettings::from_toml_str(&toml).unwrap_err();
+        assert!(matches!(error, RePathError::ParseError { line: 1, .. }), "{}", invalid);
+    }
+}
+
+#[test]
+fn test_settings_toml_rejects_unsupported_syntax() {
+    let cases = [
+        ("[[servers]]\nnavmesh_filename = \"a.obj\"", "Arrays of tables"),
+        ("[pathfinding]", "Tables"),
+        ("pathfinding.navmesh_filename = \"a.obj\"", "Dotted keys"),
+        ("\"navmesh_filename\" = \"a.obj\"", "Quoted keys"),
+        ("navmesh_filename = \"\"\"a.obj\"\"\"", "Multi-line strings"),
+        ("navmesh_filename = '''\na.obj'''", "Multi-line strings"),
+        ("navmesh_filename = 'C:\\navmesh.obj'", "Literal strings"),
+        ("navmesh_filename = \"a.obj\" \"b.obj\"", "Expected a comment"),
+        ("cache_capacity = [1, 2]", "Arrays"),
+        ("mode = { kind = \"TwoD\" }", "Inline tables"),
+        ("cache_capacity = 0x10", "Hexadecimal"),
+        ("cache_capacity = 1__000", "Invalid value"),
+        ("precompute_radius = inf", "Invalid value, strings must be quoted"),
+        ("algorithm = AStar", "Invalid value, strings must be quoted"),
+        ("navmesh_filename = 1979-05-27", "Invalid value"),
+    ];
+    for (toml, detail) in cases {
+        match RePathSettings::from_toml_str(toml) {
+            Err(RePathError::ParseError { line: 1, detail: error }) => {
+                assert!(error.starts_with(detail), "{}: {}", toml, error)
+            }
+            result => panic!("{} was read as {:?}", toml, result),
+        }
+    }
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "simd")]
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::utils::distance_simd;
+
+#[test]
+fn test_distance_simd_matches_distance() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..1000 {
+        let p1: (f32, f32, f32) = (rng.gen_range(-1e3..1e3), rng.gen_range(-1e3..1e3), rng.gen_range(-1e3..1e3));
+        let p2: (f32, f32, f32) = (rng.gen_range(-1e3..1e3), rng.gen_range(-1e3..1e3), rng.gen_range(-1e3..1e3));
+        let expected = ((p1.0 - p2.0).powi(2) + (p1.1 - p2.1).powi(2) + (p1.2 - p2.2).powi(2)).sqrt();
+        assert!((distance_simd(p1, p2) - expected).abs() < 1e-3);
+    }
+}
+
+#[test]
+fn test_nearest_node_simd_matches_nearest_node() {
+    let mut rng = StdRng::seed_from_u64(5);
+    // A node count that is not a multiple of the lane count
+    let mut graph = Graph::new();
+    for id in 0..1003 {
+        graph.add_node(Node::new(id, rng.gen_range(-500.0..500.0), rng.gen_range(-5.0..5.0), rng.gen_range(-500.0..500.0)));
+    }
+    for _ in 0..200 {
+        let (x, y, z) = (rng.gen_range(-600.0..600.0), rng.gen_range(-5.0..5.0), rng.gen_range(-600.0..600.0));
+        assert_eq!(graph.nearest_node_simd(x, y, z), graph.nearest_node(x, y, z));
+    }
+
+    // Duplicated positions resolve to the lowest id, and the last nodes are found as well
+    graph.add_node(Node::new(1003, 1000.0, 0.0, 1000.0));
+    graph.add_node(Node::new(1004, 1000.0, 0.0, 1000.0));
+    assert_eq!(graph.nearest_node_simd(1000.0, 0.0, 1000.0), Some(1003));
+    assert_eq!(Graph::new().nearest_node_simd(0.0, 0.0, 0.0), None);
+}
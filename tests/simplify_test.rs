@@ -0,0 +1,77 @@
+use dashmap::DashMap;
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::Coord;
+
+/// A graph with nodes at the given x coordinates and two-way edges between the given pairs.
+fn build_graph(xs: &[Coord], edges: &[(usize, usize, Coord)]) -> Graph {
+    let mut graph = Graph::new();
+    for (id, &x) in xs.iter().enumerate() {
+        graph.add_node(Node::new(id, x, 0.0, 0.0));
+    }
+    for &(a, b, cost) in edges {
+        graph.add_edge(a, b, cost);
+        graph.add_edge(b, a, cost);
+    }
+    graph
+}
+
+#[test]
+fn test_simplify_chain() {
+    // A - B - C - D, where B and C have two neighbors each
+    let graph = build_graph(&[0.0, 1.0, 3.0, 6.0], &[(0, 1, 1.0), (1, 2, 2.0), (2, 3, 3.0)]);
+    let simplified = graph.simplify();
+
+    assert_eq!(simplified.nodes.len(), 2);
+    assert_eq!((simplified.nodes[0].x, simplified.nodes[1].x), (0.0, 6.0));
+    assert_eq!(simplified.nodes[1].id, 1);
+    assert_eq!(simplified.edges[0].len(), 1);
+    assert_eq!(simplified.edge_cost(0, 1), Some(6.0));
+    assert_eq!(simplified.edge_cost(1, 0), Some(6.0));
+}
+
+#[test]
+fn test_simplify_keeps_junctions_and_costs() {
+    // A star with three arms of different lengths around node 0, the arms end in leaves
+    let graph = build_graph(
+        &[0.0, 1.0, 2.0, -1.0, -2.0, -3.0, 5.0],
+        &[(0, 1, 1.0), (1, 2, 1.0), (0, 3, 1.0), (3, 4, 1.0), (4, 5, 1.0), (0, 6, 5.0)],
+    );
+    let simplified = graph.simplify();
+    assert_eq!(simplified.nodes.len(), 4);
+    assert_eq!(simplified.edges[0].len(), 3);
+
+    // The cheapest paths between the remaining nodes keep their cost
+    let (before, after) = (DashMap::new(), DashMap::new());
+    let cost = |graph: &Graph, path: &[Node]| -> Coord {
+        path.windows(2).map(|pair| graph.edge_cost(pair[0].id, pair[1].id).unwrap()).sum()
+    };
+    let path = graph.a_star(2, 5, &before).unwrap();
+    let simplified_path = simplified.a_star(1, 2, &after).unwrap();
+    assert_eq!(cost(&graph, &path), cost(&simplified, &simplified_path));
+}
+
+#[test]
+fn test_simplify_weights_and_cycles() {
+    // Entering the heavy middle node of the chain costs twice as much
+    let mut graph = Graph::new();
+    graph.add_node(Node::new(0, 0.0, 0.0, 0.0));
+    graph.add_node(Node::with_weight(1, 1.0, 0.0, 0.0, 2.0));
+    graph.add_node(Node::new(2, 2.0, 0.0, 0.0));
+    for (a, b) in [(0, 1), (1, 2)] {
+        graph.add_edge(a, b, 1.0);
+        graph.add_edge(b, a, 1.0);
+    }
+    assert_eq!(graph.simplify().edge_cost(0, 1), Some(3.0));
+
+    // A ring without any junction shrinks to its smallest node
+    let ring = build_graph(&[0.0, 1.0, 2.0, 3.0], &[(0, 1, 1.0), (1, 2, 1.0), (2, 3, 1.0), (3, 0, 1.0)]);
+    let simplified = ring.simplify();
+    assert_eq!(simplified.nodes.len(), 1);
+    assert!(simplified.edges[0].is_empty());
+
+    // One-way edges are kept as they are
+    let mut one_way = build_graph(&[0.0, 1.0, 2.0], &[(0, 1, 1.0)]);
+    one_way.add_edge_directed(1, 2, 1.0);
+    assert_eq!(one_way.simplify(), one_way);
+}
@@ -0,0 +1,196 @@
+use dashmap::DashMap;
+use rand::{Rng, SeedableRng};
+use rand::rngs::StdRng;
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::spatial::SpatialIndex;
+use repath::utils::{nodes_within_radius, parse_obj};
+use repath::Coord;
+
+#[test]
+fn test_nearest_node_fast_matches_linear_scan() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let index = SpatialIndex::new(&graph);
+    let mut rng = StdRng::seed_from_u64(7);
+
+    for node in graph.nodes.iter().step_by(97) {
+        assert_eq!(index.nearest_node_fast(node.x, node.y, node.z), Some(node.id));
+    }
+    for _ in 0..1000 {
+        let node = &graph.nodes[rng.gen_range(0..graph.nodes.len())];
+        let (x, y, z) = (
+            node.x + rng.gen_range(-30.0..30.0),
+            node.y + rng.gen_range(-30.0..30.0),
+            node.z + rng.gen_range(-30.0..30.0),
+        );
+        assert_eq!(index.nearest_node_fast(x, y, z), graph.nearest_node(x, y, z));
+    }
+}
+
+#[test]
+fn test_nearest_node_fast_ties_and_empty_graph() {
+    let mut graph = Graph::new();
+    assert_eq!(SpatialIndex::new(&graph).nearest_node_fast(0.0, 0.0, 0.0), None);
+
+    // Many nodes on the same spot and at the same distance, the lowest id wins like in the
+    // linear scan
+    for id in 0..50 {
+        let x = if id % 2 == 0 { 1.0 } else { -1.0 };
+        graph.add_node(Node::new(id, x, 0.0, 0.0));
+    }
+    let index = SpatialIndex::new(&graph);
+    assert_eq!(index.nearest_node_fast(0.0, 0.0, 0.0), Some(0));
+    assert_eq!(index.nearest_node_fast(-0.5, 0.0, 0.0), Some(1));
+    assert_eq!(graph.nearest_node(-0.5, 0.0, 0.0), Some(1));
+}
+
+#[test]
+fn test_nodes_within_radius_fast_matches_linear_scan() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let index = SpatialIndex::new(&graph);
+
+    for (node_id, radius) in [(0, 10.0), (20200, 25.0), (1234, 100.0), (40400, 0.0), (777, 1e6)] {
+        let node = &graph.nodes[node_id];
+        let fast = index.nodes_within_radius_fast(node, radius);
+        assert_eq!(fast, nodes_within_radius(&graph, node, radius));
+        assert!(fast.contains(&node_id));
+    }
+}
+
+#[test]
+fn test_k_nearest_fast_matches_sorting() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let index = SpatialIndex::new(&graph);
+    let mut rng = StdRng::seed_from_u64(11);
+
+    for _ in 0..20 {
+        let (x, y, z) = (rng.gen_range(-100.0..4100.0), rng.gen_range(-50.0..50.0), rng.gen_range(-100.0..4100.0));
+        let distance = |node: &Node| ((node.x - x).powi(2) + (node.y - y).powi(2) + (node.z - z).powi(2)).sqrt();
+        let mut expected: Vec<usize> = (0..graph.nodes.len()).collect();
+        expected.sort_by(|&a, &b| distance(&graph.nodes[a]).total_cmp(&distance(&graph.nodes[b])).then(a.cmp(&b)));
+        expected.truncate(8);
+        assert_eq!(index.k_nearest_fast(x, y, z, 8), expected);
+    }
+    assert!(index.k_nearest_fast(0.0, 0.0, 0.0, 0).is_empty());
+}
+
+#[test]
+fn test_k_nearest_nodes() {
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let index = SpatialIndex::new(&graph);
+    let mut rng = StdRng::seed_from_u64(13);
+
+    for _ in 0..20 {
+        let (x, y, z) = (rng.gen_range(-100.0..4100.0), rng.gen_range(-50.0..50.0), rng.gen_range(-100.0..4100.0));
+        assert_eq!(graph.k_nearest_nodes(x, y, z, 1), vec![graph.nearest_node(x, y, z).unwrap()]);
+        assert_eq!(graph.k_nearest_nodes(x, y, z, 8), index.k_nearest_fast(x, y, z, 8));
+    }
+
+    // With k as large as the graph, every node is returned once, sorted by distance
+    let all = graph.k_nearest_nodes(0.0, 0.0, 0.0, graph.nodes.len());
+    let mut ids = all.clone();
+    ids.sort_unstable();
+    assert_eq!(ids, (0..graph.nodes.len()).collect::<Vec<_>>());
+    let distance = |id: usize| {
+        let node = &graph.nodes[id];
+        (node.x * node.x + node.y * node.y + node.z * node.z).sqrt()
+    };
+    assert!(all.windows(2).all(|pair| distance(pair[0]) <= distance(pair[1])));
+    assert_eq!(graph.k_nearest_nodes(0.0, 0.0, 0.0, graph.nodes.len() + 5).len(), graph.nodes.len());
+    assert!(graph.k_nearest_nodes(0.0, 0.0, 0.0, 0).is_empty());
+}
+
+#[test]
+fn test_nodes_in_aabb() {
+    let graph = Graph::build_from_point_cloud(&grid_points(), 1.0);
+    // Points on the faces of the box are included
+    assert_eq!(graph.nodes_in_aabb((1.0, -1.0, 2.0), (2.5, 1.0, 3.0)), vec![21, 22, 31, 32]);
+    assert!(graph.nodes_in_aabb((1.0, 0.5, 2.0), (2.0, 1.0, 3.0)).is_empty());
+
+    let graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    let index = SpatialIndex::new(&graph);
+    let mut rng = StdRng::seed_from_u64(17);
+    for _ in 0..20 {
+        // Boxes around random nodes, so none of them is empty
+        let node = &graph.nodes[rng.gen_range(0..graph.nodes.len())];
+        let min = (node.x - rng.gen_range(0.0..200.0), node.y - 10.0, node.z - rng.gen_range(0.0..200.0));
+        let max = (node.x + rng.gen_range(0.0..200.0), node.y + 10.0, node.z + rng.gen_range(0.0..200.0));
+        let expected = graph.nodes_in_aabb(min, max);
+        assert!(!expected.is_empty());
+        assert_eq!(index.nodes_in_aabb_fast(min, max), expected);
+    }
+}
+
+/// The points of a 10x10 grid with spacing 1, numbered row by row.
+fn grid_points() -> Vec<(Coord, Coord, Coord)> {
+    (0..100).map(|id| ((id % 10) as Coord, 0.0, (id / 10) as Coord)).collect()
+}
+
+#[test]
+fn test_build_from_point_cloud() {
+    let graph = Graph::build_from_point_cloud(&grid_points(), 1.0);
+    assert_eq!(graph.nodes.len(), 100);
+    assert_eq!(graph.connected_components().len(), 1);
+    // Corners have two neighbors, inner points four, and the edges lead to the closest points
+    assert_eq!(graph.edges[0].iter().map(|edge| edge.to).collect::<Vec<_>>(), vec![1, 10]);
+    assert_eq!(graph.edges[55].len(), 4);
+    assert!(graph.edges.iter().flatten().all(|edge| edge.cost == 1.0));
+
+    let path = graph.a_star(0, 99, &DashMap::new()).expect("No path found");
+    assert_eq!(path.len(), 19);
+
+    // Diagonal neighbors are within a larger radius
+    let graph = Graph::build_from_point_cloud(&grid_points(), 1.5);
+    assert_eq!(graph.a_star(0, 99, &DashMap::new()).expect("No path found").len(), 10);
+    assert_eq!(Graph::build_from_point_cloud(&grid_points(), 0.5).connected_components().len(), 100);
+}
+
+#[test]
+fn test_build_from_point_cloud_knn() {
+    let graph = Graph::build_from_point_cloud_knn(&grid_points(), 4);
+    assert_eq!(graph.connected_components().len(), 1);
+    assert!(graph.edges.iter().all(|edges| edges.len() >= 4));
+    // Every edge has its reverse and no edge is added twice
+    for (from, edges) in graph.edges.iter().enumerate() {
+        let mut targets: Vec<usize> = edges.iter().map(|edge| edge.to).collect();
+        targets.dedup();
+        assert_eq!(targets.len(), edges.len());
+        assert!(edges.iter().all(|edge| edge.to != from && graph.edge_cost(edge.to, from).is_some()));
+    }
+    assert!(graph.a_star(0, 99, &DashMap::new()).is_some());
+}
+
+#[test]
+fn test_nearest_node_parallel_matches_sequential() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let mut graph = Graph::new();
+    for id in 0..5000 {
+        // Coarse positions produce many nodes at the same distance
+        graph.add_node(Node::new(id, rng.gen_range(0..50) as Coord, 0.0, rng.gen_range(0..50) as Coord));
+    }
+    for _ in 0..200 {
+        let (x, z) = (rng.gen_range(0..50) as Coord, rng.gen_range(0..50) as Coord);
+        let sequential = graph.nearest_node_with_threshold(x, 0.0, z, usize::MAX);
+        assert_eq!(graph.nearest_node_parallel(x, 0.0, z), sequential);
+        assert_eq!(graph.nearest_node_with_threshold(x, 0.0, z, 0), sequential);
+    }
+    assert_eq!(Graph::new().nearest_node_parallel(0.0, 0.0, 0.0), None);
+}
+
+#[test]
+fn test_sphere_cast() {
+    let graph = Graph::build_from_point_cloud(&grid_points(), 1.0);
+    let mut expected: Vec<usize> = (42..=46).chain(51..=57).chain(62..=66).collect();
+    // The rows next to the sweep and the points exactly one radius beyond its ends are included,
+    // the diagonal neighbors of the ends are not
+    assert_eq!(graph.sphere_cast((2.0, 0.0, 5.0), (6.0, 0.0, 5.0), 1.0), expected);
+    // The direction of the sweep does not matter
+    assert_eq!(graph.sphere_cast((6.0, 0.0, 5.0), (2.0, 0.0, 5.0), 1.0), expected);
+
+    // A sweep above the plane only reaches the row below it with a larger radius
+    assert!(graph.sphere_cast((2.0, 2.0, 5.0), (6.0, 2.0, 5.0), 1.5).is_empty());
+    expected = (52..=56).collect();
+    assert_eq!(graph.sphere_cast((2.0, 2.0, 5.0), (6.0, 2.0, 5.0), 2.0), expected);
+    // Without movement the sweep is a sphere
+    assert_eq!(graph.sphere_cast((0.0, 0.0, 0.0), (0.0, 0.0, 0.0), 1.0), vec![0, 1, 10]);
+}
@@ -0,0 +1,166 @@
+use repath::edge::{Edge, EdgeKind};
+use repath::graph::Graph;
+use repath::node::Node;
+use repath::settings::RePathSettings;
+use repath::utils::{parse_obj, save_graph_binary};
+use repath::validation::ValidationError;
+use repath::{Coord, RePathError, RePathfinder};
+
+fn build_line(node_count: usize) -> Graph {
+    let mut graph = Graph::new();
+    for id in 0..node_count {
+        graph.add_node(Node::new(id, id as Coord, 0.0, 0.0));
+    }
+    for id in 1..node_count {
+        graph.add_edge(id - 1, id, 1.0);
+        graph.add_edge(id, id - 1, 1.0);
+    }
+    graph
+}
+
+#[test]
+fn test_validate_valid_graphs() {
+    assert!(build_line(5).validate().is_empty());
+    assert!(Graph::new().validate().is_empty());
+}
+
+#[test]
+fn test_validate_self_loop_and_duplicate_edge() {
+    let mut graph = build_line(3);
+    graph.add_edge(1, 1, 0.5);
+    graph.add_edge(2, 1, 2.0);
+    graph.add_edge(2, 1, 3.0);
+
+    // Three edges between the same nodes are reported once
+    assert_eq!(
+        graph.validate(),
+        vec![ValidationError::SelfLoop(1), ValidationError::DuplicateEdge(2, 1)]
+    );
+    assert!(graph.validate().iter().all(|error| !error.is_fatal()));
+}
+
+#[test]
+fn test_validate_negative_cost_and_dangling_edge() {
+    let mut graph = build_line(3);
+    graph.add_edge(0, 2, -1.5);
+    graph.edges[2].push(Edge {
+        to: 7,
+        cost: 1.0,
+        directed: false,
+        kind: EdgeKind::Walk,
+        clearance: Coord::INFINITY,
+        labels: Vec::new(),
+    });
+
+    let errors = graph.validate();
+    assert_eq!(
+        errors,
+        vec![ValidationError::NegativeCost(0, 2, -1.5), ValidationError::DanglingEdge(2, 7)]
+    );
+    assert!(errors.iter().all(ValidationError::is_fatal));
+    assert_eq!(errors[1].to_string(), "Node 2 has an edge to the missing node 7");
+}
+
+#[test]
+fn test_pathfinder_rejects_invalid_graph() {
+    let mut graph = parse_obj("navmesh_varied.obj").expect("Failed to parse navmesh");
+    graph.add_edge(0, 1, -1.0);
+    let filename = std::env::temp_dir().join("repath_negative_cost.rpnav");
+    let filename = filename.to_str().unwrap();
+    save_graph_binary(&graph, filename).unwrap();
+
+    let settings = RePathSettings {
+        navmesh_filename: filename.to_string(),
+        total_precompute_pairs: 0,
+        ..RePathSettings::default()
+    };
+    match RePathfinder::new(settings) {
+        Err(RePathError::InvalidGraph(errors)) => {
+            assert_eq!(errors, vec![ValidationError::NegativeCost(0, 1, -1.0)]);
+        }
+        result => panic!("Expected an invalid graph error, got {:?}", result.err()),
+    }
+}
+
+/// Builds a graph of nodes at the given XZ positions connected both ways by the given edges.
+fn build_drawing(points: &[(Coord, Coord)], edges: &[(usize, usize)]) -> Graph {
+    let mut graph = Graph::new();
+    for (id, &(x, z)) in points.iter().enumerate() {
+        graph.add_node(Node::new(id, x, 0.0, z));
+    }
+    for &(a, b) in edges {
+        graph.connect_nodes(a, b, 1.0);
+    }
+    graph
+}
+
+/// The points of a regular polygon with `count` corners.
+fn polygon(count: usize) -> Vec<(Coord, Coord)> {
+    (0..count)
+        .map(|index| {
+            let angle = index as Coord / count as Coord * std::f64::consts::TAU as Coord;
+            (angle.cos(), angle.sin())
+        })
+        .collect()
+}
+
+/// Every pair of the given nodes.
+fn complete_edges(nodes: &[usize]) -> Vec<(usize, usize)> {
+    nodes
+        .iter()
+        .enumerate()
+        .flat_map(|(index, &a)| nodes[index + 1..].iter().map(move |&b| (a, b)))
+        .collect()
+}
+
+#[test]
+fn test_is_planar() {
+    // A square with both diagonals is planar even though the diagonals cross
+    let k4 = build_drawing(&polygon(4), &complete_edges(&[0, 1, 2, 3]));
+    assert!(k4.is_planar());
+    let octahedron = build_drawing(
+        &polygon(6),
+        &[(0, 1), (1, 2), (2, 0), (3, 4), (4, 5), (5, 3), (0, 3), (0, 4), (1, 4), (1, 5), (2, 5), (2, 3)],
+    );
+    assert!(octahedron.is_planar());
+    // Two complete graphs of four nodes sharing node 3
+    let mut shared = complete_edges(&[0, 1, 2, 3]);
+    shared.extend(complete_edges(&[3, 4, 5, 6]));
+    assert!(build_drawing(&polygon(7), &shared).is_planar());
+
+    // K5 has too many edges for Euler's formula, K3,3 and the Petersen graph do not
+    assert!(!build_drawing(&polygon(5), &complete_edges(&[0, 1, 2, 3, 4])).is_planar());
+    let k33: Vec<(usize, usize)> = (0..3).flat_map(|a| (3..6).map(move |b| (a, b))).collect();
+    assert!(!build_drawing(&polygon(6), &k33).is_planar());
+    let petersen = [
+        (0, 1), (1, 2), (2, 3), (3, 4), (4, 0), (0, 5), (1, 6), (2, 7), (3, 8), (4, 9),
+        (5, 7), (7, 9), (9, 6), (6, 8), (8, 5),
+    ];
+    assert!(!build_drawing(&polygon(10), &petersen).is_planar());
+
+    // Beyond the exact limit, sparse non-planar graphs pass the approximate check
+    let mut large = build_drawing(&polygon(6), &k33);
+    for id in 6..=repath::planarity::EXACT_PLANARITY_LIMIT {
+        large.add_node(Node::new(id, id as Coord, 0.0, 10.0));
+    }
+    assert!(large.is_planar());
+}
+
+#[test]
+fn test_crossing_edges() {
+    let k4 = build_drawing(&[(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)], &complete_edges(&[0, 1, 2, 3]));
+    assert_eq!(k4.crossing_edges(), vec![((0, 2), (1, 3))]);
+
+    // Edges sharing a node, touching or lying on top of each other do not cross
+    let touching = build_drawing(
+        &[(0.0, 0.0), (2.0, 0.0), (1.0, 0.0), (1.0, 1.0), (3.0, 0.0)],
+        &[(0, 1), (0, 3), (2, 3), (1, 4), (2, 4)],
+    );
+    assert!(touching.crossing_edges().is_empty());
+
+    // Heights are ignored
+    let mut bridge = build_drawing(&[(0.0, 0.0), (2.0, 0.0), (1.0, -1.0), (1.0, 1.0)], &[(0, 1), (2, 3)]);
+    bridge.nodes[2].y = 5.0;
+    bridge.nodes[3].y = 5.0;
+    assert_eq!(bridge.crossing_edges(), vec![((0, 1), (2, 3))]);
+}